@@ -0,0 +1,121 @@
+#![no_main]
+
+use std::sync::Once;
+
+use arbitrary::Arbitrary;
+use counter::{
+    error::CounterError, instructions::CounterInstructions, process_instruction, CounterAccount,
+};
+use libfuzzer_sys::fuzz_target;
+use num_traits::FromPrimitive;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::{Clock, Epoch},
+    entrypoint::SUCCESS,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+};
+
+#[derive(Arbitrary, Debug)]
+struct FuzzAccount {
+    /// Keys come from a tiny space so authority, delegate and multisig
+    /// matches actually happen.
+    key_seed: u8,
+    is_signer: bool,
+    is_writable: bool,
+    lamports: u64,
+    /// When set, `data` is padded or truncated to a valid counter length so
+    /// the processor gets past deserialization.
+    counter_sized: bool,
+    data: Vec<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzInput {
+    accounts: Vec<FuzzAccount>,
+    instruction_data: Vec<u8>,
+}
+
+/// Writes read the clock, which only the runtime provides; this one stays at
+/// slot zero.
+struct ClockStub;
+
+impl SyscallStubs for ClockStub {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: `Clock::get` passes a pointer to a `Clock`.
+        unsafe { var_addr.cast::<Clock>().write(Clock::default()) };
+        SUCCESS
+    }
+}
+
+static STUB_CLOCK: Once = Once::new();
+
+fuzz_target!(|input: FuzzInput| {
+    STUB_CLOCK.call_once(|| {
+        set_syscall_stubs(Box::new(ClockStub));
+    });
+
+    // `AccountInfo::realloc` writes the new length just before the data,
+    // which is only valid inside the runtime's serialized input.
+    if matches!(
+        CounterInstructions::unpack(&input.instruction_data),
+        Ok(CounterInstructions::Realloc(_))
+    ) {
+        return;
+    }
+
+    let program_id = Pubkey::new_from_array([255; 32]);
+    let owner = program_id;
+
+    let keys: Vec<Pubkey> = input
+        .accounts
+        .iter()
+        .map(|account| Pubkey::new_from_array([account.key_seed % 4; 32]))
+        .collect();
+    let mut lamports: Vec<u64> = input
+        .accounts
+        .iter()
+        .map(|account| account.lamports)
+        .collect();
+    let mut data: Vec<Vec<u8>> = input
+        .accounts
+        .iter()
+        .map(|account| {
+            let mut data = account.data.clone();
+            if account.counter_sized {
+                data.resize(CounterAccount::LEN, 0);
+            }
+            data
+        })
+        .collect();
+
+    let accounts: Vec<AccountInfo> = input
+        .accounts
+        .iter()
+        .zip(keys.iter())
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|(((account, key), lamports), data)| {
+            AccountInfo::new(
+                key,
+                account.is_signer,
+                account.is_writable,
+                lamports,
+                data,
+                &owner,
+                false,
+                Epoch::default(),
+            )
+        })
+        .collect();
+
+    if let Err(ProgramError::Custom(code)) =
+        process_instruction(&program_id, &accounts, &input.instruction_data)
+    {
+        assert!(
+            CounterError::from_u32(code).is_some(),
+            "undefined custom error code {code}"
+        );
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use counter::{error::CounterError, instructions::CounterInstructions};
+use libfuzzer_sys::fuzz_target;
+use solana_program::program_error::ProgramError;
+
+fuzz_target!(|data: &[u8]| {
+    match CounterInstructions::unpack(data) {
+        Ok(instruction) => {
+            // Anything that decodes must re-encode to a payload that decodes
+            // to the same instruction.
+            let packed = instruction.pack();
+            let repacked = CounterInstructions::unpack(&packed).unwrap().pack();
+            assert_eq!(packed, repacked);
+        }
+        Err(err) => assert!(
+            err == ProgramError::InvalidInstructionData
+                || err == CounterError::UnknownInstruction.into()
+        ),
+    }
+});
@@ -0,0 +1,14 @@
+#!/usr/bin/env bash
+# Emits idl/counter.json from the shank annotations in src/.
+#
+# Usage: scripts/generate-idl.sh <program-id>
+# Requires shank-cli: cargo install shank-cli
+set -euo pipefail
+
+if [ $# -ne 1 ]; then
+    echo "usage: $0 <program-id>" >&2
+    exit 1
+fi
+
+cd "$(dirname "$0")/.."
+shank idl --crate-root . --out-dir idl --program-id "$1"
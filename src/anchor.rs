@@ -0,0 +1,206 @@
+//! An Anchor build of the core instructions, for teams whose tooling expects
+//! Anchor's IDL, account discriminators and constraints.
+//!
+//! With the `anchor` feature, Anchor's entrypoint replaces the native one
+//! and serves Initialize, Increment, Decrement, Update, Reset, Pause, Unpause
+//! and Close with the native semantics, events and error codes. The other
+//! instructions have no Anchor counterpart, so the features they configure
+//! stay off, and counters are not added to the registry or governed by the
+//! program config.
+//!
+//! A [`Counter`] is Anchor's 8-byte account discriminator followed by the
+//! native [`CounterAccount`], so every field sits 8 bytes past its native
+//! offset: the authority at 16, for one.
+
+// Anchor's macros test features of the crate they expand in (`cpi`,
+// `idl-build`, `no-idl` and others) that this crate doesn't declare.
+#![allow(unexpected_cfgs)]
+
+use anchor_lang::prelude::*;
+
+use crate::error::CounterError;
+use crate::events::CounterEvent;
+use crate::processor::{prepare_write, typed_event};
+use crate::state::CounterAccount;
+
+declare_id!("2RBGHwWvcQo8jKx3TZYMuaytyhGJZge14rcZKQ878iug");
+
+#[account(zero_copy)]
+pub struct Counter {
+    pub state: CounterAccount,
+}
+
+impl Counter {
+    pub const LEN: usize = 8 + CounterAccount::LEN;
+}
+
+#[program]
+pub mod counter_anchor {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let counter = &mut ctx.accounts.counter.load_init()?.state;
+        let authority = ctx.accounts.authority.key();
+        counter.authority = authority;
+        counter.record_creation(&Clock::get()?, ctx.accounts.payer.key);
+        CounterEvent::Initialized { authority }.emit();
+        Ok(())
+    }
+
+    /// `mode` is one of the `OVERFLOW_*` policies in [`crate::state`].
+    pub fn increment(ctx: Context<Mutate>, value: u32, mode: u8) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let counter = &mut ctx.accounts.counter.load_mut()?.state;
+        check_unpaused(counter)?;
+        prepare_write(counter, &signer)?;
+        let (old, old_value) = (counter.counter, counter.value());
+        counter.increment(Some((&signer, true)), value.into(), mode)?;
+        let event = CounterEvent::Incremented {
+            old,
+            new: counter.counter,
+            by: value,
+        };
+        typed_event(counter, old_value, event).emit();
+        Ok(())
+    }
+
+    /// `mode` is one of the `OVERFLOW_*` policies in [`crate::state`].
+    pub fn decrement(ctx: Context<Mutate>, value: u32, mode: u8) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let counter = &mut ctx.accounts.counter.load_mut()?.state;
+        check_unpaused(counter)?;
+        prepare_write(counter, &signer)?;
+        let (old, old_value) = (counter.counter, counter.value());
+        counter.decrement(Some((&signer, true)), value.into(), mode)?;
+        let event = CounterEvent::Decremented {
+            old,
+            new: counter.counter,
+            by: value,
+        };
+        typed_event(counter, old_value, event).emit();
+        Ok(())
+    }
+
+    /// Signers are the remaining accounts, as natively; at least one is
+    /// required.
+    pub fn update(ctx: Context<Write>, value: u32) -> Result<()> {
+        let counter = &mut ctx.accounts.counter.load_mut()?.state;
+        check_unpaused(counter)?;
+        let signer =
+            first_signer(ctx.remaining_accounts).ok_or(ProgramError::MissingRequiredSignature)?;
+        prepare_write(counter, &signer)?;
+        let (old, old_value) = (counter.counter, counter.value());
+        counter.set_value(value);
+        typed_event(
+            counter,
+            old_value,
+            CounterEvent::Updated { old, new: value },
+        )
+        .emit();
+        counter.record_value();
+        Ok(())
+    }
+
+    /// Signers are the remaining accounts, as natively; at least one is
+    /// required.
+    pub fn reset(ctx: Context<Write>) -> Result<()> {
+        let counter = &mut ctx.accounts.counter.load_mut()?.state;
+        check_unpaused(counter)?;
+        let signer =
+            first_signer(ctx.remaining_accounts).ok_or(ProgramError::MissingRequiredSignature)?;
+        prepare_write(counter, &signer)?;
+        let (old, old_value) = (counter.counter, counter.value());
+        counter.set_value(0);
+        typed_event(counter, old_value, CounterEvent::Reset { old }).emit();
+        counter.record_reset();
+        Ok(())
+    }
+
+    pub fn pause(ctx: Context<Authorized>) -> Result<()> {
+        ctx.accounts.counter.load_mut()?.state.paused = 1;
+        CounterEvent::Paused.emit();
+        Ok(())
+    }
+
+    pub fn unpause(ctx: Context<Authorized>) -> Result<()> {
+        ctx.accounts.counter.load_mut()?.state.paused = 0;
+        CounterEvent::Unpaused.emit();
+        Ok(())
+    }
+
+    pub fn close(ctx: Context<Close>) -> Result<()> {
+        CounterEvent::Closed {
+            destination: ctx.accounts.destination.key(),
+            lamports: ctx.accounts.counter.to_account_info().lamports(),
+        }
+        .emit();
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(init, payer = payer, space = Counter::LEN)]
+    pub counter: AccountLoader<'info, Counter>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The signer is the authority, its delegate, or anyone on a counter
+/// without an authority.
+#[derive(Accounts)]
+pub struct Mutate<'info> {
+    #[account(mut)]
+    pub counter: AccountLoader<'info, Counter>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Write<'info> {
+    #[account(mut)]
+    pub counter: AccountLoader<'info, Counter>,
+}
+
+#[derive(Accounts)]
+pub struct Authorized<'info> {
+    #[account(mut, constraint = counter.load()?.state.authority == authority.key() @ CounterError::Unauthorized)]
+    pub counter: AccountLoader<'info, Counter>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Close<'info> {
+    #[account(
+        mut,
+        close = destination,
+        constraint = counter.load()?.state.authority == authority.key() @ CounterError::Unauthorized
+    )]
+    pub counter: AccountLoader<'info, Counter>,
+    /// CHECK: Only receives the counter's lamports.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+/// Keeps the native `Custom` codes, which clients already decode.
+impl From<CounterError> for Error {
+    fn from(e: CounterError) -> Self {
+        ProgramError::from(e).into()
+    }
+}
+
+fn check_unpaused(counter: &CounterAccount) -> Result<()> {
+    if counter.is_paused() {
+        return Err(CounterError::Paused.into());
+    }
+    Ok(())
+}
+
+fn first_signer(accounts: &[AccountInfo]) -> Option<Pubkey> {
+    accounts
+        .iter()
+        .find(|account| account.is_signer)
+        .map(|account| *account.key)
+}
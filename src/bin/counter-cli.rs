@@ -0,0 +1,188 @@
+use std::{error::Error, str::FromStr};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use counter::{client::CounterClient, state::CounterValue, CounterAccount};
+use serde_json::json;
+use solana_cli_config::{Config, CONFIG_FILE};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature},
+    signer::Signer,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "counter-cli",
+    about = "Interact with deployed counter programs"
+)]
+struct Cli {
+    /// Address of the deployed counter program.
+    #[arg(long, env = "COUNTER_PROGRAM_ID")]
+    program_id: Pubkey,
+    /// Solana CLI config file; defaults to ~/.config/solana/cli/config.yml.
+    #[arg(long, global = true)]
+    config: Option<String>,
+    /// RPC URL, overriding the config file.
+    #[arg(long, short = 'u', global = true)]
+    url: Option<String>,
+    /// Signer keypair file, overriding the config file.
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<String>,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Display, global = true)]
+    output: OutputFormat,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Display,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a counter owned by the signer.
+    Init {
+        /// Keypair file for the new counter account; a fresh one is generated by default.
+        #[arg(long)]
+        counter_keypair: Option<String>,
+    },
+    /// Print a counter's state.
+    Get { counter: Pubkey },
+    /// Add `value` to a counter.
+    Increment { counter: Pubkey, value: u32 },
+    /// Subtract `value` from a counter, flooring at zero.
+    Decrement { counter: Pubkey, value: u32 },
+    /// Set a counter to `value`.
+    Update { counter: Pubkey, value: u32 },
+    /// Set a counter to zero.
+    Reset { counter: Pubkey },
+    /// Close a counter, sending its lamports to `destination` (the signer by default).
+    Close {
+        counter: Pubkey,
+        #[arg(long)]
+        destination: Option<Pubkey>,
+    },
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let config = match cli.config.as_ref().or(CONFIG_FILE.as_ref()) {
+        Some(path) => Config::load(path).unwrap_or_default(),
+        None => Config::default(),
+    };
+    let url = cli.url.unwrap_or(config.json_rpc_url);
+    let keypair_path = cli.keypair.unwrap_or(config.keypair_path);
+    let signer = read_keypair_file(&keypair_path)
+        .map_err(|err| format!("failed to read keypair {keypair_path}: {err}"))?;
+    let commitment = CommitmentConfig::from_str(&config.commitment).unwrap_or_default();
+
+    let client = CounterClient::new(
+        RpcClient::new_with_commitment(url, commitment),
+        cli.program_id,
+    );
+
+    match cli.command {
+        Command::Init { counter_keypair } => {
+            let counter = match counter_keypair {
+                Some(path) => read_keypair_file(&path)
+                    .map_err(|err| format!("failed to read keypair {path}: {err}"))?,
+                None => Keypair::new(),
+            };
+            let signature = client.create_counter(&signer, &counter, &signer)?;
+            match cli.output {
+                OutputFormat::Display => {
+                    println!("Counter: {}", counter.pubkey());
+                    println!("Signature: {signature}");
+                }
+                OutputFormat::Json => println!(
+                    "{}",
+                    json!({
+                        "counter": counter.pubkey().to_string(),
+                        "signature": signature.to_string(),
+                    })
+                ),
+            }
+        }
+        Command::Get { counter } => {
+            let account = client.fetch_counter(&counter)?;
+            print_counter(cli.output, &counter, &account);
+        }
+        Command::Increment { counter, value } => {
+            let signature = client.send_increment(&counter, &signer, value)?;
+            print_signature(cli.output, &signature);
+        }
+        Command::Decrement { counter, value } => {
+            let signature = client.send_decrement(&counter, &signer, value)?;
+            print_signature(cli.output, &signature);
+        }
+        Command::Update { counter, value } => {
+            let signature = client.send_update(&counter, &signer, &[&signer], value)?;
+            print_signature(cli.output, &signature);
+        }
+        Command::Reset { counter } => {
+            let signature = client.send_reset(&counter, &signer, &[&signer])?;
+            print_signature(cli.output, &signature);
+        }
+        Command::Close {
+            counter,
+            destination,
+        } => {
+            let destination = destination.unwrap_or_else(|| signer.pubkey());
+            let signature = client.send_close(&counter, &destination, &signer, &[&signer])?;
+            print_signature(cli.output, &signature);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_counter(output: OutputFormat, counter: &Pubkey, account: &CounterAccount) {
+    let value = account.value();
+    match output {
+        OutputFormat::Display => {
+            println!("Counter: {counter}");
+            println!("Value: {value}");
+            println!("Decimals: {}", account.decimals);
+            println!("Authority: {}", account.authority);
+            println!("Paused: {}", account.is_paused());
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            json!({
+                "counter": counter.to_string(),
+                // JSON numbers can't carry every `u128`.
+                "value": match value {
+                    CounterValue::U32(value) => json!(value),
+                    CounterValue::I64(value) => json!(value),
+                    CounterValue::U128(value) => json!(value.to_string()),
+                },
+                "decimals": account.decimals,
+                "authority": account.authority.to_string(),
+                "paused": account.is_paused(),
+            })
+        ),
+    }
+}
+
+fn print_signature(output: OutputFormat, signature: &Signature) {
+    match output {
+        OutputFormat::Display => println!("Signature: {signature}"),
+        OutputFormat::Json => println!("{}", json!({ "signature": signature.to_string() })),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+}
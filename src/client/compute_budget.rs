@@ -0,0 +1,129 @@
+//! Compute budget instructions the clients prepend to their transactions, so
+//! they keep landing while blocks are contended.
+//!
+//! A transaction only pays for the compute units it asks for, so the limit
+//! comes from simulating the transaction first, and the price per unit from
+//! what recently landed transactions paid to write the same accounts.
+
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+
+/// The most compute units a transaction may request, used while simulating.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Headroom added to the simulated units, in basis points, since the state a
+/// transaction lands on may differ from the simulated one.
+pub const COMPUTE_UNIT_MARGIN_BPS: u64 = 1_000;
+
+/// How the client prices and limits the compute of its transactions. The
+/// default leaves transactions as built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// Limits each transaction to the compute units a simulation of it used,
+    /// plus [`COMPUTE_UNIT_MARGIN_BPS`].
+    pub simulate_limit: bool,
+    pub priority_fee: PriorityFee,
+}
+
+/// The price per compute unit, in micro-lamports, a transaction offers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PriorityFee {
+    #[default]
+    None,
+    Fixed(u64),
+    /// This percentile of the fees recent transactions paid per unit to
+    /// write the transaction's writable accounts.
+    Percentile(u8),
+}
+
+/// The compute unit limit for a transaction that used `units_consumed` in
+/// simulation.
+pub fn unit_limit(units_consumed: u64) -> u32 {
+    let margin = units_consumed.saturating_mul(COMPUTE_UNIT_MARGIN_BPS) / 10_000;
+    units_consumed
+        .saturating_add(margin)
+        .min(MAX_COMPUTE_UNIT_LIMIT.into()) as u32
+}
+
+/// The `percentile`th of `fees` by nearest rank, or zero without fees.
+pub fn percentile_fee(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    fees.sort_unstable();
+    let rank = (fees.len() * usize::from(percentile.min(100))).div_ceil(100);
+    fees.get(rank.saturating_sub(1)).copied().unwrap_or(0)
+}
+
+/// The accounts `instructions` write, which priority fee samples are
+/// scoped to.
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = instructions
+        .iter()
+        .flat_map(|instruction| &instruction.accounts)
+        .filter(|meta| meta.is_writable)
+        .map(|meta| meta.pubkey)
+        .collect();
+    accounts.sort_unstable();
+    accounts.dedup();
+    accounts
+}
+
+/// `instructions` after the compute budget instructions for `unit_limit`
+/// and `unit_price`, each left out when `None`.
+pub fn with_compute_budget(
+    instructions: &[Instruction],
+    unit_limit: Option<u32>,
+    unit_price: Option<u64>,
+) -> Vec<Instruction> {
+    unit_limit
+        .map(ComputeBudgetInstruction::set_compute_unit_limit)
+        .into_iter()
+        .chain(unit_price.map(ComputeBudgetInstruction::set_compute_unit_price))
+        .chain(instructions.iter().cloned())
+        .collect()
+}
+
+/// Simulates unsigned transactions against the latest blockhash.
+pub(super) fn simulation_config() -> RpcSimulateTransactionConfig {
+    RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::instruction::AccountMeta;
+    use solana_sdk::compute_budget;
+
+    #[test]
+    fn test_compute_budget() {
+        assert_eq!(unit_limit(20_000), 22_000);
+        assert_eq!(unit_limit(2_000_000), MAX_COMPUTE_UNIT_LIMIT);
+
+        assert_eq!(percentile_fee(vec![], 50), 0);
+        assert_eq!(percentile_fee(vec![40, 10, 30, 20], 50), 20);
+        assert_eq!(percentile_fee(vec![40, 10, 30, 20], 75), 30);
+        assert_eq!(percentile_fee(vec![40, 10, 30, 20], 100), 40);
+        assert_eq!(percentile_fee(vec![40, 10, 30, 20], 0), 10);
+
+        let (counter, signer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![
+                AccountMeta::new(counter, false),
+                AccountMeta::new_readonly(signer, true),
+            ],
+        );
+        let instructions = [instruction.clone(), instruction];
+        assert_eq!(writable_accounts(&instructions), [counter]);
+
+        let budgeted = with_compute_budget(&instructions, Some(22_000), Some(5));
+        assert_eq!(budgeted.len(), 4);
+        assert_eq!(budgeted[0].program_id, compute_budget::id());
+        assert_eq!(budgeted[2..], instructions);
+        assert_eq!(with_compute_budget(&instructions, None, None), instructions);
+    }
+}
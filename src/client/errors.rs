@@ -0,0 +1,114 @@
+//! Counter errors recovered from failed transactions.
+//!
+//! The program fails with `ProgramError::Custom(code)`, which RPC nodes only
+//! report as "custom program error: 0x5". [`decode`] turns that back into the
+//! [`CounterError`], whose `Display` reads "counter is paused".
+
+use num_traits::FromPrimitive;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_sdk::transaction::TransactionError;
+
+use crate::error::CounterError;
+
+/// The counter error `err` failed with. Custom codes from other programs,
+/// called by the transaction or by the counter, are told apart by the logs
+/// when `err` carries them, as preflight failures do; without logs a custom
+/// code is taken to be the counter's.
+pub fn decode(program_id: &Pubkey, err: &ClientError) -> Option<CounterError> {
+    let TransactionError::InstructionError(_, InstructionError::Custom(code)) =
+        err.get_transaction_error()?
+    else {
+        return None;
+    };
+    match logs(err) {
+        Some(logs) => from_logs(program_id, logs).filter(|error| *error as u32 == code),
+        None => CounterError::from_u32(code),
+    }
+}
+
+/// The counter error in `logs`, if the counter is the program that failed.
+/// When a failure unwinds through invocations, the innermost program logs
+/// it first.
+pub fn from_logs(program_id: &Pubkey, logs: &[String]) -> Option<CounterError> {
+    let failure = logs
+        .iter()
+        .find(|log| log.starts_with("Program ") && log.contains(" failed: "))?;
+    let code = failure.strip_prefix(&format!(
+        "Program {program_id} failed: custom program error: 0x"
+    ))?;
+    CounterError::from_u32(u32::from_str_radix(code, 16).ok()?)
+}
+
+fn logs(err: &ClientError) -> Option<&[String]> {
+    match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) => result.logs.as_deref(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_client::rpc_response::RpcSimulateTransactionResult;
+
+    fn preflight_failure(code: u32, logs: Vec<String>) -> ClientError {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: -32002,
+            message: "Transaction simulation failed".to_string(),
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(
+                RpcSimulateTransactionResult {
+                    err: Some(TransactionError::InstructionError(
+                        0,
+                        InstructionError::Custom(code),
+                    )),
+                    logs: Some(logs),
+                    accounts: None,
+                    units_consumed: None,
+                    return_data: None,
+                    inner_instructions: None,
+                },
+            ),
+        })
+        .into()
+    }
+
+    #[test]
+    fn test_decode() {
+        let (program_id, token_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let paused = CounterError::Paused as u32;
+        let err: ClientError =
+            TransactionError::InstructionError(1, InstructionError::Custom(paused)).into();
+        assert_eq!(decode(&program_id, &err), Some(CounterError::Paused));
+        assert_eq!(
+            decode(&program_id, &err).unwrap().to_string(),
+            "counter is paused"
+        );
+
+        let logs = vec![
+            format!("Program {program_id} invoke [1]"),
+            format!("Program {program_id} failed: custom program error: 0x1"),
+        ];
+        let err = preflight_failure(paused, logs);
+        assert_eq!(decode(&program_id, &err), Some(CounterError::Paused));
+
+        // The token program failed inside the counter, with a code of its own.
+        let logs = vec![
+            format!("Program {program_id} invoke [1]"),
+            format!("Program {token_program} invoke [2]"),
+            format!("Program {token_program} failed: custom program error: 0x1"),
+            format!("Program {program_id} failed: custom program error: 0x1"),
+        ];
+        let err = preflight_failure(paused, logs);
+        assert_eq!(decode(&program_id, &err), None);
+
+        let err: ClientError = TransactionError::AccountNotFound.into();
+        assert_eq!(decode(&program_id, &err), None);
+    }
+}
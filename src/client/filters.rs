@@ -0,0 +1,76 @@
+//! `getProgramAccounts` filters over the fixed offsets of
+//! [`CounterAccount`], for finding counters without an indexer.
+//!
+//! Counters initialized before [`COUNTER_DISCRIMINATOR`] was introduced
+//! carry a zero there and are only found through the registry.
+
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::pubkey::Pubkey;
+
+use crate::state::COUNTER_DISCRIMINATOR;
+use crate::CounterAccount;
+
+/// Matches counters.
+pub fn is_counter() -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+        CounterAccount::DISCRIMINATOR_OFFSET,
+        vec![COUNTER_DISCRIMINATOR],
+    ))
+}
+
+/// Matches counters whose authority is `authority`.
+pub fn authority(authority: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+        CounterAccount::AUTHORITY_OFFSET,
+        authority.to_bytes().to_vec(),
+    ))
+}
+
+/// Matches counters of one of the `KIND_*` value types.
+pub fn kind(kind: u8) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+        CounterAccount::KIND_OFFSET,
+        vec![kind],
+    ))
+}
+
+/// All counters owned by `owner`.
+pub fn counters_by_authority(owner: &Pubkey) -> Vec<RpcFilterType> {
+    vec![is_counter(), authority(owner)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::{KIND_I64, KIND_U32};
+    use bytemuck::Zeroable;
+
+    fn matches(filters: &[RpcFilterType], data: &[u8]) -> bool {
+        filters.iter().all(|filter| match filter {
+            RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+            _ => unreachable!(),
+        })
+    }
+
+    #[test]
+    fn test_filters() {
+        let owner = Pubkey::new_unique();
+        let mut counter = CounterAccount::zeroed();
+        counter.authority = owner;
+        counter.kind = KIND_I64;
+        let data = bytemuck::bytes_of(&counter).to_vec();
+        // Until it is initialized, or on a legacy counter, the discriminator
+        // is zero.
+        assert!(!matches(&counters_by_authority(&owner), &data));
+
+        counter.discriminator = COUNTER_DISCRIMINATOR;
+        let data = bytemuck::bytes_of(&counter).to_vec();
+        assert!(matches(&counters_by_authority(&owner), &data));
+        assert!(matches(&[kind(KIND_I64)], &data));
+        assert!(!matches(&[kind(KIND_U32)], &data));
+        assert!(!matches(
+            &counters_by_authority(&Pubkey::new_unique()),
+            &data
+        ));
+    }
+}
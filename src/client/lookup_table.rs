@@ -0,0 +1,155 @@
+//! Address lookup tables and the v0 transactions that use them.
+//!
+//! A legacy transaction spells out every account key, which caps the larger
+//! instructions (payments, NFT claims, fees with history) at a handful per
+//! transaction. A v0 transaction names keys stored in a lookup table by a
+//! one-byte index instead. Tables only resolve from the slot after they were
+//! extended, so build them ahead of the transactions that use them.
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_program::{
+    address_lookup_table::{
+        instruction::extend_lookup_table, state::AddressLookupTable, AddressLookupTableAccount,
+    },
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::state::{
+    find_config_address, find_registry_address, find_treasury_address, find_vault_address,
+    find_vault_authority_address,
+};
+
+/// Addresses added per `ExtendLookupTable`, few enough that each extension
+/// fits a legacy transaction.
+pub const EXTEND_CHUNK_LEN: usize = 20;
+
+/// The accounts instructions on `counter` share: the program, its config and
+/// registry, the counter's treasury and vault authority, and for each
+/// `(mint, token_program)` the counter accepts payment in, the mint, its
+/// program and the counter's vault.
+pub fn counter_addresses(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    mints: &[(Pubkey, Pubkey)],
+) -> Vec<Pubkey> {
+    let mut addresses = vec![
+        *program_id,
+        system_program::id(),
+        find_config_address(program_id).0,
+        find_registry_address(program_id).0,
+        find_treasury_address(program_id, counter).0,
+        find_vault_authority_address(program_id, counter).0,
+    ];
+    for (mint, token_program) in mints {
+        addresses.extend([
+            *mint,
+            *token_program,
+            find_vault_address(program_id, counter, mint, token_program),
+        ]);
+    }
+    let mut seen = Vec::with_capacity(addresses.len());
+    addresses.retain(|address| {
+        let new = !seen.contains(address);
+        seen.push(*address);
+        new
+    });
+    addresses
+}
+
+/// The instructions adding `addresses` to `table`, one per transaction.
+pub fn extend_instructions(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> Vec<Instruction> {
+    addresses
+        .chunks(EXTEND_CHUNK_LEN)
+        .map(|chunk| extend_lookup_table(*table, *authority, Some(*payer), chunk.to_vec()))
+        .collect()
+}
+
+/// Reads the lookup table at `table` from its account data.
+pub fn unpack(table: &Pubkey, data: &[u8]) -> Result<AddressLookupTableAccount, ClientError> {
+    let lookup_table = AddressLookupTable::deserialize(data)
+        .map_err(|err| ClientErrorKind::Custom(err.to_string()))?;
+    Ok(AddressLookupTableAccount {
+        key: *table,
+        addresses: lookup_table.addresses.to_vec(),
+    })
+}
+
+/// Compiles `instructions` into a v0 message paid for by `payer`, naming
+/// every key it can through `lookup_tables`.
+pub fn compile(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedMessage, ClientError> {
+    v0::Message::try_compile(payer, instructions, lookup_tables, blockhash)
+        .map(VersionedMessage::V0)
+        .map_err(|err| ClientErrorKind::Custom(err.to_string()).into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::address_lookup_table::state::LookupTableMeta;
+    use std::borrow::Cow;
+
+    use crate::sdk;
+
+    #[test]
+    fn test_counter_addresses() {
+        let (program_id, counter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = (Pubkey::new_unique(), spl_token::id());
+        let other_mint = (Pubkey::new_unique(), spl_token::id());
+        let addresses = counter_addresses(&program_id, &counter, &[mint, other_mint]);
+        // The token program is listed once for both mints.
+        assert_eq!(addresses.len(), 6 + 2 + 3);
+        assert!(addresses.contains(&find_treasury_address(&program_id, &counter).0));
+
+        let (table, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let addresses: Vec<Pubkey> = (0..45).map(|_| Pubkey::new_unique()).collect();
+        assert_eq!(
+            extend_instructions(&table, &authority, &authority, &addresses).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_compile() {
+        let (program_id, counter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (payer, table) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = AddressLookupTable {
+            meta: LookupTableMeta::default(),
+            addresses: Cow::Owned(counter_addresses(&program_id, &counter, &[])),
+        }
+        .serialize_for_tests()
+        .unwrap();
+        let lookup_table = unpack(&table, &data).unwrap();
+        assert_eq!(lookup_table.addresses.len(), 6);
+        assert!(unpack(&table, &[]).is_err());
+
+        let instruction = sdk::with_fee(
+            sdk::increment(&program_id, &counter, &payer, 1),
+            &counter,
+            &payer,
+        );
+        let VersionedMessage::V0(message) =
+            compile(&payer, &[instruction], &[lookup_table], Hash::default()).unwrap()
+        else {
+            unreachable!();
+        };
+        // The treasury and system program come from the table, but a program
+        // a transaction invokes must be named in its message.
+        assert_eq!(message.address_table_lookups.len(), 1);
+        assert_eq!(message.address_table_lookups[0].writable_indexes, [4]);
+        assert_eq!(message.address_table_lookups[0].readonly_indexes, [1]);
+    }
+}
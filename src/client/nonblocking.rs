@@ -0,0 +1,535 @@
+//! Async counterparts of [`CounterClient`](super::CounterClient) plus
+//! websocket subscriptions to counter changes.
+
+use futures_util::{future, Stream, StreamExt};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    nonblocking::{
+        pubsub_client::{PubsubClient, PubsubClientResult},
+        rpc_client::RpcClient,
+    },
+    rpc_client::SerializableTransaction,
+    rpc_config::RpcAccountInfoConfig,
+    rpc_filter::RpcFilterType,
+};
+use solana_program::{
+    address_lookup_table::{instruction::create_lookup_table, AddressLookupTableAccount},
+    instruction::Instruction,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use super::compute_budget::{
+    percentile_fee, simulation_config, unit_limit, with_compute_budget, writable_accounts,
+    ComputeBudget, PriorityFee, MAX_COMPUTE_UNIT_LIMIT,
+};
+use super::retry::{is_retryable, landed, not_landed, RetryPolicy};
+use super::{filters, lookup_table, scan_config, unpack_counters};
+use crate::state::{
+    find_checkpoint_address, find_history_address, find_registry_address, Checkpoint, HistoryEntry,
+    HistoryHeader, RegistryEntry, RegistryHeader,
+};
+use crate::{sdk, CounterAccount};
+
+pub struct AsyncCounterClient {
+    rpc: RpcClient,
+    program_id: Pubkey,
+    compute_budget: ComputeBudget,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl AsyncCounterClient {
+    pub fn new(rpc: RpcClient, program_id: Pubkey) -> Self {
+        Self {
+            rpc,
+            program_id,
+            compute_budget: ComputeBudget::default(),
+            retry_policy: None,
+        }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    /// Prepends compute budget instructions to every transaction `send`
+    /// builds from now on.
+    pub fn with_compute_budget(mut self, compute_budget: ComputeBudget) -> Self {
+        self.compute_budget = compute_budget;
+        self
+    }
+
+    pub async fn fetch_counter(&self, counter: &Pubkey) -> ClientResult<CounterAccount> {
+        let account = self.rpc.get_account(counter).await?;
+        CounterAccount::unpack(&account.data)
+            .map_err(|err| ClientErrorKind::Custom(err.to_string()).into())
+    }
+
+    /// Fetches the values recorded in `counter`'s history, oldest first.
+    pub async fn fetch_history(&self, counter: &Pubkey) -> ClientResult<Vec<HistoryEntry>> {
+        let (history, _) = find_history_address(&self.program_id, counter);
+        let account = self.rpc.get_account(&history).await?;
+        HistoryHeader::unpack_entries(&account.data)
+            .map_err(|err| ClientErrorKind::Custom(err.to_string()).into())
+    }
+
+    /// Fetches `counter`'s checkpoint for epoch `period_index`.
+    pub async fn fetch_checkpoint(
+        &self,
+        counter: &Pubkey,
+        period_index: u64,
+    ) -> ClientResult<Checkpoint> {
+        let (checkpoint, _) = find_checkpoint_address(&self.program_id, counter, period_index);
+        let account = self.rpc.get_account(&checkpoint).await?;
+        Checkpoint::unpack(&account.data)
+            .map_err(|err| ClientErrorKind::Custom(err.to_string()).into())
+    }
+
+    /// Fetches one page of the program's counter registry using an RPC
+    /// `dataSlice`, so only `page_len` entries are downloaded. Pages past the
+    /// end come back short or empty.
+    pub async fn fetch_registry_page(
+        &self,
+        page: usize,
+        page_len: usize,
+    ) -> ClientResult<Vec<RegistryEntry>> {
+        let range = RegistryHeader::page_range(page, page_len);
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig {
+                offset: range.start,
+                length: range.len(),
+            }),
+            ..RpcAccountInfoConfig::default()
+        };
+        let (registry, _) = find_registry_address(&self.program_id);
+        let account = self
+            .rpc
+            .get_account_with_config(&registry, config)
+            .await?
+            .value
+            .ok_or_else(|| ClientErrorKind::Custom(format!("registry {registry} not found")))?;
+        Ok(RegistryEntry::unpack_slice(&account.data))
+    }
+
+    /// Fetches every counter `authority` owns with their addresses, through a
+    /// `getProgramAccounts` scan filtered by
+    /// [`filters::counters_by_authority`].
+    pub async fn find_counters_by_authority(
+        &self,
+        authority: &Pubkey,
+    ) -> ClientResult<Vec<(Pubkey, CounterAccount)>> {
+        self.find_counters(filters::counters_by_authority(authority))
+            .await
+    }
+
+    /// Fetches every counter of the program with their addresses.
+    pub async fn find_all_counters(&self) -> ClientResult<Vec<(Pubkey, CounterAccount)>> {
+        self.find_counters(vec![filters::is_counter()]).await
+    }
+
+    async fn find_counters(
+        &self,
+        filters: Vec<RpcFilterType>,
+    ) -> ClientResult<Vec<(Pubkey, CounterAccount)>> {
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(&self.program_id, scan_config(filters))
+            .await?;
+        Ok(unpack_counters(accounts))
+    }
+
+    /// `instructions` behind the compute budget instructions the client's
+    /// [`ComputeBudget`] asks for.
+    pub async fn budgeted(
+        &self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+    ) -> ClientResult<Vec<Instruction>> {
+        let unit_price = match self.compute_budget.priority_fee {
+            PriorityFee::None => None,
+            PriorityFee::Fixed(unit_price) => Some(unit_price),
+            PriorityFee::Percentile(percentile) => {
+                let fees = self
+                    .rpc
+                    .get_recent_prioritization_fees(&writable_accounts(instructions))
+                    .await?;
+                let fees = fees.iter().map(|fee| fee.prioritization_fee).collect();
+                Some(percentile_fee(fees, percentile))
+            }
+        };
+        let unit_limit = match self.compute_budget.simulate_limit {
+            false => None,
+            true => {
+                let simulated =
+                    with_compute_budget(instructions, Some(MAX_COMPUTE_UNIT_LIMIT), unit_price);
+                let result = self
+                    .rpc
+                    .simulate_transaction_with_config(
+                        &Transaction::new_with_payer(&simulated, Some(payer)),
+                        simulation_config(),
+                    )
+                    .await?
+                    .value;
+                if let Some(err) = result.err {
+                    return Err(err.into());
+                }
+                result.units_consumed.map(unit_limit)
+            }
+        };
+        Ok(with_compute_budget(instructions, unit_limit, unit_price))
+    }
+
+    /// Resends transactions until they land under `retry_policy`, instead
+    /// of trusting a single send.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Sends `instructions` in one transaction paid for by `payer`, behind
+    /// any compute budget instructions; see [`Self::with_compute_budget`].
+    /// `signers` lists any additional keypairs the instructions require.
+    pub async fn send(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+    ) -> ClientResult<Signature> {
+        let mut all_signers = vec![payer];
+        all_signers.extend(
+            signers
+                .iter()
+                .filter(|signer| signer.pubkey() != payer.pubkey()),
+        );
+
+        let instructions = self.budgeted(instructions, &payer.pubkey()).await?;
+        self.deliver(|blockhash| {
+            Ok(Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&payer.pubkey()),
+                &all_signers,
+                blockhash,
+            ))
+        })
+        .await
+    }
+
+    /// Sends `instructions` like [`Self::send`], but as a v0 transaction
+    /// naming accounts through `lookup_tables`.
+    pub async fn send_v0(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> ClientResult<Signature> {
+        let mut all_signers = vec![payer];
+        all_signers.extend(
+            signers
+                .iter()
+                .filter(|signer| signer.pubkey() != payer.pubkey()),
+        );
+
+        let instructions = self.budgeted(instructions, &payer.pubkey()).await?;
+        self.deliver(|blockhash| {
+            let message =
+                lookup_table::compile(&payer.pubkey(), &instructions, lookup_tables, blockhash)?;
+            Ok(VersionedTransaction::try_new(message, &all_signers)?)
+        })
+        .await
+    }
+
+    /// Sends the transaction `sign` signs with a recent blockhash and waits
+    /// for it to land, following the client's [`RetryPolicy`] if it has one.
+    async fn deliver<T: SerializableTransaction>(
+        &self,
+        sign: impl Fn(Hash) -> ClientResult<T>,
+    ) -> ClientResult<Signature> {
+        let Some(policy) = self.retry_policy else {
+            let blockhash = self.rpc.get_latest_blockhash().await?;
+            return self
+                .rpc
+                .send_and_confirm_transaction(&sign(blockhash)?)
+                .await;
+        };
+        let (blockhash, mut last_valid_block_height) = self
+            .rpc
+            .get_latest_blockhash_with_commitment(policy.commitment)
+            .await?;
+        let mut transaction = sign(blockhash)?;
+        let mut sent = Vec::new();
+        for attempt in 0..policy.max_attempts.max(1) {
+            let signature = *transaction.get_signature();
+            if !sent.contains(&signature) {
+                sent.push(signature);
+            }
+            if let Err(err) = self.rpc.send_transaction(&transaction).await {
+                if !is_retryable(&err) {
+                    return Err(err);
+                }
+            }
+            tokio::time::sleep(policy.backoff(attempt)).await;
+
+            let statuses = self.rpc.get_signature_statuses(&sent).await?.value;
+            let statuses = statuses
+                .into_iter()
+                .map(|status| {
+                    status
+                        .filter(|status| status.satisfies_commitment(policy.commitment))
+                        .map(|status| status.status)
+                })
+                .collect();
+            if let Some(signature) = landed(&sent, statuses)? {
+                return Ok(signature);
+            }
+            // Copies signed with the old blockhash can no longer land.
+            if self.rpc.get_block_height().await? > last_valid_block_height {
+                let (blockhash, last_valid) = self
+                    .rpc
+                    .get_latest_blockhash_with_commitment(policy.commitment)
+                    .await?;
+                last_valid_block_height = last_valid;
+                transaction = sign(blockhash)?;
+            }
+        }
+        Err(not_landed(&sent))
+    }
+
+    /// Creates a lookup table `authority` may extend holding `addresses`,
+    /// e.g. [`lookup_table::counter_addresses`], and returns its address.
+    /// Extensions go in their own transactions after the one creating it.
+    pub async fn create_lookup_table(
+        &self,
+        payer: &Keypair,
+        authority: &Keypair,
+        addresses: &[Pubkey],
+    ) -> ClientResult<Pubkey> {
+        let recent_slot = self.rpc.get_slot().await?;
+        let (instruction, table) =
+            create_lookup_table(authority.pubkey(), payer.pubkey(), recent_slot);
+        self.send(&[instruction], payer, &[authority]).await?;
+        self.extend_lookup_table(payer, authority, &table, addresses)
+            .await?;
+        Ok(table)
+    }
+
+    /// Adds `addresses` to `table`, [`lookup_table::EXTEND_CHUNK_LEN`] per
+    /// transaction.
+    pub async fn extend_lookup_table(
+        &self,
+        payer: &Keypair,
+        authority: &Keypair,
+        table: &Pubkey,
+        addresses: &[Pubkey],
+    ) -> ClientResult<()> {
+        let instructions = lookup_table::extend_instructions(
+            table,
+            &authority.pubkey(),
+            &payer.pubkey(),
+            addresses,
+        );
+        for instruction in instructions {
+            self.send(&[instruction], payer, &[authority]).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn fetch_lookup_table(
+        &self,
+        table: &Pubkey,
+    ) -> ClientResult<AddressLookupTableAccount> {
+        let account = self.rpc.get_account(table).await?;
+        lookup_table::unpack(table, &account.data)
+    }
+
+    /// Allocates a rent-exempt counter account owned by the program and
+    /// initializes it with `authority`.
+    pub async fn create_counter(
+        &self,
+        payer: &Keypair,
+        counter: &Keypair,
+        authority: &Keypair,
+    ) -> ClientResult<Signature> {
+        let lamports = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(CounterAccount::LEN)
+            .await?;
+        let instructions = [
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &counter.pubkey(),
+                lamports,
+                CounterAccount::LEN as u64,
+                &self.program_id,
+            ),
+            sdk::initialize(
+                &self.program_id,
+                &counter.pubkey(),
+                &authority.pubkey(),
+                &payer.pubkey(),
+            ),
+        ];
+        self.send(&instructions, payer, &[counter, authority]).await
+    }
+
+    /// Creates the counter named `name` owned by `authority`, at the address
+    /// given by [`find_counter_address`](crate::state::find_counter_address).
+    pub async fn create_named_counter(
+        &self,
+        payer: &Keypair,
+        authority: &Keypair,
+        name: &str,
+    ) -> ClientResult<Signature> {
+        let instruction =
+            sdk::initialize_named(&self.program_id, &authority.pubkey(), &payer.pubkey(), name);
+        self.send(&[instruction], payer, &[authority]).await
+    }
+
+    pub async fn send_increment(
+        &self,
+        counter: &Pubkey,
+        signer: &Keypair,
+        value: u32,
+    ) -> ClientResult<Signature> {
+        let instruction = sdk::increment(&self.program_id, counter, &signer.pubkey(), value);
+        self.send(&[instruction], signer, &[]).await
+    }
+
+    pub async fn send_decrement(
+        &self,
+        counter: &Pubkey,
+        signer: &Keypair,
+        value: u32,
+    ) -> ClientResult<Signature> {
+        let instruction = sdk::decrement(&self.program_id, counter, &signer.pubkey(), value);
+        self.send(&[instruction], signer, &[]).await
+    }
+
+    pub async fn send_update(
+        &self,
+        counter: &Pubkey,
+        payer: &Keypair,
+        signers: &[&Keypair],
+        value: u32,
+    ) -> ClientResult<Signature> {
+        let signer_keys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let signer_refs: Vec<&Pubkey> = signer_keys.iter().collect();
+        let instruction = sdk::update(&self.program_id, counter, &signer_refs, value);
+        self.send(&[instruction], payer, signers).await
+    }
+
+    pub async fn send_reset(
+        &self,
+        counter: &Pubkey,
+        payer: &Keypair,
+        signers: &[&Keypair],
+    ) -> ClientResult<Signature> {
+        let signer_keys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let signer_refs: Vec<&Pubkey> = signer_keys.iter().collect();
+        let instruction = sdk::reset(&self.program_id, counter, &signer_refs);
+        self.send(&[instruction], payer, signers).await
+    }
+
+    pub async fn send_close(
+        &self,
+        counter: &Pubkey,
+        destination: &Pubkey,
+        payer: &Keypair,
+        signers: &[&Keypair],
+    ) -> ClientResult<Signature> {
+        let signer_keys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let signer_refs: Vec<&Pubkey> = signer_keys.iter().collect();
+        let instruction = sdk::close(&self.program_id, counter, destination, &signer_refs);
+        self.send(&[instruction], payer, signers).await
+    }
+}
+
+/// Streams decoded counter state from a websocket `accountSubscribe`.
+pub struct CounterSubscriber {
+    pubsub: PubsubClient,
+}
+
+impl CounterSubscriber {
+    pub fn new(pubsub: PubsubClient) -> Self {
+        Self { pubsub }
+    }
+
+    pub async fn connect(ws_url: &str) -> PubsubClientResult<Self> {
+        Ok(Self::new(PubsubClient::new(ws_url).await?))
+    }
+
+    /// Yields the counter state after every change. Updates that no longer
+    /// decode as a counter, such as the account being closed, are skipped.
+    pub async fn subscribe_counter(
+        &self,
+        counter: &Pubkey,
+    ) -> PubsubClientResult<impl Stream<Item = CounterAccount> + '_> {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        };
+        let (stream, _unsubscribe) = self.pubsub.account_subscribe(counter, Some(config)).await?;
+        Ok(stream.filter_map(|response| {
+            future::ready(
+                response
+                    .value
+                    .data
+                    .decode()
+                    .and_then(|data| CounterAccount::unpack(&data).ok()),
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_increment() {
+        let client = AsyncCounterClient::new(
+            RpcClient::new_mock("succeeds".to_string()),
+            Pubkey::new_unique(),
+        );
+        let signer = Keypair::new();
+
+        let signature = client
+            .send_increment(&Pubkey::new_unique(), &signer, 1)
+            .await
+            .unwrap();
+        assert_ne!(signature, Signature::default());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_missing_counter() {
+        let client = AsyncCounterClient::new(
+            RpcClient::new_mock("succeeds".to_string()),
+            Pubkey::new_unique(),
+        );
+
+        assert!(client.fetch_counter(&Pubkey::new_unique()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_all_counters() {
+        let client = AsyncCounterClient::new(
+            RpcClient::new_mock("succeeds".to_string()),
+            Pubkey::new_unique(),
+        );
+
+        // The mock answers with a single empty account, which is no counter.
+        assert_eq!(client.find_all_counters().await.unwrap(), []);
+    }
+}
@@ -0,0 +1,125 @@
+//! Delivery of transactions through dropped packets, congestion and expired
+//! blockhashes.
+//!
+//! With a [`RetryPolicy`], the clients resend a transaction with exponential
+//! backoff until it lands at the policy's commitment, re-signing it with a
+//! fresh blockhash once its own expires. Every signature sent is tracked, so
+//! a copy that lands late is recognized rather than sent again.
+
+use std::time::Duration;
+
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::Signature,
+    transaction::{self, TransactionError},
+};
+
+/// How the clients deliver transactions. Without one, `send` makes a single
+/// `send_and_confirm_transaction` call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Sends before giving up, counting the first.
+    pub max_attempts: u32,
+    /// Wait after the first send before checking on it; doubled after each
+    /// further send.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Commitment a transaction must reach to count as landed.
+    pub commitment: CommitmentConfig,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            commitment: CommitmentConfig::confirmed(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait after send number `attempt`, counting from zero.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// Whether sending again may succeed where sending failed with `err`: the
+/// network failed, the blockhash was not yet or no longer known, or the
+/// transaction already landed. A transaction that failed preflight would
+/// only fail again.
+pub fn is_retryable(err: &ClientError) -> bool {
+    match err.get_transaction_error() {
+        Some(TransactionError::BlockhashNotFound | TransactionError::AlreadyProcessed) => true,
+        Some(_) => false,
+        None => matches!(
+            err.kind(),
+            ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) | ClientErrorKind::RpcError(_)
+        ),
+    }
+}
+
+/// The signature among `sent` that landed, given their statuses at the
+/// policy's commitment, or the error its transaction failed with.
+pub fn landed(
+    sent: &[Signature],
+    statuses: Vec<Option<transaction::Result<()>>>,
+) -> ClientResult<Option<Signature>> {
+    for (signature, status) in sent.iter().zip(statuses) {
+        match status {
+            Some(Ok(())) => return Ok(Some(*signature)),
+            Some(Err(err)) => return Err(err.into()),
+            None => {}
+        }
+    }
+    Ok(None)
+}
+
+pub(super) fn not_landed(sent: &[Signature]) -> ClientError {
+    ClientErrorKind::Custom(format!(
+        "transaction not confirmed after {} signatures: {sent:?}",
+        sent.len()
+    ))
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::instruction::InstructionError;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(9), policy.max_backoff);
+        assert_eq!(policy.backoff(u32::MAX), policy.max_backoff);
+    }
+
+    #[test]
+    fn test_landed() {
+        let sent = [Signature::new_unique(), Signature::new_unique()];
+        assert_eq!(landed(&sent, vec![None, None]).unwrap(), None);
+        assert_eq!(
+            landed(&sent, vec![None, Some(Ok(()))]).unwrap(),
+            Some(sent[1])
+        );
+        let failed = Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(1),
+        ));
+        let err = landed(&sent, vec![Some(failed), None]).unwrap_err();
+        assert!(!is_retryable(&err));
+
+        assert!(is_retryable(&TransactionError::BlockhashNotFound.into()));
+        assert!(!is_retryable(
+            &ClientErrorKind::Custom("no".to_string()).into()
+        ));
+    }
+}
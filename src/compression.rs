@@ -0,0 +1,131 @@
+//! Just enough of SPL Account Compression to keep counters as leaves of a
+//! concurrent Merkle tree, without depending on the compression crates.
+//!
+//! The compression program owns the tree and checks every proof; this
+//! program only signs as the tree's authority. A leaf commits to a counter's
+//! authority and value, so a valid proof of the previous leaf proves the
+//! value a mutation starts from. The compression program logs each change
+//! through SPL Noop, and counter events carry the values, from which
+//! indexers rebuild every counter off-chain.
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    keccak, pubkey,
+    pubkey::Pubkey,
+};
+
+/// The SPL Account Compression program, owner of the trees.
+pub const PROGRAM_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+/// The SPL Noop program the compression program logs changes through.
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AFQJkchtXWfRhyoRrLSV");
+
+/// Anchor discriminators, `sha256("global:<name>")[..8]`, of the
+/// instructions used here.
+pub const INIT_EMPTY_MERKLE_TREE: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+pub const APPEND: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+pub const REPLACE_LEAF: [u8; 8] = [204, 165, 76, 100, 73, 147, 0, 128];
+
+/// The leaf of a compressed counter: Keccak-256 of its authority and
+/// little-endian value.
+pub fn leaf(authority: &Pubkey, value: u32) -> [u8; 32] {
+    keccak::hashv(&[authority.as_ref(), &value.to_le_bytes()]).to_bytes()
+}
+
+fn accounts(tree: &Pubkey, authority: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*tree, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+    ]
+}
+
+/// Sets up `tree`, allocated by the caller for `max_depth` and
+/// `max_buffer_size`, with `authority` as the only key that may change it.
+pub fn init_empty_merkle_tree(
+    tree: &Pubkey,
+    authority: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts(tree, authority),
+        data: [
+            &INIT_EMPTY_MERKLE_TREE[..],
+            &max_depth.to_le_bytes(),
+            &max_buffer_size.to_le_bytes(),
+        ]
+        .concat(),
+    }
+}
+
+/// Appends `leaf` at the tree's next index.
+pub fn append(tree: &Pubkey, authority: &Pubkey, leaf: &[u8; 32]) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts(tree, authority),
+        data: [&APPEND[..], leaf].concat(),
+    }
+}
+
+/// Replaces `previous_leaf` at `index` with `new_leaf`, proven against
+/// `root` by the `proof` nodes, passed as account keys from the leaf up.
+pub fn replace_leaf(
+    tree: &Pubkey,
+    authority: &Pubkey,
+    root: &[u8; 32],
+    previous_leaf: &[u8; 32],
+    new_leaf: &[u8; 32],
+    index: u32,
+    proof: &[Pubkey],
+) -> Instruction {
+    let mut accounts = accounts(tree, authority);
+    accounts.extend(
+        proof
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false)),
+    );
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts,
+        data: [
+            &REPLACE_LEAF[..],
+            root,
+            previous_leaf,
+            new_leaf,
+            &index.to_le_bytes(),
+        ]
+        .concat(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_replace_leaf() {
+        let (tree, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let proof = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let instruction = replace_leaf(
+            &tree,
+            &authority,
+            &[1; 32],
+            &leaf(&authority, 4),
+            &leaf(&authority, 5),
+            3,
+            &proof,
+        );
+        assert_eq!(instruction.program_id, PROGRAM_ID);
+        assert_eq!(instruction.data.len(), 8 + 3 * 32 + 4);
+        assert_eq!(instruction.data[..8], REPLACE_LEAF);
+        assert_eq!(instruction.data[8 + 3 * 32..], 3u32.to_le_bytes());
+        assert_eq!(instruction.accounts.len(), 5);
+        assert!(instruction.accounts[1].is_signer);
+        assert_eq!(instruction.accounts[4].pubkey, proof[1]);
+        // The value is part of the leaf.
+        assert_ne!(leaf(&authority, 4), leaf(&authority, 5));
+        assert_ne!(leaf(&authority, 4), leaf(&tree, 4));
+    }
+}
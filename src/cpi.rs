@@ -0,0 +1,173 @@
+//! Helpers for programs that drive the counter over CPI.
+//!
+//! Each helper builds the instruction with [`crate::sdk`] from the account
+//! infos in a [`CpiContext`] and invokes it, signing with the context's
+//! seeds when a PDA of the caller is the signer.
+
+use std::slice;
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program::{get_return_data, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::sdk;
+use crate::state::{CounterStats, CounterValue};
+
+/// The counter program, the accounts an instruction takes, and the seeds of
+/// the caller's PDAs signing it.
+pub struct CpiContext<'a, 'info, T> {
+    pub program: AccountInfo<'info>,
+    pub accounts: T,
+    pub signer_seeds: &'a [&'a [&'a [u8]]],
+}
+
+impl<'a, 'info, T> CpiContext<'a, 'info, T> {
+    pub fn new(program: AccountInfo<'info>, accounts: T) -> Self {
+        Self {
+            program,
+            accounts,
+            signer_seeds: &[],
+        }
+    }
+
+    pub fn new_with_signer(
+        program: AccountInfo<'info>,
+        accounts: T,
+        signer_seeds: &'a [&'a [&'a [u8]]],
+    ) -> Self {
+        Self {
+            program,
+            accounts,
+            signer_seeds,
+        }
+    }
+
+    fn invoke(&self, instruction: Instruction, accounts: &[AccountInfo<'info>]) -> ProgramResult {
+        let mut account_infos = accounts.to_vec();
+        account_infos.push(self.program.clone());
+        invoke_signed(&instruction, &account_infos, self.signer_seeds)
+    }
+}
+
+/// Accounts for Increment and Decrement. `signer` is the authority or a
+/// delegate; counters without an authority accept any signer.
+pub struct Mutate<'info> {
+    pub counter: AccountInfo<'info>,
+    pub signer: AccountInfo<'info>,
+}
+
+/// Accounts for Update and Reset: the authority, or the multisig members
+/// signing the call.
+pub struct Write<'info> {
+    pub counter: AccountInfo<'info>,
+    pub signers: Vec<AccountInfo<'info>>,
+}
+
+/// Accounts for Pause and Unpause.
+pub struct Authorized<'info> {
+    pub counter: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+/// Accounts for Get and GetStats.
+pub struct Read<'info> {
+    pub counter: AccountInfo<'info>,
+}
+
+/// See [`sdk::increment`].
+pub fn increment<'info>(ctx: CpiContext<'_, 'info, Mutate<'info>>, value: u32) -> ProgramResult {
+    let Mutate { counter, signer } = &ctx.accounts;
+    let instruction = sdk::increment(ctx.program.key, counter.key, signer.key, value);
+    ctx.invoke(instruction, &[counter.clone(), signer.clone()])
+}
+
+/// See [`sdk::increment_with_mode`].
+pub fn increment_with_mode<'info>(
+    ctx: CpiContext<'_, 'info, Mutate<'info>>,
+    value: u32,
+    mode: u8,
+) -> ProgramResult {
+    let Mutate { counter, signer } = &ctx.accounts;
+    let instruction =
+        sdk::increment_with_mode(ctx.program.key, counter.key, signer.key, value, mode);
+    ctx.invoke(instruction, &[counter.clone(), signer.clone()])
+}
+
+/// See [`sdk::decrement`].
+pub fn decrement<'info>(ctx: CpiContext<'_, 'info, Mutate<'info>>, value: u32) -> ProgramResult {
+    let Mutate { counter, signer } = &ctx.accounts;
+    let instruction = sdk::decrement(ctx.program.key, counter.key, signer.key, value);
+    ctx.invoke(instruction, &[counter.clone(), signer.clone()])
+}
+
+/// See [`sdk::decrement_with_mode`].
+pub fn decrement_with_mode<'info>(
+    ctx: CpiContext<'_, 'info, Mutate<'info>>,
+    value: u32,
+    mode: u8,
+) -> ProgramResult {
+    let Mutate { counter, signer } = &ctx.accounts;
+    let instruction =
+        sdk::decrement_with_mode(ctx.program.key, counter.key, signer.key, value, mode);
+    ctx.invoke(instruction, &[counter.clone(), signer.clone()])
+}
+
+pub fn update<'info>(ctx: CpiContext<'_, 'info, Write<'info>>, value: u32) -> ProgramResult {
+    let Write { counter, signers } = &ctx.accounts;
+    let signer_keys: Vec<&Pubkey> = signers.iter().map(|signer| signer.key).collect();
+    let instruction = sdk::update(ctx.program.key, counter.key, &signer_keys, value);
+    ctx.invoke(instruction, &[slice::from_ref(counter), signers].concat())
+}
+
+pub fn reset<'info>(ctx: CpiContext<'_, 'info, Write<'info>>) -> ProgramResult {
+    let Write { counter, signers } = &ctx.accounts;
+    let signer_keys: Vec<&Pubkey> = signers.iter().map(|signer| signer.key).collect();
+    let instruction = sdk::reset(ctx.program.key, counter.key, &signer_keys);
+    ctx.invoke(instruction, &[slice::from_ref(counter), signers].concat())
+}
+
+pub fn pause<'info>(ctx: CpiContext<'_, 'info, Authorized<'info>>) -> ProgramResult {
+    let Authorized { counter, authority } = &ctx.accounts;
+    let instruction = sdk::pause(ctx.program.key, counter.key, authority.key);
+    ctx.invoke(instruction, &[counter.clone(), authority.clone()])
+}
+
+pub fn unpause<'info>(ctx: CpiContext<'_, 'info, Authorized<'info>>) -> ProgramResult {
+    let Authorized { counter, authority } = &ctx.accounts;
+    let instruction = sdk::unpause(ctx.program.key, counter.key, authority.key);
+    ctx.invoke(instruction, &[counter.clone(), authority.clone()])
+}
+
+/// The counter's value, as the next write would start from it.
+pub fn get<'info>(ctx: CpiContext<'_, 'info, Read<'info>>) -> Result<CounterValue, ProgramError> {
+    let counter = &ctx.accounts.counter;
+    ctx.invoke(
+        sdk::get(ctx.program.key, counter.key),
+        slice::from_ref(counter),
+    )?;
+    CounterValue::from_le_bytes(&return_data(&ctx)?).ok_or(ProgramError::InvalidAccountData)
+}
+
+pub fn get_stats<'info>(
+    ctx: CpiContext<'_, 'info, Read<'info>>,
+) -> Result<CounterStats, ProgramError> {
+    let counter = &ctx.accounts.counter;
+    ctx.invoke(
+        sdk::get_stats(ctx.program.key, counter.key),
+        slice::from_ref(counter),
+    )?;
+    CounterStats::unpack(&return_data(&ctx)?)
+}
+
+/// The data the counter program just returned.
+fn return_data<T>(ctx: &CpiContext<T>) -> Result<Vec<u8>, ProgramError> {
+    match get_return_data() {
+        Some((program_id, data)) if program_id == *ctx.program.key => Ok(data),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
@@ -0,0 +1,102 @@
+//! Just enough of the Ed25519 program's instruction format to build one and
+//! to read what it verified.
+//!
+//! The Ed25519 program is a precompile: the runtime checks its signatures
+//! before the transaction runs, so a program only has to find the
+//! instruction through the Instructions sysvar and read what was signed.
+//! Offsets may point into other instructions; only instructions carrying
+//! their own public key, signature and message are read here.
+
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+pub use solana_program::ed25519_program::ID;
+
+pub const PUBKEY_LEN: usize = 32;
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Signature count and a padding byte.
+const HEADER_LEN: usize = 2;
+/// Seven little-endian `u16`s per signature.
+const OFFSETS_LEN: usize = 14;
+/// Instruction index meaning the Ed25519 instruction itself.
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// An Ed25519 program instruction verifying `signature` by `pubkey` over
+/// `message`, laid out as the Solana SDK does: offsets, public key,
+/// signature, message.
+pub fn new_instruction(
+    pubkey: &Pubkey,
+    signature: &[u8; SIGNATURE_LEN],
+    message: &[u8],
+) -> Instruction {
+    let pubkey_offset = HEADER_LEN + OFFSETS_LEN;
+    let signature_offset = pubkey_offset + PUBKEY_LEN;
+    let message_offset = signature_offset + SIGNATURE_LEN;
+    let offsets = [
+        signature_offset as u16,
+        CURRENT_INSTRUCTION,
+        pubkey_offset as u16,
+        CURRENT_INSTRUCTION,
+        message_offset as u16,
+        message.len() as u16,
+        CURRENT_INSTRUCTION,
+    ];
+
+    let mut data = Vec::with_capacity(message_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for offset in offsets {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+/// The public key and message of an Ed25519 instruction's data verifying a
+/// single signature, or `None` unless everything it verified is in `data`.
+pub fn signed_message(data: &[u8]) -> Option<(Pubkey, &[u8])> {
+    if data.first() != Some(&1) {
+        return None;
+    }
+    let offsets = data.get(HEADER_LEN..HEADER_LEN + OFFSETS_LEN)?;
+    let offset = |index: usize| u16::from_le_bytes([offsets[2 * index], offsets[2 * index + 1]]);
+    if [offset(1), offset(3), offset(6)] != [CURRENT_INSTRUCTION; 3] {
+        return None;
+    }
+    let pubkey_offset = usize::from(offset(2));
+    let pubkey = data.get(pubkey_offset..pubkey_offset + PUBKEY_LEN)?;
+    let message_offset = usize::from(offset(4));
+    let message = data.get(message_offset..message_offset + usize::from(offset(5)))?;
+    Some((Pubkey::try_from(pubkey).ok()?, message))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signed_message() {
+        let pubkey = Pubkey::new_unique();
+        let instruction = new_instruction(&pubkey, &[7; SIGNATURE_LEN], b"counter");
+        assert_eq!(instruction.program_id, ID);
+        assert_eq!(
+            signed_message(&instruction.data),
+            Some((pubkey, &b"counter"[..]))
+        );
+
+        // A message in another instruction isn't ours to read.
+        let mut data = instruction.data.clone();
+        data[HEADER_LEN + 12..HEADER_LEN + 14].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(signed_message(&data), None);
+        // Nor is any signature after the first.
+        let mut data = instruction.data;
+        data[0] = 2;
+        assert_eq!(signed_message(&data), None);
+        assert_eq!(signed_message(&[]), None);
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt;
+
+use num_derive::FromPrimitive;
+use solana_program::{decode_error::DecodeError, program_error::ProgramError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum CounterError {
+    /// The signer does not match the counter's authority.
+    Unauthorized,
+    /// The counter is paused and rejects mutations.
+    Paused,
+    /// The multisig threshold or signer set is out of range.
+    InvalidMultisigConfig,
+    /// Fewer multisig signers than the threshold signed the instruction.
+    NotEnoughSigners,
+    /// The delegate has used up its approved operations.
+    AllowanceExceeded,
+    /// The operation would overflow the counter.
+    Overflow,
+    /// The counter account does not hold enough lamports to be rent-exempt.
+    NotRentExempt,
+    /// The counter already took `max_ops_per_slot` mutations this slot.
+    RateLimited,
+    /// The caller's increments in the current 24h window would exceed the
+    /// counter's daily cap.
+    DailyCapExceeded,
+    /// The account passed as the counter's treasury is not its treasury PDA.
+    InvalidTreasury,
+    /// The mint or vault passed for a payment is not the counter's payment
+    /// mint or its vault.
+    InvalidVault,
+    /// The caller did not show a token account holding an NFT of the
+    /// counter's gate collection.
+    NftRequired,
+    /// The mint passed for a milestone reward is not the counter's reward
+    /// mint.
+    InvalidRewardMint,
+    /// The counter has not reached its milestone NFT target.
+    MilestoneNotReached,
+    /// The milestone NFT for the current target was already claimed.
+    MilestoneNftClaimed,
+    /// The price account is not a fully verified Pyth update for the
+    /// counter's feed.
+    InvalidPriceFeed,
+    /// The oracle price is older than the counter accepts.
+    StalePrice,
+    /// The oracle price's confidence interval is wider than the counter
+    /// accepts.
+    PriceTooUncertain,
+    /// The randomness account is not a Switchboard account committed to in
+    /// the previous slot, or not the one the pending request used.
+    InvalidRandomness,
+    /// Switchboard has not revealed the randomness in the current slot.
+    RandomnessNotRevealed,
+    /// A random increment is already waiting to be settled.
+    RandomRequestPending,
+    /// There is no random increment waiting to be settled.
+    NoRandomRequest,
+    /// The signer has no allow-list entry for the counter.
+    NotAllowlisted,
+    /// The signer is banned from the counter.
+    CallerBanned,
+    /// The preceding instruction is not the off-chain signer's Ed25519
+    /// signature over the counter and its current nonce.
+    InvalidOffchainSignature,
+    /// The preceding instruction is not the Ethereum authority's secp256k1
+    /// signature over the counter and its current nonce.
+    InvalidEthSignature,
+    /// The VAA wasn't posted by the Core Bridge from the configured emitter,
+    /// or its payload isn't an operation on this counter.
+    InvalidVaa,
+    /// The VAA was already applied.
+    VaaAlreadyConsumed,
+    /// Update and Reset need the governance's native treasury as a signer.
+    GovernanceRequired,
+    /// The counter has no scheduled reset due.
+    CrankNotDue,
+    /// The counter holds less than the amount to move out of it.
+    InsufficientValue,
+    /// The counter was renounced and can no longer change.
+    Renounced,
+    /// The counter is frozen and keeps its value until thawed.
+    Frozen,
+    /// The counter expired and only awaits being reaped.
+    Expired,
+    /// The counter has neither expired nor been abandoned.
+    NotExpired,
+    /// The operation would take the counter below zero.
+    Underflow,
+    /// The instruction only works on `u32` counters.
+    UnsupportedKind,
+    /// A counter's kind is set once, before its first write and before any
+    /// feature that only `u32` counters support.
+    KindLocked,
+    /// The counter's value must be zero first.
+    NonZeroValue,
+    /// The amount isn't a multiple of the counter's step.
+    InvalidStep,
+    /// The program config is paused, and with it every governed counter.
+    ProgramPaused,
+    /// The index is past the end of the counter array.
+    IndexOutOfBounds,
+    /// Every entry of the keyed counter account is in use.
+    KeyedFull,
+    /// The keyed counter account has no entry for the key.
+    KeyNotFound,
+    /// The transaction has no SPL Memo instruction, which the counter
+    /// requires of every mutation.
+    MemoRequired,
+    /// An Increment named its own signer as referrer.
+    SelfReferral,
+    /// The stake account a stake-weighted Increment passed isn't delegated,
+    /// or its delegation is still activating or already deactivating.
+    StakeNotActive,
+    /// `UnlockBoost` came before the escrow's unlock time.
+    BoostStillLocked,
+    /// The counter is under a running lease, whose terms can't change and
+    /// which nobody else can take.
+    LeaseActive,
+    /// `TakeLease` on a counter that isn't listed for lease.
+    NotForLease,
+    /// The signer of a mutation in credit mode has no credits left.
+    NoCredits,
+    /// `BuyCredits` on a counter that isn't in credit mode.
+    CreditsDisabled,
+    /// The signer's last mutation was less than the counter's cooldown ago.
+    CooldownActive,
+    /// The instruction data starts with a tag no instruction has.
+    UnknownInstruction,
+    /// An instruction that writes the counter got it read-only.
+    CounterNotWritable,
+    /// `IncrementIfBelow` on a counter already at or above the threshold.
+    ThresholdReached,
+    /// `ShiftLeft` or `ShiftRight` by 32 bits or more.
+    InvalidShift,
+    /// A write other than a decrement to a countdown counter.
+    CountdownOnly,
+    /// A write to a countdown counter that has reached zero.
+    CountdownCompleted,
+    /// Initialize on an account too short for the current layout, which
+    /// `MigrateAccount` has to grow first.
+    MigrationRequired,
+}
+
+impl fmt::Display for CounterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Unauthorized => "signer is not the counter's authority",
+            Self::Paused => "counter is paused",
+            Self::InvalidMultisigConfig => "multisig threshold or signers out of range",
+            Self::NotEnoughSigners => "not enough multisig signers",
+            Self::AllowanceExceeded => "delegate allowance used up",
+            Self::Overflow => "counter would overflow",
+            Self::NotRentExempt => "counter account is not rent-exempt",
+            Self::RateLimited => "counter rate limit reached for this slot",
+            Self::DailyCapExceeded => "daily cap exceeded",
+            Self::InvalidTreasury => "account is not the counter's treasury",
+            Self::InvalidVault => "mint or vault is not the counter's payment mint or vault",
+            Self::NftRequired => "an NFT of the gate collection is required",
+            Self::InvalidRewardMint => "mint is not the counter's reward mint",
+            Self::MilestoneNotReached => "milestone NFT target not reached",
+            Self::MilestoneNftClaimed => "milestone NFT already claimed",
+            Self::InvalidPriceFeed => {
+                "price account is not a verified update for the counter's feed"
+            }
+            Self::StalePrice => "oracle price is too old",
+            Self::PriceTooUncertain => "oracle price is too uncertain",
+            Self::InvalidRandomness => "invalid randomness account",
+            Self::RandomnessNotRevealed => "randomness not revealed yet",
+            Self::RandomRequestPending => "a random increment is already pending",
+            Self::NoRandomRequest => "no random increment is pending",
+            Self::NotAllowlisted => "signer is not allow-listed",
+            Self::CallerBanned => "signer is banned",
+            Self::InvalidOffchainSignature => "missing or invalid off-chain signature",
+            Self::InvalidEthSignature => "missing or invalid Ethereum signature",
+            Self::InvalidVaa => "VAA is not from the configured emitter or not for this counter",
+            Self::VaaAlreadyConsumed => "VAA already applied",
+            Self::GovernanceRequired => "governance treasury must sign",
+            Self::CrankNotDue => "no scheduled reset is due",
+            Self::InsufficientValue => "counter holds less than the amount",
+            Self::Renounced => "counter is renounced",
+            Self::Frozen => "counter is frozen",
+            Self::Expired => "counter has expired",
+            Self::NotExpired => "counter has not expired",
+            Self::Underflow => "counter would go below zero",
+            Self::UnsupportedKind => "instruction only works on u32 counters",
+            Self::KindLocked => "counter kind can no longer change",
+            Self::NonZeroValue => "counter value must be zero",
+            Self::InvalidStep => "amount is not a multiple of the counter's step",
+            Self::ProgramPaused => "program is paused",
+            Self::IndexOutOfBounds => "index is past the end of the counter array",
+            Self::KeyedFull => "keyed counter account is full",
+            Self::KeyNotFound => "key not found",
+            Self::MemoRequired => "transaction needs a memo",
+            Self::SelfReferral => "signer cannot refer itself",
+            Self::StakeNotActive => "stake account has no active delegation",
+            Self::BoostStillLocked => "boost escrow is still locked",
+            Self::LeaseActive => "counter is under an active lease",
+            Self::NotForLease => "counter is not listed for lease",
+            Self::NoCredits => "signer has no operation credits left",
+            Self::CreditsDisabled => "counter does not sell operation credits",
+            Self::CooldownActive => "signer is still cooling down",
+            Self::UnknownInstruction => "unknown instruction tag",
+            Self::CounterNotWritable => "counter account is not writable",
+            Self::ThresholdReached => "counter is not below the threshold",
+            Self::InvalidShift => "shift amount must be below 32 bits",
+            Self::CountdownOnly => "countdown counter only decrements",
+            Self::CountdownCompleted => "countdown has completed",
+            Self::MigrationRequired => "counter account is too short, run MigrateAccount first",
+        })
+    }
+}
+
+impl From<CounterError> for ProgramError {
+    fn from(e: CounterError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for CounterError {
+    fn type_of() -> &'static str {
+        "CounterError"
+    }
+}
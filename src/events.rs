@@ -0,0 +1,465 @@
+//! Events emitted with `sol_log_data` on every state change.
+//!
+//! Each event is logged as a single Borsh-encoded field, which the runtime
+//! prints as `Program data: <base64>`. Decode it with
+//! `CounterEvent::try_from_slice` after base64-decoding the log line.
+//!
+//! With the `kv-logs` feature, every value write also logs a plain
+//! [`kv_log_line`], which log scrapers can filter on and parse without the
+//! Borsh schema.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+use crate::state::CounterValue;
+
+/// Starts every [`kv_log_line`], after the runtime's `Program log: `.
+pub const KV_LOG_PREFIX: &str = "counter:";
+
+/// The line logged for `writer`'s `op` on `counter`, taking its value from
+/// `old` to `new`:
+///
+/// ```text
+/// counter: program=<id> counter=<pubkey> op=increment old=1 new=3 writer=<pubkey>
+/// ```
+///
+/// `op` is the instruction's snake_case name. Keys keep this order and new
+/// ones only go at the end.
+pub fn kv_log_line(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    op: &str,
+    old: CounterValue,
+    new: CounterValue,
+    writer: &Pubkey,
+) -> String {
+    format!(
+        "{KV_LOG_PREFIX} program={program_id} counter={counter} op={op} old={old} new={new} \
+         writer={writer}"
+    )
+}
+
+// Variants are append-only: the Borsh tag is the variant's position, and
+// indexers decode old transactions with new builds.
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CounterEvent {
+    Incremented {
+        old: u32,
+        new: u32,
+        by: u32,
+    },
+    Decremented {
+        old: u32,
+        new: u32,
+        by: u32,
+    },
+    Updated {
+        old: u32,
+        new: u32,
+    },
+    Reset {
+        old: u32,
+    },
+    Initialized {
+        authority: Pubkey,
+    },
+    Paused,
+    Unpaused,
+    AuthorityProposed {
+        new_authority: Pubkey,
+    },
+    AuthorityAccepted {
+        old_authority: Pubkey,
+        new_authority: Pubkey,
+    },
+    Closed {
+        destination: Pubkey,
+        lamports: u64,
+    },
+    MultisigConfigured {
+        threshold: u8,
+        signer_count: u8,
+    },
+    DelegateApproved {
+        delegate: Pubkey,
+        allowance: u64,
+    },
+    DelegateRevoked,
+    Reallocated {
+        old_size: u64,
+        new_size: u64,
+    },
+    EpochScopeSet {
+        enabled: bool,
+    },
+    /// An epoch-scoped counter was zeroed by its first write in `epoch`.
+    EpochReset {
+        old: u32,
+        epoch: u64,
+    },
+    DecayConfigured {
+        per_slot: u64,
+    },
+    /// Decay since the last update was applied ahead of a write.
+    Decayed {
+        old: u32,
+        new: u32,
+    },
+    RateLimitConfigured {
+        max_ops_per_slot: u32,
+    },
+    DailyCapConfigured {
+        cap: u64,
+    },
+    FeeConfigured {
+        lamports: u64,
+    },
+    TreasuryWithdrawn {
+        destination: Pubkey,
+        lamports: u64,
+    },
+    PaymentConfigured {
+        mint: Pubkey,
+        price: u64,
+    },
+    VaultWithdrawn {
+        destination: Pubkey,
+        amount: u64,
+    },
+    NftGateConfigured {
+        collection: Pubkey,
+    },
+    MilestoneRewardConfigured {
+        mint: Pubkey,
+        every: u32,
+        amount: u64,
+    },
+    MilestoneRewarded {
+        milestone: u32,
+        destination: Pubkey,
+        amount: u64,
+    },
+    MilestoneNftConfigured {
+        target: u32,
+    },
+    MilestoneNftClaimed {
+        claimer: Pubkey,
+        mint: Pubkey,
+    },
+    OracleConfigured {
+        feed_id: [u8; 32],
+    },
+    OracleUpdated {
+        old: u32,
+        new: u32,
+        publish_time: i64,
+    },
+    RandomIncrementRequested {
+        randomness: Pubkey,
+        max: u32,
+    },
+    AllowlistSet {
+        enabled: bool,
+    },
+    AllowlistAdded {
+        member: Pubkey,
+    },
+    AllowlistRemoved {
+        member: Pubkey,
+    },
+    BlocklistSet {
+        enabled: bool,
+    },
+    Banned {
+        caller: Pubkey,
+    },
+    Unbanned {
+        caller: Pubkey,
+    },
+    OffchainSignerSet {
+        signer: Pubkey,
+    },
+    EthAuthoritySet {
+        eth_address: [u8; 20],
+    },
+    WormholeConfigured {
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+    },
+    WormholeMessageReceived {
+        emitter_chain: u16,
+        sequence: u64,
+    },
+    GovernanceSet {
+        governance: Pubkey,
+        native_treasury: Pubkey,
+    },
+    CrankConfigured {
+        every_slots: u64,
+        tip_lamports: u64,
+    },
+    Cranked {
+        cranker: Pubkey,
+        tip: u64,
+    },
+    HistoryInitialized {
+        capacity: u32,
+    },
+    Checkpointed {
+        period_index: u64,
+        value: u32,
+    },
+    Merged {
+        source: Pubkey,
+        value: u32,
+    },
+    Split {
+        new_counter: Pubkey,
+        amount: u32,
+    },
+    Transferred {
+        destination: Pubkey,
+        amount: u32,
+    },
+    Cloned {
+        new_counter: Pubkey,
+    },
+    AuthoritySet {
+        old_authority: Pubkey,
+        /// `None` when the counter was renounced.
+        new_authority: Option<Pubkey>,
+    },
+    Frozen,
+    Thawed,
+    ExpirySet {
+        expires_at_slot: u64,
+        recipient: Pubkey,
+    },
+    Reaped {
+        recipient: Pubkey,
+        lamports: u64,
+        reaper: Pubkey,
+        reaper_lamports: u64,
+    },
+    KindSet {
+        kind: u8,
+    },
+    /// Any write to a signed counter.
+    SignedChanged {
+        old: i64,
+        new: i64,
+    },
+    /// Any write to a wide counter.
+    WideChanged {
+        old: u128,
+        new: u128,
+    },
+    DecimalsSet {
+        decimals: u8,
+    },
+    StepSet {
+        step: u32,
+    },
+    ConfigInitialized {
+        super_admin: Pubkey,
+        default_fee_lamports: u64,
+    },
+    ConfigUpdated {
+        super_admin: Pubkey,
+        default_fee_lamports: u64,
+        paused: bool,
+    },
+    ArrayInitialized {
+        len: u16,
+    },
+    ArrayValueChanged {
+        index: u16,
+        old: u32,
+        new: u32,
+    },
+    KeyedInitialized {
+        capacity: u32,
+    },
+    /// `old` is zero for a new entry and `new` zero for a removed one.
+    KeyedValueChanged {
+        key: [u8; 32],
+        old: u64,
+        new: u64,
+    },
+    KeyedResized {
+        capacity: u32,
+    },
+    CompressedTreeInitialized {
+        tree: Pubkey,
+        max_depth: u32,
+        max_buffer_size: u32,
+    },
+    /// A compressed counter starting at zero was appended to `tree`.
+    CompressedCreated {
+        tree: Pubkey,
+        authority: Pubkey,
+    },
+    CompressedChanged {
+        tree: Pubkey,
+        index: u32,
+        authority: Pubkey,
+        old: u32,
+        new: u32,
+    },
+    /// `MigrateAccount` brought a counter from layout `from` to `to`.
+    Migrated {
+        from: u8,
+        to: u8,
+    },
+    MemoRequiredSet {
+        enabled: bool,
+    },
+    ReferralsSet {
+        enabled: bool,
+        share_bps: u16,
+    },
+    /// An Increment credited `referrer` with `value`, paying it
+    /// `fee_lamports` of the fee.
+    Referred {
+        referrer: Pubkey,
+        value: u32,
+        fee_lamports: u64,
+    },
+    ReferralFeesWithdrawn {
+        referrer: Pubkey,
+        destination: Pubkey,
+        lamports: u64,
+    },
+    LeaderboardInitialized {
+        authority: Pubkey,
+        capacity: u32,
+    },
+    /// `rank` counts from zero; `None` when the counter didn't make the board.
+    ScoreSubmitted {
+        leaderboard: Pubkey,
+        score: u64,
+        rank: Option<u32>,
+    },
+    StakeWeightedSet {
+        enabled: bool,
+    },
+    BoostSet {
+        factor: u8,
+        min_sol: u16,
+    },
+    /// `owner`'s escrow now holds `locked_lamports` until `unlocks_at`.
+    BoostLocked {
+        owner: Pubkey,
+        locked_lamports: u64,
+        unlocks_at: i64,
+    },
+    BoostUnlocked {
+        owner: Pubkey,
+        lamports: u64,
+    },
+    ListedForLease {
+        price_lamports: u64,
+        duration: u64,
+    },
+    Leased {
+        lessee: Pubkey,
+        expires_at: i64,
+    },
+    /// The lessee, or the authority refunding `refund_lamports` to them,
+    /// ended the lease early.
+    LeaseEnded {
+        lessee: Pubkey,
+        refund_lamports: u64,
+    },
+    Delisted,
+    CreditPriceSet {
+        lamports: u32,
+    },
+    /// `buyer` bought `count` credits and now holds `credits`.
+    CreditsBought {
+        buyer: Pubkey,
+        count: u64,
+        credits: u64,
+    },
+    CooldownSet {
+        cooldown: u32,
+        unit: u8,
+    },
+    ModulusSet {
+        modulus: u32,
+    },
+    /// `SetCountdown` took the counter from `old` to `target`, or left it at
+    /// `old` for a target of zero.
+    CountdownSet {
+        old: u32,
+        target: u32,
+    },
+    /// A decrement brought a countdown counter to zero.
+    CountdownCompleted,
+}
+
+impl CounterEvent {
+    pub fn emit(&self) {
+        // Serializing into a `Vec` cannot fail.
+        if let Ok(data) = borsh::to_vec(self) {
+            sol_log_data(&[&data]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_kv_log_line() {
+        let (program_id, counter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let line = kv_log_line(
+            &program_id,
+            &counter,
+            "increment",
+            CounterValue::U32(1),
+            CounterValue::U32(3),
+            &Pubkey::default(),
+        );
+        let fields: Vec<(&str, &str)> = line
+            .strip_prefix(KV_LOG_PREFIX)
+            .unwrap()
+            .split_whitespace()
+            .map(|field| field.split_once('=').unwrap())
+            .collect();
+        assert_eq!(fields[0], ("program", program_id.to_string().as_str()));
+        assert_eq!(fields[1], ("counter", counter.to_string().as_str()));
+        assert_eq!(
+            fields[2..5],
+            [("op", "increment"), ("old", "1"), ("new", "3")]
+        );
+        assert_eq!(fields[5], ("writer", "11111111111111111111111111111111"));
+    }
+
+    #[test]
+    fn test_event_round_trip() {
+        let event = CounterEvent::Incremented {
+            old: 1,
+            new: 3,
+            by: 2,
+        };
+        let data = borsh::to_vec(&event).unwrap();
+        assert_eq!(data, [0, 1, 0, 0, 0, 3, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(CounterEvent::try_from_slice(&data).unwrap(), event);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_event_json() {
+        let event = CounterEvent::Incremented {
+            old: 1,
+            new: 3,
+            by: 2,
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"Incremented":{"old":1,"new":3,"by":2}}"#);
+        assert_eq!(serde_json::from_str::<CounterEvent>(&json).unwrap(), event);
+    }
+}
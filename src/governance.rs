@@ -0,0 +1,27 @@
+//! Just enough of SPL Governance (Realms) to recognize a governance's native
+//! treasury, without depending on the governance crates.
+//!
+//! When a proposal passes, its governance executes the proposal's
+//! instructions with the native treasury, a PDA of the governance program,
+//! as a signer. A signature from that PDA therefore means a passed proposal.
+
+use solana_program::{pubkey, pubkey::Pubkey};
+
+/// The SPL Governance program Realms deploys. DAOs running their own
+/// instance pass that program instead.
+pub const PROGRAM_ID: Pubkey = pubkey!("GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw");
+
+/// Prefix seed of native treasury PDAs, followed by the governance.
+pub const NATIVE_TREASURY_SEED: &[u8] = b"native-treasury";
+
+/// Address of the native treasury of `governance`, an account of
+/// `governance_program`.
+pub fn find_native_treasury_address(
+    governance_program: &Pubkey,
+    governance: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[NATIVE_TREASURY_SEED, governance.as_ref()],
+        governance_program,
+    )
+}
@@ -1,40 +1,2302 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::program_error::ProgramError;
+use shank::ShankInstruction;
+use solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::error::CounterError;
+use crate::state::{OVERFLOW_CHECKED, OVERFLOW_SATURATING};
 
 #[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IncrementArgs {
     pub value: u32,
+    /// One of the `OVERFLOW_*` policies in [`crate::state`].
+    pub mode: u8,
 }
 
 #[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DecrementArgs {
     pub value: u32,
+    /// One of the `OVERFLOW_*` policies in [`crate::state`].
+    pub mode: u8,
 }
 
 #[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdateArgs {
     pub value: u32,
 }
 
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProposeAuthorityArgs {
+    pub new_authority: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigureMultisigArgs {
+    pub threshold: u8,
+    pub signers: Vec<Pubkey>,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ApproveArgs {
+    pub delegate: Pubkey,
+    pub allowance: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReallocArgs {
+    pub new_size: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitializeNamedArgs {
+    pub name: String,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetEpochScopedArgs {
+    pub enabled: bool,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetDecayArgs {
+    pub per_slot: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetRateLimitArgs {
+    pub max_ops_per_slot: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetDailyCapArgs {
+    pub cap: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetFeeArgs {
+    pub lamports: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigurePaymentArgs {
+    pub mint: Pubkey,
+    pub price: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawVaultArgs {
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetNftGateArgs {
+    pub collection: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigureMilestoneRewardArgs {
+    pub mint: Pubkey,
+    pub every: u32,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigureMilestoneNftArgs {
+    pub target: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClaimMilestoneNftArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigureOracleArgs {
+    pub feed_id: [u8; 32],
+    pub max_age_seconds: u32,
+    pub max_confidence_bps: u16,
+    pub exponent: i32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestRandomIncrementArgs {
+    pub max: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetAllowlistEnabledArgs {
+    pub enabled: bool,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AddToAllowlistArgs {
+    pub member: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RemoveFromAllowlistArgs {
+    pub member: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetMemoRequiredArgs {
+    pub enabled: bool,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetReferralsArgs {
+    pub enabled: bool,
+    /// Basis points of the fee referred increments pay their referrer, at
+    /// most 10,000.
+    pub share_bps: u16,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitializeLeaderboardArgs {
+    pub capacity: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetStakeWeightedArgs {
+    pub enabled: bool,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetBoostArgs {
+    /// Multiplier of boosted Increments; zero or one turns boosting off.
+    pub factor: u8,
+    /// Whole SOL an escrow must lock to boost.
+    pub min_sol: u16,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockBoostArgs {
+    pub lamports: u64,
+    /// Seconds from now until the lamports can be unlocked.
+    pub duration: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListForLeaseArgs {
+    pub price_lamports: u64,
+    /// Seconds a lease runs.
+    pub duration: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetCreditPriceArgs {
+    /// Lamports per credit; zero turns credit mode off.
+    pub lamports: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BuyCreditsArgs {
+    pub count: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetCooldownArgs {
+    /// Slots or seconds between a caller's mutations; zero disables the
+    /// cooldown.
+    pub cooldown: u32,
+    /// [`crate::state::COOLDOWN_SLOTS`] or [`crate::state::COOLDOWN_SECONDS`].
+    pub unit: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetBlocklistEnabledArgs {
+    pub enabled: bool,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetOffchainSignerArgs {
+    pub signer: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetEthAuthorityArgs {
+    pub eth_address: [u8; 20],
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigureWormholeArgs {
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetGovernanceArgs {
+    pub governance_program: Pubkey,
+    pub governance: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigureCrankArgs {
+    pub every_slots: u64,
+    pub tip_lamports: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitializeHistoryArgs {
+    pub capacity: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitArgs {
+    pub amount: u32,
+    /// Name of the new counter; at most 32 bytes.
+    pub name: String,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferArgs {
+    pub amount: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CloneArgs {
+    /// Name of the new counter; at most 32 bytes.
+    pub name: String,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetAuthorityArgs {
+    pub new_authority: Option<Pubkey>,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetExpiryArgs {
+    /// Zero removes the expiry.
+    pub expires_at_slot: u64,
+    pub recipient: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetKindArgs {
+    pub kind: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncrementWideArgs {
+    pub amount: u64,
+    /// One of the `OVERFLOW_*` policies in [`crate::state`].
+    pub mode: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecrementWideArgs {
+    pub amount: u64,
+    /// One of the `OVERFLOW_*` policies in [`crate::state`].
+    pub mode: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetDecimalsArgs {
+    pub decimals: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncrementScaledArgs {
+    pub amount: u64,
+    /// Fractional digits of `amount`.
+    pub decimals: u8,
+    /// One of the `ROUND_*` rules in [`crate::state`].
+    pub rounding: u8,
+    /// One of the `OVERFLOW_*` policies in [`crate::state`].
+    pub mode: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecrementScaledArgs {
+    pub amount: u64,
+    /// Fractional digits of `amount`.
+    pub decimals: u8,
+    /// One of the `ROUND_*` rules in [`crate::state`].
+    pub rounding: u8,
+    /// One of the `OVERFLOW_*` policies in [`crate::state`].
+    pub mode: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetStepArgs {
+    pub step: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitializeConfigArgs {
+    pub super_admin: Pubkey,
+    pub default_fee_lamports: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpdateConfigArgs {
+    pub super_admin: Pubkey,
+    pub default_fee_lamports: u64,
+    pub paused: bool,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitializeArrayArgs {
+    pub len: u16,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncrementAtArgs {
+    pub index: u16,
+    pub value: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecrementAtArgs {
+    pub index: u16,
+    pub value: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpdateAtArgs {
+    pub index: u16,
+    pub value: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitializeKeyedArgs {
+    pub capacity: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncrementKeyedArgs {
+    pub key: [u8; 32],
+    pub value: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecrementKeyedArgs {
+    pub key: [u8; 32],
+    pub value: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RemoveKeyedArgs {
+    pub key: [u8; 32],
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResizeKeyedArgs {
+    pub capacity: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitializeCompressedTreeArgs {
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+}
+
+/// `value` is the compressed counter's current value, which the proof
+/// against `root` confirms.
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncrementCompressedArgs {
+    pub root: [u8; 32],
+    pub index: u32,
+    pub value: u32,
+    pub by: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecrementCompressedArgs {
+    pub root: [u8; 32],
+    pub index: u32,
+    pub value: u32,
+    pub by: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BanCallerArgs {
+    pub caller: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnbanCallerArgs {
+    pub caller: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncrementIfBelowArgs {
+    pub value: u32,
+    /// The increment applies only while the value is below this.
+    pub threshold: u32,
+    /// One of the `OVERFLOW_*` policies in [`crate::state`].
+    pub mode: u8,
+    /// Succeed without incrementing at or above `threshold` rather than fail
+    /// with `ThresholdReached`.
+    pub skip: bool,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AndArgs {
+    pub value: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrArgs {
+    pub value: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct XorArgs {
+    pub value: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShiftLeftArgs {
+    /// Below 32, or the instruction fails with `InvalidShift`.
+    pub bits: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShiftRightArgs {
+    /// Below 32, or the instruction fails with `InvalidShift`.
+    pub bits: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetModulusArgs {
+    pub modulus: u32,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetCountdownArgs {
+    /// The value to count down from, or zero to leave countdown mode.
+    pub target: u32,
+}
+
+// Variant order must match the tags in `unpack`: shank derives each
+// instruction's discriminant from its position in the enum.
+#[derive(ShankInstruction)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CounterInstructions {
+    /// Adds `value` to the counter, failing, saturating or wrapping on overflow as `mode` says.
+    /// Without the `mode` byte it fails.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
     Increment(IncrementArgs),
+    /// Subtracts `value` from the counter, failing, flooring at zero or wrapping as `mode` says.
+    /// Without the `mode` byte it floors at zero.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
     Decrement(DecrementArgs),
+    /// Sets the counter to `value`. Remaining accounts are multisig signers when one is configured.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
     Update(UpdateArgs),
+    /// Sets the counter to zero. Remaining accounts are multisig signers when one is configured.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
     Reset,
+    /// Assigns the signer as authority of an uninitialized counter and appends it to the
+    /// program's registry, creating the registry on first use. Once the program config exists,
+    /// the counter starts with its default fee and is governed by it.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "New authority")]
+    #[account(
+        2,
+        writable,
+        name = "registry",
+        desc = "Registry PDA, seeds [\"registry\"]"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the registry entry"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(
+        5,
+        name = "config",
+        desc = "Config PDA, seeds [\"config\"], whether or not it exists"
+    )]
+    Initialize,
+    /// Rejects all counter mutations until unpaused.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    Pause,
+    /// Lifts a previous `Pause`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    Unpause,
+    /// Records `new_authority` as pending until it signs `AcceptAuthority`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    ProposeAuthority(ProposeAuthorityArgs),
+    /// Completes a pending authority transfer.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "new_authority", desc = "Pending authority")]
+    AcceptAuthority,
+    /// Zeroes the counter and moves its lamports to `destination`. The authority signs unless a
+    /// multisig is configured, in which case the remaining accounts are multisig signers.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        writable,
+        name = "destination",
+        desc = "Receives the counter's lamports"
+    )]
+    #[account(
+        2,
+        optional_signer,
+        name = "authority",
+        desc = "Counter authority when no multisig is configured"
+    )]
+    Close,
+    /// Requires `threshold` of `signers` on Reset, Update and Close; a zero threshold disables it.
+    /// Remaining accounts are multisig signers when one is already configured.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    ConfigureMultisig(ConfigureMultisigArgs),
+    /// Lets `delegate` perform up to `allowance` Increment/Decrement operations.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    Approve(ApproveArgs),
+    /// Removes the approved delegate.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    Revoke,
+    /// Returns the counter value as a little-endian `u32` through the transaction's return data.
+    #[account(0, name = "counter", desc = "Counter account")]
+    Get,
+    /// Resizes the counter account to `new_size` bytes, topping up rent from `payer`. The size
+    /// may not drop below the current state layout.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(2, writable, signer, name = "payer", desc = "Pays any additional rent")]
+    #[account(3, name = "system_program", desc = "System program")]
+    Realloc(ReallocArgs),
+    /// Creates the counter at the PDA `["counter", authority, name]` with the signer as
+    /// authority and appends it to the registry, applying the program config as `Initialize`
+    /// does. `name` is at most 32 bytes.
+    #[account(
+        0,
+        writable,
+        name = "counter",
+        desc = "Counter PDA, seeds [\"counter\", authority, name]"
+    )]
+    #[account(1, signer, name = "authority", desc = "New authority")]
+    #[account(
+        2,
+        writable,
+        name = "registry",
+        desc = "Registry PDA, seeds [\"registry\"]"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the counter and registry entry"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(
+        5,
+        name = "config",
+        desc = "Config PDA, seeds [\"config\"], whether or not it exists"
+    )]
+    InitializeNamed(InitializeNamedArgs),
+    /// Makes the counter start from zero at the first write of every epoch while `enabled`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetEpochScoped(SetEpochScopedArgs),
+    /// Makes the counter lose `per_slot` units for every slot between writes, applied ahead of
+    /// each mutation. Zero disables decay.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetDecay(SetDecayArgs),
+    /// Allows at most `max_ops_per_slot` Increment/Decrement/Update/Reset per slot. Zero
+    /// disables the limit.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetRateLimit(SetRateLimitArgs),
+    /// Creates the caller's quota account for a counter with a daily cap, at the PDA
+    /// `["quota", counter, caller]`.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        signer,
+        name = "caller",
+        desc = "Pubkey whose increments the quota tracks"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "quota",
+        desc = "Quota PDA, seeds [\"quota\", counter, caller]"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the quota account"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    CreateQuota,
+    /// Limits each caller to incrementing by `cap` in total per 24 hours, tracked in their quota
+    /// account. Zero removes the cap.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetDailyCap(SetDailyCapArgs),
+    /// Charges `lamports` into the counter's treasury on every Increment/Decrement/Update/Reset,
+    /// paid by a payer passed after the instruction's own accounts together with the treasury
+    /// and the system program. Zero removes the fee. Funds the treasury to rent exemption.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "treasury",
+        desc = "Treasury PDA, seeds [\"treasury\", counter]"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Funds the treasury's rent exemption"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    SetFee(SetFeeArgs),
+    /// Moves the treasury's lamports above its rent-exempt minimum to `destination`.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "treasury",
+        desc = "Treasury PDA, seeds [\"treasury\", counter]"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "destination",
+        desc = "Receives the withdrawn lamports"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    WithdrawTreasury,
+    /// Makes every Increment pay `price` tokens of `mint` into the counter's vault. The caller's
+    /// token account, the mint, the vault and the token program go after the Increment's own
+    /// accounts and before any fee accounts. A zero price removes the payment. `mint` may belong
+    /// to spl-token or Token-2022; with a Token-2022 transfer fee the caller pays the fee on top.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    ConfigurePayment(ConfigurePaymentArgs),
+    /// Creates the counter's vault for `mint`, the associated token account of the vault
+    /// authority PDA. Succeeds if the vault already exists.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        name = "vault_authority",
+        desc = "Vault authority PDA, seeds [\"vault\", counter]"
+    )]
+    #[account(2, writable, name = "vault", desc = "Vault associated token account")]
+    #[account(3, name = "mint", desc = "Payment mint")]
+    #[account(4, writable, signer, name = "payer", desc = "Pays rent for the vault")]
+    #[account(5, name = "system_program", desc = "System program")]
+    #[account(6, name = "token_program", desc = "Token program owning the mint")]
+    #[account(
+        7,
+        name = "associated_token_program",
+        desc = "Associated Token Account program"
+    )]
+    InitializeVault,
+    /// Transfers `amount` tokens from one of the counter's vaults to `destination`.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        name = "vault_authority",
+        desc = "Vault authority PDA, seeds [\"vault\", counter]"
+    )]
+    #[account(3, writable, name = "vault", desc = "Vault token account")]
+    #[account(4, name = "mint", desc = "Vault mint")]
+    #[account(5, writable, name = "destination", desc = "Receives the tokens")]
+    #[account(6, name = "token_program", desc = "Token program owning the mint")]
+    WithdrawVault(WithdrawVaultArgs),
+    /// Only holders of an NFT in `collection` may Increment or Decrement; the default pubkey
+    /// removes the gate. Gated calls must be signed, and put the caller's token account and the
+    /// NFT's Metaplex metadata account after the instruction's own accounts, before any payment
+    /// or fee accounts.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetNftGate(SetNftGateArgs),
+    /// Mints `amount` tokens of `mint` to a token account of the caller's choosing for every
+    /// multiple of `every` an Increment takes the counter past for the first time; zero `every`
+    /// stops the rewards. The mint authority must be the reward authority PDA. While rewards are
+    /// on, every Increment passes the destination token account, the mint, the reward authority
+    /// and the token program after its own accounts, before any NFT gate, payment or fee
+    /// accounts. Milestones the counter has already passed are never rewarded.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    ConfigureMilestoneReward(ConfigureMilestoneRewardArgs),
+    /// Lets the first caller to see the counter at or above `target` claim a one-off NFT with
+    /// `ClaimMilestoneNft`. Setting a target again allows one more claim; zero turns it off.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    ConfigureMilestoneNft(ConfigureMilestoneNftArgs),
+    /// Mints a one-off NFT to the claimer once the counter has reached its milestone NFT target.
+    /// The program creates `mint`, the claimer's token account, and the Token Metadata metadata
+    /// and master edition, with the NFT authority PDA as update authority. Each target can be
+    /// claimed once.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        writable,
+        signer,
+        name = "claimer",
+        desc = "Receives the NFT and pays for it"
+    )]
+    #[account(2, writable, signer, name = "mint", desc = "New NFT mint")]
+    #[account(
+        3,
+        writable,
+        name = "token_account",
+        desc = "Claimer's associated token account"
+    )]
+    #[account(
+        4,
+        writable,
+        name = "metadata",
+        desc = "Token Metadata PDA of the mint"
+    )]
+    #[account(
+        5,
+        writable,
+        name = "master_edition",
+        desc = "Master edition PDA of the mint"
+    )]
+    #[account(
+        6,
+        name = "nft_authority",
+        desc = "NFT authority PDA, seeds [\"nft\", counter]"
+    )]
+    #[account(7, name = "system_program", desc = "System program")]
+    #[account(8, name = "token_program", desc = "SPL Token program")]
+    #[account(
+        9,
+        name = "associated_token_program",
+        desc = "Associated Token Account program"
+    )]
+    #[account(
+        10,
+        name = "token_metadata_program",
+        desc = "Metaplex Token Metadata program"
+    )]
+    ClaimMilestoneNft(ClaimMilestoneNftArgs),
+    /// Points `UpdateFromOracle` at the Pyth price feed `feed_id`. Prices older than
+    /// `max_age_seconds`, or with a confidence interval wider than `max_confidence_bps` of the
+    /// price, are rejected. The counter stores prices in units of `10^exponent`. An all-zero
+    /// feed id turns the oracle off.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    ConfigureOracle(ConfigureOracleArgs),
+    /// Sets the counter to the latest price of its Pyth feed, scaled to its oracle exponent.
+    /// Anyone may call it.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, name = "price_update", desc = "Pyth receiver PriceUpdateV2 account")]
+    UpdateFromOracle,
+    /// Starts a random increment of 1 to `max`, paid for by the Switchboard On-Demand randomness
+    /// account the caller committed to in the previous slot. The signer is checked as for
+    /// Increment. Only one request can be pending at a time.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        signer,
+        name = "signer",
+        desc = "Authority or delegate; unused for unowned counters"
+    )]
+    #[account(
+        2,
+        name = "randomness",
+        desc = "Switchboard On-Demand randomness account"
+    )]
+    RequestRandomIncrement(RequestRandomIncrementArgs),
+    /// Applies the pending random increment once Switchboard has revealed the value, in the slot
+    /// it was revealed. Anyone may call it.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, name = "randomness", desc = "The request's randomness account")]
+    SettleRandomIncrement,
+    /// Turns allow-list mode on or off. While it is on, only signers with an allow-list entry
+    /// may Increment, Decrement, Update or Reset. They pass their entry after the instruction's
+    /// own accounts and any NFT gate and payment accounts, before any fee accounts.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetAllowlistEnabled(SetAllowlistEnabledArgs),
+    /// Creates the allow-list entry PDA for `member`.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "entry",
+        desc = "Allow-list entry PDA, seeds [\"allowlist\", counter, member]"
+    )]
+    #[account(3, writable, signer, name = "payer", desc = "Pays rent for the entry")]
+    #[account(4, name = "system_program", desc = "System program")]
+    AddToAllowlist(AddToAllowlistArgs),
+    /// Closes the allow-list entry PDA for `member`, refunding its rent to `destination`.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "entry",
+        desc = "Allow-list entry PDA, seeds [\"allowlist\", counter, member]"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "destination",
+        desc = "Receives the entry's lamports"
+    )]
+    RemoveFromAllowlist(RemoveFromAllowlistArgs),
+    /// Turns the block-list on or off. While it is on, Increment, Decrement, Update and Reset
+    /// must be signed and pass the signer's ban entry address, whether or not the entry exists,
+    /// after any allow-list entry and before any fee accounts. Banned signers are rejected.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetBlocklistEnabled(SetBlocklistEnabledArgs),
+    /// Creates the ban entry PDA for `caller`.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "entry",
+        desc = "Ban entry PDA, seeds [\"ban\", counter, caller]"
+    )]
+    #[account(3, writable, signer, name = "payer", desc = "Pays rent for the entry")]
+    #[account(4, name = "system_program", desc = "System program")]
+    BanCaller(BanCallerArgs),
+    /// Closes the ban entry PDA for `caller`, refunding its rent to `destination`.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "entry",
+        desc = "Ban entry PDA, seeds [\"ban\", counter, caller]"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "destination",
+        desc = "Receives the entry's lamports"
+    )]
+    UnbanCaller(UnbanCallerArgs),
+    /// Requires every Increment to be authorized by `signer`, an off-chain key; the default pubkey
+    /// turns this off. The instruction before the Increment must be an Ed25519 program
+    /// instruction in which `signer` signed the counter's address followed by its current
+    /// `offchain_nonce`, and the Increment puts the Instructions sysvar after its own accounts,
+    /// before any milestone reward, NFT gate, payment or fee accounts.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetOffchainSigner(SetOffchainSignerArgs),
+    /// Hands Increment, Decrement, Update and Reset to the Ethereum key `eth_address`; all zeroes
+    /// hands them back. Each such call must come right after a secp256k1 program instruction in
+    /// which that key signed `eth_message` for the counter and its current `eth_nonce`, and puts
+    /// the Instructions sysvar after the instruction's own accounts, before any allow-list, ban
+    /// or fee accounts. The Ethereum signature stands in for the authority's or delegate's.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetEthAuthority(SetEthAuthorityArgs),
+    /// Lets the Wormhole emitter `emitter_address` on chain `emitter_chain` drive the counter
+    /// through ReceiveWormholeMessage; chain 0 turns this off.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    ConfigureWormhole(ConfigureWormholeArgs),
+    /// Applies the counter operation in a VAA the Core Bridge posted from the configured emitter,
+    /// and records the VAA as consumed so it can't be applied again.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, name = "posted_vaa", desc = "Core Bridge PostedVAA account")]
+    #[account(
+        2,
+        writable,
+        name = "received",
+        desc = "Consumed-VAA PDA, seeds [\"received\", counter, vaa_hash]"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the consumed-VAA PDA"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    ReceiveWormholeMessage,
+    /// Puts Update and Reset under `governance`, an SPL Governance account of
+    /// `governance_program`: they then need its native treasury among their signers, which only
+    /// a passed proposal provides. The default governance lifts this.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetGovernance(SetGovernanceArgs),
+    /// Schedules a reset every `every_slots` slots, counted from now, for anyone to perform with
+    /// Crank; zero removes the schedule. Each crank tips its caller up to `tip_lamports` from the
+    /// treasury.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    ConfigureCrank(ConfigureCrankArgs),
+    /// Resets the counter once its scheduled reset is due. Anyone may call it; the tip is however
+    /// much of it the treasury holds above its rent-exempt minimum.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, writable, signer, name = "cranker", desc = "Receives the tip")]
+    #[account(
+        2,
+        writable,
+        name = "treasury",
+        desc = "Treasury PDA, seeds [\"treasury\", counter]"
+    )]
+    #[account(3, name = "system_program", desc = "System program")]
+    Crank,
+    /// Returns the counter's [`CounterStats`](crate::state::CounterStats) through the
+    /// transaction's return data, in its in-memory layout.
+    #[account(0, name = "counter", desc = "Counter account")]
+    GetStats,
+    /// Creates the counter's history PDA holding the last `capacity` values it was written to.
+    /// Once it exists, every write must pass it as the last account.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "history",
+        desc = "History PDA, seeds [\"history\", counter]"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the history"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    InitializeHistory(InitializeHistoryArgs),
+    /// Snapshots the counter's current value into the checkpoint PDA for the current epoch. Anyone
+    /// may call it, once per epoch.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        writable,
+        name = "checkpoint",
+        desc = "Checkpoint PDA, seeds [\"checkpoint\", counter, epoch as u64 LE]"
+    )]
+    #[account(
+        2,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the checkpoint"
+    )]
+    #[account(3, name = "system_program", desc = "System program")]
+    Checkpoint,
+    /// Adds `source`'s value into the counter, then closes `source` and moves its lamports to
+    /// `destination`. Both counters must have the signing authority; remaining accounts are
+    /// multisig signers of whichever has one configured.
+    #[account(0, writable, name = "counter", desc = "Counter merged into")]
+    #[account(1, writable, name = "source", desc = "Counter merged from and closed")]
+    #[account(2, signer, name = "authority", desc = "Authority of both counters")]
+    #[account(
+        3,
+        writable,
+        name = "destination",
+        desc = "Receives the source's lamports"
+    )]
+    Merge,
+    /// Moves `amount` out of the counter into a new named counter with the same authority,
+    /// created at the PDA for `name` and appended to the registry. Remaining accounts are multisig
+    /// signers when one is configured.
+    #[account(0, writable, name = "counter", desc = "Counter split from")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "new_counter",
+        desc = "Counter PDA, seeds [\"counter\", authority, name]"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "registry",
+        desc = "Registry PDA, seeds [\"registry\"]"
+    )]
+    #[account(
+        4,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the new counter and registry entry"
+    )]
+    #[account(5, name = "system_program", desc = "System program")]
+    Split(SplitArgs),
+    /// Moves `amount` from the counter to `destination`. A destination keeping a history passes it
+    /// right after itself; remaining accounts are multisig signers when the counter has one.
+    #[account(0, writable, name = "counter", desc = "Counter transferred from")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(2, writable, name = "destination", desc = "Counter transferred to")]
+    Transfer(TransferArgs),
+    /// Creates the named counter `name` as a copy of the counter's state: value, configuration and
+    /// statistics. The copy starts without a history or a pending random increment, which belong
+    /// to the original.
+    #[account(0, name = "counter", desc = "Counter copied")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "new_counter",
+        desc = "Counter PDA, seeds [\"counter\", authority, name]"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "registry",
+        desc = "Registry PDA, seeds [\"registry\"]"
+    )]
+    #[account(
+        4,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the new counter and registry entry"
+    )]
+    #[account(5, name = "system_program", desc = "System program")]
+    Clone(CloneArgs),
+    /// Hands the counter to `new_authority` in one step. `None` renounces it: the counter then
+    /// rejects every instruction that would change it, and can only be read.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetAuthority(SetAuthorityArgs),
+    /// Locks the counter's value: every write fails, even the authority's, and decay and epoch
+    /// rollover stop until `Thaw`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    Freeze,
+    /// Lifts a previous `Freeze`. Decay and epoch rollover resume from now.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    Thaw,
+    /// Expires the counter at `expires_at_slot`. From then on writes fail and anyone may `Reap`
+    /// it, sending its lamports to `recipient`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetExpiry(SetExpiryArgs),
+    /// Closes a counter that expired, or that nobody wrote to for `ABANDONED_AFTER_EPOCHS`.
+    /// Anyone may call it: the reaper keeps `REAPER_SHARE_BPS` of the lamports and the rest go
+    /// to the counter's expiry recipient if it expired, else to whoever paid to create it.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        writable,
+        name = "recipient",
+        desc = "The counter's expiry recipient or rent payer"
+    )]
+    #[account(2, writable, signer, name = "reaper", desc = "Keeps a share")]
+    Reap,
+    /// Sets the type of value the counter holds, one of the `KIND_*` constants: `u32`, `i64` or
+    /// `u128`. Only possible once, before the counter's first write, so it belongs right after
+    /// `Initialize`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetKind(SetKindArgs),
+    /// `Increment` with a `u64` amount, for counts that outgrow a `u32` step. Takes the same
+    /// accounts as `Increment`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
+    IncrementWide(IncrementWideArgs),
+    /// `Decrement` with a `u64` amount. Takes the same accounts as `Decrement`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
+    DecrementWide(DecrementWideArgs),
+    /// Sets how many of the value's digits are fractional. Only possible while the value is zero,
+    /// so no value is reinterpreted.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetDecimals(SetDecimalsArgs),
+    /// `IncrementWide` by `amount` given with `decimals` fractional digits, rescaled to the
+    /// counter's decimals with `rounding`, one of the `ROUND_*` constants. Takes the same
+    /// accounts as `Increment`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
+    IncrementScaled(IncrementScaledArgs),
+    /// `DecrementWide` by a scaled amount, as `IncrementScaled` takes it. Takes the same accounts
+    /// as `Decrement`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
+    DecrementScaled(DecrementScaledArgs),
+    /// Sets the step every later Increment and Decrement amount must be a multiple of, or zero
+    /// to allow any amount. Scaled amounts are checked after rescaling.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetStep(SetStepArgs),
+    /// Creates the program config with its super-admin and default fee. It exists once, so whoever
+    /// initializes it first picks the super-admin: do so right after deploying.
+    #[account(0, writable, name = "config", desc = "Config PDA, seeds [\"config\"]")]
+    #[account(1, writable, signer, name = "payer", desc = "Pays rent for the config")]
+    #[account(2, name = "system_program", desc = "System program")]
+    InitializeConfig(InitializeConfigArgs),
+    /// Replaces the program config's super-admin, default fee and pause flag. While paused, value
+    /// writes to every governed counter fail with `ProgramPaused`.
+    #[account(0, writable, name = "config", desc = "Config PDA, seeds [\"config\"]")]
+    #[account(1, signer, name = "super_admin", desc = "Current super-admin")]
+    UpdateConfig(UpdateConfigArgs),
+    /// Sets up a counter array of `len` counters, all zero, with the signer as authority. The
+    /// caller creates the account first, owned by the program, rent-exempt and sized
+    /// `CounterArrayHeader::account_len(len)`.
+    #[account(0, writable, name = "array", desc = "Counter array account")]
+    #[account(1, signer, name = "authority", desc = "New authority")]
+    InitializeArray(InitializeArrayArgs),
+    /// Adds `value` to the counter at `index` of a counter array.
+    #[account(0, writable, name = "array", desc = "Counter array account")]
+    #[account(1, signer, name = "authority", desc = "Array authority")]
+    IncrementAt(IncrementAtArgs),
+    /// Subtracts `value` from the counter at `index` of a counter array.
+    #[account(0, writable, name = "array", desc = "Counter array account")]
+    #[account(1, signer, name = "authority", desc = "Array authority")]
+    DecrementAt(DecrementAtArgs),
+    /// Sets the counter at `index` of a counter array to `value`.
+    #[account(0, writable, name = "array", desc = "Counter array account")]
+    #[account(1, signer, name = "authority", desc = "Array authority")]
+    UpdateAt(UpdateAtArgs),
+    /// Sets up a keyed counter account with room for `capacity` entries and the signer as
+    /// authority. The caller creates the account first, owned by the program, rent-exempt and
+    /// sized `KeyedCounterHeader::account_len(capacity)`.
+    #[account(0, writable, name = "keyed", desc = "Keyed counter account")]
+    #[account(1, signer, name = "authority", desc = "New authority")]
+    InitializeKeyed(InitializeKeyedArgs),
+    /// Adds `value` to the counter of `key`, inserting an entry for a new key while there is room.
+    #[account(0, writable, name = "keyed", desc = "Keyed counter account")]
+    #[account(1, signer, name = "authority", desc = "Keyed counter authority")]
+    IncrementKeyed(IncrementKeyedArgs),
+    /// Subtracts `value` from the counter of an existing `key`. The entry stays at zero.
+    #[account(0, writable, name = "keyed", desc = "Keyed counter account")]
+    #[account(1, signer, name = "authority", desc = "Keyed counter authority")]
+    DecrementKeyed(DecrementKeyedArgs),
+    /// Removes the entry of `key`, freeing room for another.
+    #[account(0, writable, name = "keyed", desc = "Keyed counter account")]
+    #[account(1, signer, name = "authority", desc = "Keyed counter authority")]
+    RemoveKeyed(RemoveKeyedArgs),
+    /// Reallocates a keyed counter account to hold `capacity` entries, no fewer than are in use.
+    /// Growing takes at most 10 KiB more per instruction; shrinking keeps the lamports.
+    #[account(0, writable, name = "keyed", desc = "Keyed counter account")]
+    #[account(1, signer, name = "authority", desc = "Keyed counter authority")]
+    #[account(2, writable, signer, name = "payer", desc = "Pays any additional rent")]
+    #[account(3, name = "system_program", desc = "System program")]
+    ResizeKeyed(ResizeKeyedArgs),
+    /// Sets up a concurrent Merkle tree for compressed counters, with the program's tree authority
+    /// PDA as its authority. The caller allocates the tree for `max_depth` and `max_buffer_size`,
+    /// owned by SPL Account Compression.
+    #[account(0, writable, name = "tree", desc = "Merkle tree account")]
+    #[account(
+        1,
+        name = "tree_authority",
+        desc = "Tree authority PDA, seeds [\"tree_authority\", tree]"
+    )]
+    #[account(2, name = "compression_program", desc = "SPL Account Compression")]
+    #[account(3, name = "noop_program", desc = "SPL Noop")]
+    InitializeCompressedTree(InitializeCompressedTreeArgs),
+    /// Appends a compressed counter at zero, with the signer as its authority, to the tree's next
+    /// index.
+    #[account(0, writable, name = "tree", desc = "Merkle tree account")]
+    #[account(1, signer, name = "authority", desc = "Authority of the new counter")]
+    #[account(
+        2,
+        name = "tree_authority",
+        desc = "Tree authority PDA, seeds [\"tree_authority\", tree]"
+    )]
+    #[account(3, name = "compression_program", desc = "SPL Account Compression")]
+    #[account(4, name = "noop_program", desc = "SPL Noop")]
+    CreateCompressed,
+    /// Adds `by` to the compressed counter at `index`, whose current `value` the proof against
+    /// `root` confirms. Remaining accounts are the proof nodes, from the leaf up.
+    #[account(0, writable, name = "tree", desc = "Merkle tree account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        name = "tree_authority",
+        desc = "Tree authority PDA, seeds [\"tree_authority\", tree]"
+    )]
+    #[account(3, name = "compression_program", desc = "SPL Account Compression")]
+    #[account(4, name = "noop_program", desc = "SPL Noop")]
+    IncrementCompressed(IncrementCompressedArgs),
+    /// Subtracts `by` from the compressed counter at `index`, whose current `value` the proof against
+    /// `root` confirms. Remaining accounts are the proof nodes, from the leaf up.
+    #[account(0, writable, name = "tree", desc = "Merkle tree account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        name = "tree_authority",
+        desc = "Tree authority PDA, seeds [\"tree_authority\", tree]"
+    )]
+    #[account(3, name = "compression_program", desc = "SPL Account Compression")]
+    #[account(4, name = "noop_program", desc = "SPL Noop")]
+    DecrementCompressed(DecrementCompressedArgs),
+    /// Upgrades the counter from the layout it was written in to the current one, running each
+    /// migration in turn and growing the account, with rent from `payer`, as they need.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        signer,
+        name = "authority",
+        desc = "Counter authority, or any signer on a counter without one"
+    )]
+    #[account(2, writable, signer, name = "payer", desc = "Pays any additional rent")]
+    #[account(3, name = "system_program", desc = "System program")]
+    MigrateAccount,
+    /// Turns the memo requirement on or off. While it is on, Increment, Decrement, Update and
+    /// Reset (wide and scaled ones included) fail with `MemoRequired` unless their transaction
+    /// also holds an SPL Memo instruction, found through the Instructions sysvar, which they pass
+    /// right before any Ethereum authority's.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetMemoRequired(SetMemoRequiredArgs),
+    /// Turns referrals on or off. While they are on, Increment passes a referrer entry (see
+    /// `CreateReferrer`), or the program ID for none, after any fee accounts and before any
+    /// history. The entry counts the increments naming its referrer, and receives `share_bps`
+    /// of any fee they pay instead of the treasury.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetReferrals(SetReferralsArgs),
+    /// Creates the signer's referrer entry for the counter, the PDA
+    /// `["referrer", counter, referrer]`, so increments can name it.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "referrer", desc = "Referrer the entry is for")]
+    #[account(
+        2,
+        writable,
+        name = "entry",
+        desc = "Referrer entry PDA, seeds [\"referrer\", counter, referrer]"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the referrer entry"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    CreateReferrer,
+    /// Moves the fee share a referrer entry has collected, everything above its rent-exempt
+    /// minimum, to `destination`. Works whatever became of the counter since.
+    #[account(0, name = "counter", desc = "Counter the entry is for")]
+    #[account(1, signer, name = "referrer", desc = "Referrer the entry is for")]
+    #[account(2, writable, name = "entry", desc = "Referrer entry PDA")]
+    #[account(3, writable, name = "destination", desc = "Receives the fees")]
+    WithdrawReferralFees,
+    /// Creates `authority`'s leaderboard, ranking up to `capacity` of its counters by value.
+    #[account(
+        0,
+        writable,
+        name = "leaderboard",
+        desc = "Leaderboard PDA, seeds [\"leaderboard\", authority]"
+    )]
+    #[account(
+        1,
+        signer,
+        name = "authority",
+        desc = "Authority of the ranked counters"
+    )]
+    #[account(
+        2,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the leaderboard"
+    )]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitializeLeaderboard(InitializeLeaderboardArgs),
+    /// Ranks the counter on the leaderboard of its authority at its current value, moving it up
+    /// or down past the others. A counter not yet on a full board enters it only by beating the
+    /// last entry, which it then replaces. Anyone may submit; only `u32` counters are ranked.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        writable,
+        name = "leaderboard",
+        desc = "Leaderboard of the counter's authority"
+    )]
+    SubmitScore,
+    /// Turns stake weighting on or off. While it is on, every Increment (wide and scaled ones
+    /// included) is multiplied by the whole SOL delegated by a native stake account its signer
+    /// is the staker or withdrawer of. The stake account goes after any payment accounts and
+    /// before those of the memo, Ethereum authority, allow-list, block-list and fee checks. It
+    /// counts from the epoch after it was delegated until it is deactivated.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetStakeWeighted(SetStakeWeightedArgs),
+    /// Sets the boost: while an escrow locks at least `min_sol` SOL, its owner's Increments
+    /// (wide and scaled ones included) are multiplied by `factor`. A factor of zero or one turns
+    /// boosting off. While it is on, every Increment passes its signer's escrow, or the program
+    /// ID for none, right before any stake account.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetBoost(SetBoostArgs),
+    /// Moves `lamports` from the owner into its boost escrow for the counter, creating the
+    /// escrow on first use, and keeps them locked for at least `duration` seconds more.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        writable,
+        signer,
+        name = "owner",
+        desc = "Locks the lamports and pays rent"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "escrow",
+        desc = "Boost escrow PDA, seeds [\"boost\", counter, owner]"
+    )]
+    #[account(3, name = "system_program", desc = "System program")]
+    LockBoost(LockBoostArgs),
+    /// Closes a boost escrow once it unlocked, returning its lamports, rent included, to the
+    /// owner. Works whatever became of the counter since.
+    #[account(0, name = "counter", desc = "Counter the escrow is for")]
+    #[account(1, writable, signer, name = "owner", desc = "Owner of the escrow")]
+    #[account(2, writable, name = "escrow", desc = "Boost escrow PDA")]
+    UnlockBoost,
+    /// Lists the counter for lease on these terms, creating its lease PDA on first use. Terms
+    /// can't change while a lease runs.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "lease",
+        desc = "Lease PDA, seeds [\"lease\", counter]"
+    )]
+    #[account(3, writable, name = "treasury", desc = "Treasury PDA lessees pay")]
+    #[account(4, writable, signer, name = "payer", desc = "Pays rent")]
+    #[account(5, name = "system_program", desc = "System program")]
+    ListForLease(ListForLeaseArgs),
+    /// Pays the listed price into the treasury and hands the lessee the counter's authority
+    /// over Increment, Decrement, Update and Reset for the listed duration, after which it
+    /// reverts to the owner by itself.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, writable, signer, name = "lessee", desc = "Pays the price")]
+    #[account(2, writable, name = "lease", desc = "Lease PDA")]
+    #[account(3, writable, name = "treasury", desc = "Treasury PDA")]
+    #[account(4, name = "system_program", desc = "System program")]
+    TakeLease,
+    /// Ends a running lease early. The lessee may end it at any time and forfeits the price;
+    /// the authority may only by refunding the lessee the share of the price for the time
+    /// left, out of the treasury.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "signer", desc = "Lessee or counter authority")]
+    #[account(2, writable, name = "lease", desc = "Lease PDA")]
+    #[account(3, writable, name = "treasury", desc = "Treasury PDA")]
+    #[account(4, writable, name = "lessee", desc = "Receives the refund")]
+    #[account(5, name = "system_program", desc = "System program")]
+    EndLease,
+    /// Takes the counter off the lease market and closes its lease PDA. Fails while a lease
+    /// runs.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(2, writable, name = "lease", desc = "Lease PDA")]
+    #[account(3, writable, name = "destination", desc = "Receives the lease's rent")]
+    Delist,
+    /// Sets the lamports `BuyCredits` charges per credit, or turns credit mode off with zero.
+    /// In credit mode every mutation spends one of its signer's credits.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    #[account(
+        2,
+        writable,
+        name = "treasury",
+        desc = "Treasury PDA credits are paid into"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Tops up the treasury's rent"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    SetCreditPrice(SetCreditPriceArgs),
+    /// Buys `count` operation credits for the buyer at the counter's credit price, paid into
+    /// its treasury, creating the buyer's credit balance on first use.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        writable,
+        signer,
+        name = "buyer",
+        desc = "Owner of the credits; pays the price and rent"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "credits",
+        desc = "Credit balance PDA, seeds [\"credits\", counter, buyer]"
+    )]
+    #[account(3, writable, name = "treasury", desc = "Treasury PDA")]
+    #[account(4, name = "system_program", desc = "System program")]
+    BuyCredits(BuyCreditsArgs),
+    /// Makes each caller wait `cooldown` slots or seconds between its Increments, Decrements,
+    /// Updates and Resets. Zero disables the cooldown.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetCooldown(SetCooldownArgs),
+    /// Creates the caller's cooldown account for a counter with a cooldown, at the PDA
+    /// `["cooldown", counter, caller]`.
+    #[account(0, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        signer,
+        name = "caller",
+        desc = "Pubkey whose mutations the cooldown tracks"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "cooldown",
+        desc = "Cooldown PDA, seeds [\"cooldown\", counter, caller]"
+    )]
+    #[account(
+        3,
+        writable,
+        signer,
+        name = "payer",
+        desc = "Pays rent for the cooldown account"
+    )]
+    #[account(4, name = "system_program", desc = "System program")]
+    CreateCooldown,
+    /// Logs the counter's value, authority, stats and full state without writing anything, for
+    /// inspecting a counter through a simulation.
+    #[account(0, name = "counter", desc = "Counter account")]
+    Log,
+    /// `Increment` by `value` if the counter is below `threshold`, judged on the value the
+    /// increment would start from; at or above it the instruction fails with
+    /// `ThresholdReached`, or with `skip` succeeds without writing. `u32` counters only. Takes
+    /// the same accounts as `Increment`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
+    IncrementIfBelow(IncrementIfBelowArgs),
+    /// Sets the counter to its bitwise AND with `value`, for counters used as flag registers.
+    /// Takes signers as `Update` does. `u32` counters only.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    And(AndArgs),
+    /// Sets the counter to its bitwise OR with `value`. Takes signers as `Update` does. `u32`
+    /// counters only.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    Or(OrArgs),
+    /// Sets the counter to its bitwise XOR with `value`. Takes signers as `Update` does. `u32`
+    /// counters only.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    Xor(XorArgs),
+    /// Shifts the counter left by `bits`, dropping the bits shifted out. Takes signers as
+    /// `Update` does. `u32` counters only.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    ShiftLeft(ShiftLeftArgs),
+    /// Shifts the counter right by `bits`, dropping the bits shifted out. Takes signers as
+    /// `Update` does. `u32` counters only.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    ShiftRight(ShiftRightArgs),
+    /// Makes every later Increment and Decrement of a `u32` counter wrap within `0..modulus`,
+    /// whatever their overflow mode, or with zero leaves them to it.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetModulus(SetModulusArgs),
+    /// Arms a countdown: sets a `u32` counter to `target`, after which it only takes Decrements,
+    /// which stop at zero. The one reaching zero emits `CountdownCompleted` and locks the
+    /// counter until it is armed again. A target of zero leaves countdown mode at the current
+    /// value.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(1, signer, name = "authority", desc = "Counter authority")]
+    SetCountdown(SetCountdownArgs),
+    /// `Increment` by one, failing on overflow, as a tag with no arguments. Takes the same
+    /// accounts as `Increment`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
+    IncrementOne,
+    /// `Decrement` by one, flooring at zero, as a tag with no arguments. Takes the same accounts
+    /// as `Decrement`.
+    #[account(0, writable, name = "counter", desc = "Counter account")]
+    #[account(
+        1,
+        optional_signer,
+        name = "signer",
+        desc = "Authority or delegate; required once the counter has an authority"
+    )]
+    DecrementOne,
 }
 
+/// Set on the first byte of versioned instruction data, `[VERSIONED | version,
+/// tag, args]`. Tags stay below it, so unversioned `[tag, args]` data from
+/// deployed clients still reads as version 0.
+pub const VERSIONED: u8 = 0x80;
+
+/// The latest layout of instruction args. Version 0 is the layout every
+/// instruction had before versioning; a version that adds args to an
+/// instruction decodes the instructions it leaves alone as the previous one
+/// did, and older data keeps decoding with the new args at their defaults.
+pub const INSTRUCTION_VERSION: u8 = 0;
+
 impl CounterInstructions {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        #[cfg(feature = "anchor-discriminators")]
+        if let Some((variant, rest)) = split_anchor_discriminator(input) {
+            return Self::unpack_variant(variant, rest);
+        }
+
+        let (version, input) = match input.split_first() {
+            Some((&first, rest)) if first & VERSIONED != 0 => (first & !VERSIONED, rest),
+            _ => (0, input),
+        };
         let (&variant, rest) = input
             .split_first()
             .ok_or(ProgramError::InvalidInstructionData)?;
 
+        match version {
+            0 => Self::unpack_variant(variant, rest),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    fn unpack_variant(variant: u8, rest: &[u8]) -> Result<Self, ProgramError> {
         Ok(match variant {
-            0 => Self::Increment(IncrementArgs::try_from_slice(rest).unwrap()),
-            1 => Self::Decrement(DecrementArgs::try_from_slice(rest).unwrap()),
-            2 => Self::Update(UpdateArgs::try_from_slice(rest).unwrap()),
-            3 => Self::Reset,
-            _ => return Err(ProgramError::InvalidInstructionData),
+            0 => Self::Increment(unpack_moded_args(rest, OVERFLOW_CHECKED)?),
+            1 => Self::Decrement(unpack_moded_args(rest, OVERFLOW_SATURATING)?),
+            2 => Self::Update(unpack_args(rest)?),
+            3 => unpack_unit(rest, Self::Reset)?,
+            4 => unpack_unit(rest, Self::Initialize)?,
+            5 => unpack_unit(rest, Self::Pause)?,
+            6 => unpack_unit(rest, Self::Unpause)?,
+            7 => Self::ProposeAuthority(unpack_args(rest)?),
+            8 => unpack_unit(rest, Self::AcceptAuthority)?,
+            9 => unpack_unit(rest, Self::Close)?,
+            10 => Self::ConfigureMultisig(unpack_args(rest)?),
+            11 => Self::Approve(unpack_args(rest)?),
+            12 => unpack_unit(rest, Self::Revoke)?,
+            13 => unpack_unit(rest, Self::Get)?,
+            14 => Self::Realloc(unpack_args(rest)?),
+            15 => Self::InitializeNamed(unpack_args(rest)?),
+            16 => Self::SetEpochScoped(unpack_args(rest)?),
+            17 => Self::SetDecay(unpack_args(rest)?),
+            18 => Self::SetRateLimit(unpack_args(rest)?),
+            19 => unpack_unit(rest, Self::CreateQuota)?,
+            20 => Self::SetDailyCap(unpack_args(rest)?),
+            21 => Self::SetFee(unpack_args(rest)?),
+            22 => unpack_unit(rest, Self::WithdrawTreasury)?,
+            23 => Self::ConfigurePayment(unpack_args(rest)?),
+            24 => unpack_unit(rest, Self::InitializeVault)?,
+            25 => Self::WithdrawVault(unpack_args(rest)?),
+            26 => Self::SetNftGate(unpack_args(rest)?),
+            27 => Self::ConfigureMilestoneReward(unpack_args(rest)?),
+            28 => Self::ConfigureMilestoneNft(unpack_args(rest)?),
+            29 => Self::ClaimMilestoneNft(unpack_args(rest)?),
+            30 => Self::ConfigureOracle(unpack_args(rest)?),
+            31 => unpack_unit(rest, Self::UpdateFromOracle)?,
+            32 => Self::RequestRandomIncrement(unpack_args(rest)?),
+            33 => unpack_unit(rest, Self::SettleRandomIncrement)?,
+            34 => Self::SetAllowlistEnabled(unpack_args(rest)?),
+            35 => Self::AddToAllowlist(unpack_args(rest)?),
+            36 => Self::RemoveFromAllowlist(unpack_args(rest)?),
+            37 => Self::SetBlocklistEnabled(unpack_args(rest)?),
+            38 => Self::BanCaller(unpack_args(rest)?),
+            39 => Self::UnbanCaller(unpack_args(rest)?),
+            40 => Self::SetOffchainSigner(unpack_args(rest)?),
+            41 => Self::SetEthAuthority(unpack_args(rest)?),
+            42 => Self::ConfigureWormhole(unpack_args(rest)?),
+            43 => unpack_unit(rest, Self::ReceiveWormholeMessage)?,
+            44 => Self::SetGovernance(unpack_args(rest)?),
+            45 => Self::ConfigureCrank(unpack_args(rest)?),
+            46 => unpack_unit(rest, Self::Crank)?,
+            47 => unpack_unit(rest, Self::GetStats)?,
+            48 => Self::InitializeHistory(unpack_args(rest)?),
+            49 => unpack_unit(rest, Self::Checkpoint)?,
+            50 => unpack_unit(rest, Self::Merge)?,
+            51 => Self::Split(unpack_args(rest)?),
+            52 => Self::Transfer(unpack_args(rest)?),
+            53 => Self::Clone(unpack_args(rest)?),
+            54 => Self::SetAuthority(unpack_args(rest)?),
+            55 => unpack_unit(rest, Self::Freeze)?,
+            56 => unpack_unit(rest, Self::Thaw)?,
+            57 => Self::SetExpiry(unpack_args(rest)?),
+            58 => unpack_unit(rest, Self::Reap)?,
+            59 => Self::SetKind(unpack_args(rest)?),
+            60 => Self::IncrementWide(unpack_args(rest)?),
+            61 => Self::DecrementWide(unpack_args(rest)?),
+            62 => Self::SetDecimals(unpack_args(rest)?),
+            63 => Self::IncrementScaled(unpack_args(rest)?),
+            64 => Self::DecrementScaled(unpack_args(rest)?),
+            65 => Self::SetStep(unpack_args(rest)?),
+            66 => Self::InitializeConfig(unpack_args(rest)?),
+            67 => Self::UpdateConfig(unpack_args(rest)?),
+            68 => Self::InitializeArray(unpack_args(rest)?),
+            69 => Self::IncrementAt(unpack_args(rest)?),
+            70 => Self::DecrementAt(unpack_args(rest)?),
+            71 => Self::UpdateAt(unpack_args(rest)?),
+            72 => Self::InitializeKeyed(unpack_args(rest)?),
+            73 => Self::IncrementKeyed(unpack_args(rest)?),
+            74 => Self::DecrementKeyed(unpack_args(rest)?),
+            75 => Self::RemoveKeyed(unpack_args(rest)?),
+            76 => Self::ResizeKeyed(unpack_args(rest)?),
+            77 => Self::InitializeCompressedTree(unpack_args(rest)?),
+            78 => unpack_unit(rest, Self::CreateCompressed)?,
+            79 => Self::IncrementCompressed(unpack_args(rest)?),
+            80 => Self::DecrementCompressed(unpack_args(rest)?),
+            81 => unpack_unit(rest, Self::MigrateAccount)?,
+            82 => Self::SetMemoRequired(unpack_args(rest)?),
+            83 => Self::SetReferrals(unpack_args(rest)?),
+            84 => unpack_unit(rest, Self::CreateReferrer)?,
+            85 => unpack_unit(rest, Self::WithdrawReferralFees)?,
+            86 => Self::InitializeLeaderboard(unpack_args(rest)?),
+            87 => unpack_unit(rest, Self::SubmitScore)?,
+            88 => Self::SetStakeWeighted(unpack_args(rest)?),
+            89 => Self::SetBoost(unpack_args(rest)?),
+            90 => Self::LockBoost(unpack_args(rest)?),
+            91 => unpack_unit(rest, Self::UnlockBoost)?,
+            92 => Self::ListForLease(unpack_args(rest)?),
+            93 => unpack_unit(rest, Self::TakeLease)?,
+            94 => unpack_unit(rest, Self::EndLease)?,
+            95 => unpack_unit(rest, Self::Delist)?,
+            96 => Self::SetCreditPrice(unpack_args(rest)?),
+            97 => Self::BuyCredits(unpack_args(rest)?),
+            98 => Self::SetCooldown(unpack_args(rest)?),
+            99 => unpack_unit(rest, Self::CreateCooldown)?,
+            100 => unpack_unit(rest, Self::Log)?,
+            101 => Self::IncrementIfBelow(unpack_args(rest)?),
+            102 => Self::And(unpack_args(rest)?),
+            103 => Self::Or(unpack_args(rest)?),
+            104 => Self::Xor(unpack_args(rest)?),
+            105 => Self::ShiftLeft(unpack_args(rest)?),
+            106 => Self::ShiftRight(unpack_args(rest)?),
+            107 => Self::SetModulus(unpack_args(rest)?),
+            108 => Self::SetCountdown(unpack_args(rest)?),
+            109 => unpack_unit(rest, Self::IncrementOne)?,
+            110 => unpack_unit(rest, Self::DecrementOne)?,
+            // Likely a client built for a later program; the tag tells which.
+            _ => {
+                msg!("Unknown instruction tag {}", variant);
+                return Err(CounterError::UnknownInstruction.into());
+            }
         })
     }
-}
\ No newline at end of file
+
+    pub fn pack(&self) -> Vec<u8> {
+        match self {
+            Self::Increment(args) => pack_with_args(0, args),
+            Self::Decrement(args) => pack_with_args(1, args),
+            Self::Update(args) => pack_with_args(2, args),
+            Self::Reset => vec![3],
+            Self::Initialize => vec![4],
+            Self::Pause => vec![5],
+            Self::Unpause => vec![6],
+            Self::ProposeAuthority(args) => pack_with_args(7, args),
+            Self::AcceptAuthority => vec![8],
+            Self::Close => vec![9],
+            Self::ConfigureMultisig(args) => pack_with_args(10, args),
+            Self::Approve(args) => pack_with_args(11, args),
+            Self::Revoke => vec![12],
+            Self::Get => vec![13],
+            Self::Realloc(args) => pack_with_args(14, args),
+            Self::InitializeNamed(args) => pack_with_args(15, args),
+            Self::SetEpochScoped(args) => pack_with_args(16, args),
+            Self::SetDecay(args) => pack_with_args(17, args),
+            Self::SetRateLimit(args) => pack_with_args(18, args),
+            Self::CreateQuota => vec![19],
+            Self::SetDailyCap(args) => pack_with_args(20, args),
+            Self::SetFee(args) => pack_with_args(21, args),
+            Self::WithdrawTreasury => vec![22],
+            Self::ConfigurePayment(args) => pack_with_args(23, args),
+            Self::InitializeVault => vec![24],
+            Self::WithdrawVault(args) => pack_with_args(25, args),
+            Self::SetNftGate(args) => pack_with_args(26, args),
+            Self::ConfigureMilestoneReward(args) => pack_with_args(27, args),
+            Self::ConfigureMilestoneNft(args) => pack_with_args(28, args),
+            Self::ClaimMilestoneNft(args) => pack_with_args(29, args),
+            Self::ConfigureOracle(args) => pack_with_args(30, args),
+            Self::UpdateFromOracle => vec![31],
+            Self::RequestRandomIncrement(args) => pack_with_args(32, args),
+            Self::SettleRandomIncrement => vec![33],
+            Self::SetAllowlistEnabled(args) => pack_with_args(34, args),
+            Self::AddToAllowlist(args) => pack_with_args(35, args),
+            Self::RemoveFromAllowlist(args) => pack_with_args(36, args),
+            Self::SetBlocklistEnabled(args) => pack_with_args(37, args),
+            Self::BanCaller(args) => pack_with_args(38, args),
+            Self::UnbanCaller(args) => pack_with_args(39, args),
+            Self::SetOffchainSigner(args) => pack_with_args(40, args),
+            Self::SetEthAuthority(args) => pack_with_args(41, args),
+            Self::ConfigureWormhole(args) => pack_with_args(42, args),
+            Self::ReceiveWormholeMessage => vec![43],
+            Self::SetGovernance(args) => pack_with_args(44, args),
+            Self::ConfigureCrank(args) => pack_with_args(45, args),
+            Self::Crank => vec![46],
+            Self::GetStats => vec![47],
+            Self::InitializeHistory(args) => pack_with_args(48, args),
+            Self::Checkpoint => vec![49],
+            Self::Merge => vec![50],
+            Self::Split(args) => pack_with_args(51, args),
+            Self::Transfer(args) => pack_with_args(52, args),
+            Self::Clone(args) => pack_with_args(53, args),
+            Self::SetAuthority(args) => pack_with_args(54, args),
+            Self::Freeze => vec![55],
+            Self::Thaw => vec![56],
+            Self::SetExpiry(args) => pack_with_args(57, args),
+            Self::Reap => vec![58],
+            Self::SetKind(args) => pack_with_args(59, args),
+            Self::IncrementWide(args) => pack_with_args(60, args),
+            Self::DecrementWide(args) => pack_with_args(61, args),
+            Self::SetDecimals(args) => pack_with_args(62, args),
+            Self::IncrementScaled(args) => pack_with_args(63, args),
+            Self::DecrementScaled(args) => pack_with_args(64, args),
+            Self::SetStep(args) => pack_with_args(65, args),
+            Self::InitializeConfig(args) => pack_with_args(66, args),
+            Self::UpdateConfig(args) => pack_with_args(67, args),
+            Self::InitializeArray(args) => pack_with_args(68, args),
+            Self::IncrementAt(args) => pack_with_args(69, args),
+            Self::DecrementAt(args) => pack_with_args(70, args),
+            Self::UpdateAt(args) => pack_with_args(71, args),
+            Self::InitializeKeyed(args) => pack_with_args(72, args),
+            Self::IncrementKeyed(args) => pack_with_args(73, args),
+            Self::DecrementKeyed(args) => pack_with_args(74, args),
+            Self::RemoveKeyed(args) => pack_with_args(75, args),
+            Self::ResizeKeyed(args) => pack_with_args(76, args),
+            Self::InitializeCompressedTree(args) => pack_with_args(77, args),
+            Self::CreateCompressed => vec![78],
+            Self::IncrementCompressed(args) => pack_with_args(79, args),
+            Self::DecrementCompressed(args) => pack_with_args(80, args),
+            Self::MigrateAccount => vec![81],
+            Self::SetMemoRequired(args) => pack_with_args(82, args),
+            Self::SetReferrals(args) => pack_with_args(83, args),
+            Self::CreateReferrer => vec![84],
+            Self::WithdrawReferralFees => vec![85],
+            Self::InitializeLeaderboard(args) => pack_with_args(86, args),
+            Self::SubmitScore => vec![87],
+            Self::SetStakeWeighted(args) => pack_with_args(88, args),
+            Self::SetBoost(args) => pack_with_args(89, args),
+            Self::LockBoost(args) => pack_with_args(90, args),
+            Self::UnlockBoost => vec![91],
+            Self::ListForLease(args) => pack_with_args(92, args),
+            Self::TakeLease => vec![93],
+            Self::EndLease => vec![94],
+            Self::Delist => vec![95],
+            Self::SetCreditPrice(args) => pack_with_args(96, args),
+            Self::BuyCredits(args) => pack_with_args(97, args),
+            Self::SetCooldown(args) => pack_with_args(98, args),
+            Self::CreateCooldown => vec![99],
+            Self::Log => vec![100],
+            Self::IncrementIfBelow(args) => pack_with_args(101, args),
+            Self::And(args) => pack_with_args(102, args),
+            Self::Or(args) => pack_with_args(103, args),
+            Self::Xor(args) => pack_with_args(104, args),
+            Self::ShiftLeft(args) => pack_with_args(105, args),
+            Self::ShiftRight(args) => pack_with_args(106, args),
+            Self::SetModulus(args) => pack_with_args(107, args),
+            Self::SetCountdown(args) => pack_with_args(108, args),
+            Self::IncrementOne => vec![109],
+            Self::DecrementOne => vec![110],
+        }
+    }
+
+    /// [`Self::pack`] behind the [`INSTRUCTION_VERSION`] byte. Programs
+    /// deployed before versioning reject it, so send it only to those after.
+    pub fn pack_versioned(&self) -> Vec<u8> {
+        let mut buf = vec![VERSIONED | INSTRUCTION_VERSION];
+        buf.extend(self.pack());
+        buf
+    }
+}
+
+/// Args must take up exactly the data after the tag; Borsh rejects both
+/// trailing and missing bytes.
+fn unpack_args<T: BorshDeserialize>(rest: &[u8]) -> Result<T, ProgramError> {
+    T::try_from_slice(rest).map_err(|_| ProgramError::InvalidInstructionData)
+}
+
+/// Increment and Decrement from before overflow modes end after the value,
+/// and keep the policy they always had: `default_mode`.
+fn unpack_moded_args<T: BorshDeserialize>(
+    rest: &[u8],
+    default_mode: u8,
+) -> Result<T, ProgramError> {
+    match rest.len() {
+        4 => unpack_args(&[rest, &[default_mode]].concat()),
+        _ => unpack_args(rest),
+    }
+}
+
+/// Instructions without args take no data after the tag.
+fn unpack_unit(
+    rest: &[u8],
+    instruction: CounterInstructions,
+) -> Result<CounterInstructions, ProgramError> {
+    match rest {
+        [] => Ok(instruction),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn pack_with_args<T: BorshSerialize>(variant: u8, args: &T) -> Vec<u8> {
+    let mut buf = vec![variant];
+    args.serialize(&mut buf)
+        .expect("serializing into a Vec cannot fail");
+    buf
+}
+
+/// Anchor sighash discriminators (`sha256("global:<snake_case_name>")[..8]`)
+/// and the single-byte tag each one maps to.
+pub const ANCHOR_DISCRIMINATORS: [([u8; 8], u8); 111] = [
+    ([11, 18, 104, 9, 104, 174, 59, 33], 0),
+    ([106, 227, 168, 59, 248, 27, 150, 101], 1),
+    ([219, 200, 88, 176, 158, 63, 253, 127], 2),
+    ([23, 81, 251, 84, 138, 183, 240, 214], 3),
+    ([175, 175, 109, 31, 13, 152, 155, 237], 4),
+    ([211, 22, 221, 251, 74, 121, 193, 47], 5),
+    ([169, 144, 4, 38, 10, 141, 188, 255], 6),
+    ([20, 148, 236, 198, 76, 119, 99, 142], 7),
+    ([107, 86, 198, 91, 33, 12, 107, 160], 8),
+    ([98, 165, 201, 177, 108, 65, 206, 96], 9),
+    ([159, 211, 99, 149, 224, 74, 185, 6], 10),
+    ([69, 74, 217, 36, 115, 117, 97, 76], 11),
+    ([170, 23, 31, 34, 133, 173, 93, 242], 12),
+    ([161, 224, 50, 61, 5, 210, 122, 216], 13),
+    ([138, 139, 3, 51, 58, 130, 86, 208], 14),
+    ([230, 152, 253, 141, 34, 233, 7, 186], 15),
+    ([163, 11, 67, 4, 123, 63, 127, 156], 16),
+    ([132, 95, 23, 90, 52, 217, 157, 150], 17),
+    ([42, 212, 44, 91, 198, 58, 60, 239], 18),
+    ([81, 118, 218, 107, 107, 81, 88, 181], 19),
+    ([103, 238, 213, 47, 23, 76, 41, 51], 20),
+    ([18, 154, 24, 18, 237, 214, 19, 80], 21),
+    ([40, 63, 122, 158, 144, 216, 83, 96], 22),
+    ([25, 142, 129, 192, 184, 237, 84, 177], 23),
+    ([48, 191, 163, 44, 71, 129, 63, 164], 24),
+    ([135, 7, 237, 120, 149, 94, 95, 7], 25),
+    ([165, 149, 212, 81, 163, 52, 198, 203], 26),
+    ([18, 156, 59, 156, 53, 233, 73, 173], 27),
+    ([58, 48, 16, 16, 209, 191, 111, 173], 28),
+    ([105, 85, 19, 92, 39, 65, 252, 186], 29),
+    ([245, 58, 202, 16, 204, 36, 82, 199], 30),
+    ([176, 242, 37, 207, 48, 149, 193, 29], 31),
+    ([89, 241, 157, 212, 158, 114, 205, 58], 32),
+    ([206, 208, 32, 94, 134, 205, 100, 138], 33),
+    ([204, 179, 188, 165, 225, 141, 118, 243], 34),
+    ([149, 143, 78, 134, 241, 244, 7, 56], 35),
+    ([45, 46, 214, 56, 189, 77, 242, 227], 36),
+    ([239, 229, 45, 231, 56, 135, 133, 96], 37),
+    ([184, 228, 118, 253, 253, 70, 48, 86], 38),
+    ([36, 39, 227, 27, 245, 59, 175, 178], 39),
+    ([6, 187, 168, 120, 159, 63, 136, 219], 40),
+    ([188, 2, 95, 34, 221, 42, 100, 122], 41),
+    ([194, 147, 75, 118, 241, 90, 94, 129], 42),
+    ([134, 213, 143, 68, 235, 102, 232, 96], 43),
+    ([34, 71, 128, 245, 179, 42, 140, 137], 44),
+    ([234, 163, 184, 69, 41, 252, 103, 141], 45),
+    ([0, 232, 3, 195, 124, 117, 105, 53], 46),
+    ([241, 65, 112, 185, 230, 140, 139, 177], 47),
+    ([16, 143, 228, 87, 255, 10, 177, 154], 48),
+    ([213, 200, 19, 204, 240, 143, 184, 252], 49),
+    ([148, 141, 236, 47, 174, 126, 69, 111], 50),
+    ([124, 189, 27, 43, 216, 40, 147, 66], 51),
+    ([163, 52, 200, 231, 140, 3, 69, 186], 52),
+    ([153, 50, 26, 120, 125, 12, 36, 151], 53),
+    ([133, 250, 37, 21, 110, 163, 26, 121], 54),
+    ([255, 91, 207, 84, 251, 194, 254, 63], 55),
+    ([226, 249, 34, 57, 189, 21, 177, 101], 56),
+    ([3, 38, 117, 33, 73, 152, 213, 179], 57),
+    ([147, 15, 172, 179, 252, 146, 108, 91], 58),
+    ([106, 145, 103, 126, 142, 31, 189, 46], 59),
+    ([185, 94, 128, 138, 53, 22, 216, 124], 60),
+    ([125, 8, 51, 118, 65, 145, 84, 135], 61),
+    ([129, 147, 50, 79, 106, 97, 191, 186], 62),
+    ([17, 159, 30, 205, 1, 47, 148, 100], 63),
+    ([252, 147, 184, 51, 76, 180, 109, 153], 64),
+    ([34, 98, 36, 122, 96, 133, 242, 181], 65),
+    ([208, 127, 21, 1, 194, 190, 196, 70], 66),
+    ([29, 158, 252, 191, 10, 83, 219, 99], 67),
+    ([117, 181, 248, 204, 67, 91, 70, 121], 68),
+    ([58, 87, 254, 71, 14, 157, 165, 85], 69),
+    ([108, 255, 169, 248, 81, 105, 5, 111], 70),
+    ([148, 201, 31, 234, 21, 203, 206, 130], 71),
+    ([30, 243, 143, 237, 254, 164, 79, 206], 72),
+    ([161, 62, 253, 197, 69, 253, 135, 140], 73),
+    ([119, 190, 172, 50, 69, 207, 17, 111], 74),
+    ([110, 132, 121, 18, 204, 72, 141, 124], 75),
+    ([127, 112, 59, 119, 114, 49, 231, 237], 76),
+    ([144, 185, 192, 131, 93, 118, 162, 250], 77),
+    ([184, 179, 91, 77, 15, 30, 215, 140], 78),
+    ([223, 55, 122, 34, 162, 26, 201, 151], 79),
+    ([165, 108, 146, 155, 134, 243, 253, 199], 80),
+    ([177, 228, 60, 125, 13, 116, 44, 84], 81),
+    ([119, 200, 201, 132, 62, 0, 37, 49], 82),
+    ([54, 168, 143, 132, 36, 207, 178, 73], 83),
+    ([159, 116, 231, 149, 233, 192, 108, 93], 84),
+    ([108, 76, 35, 49, 159, 234, 45, 28], 85),
+    ([47, 23, 34, 39, 46, 108, 91, 176], 86),
+    ([212, 128, 45, 22, 112, 82, 85, 235], 87),
+    ([213, 86, 231, 239, 115, 88, 182, 146], 88),
+    ([14, 249, 151, 198, 131, 89, 177, 239], 89),
+    ([70, 44, 69, 144, 142, 15, 72, 6], 90),
+    ([31, 246, 174, 137, 246, 97, 49, 194], 91),
+    ([170, 213, 64, 219, 57, 221, 16, 205], 92),
+    ([91, 1, 62, 36, 217, 120, 59, 78], 93),
+    ([103, 185, 73, 192, 161, 29, 161, 186], 94),
+    ([55, 136, 205, 107, 107, 173, 4, 31], 95),
+    ([40, 53, 30, 92, 141, 163, 119, 114], 96),
+    ([14, 173, 58, 38, 248, 235, 115, 102], 97),
+    ([57, 78, 91, 178, 112, 152, 211, 87], 98),
+    ([126, 183, 0, 17, 52, 82, 240, 76], 99),
+    ([141, 230, 214, 242, 9, 209, 207, 170], 100),
+    ([171, 228, 226, 51, 223, 213, 232, 61], 101),
+    ([71, 180, 101, 242, 111, 67, 87, 5], 102),
+    ([70, 231, 89, 184, 173, 249, 114, 246], 103),
+    ([85, 147, 200, 120, 102, 119, 80, 76], 104),
+    ([136, 130, 192, 142, 161, 29, 211, 95], 105),
+    ([70, 230, 131, 214, 164, 221, 163, 153], 106),
+    ([172, 23, 102, 27, 131, 128, 0, 108], 107),
+    ([40, 29, 221, 118, 204, 29, 134, 144], 108),
+    ([229, 191, 121, 118, 20, 71, 243, 67], 109),
+    ([97, 55, 8, 72, 208, 243, 56, 191], 110),
+];
+
+#[cfg(feature = "anchor-discriminators")]
+fn split_anchor_discriminator(input: &[u8]) -> Option<(u8, &[u8])> {
+    let discriminator = input.get(..8)?;
+    ANCHOR_DISCRIMINATORS
+        .iter()
+        .find(|(anchor, _)| anchor == discriminator)
+        .map(|&(_, variant)| (variant, &input[8..]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::hash::hash;
+
+    #[test]
+    fn test_anchor_discriminators_match_sighash() {
+        let names = [
+            "increment",
+            "decrement",
+            "update",
+            "reset",
+            "initialize",
+            "pause",
+            "unpause",
+            "propose_authority",
+            "accept_authority",
+            "close",
+            "configure_multisig",
+            "approve",
+            "revoke",
+            "get",
+            "realloc",
+            "initialize_named",
+            "set_epoch_scoped",
+            "set_decay",
+            "set_rate_limit",
+            "create_quota",
+            "set_daily_cap",
+            "set_fee",
+            "withdraw_treasury",
+            "configure_payment",
+            "initialize_vault",
+            "withdraw_vault",
+            "set_nft_gate",
+            "configure_milestone_reward",
+            "configure_milestone_nft",
+            "claim_milestone_nft",
+            "configure_oracle",
+            "update_from_oracle",
+            "request_random_increment",
+            "settle_random_increment",
+            "set_allowlist_enabled",
+            "add_to_allowlist",
+            "remove_from_allowlist",
+            "set_blocklist_enabled",
+            "ban_caller",
+            "unban_caller",
+            "set_offchain_signer",
+            "set_eth_authority",
+            "configure_wormhole",
+            "receive_wormhole_message",
+            "set_governance",
+            "configure_crank",
+            "crank",
+            "get_stats",
+            "initialize_history",
+            "checkpoint",
+            "merge",
+            "split",
+            "transfer",
+            "clone",
+            "set_authority",
+            "freeze",
+            "thaw",
+            "set_expiry",
+            "reap",
+            "set_kind",
+            "increment_wide",
+            "decrement_wide",
+            "set_decimals",
+            "increment_scaled",
+            "decrement_scaled",
+            "set_step",
+            "initialize_config",
+            "update_config",
+            "initialize_array",
+            "increment_at",
+            "decrement_at",
+            "update_at",
+            "initialize_keyed",
+            "increment_keyed",
+            "decrement_keyed",
+            "remove_keyed",
+            "resize_keyed",
+            "initialize_compressed_tree",
+            "create_compressed",
+            "increment_compressed",
+            "decrement_compressed",
+            "migrate_account",
+            "set_memo_required",
+            "set_referrals",
+            "create_referrer",
+            "withdraw_referral_fees",
+            "initialize_leaderboard",
+            "submit_score",
+            "set_stake_weighted",
+            "set_boost",
+            "lock_boost",
+            "unlock_boost",
+            "list_for_lease",
+            "take_lease",
+            "end_lease",
+            "delist",
+            "set_credit_price",
+            "buy_credits",
+            "set_cooldown",
+            "create_cooldown",
+            "log",
+            "increment_if_below",
+            "and",
+            "or",
+            "xor",
+            "shift_left",
+            "shift_right",
+            "set_modulus",
+            "set_countdown",
+            "increment_one",
+            "decrement_one",
+        ];
+        for (index, (name, (discriminator, variant))) in
+            names.iter().zip(ANCHOR_DISCRIMINATORS).enumerate()
+        {
+            let sighash = hash(format!("global:{name}").as_bytes());
+            assert_eq!(discriminator, sighash.to_bytes()[..8], "{name}");
+            assert_eq!(variant as usize, index, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_unpack_malformed_args() {
+        assert_eq!(
+            CounterInstructions::unpack(&[]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            CounterInstructions::unpack(&[0, 1, 2]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            CounterInstructions::unpack(&[10, 1, 255, 255, 255, 255]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+        // Trailing bytes are rejected, not ignored.
+        assert_eq!(
+            CounterInstructions::unpack(&[0, 1, 0, 0, 0, 0, 0]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            CounterInstructions::unpack(&[3, 0]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+        // Neither are bool args outside 0 and 1.
+        assert_eq!(
+            CounterInstructions::unpack(&[88, 2]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn test_unpack_unknown_tag() {
+        let unknown = Some(ProgramError::from(CounterError::UnknownInstruction));
+        for tag in ANCHOR_DISCRIMINATORS.len() as u8..=u8::MAX {
+            // Tags from `VERSIONED` up only follow a version byte.
+            if tag < VERSIONED {
+                assert_eq!(CounterInstructions::unpack(&[tag]).err(), unknown, "{tag}");
+            }
+            assert_eq!(
+                CounterInstructions::unpack(&[VERSIONED, tag]).err(),
+                unknown,
+                "{tag}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unpack_versioned() {
+        let instruction = CounterInstructions::Increment(IncrementArgs { value: 48, mode: 0 });
+        let versioned = instruction.pack_versioned();
+        assert_eq!(versioned[0], VERSIONED);
+        assert_eq!(versioned[1..], instruction.pack());
+        match CounterInstructions::unpack(&versioned).unwrap() {
+            CounterInstructions::Increment(args) => assert_eq!(args.value, 48),
+            _ => panic!("expected Increment"),
+        }
+
+        assert!(matches!(
+            CounterInstructions::unpack(&[VERSIONED, 3]).unwrap(),
+            CounterInstructions::Reset
+        ));
+        assert_eq!(
+            CounterInstructions::unpack(&[VERSIONED]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+        // Data from a later version is rejected rather than misread.
+        assert_eq!(
+            CounterInstructions::unpack(&[VERSIONED | 1, 3]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[cfg(feature = "anchor-discriminators")]
+    #[test]
+    fn test_unpack_anchor_discriminator() {
+        let mut instruction_data = ANCHOR_DISCRIMINATORS[0].0.to_vec();
+        instruction_data.extend_from_slice(&48u32.to_le_bytes());
+        match CounterInstructions::unpack(&instruction_data).unwrap() {
+            CounterInstructions::Increment(args) => assert_eq!(args.value, 48),
+            _ => panic!("expected Increment"),
+        }
+
+        match CounterInstructions::unpack(&[0, 48, 0, 0, 0]).unwrap() {
+            CounterInstructions::Increment(args) => assert_eq!(args.value, 48),
+            _ => panic!("expected Increment"),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! Program logs. Every log costs compute on every call, so they are only
+//! compiled in for builds that ask: `verbose-logs` for the messages and
+//! `log-compute-units` for the compute left on entry and exit.
+
+/// `msg!` with the `verbose-logs` feature, nothing without it.
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "verbose-logs")]
+        solana_program::msg!($($arg)*);
+    };
+}
+
+/// Logs `label` and the remaining compute units with the `log-compute-units`
+/// feature, nothing without it.
+macro_rules! log_compute_units {
+    ($label:literal) => {
+        #[cfg(feature = "log-compute-units")]
+        {
+            solana_program::msg!($label);
+            solana_program::log::sol_log_compute_units();
+        }
+    };
+}
+
+pub(crate) use {log_compute_units, verbose};
@@ -0,0 +1,32 @@
+//! Just enough of SPL Memo for counters that require a memo on every
+//! mutation, without depending on the memo crate.
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+};
+
+/// The SPL Memo program.
+pub const PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// The first version of SPL Memo, which some wallets still send memos to.
+pub const LEGACY_PROGRAM_ID: Pubkey = pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+
+/// Whether `program_id` is either version of SPL Memo.
+pub fn is_memo_program(program_id: &Pubkey) -> bool {
+    *program_id == PROGRAM_ID || *program_id == LEGACY_PROGRAM_ID
+}
+
+/// The SPL Memo instruction logging `memo`, which each of `signers` must
+/// sign.
+pub fn new_instruction(memo: &str, signers: &[&Pubkey]) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(**signer, true))
+            .collect(),
+        data: memo.as_bytes().to_vec(),
+    }
+}
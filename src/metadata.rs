@@ -0,0 +1,261 @@
+//! Just enough of Metaplex Token Metadata to read an NFT's collection and to
+//! create a one-off NFT, without depending on the Metaplex crates.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// The Metaplex Token Metadata program.
+pub const ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Prefix seed of metadata PDAs, `["metadata", ID, mint]`.
+pub const METADATA_SEED: &[u8] = b"metadata";
+
+/// `Key::MetadataV1`, the first byte of every metadata account.
+pub const METADATA_V1_KEY: u8 = 4;
+
+/// Suffix seed of master edition PDAs, `["metadata", ID, mint, "edition"]`.
+pub const EDITION_SEED: &[u8] = b"edition";
+
+/// Instruction discriminators of the Token Metadata program.
+pub const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+pub const CREATE_MASTER_EDITION_V3: u8 = 17;
+
+pub fn find_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED, ID.as_ref(), mint.as_ref()], &ID)
+}
+
+pub fn find_master_edition_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[METADATA_SEED, ID.as_ref(), mint.as_ref(), EDITION_SEED],
+        &ID,
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct Collection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+/// The leading fields of a metadata account, up to and including
+/// `collection`. Anything after it is ignored.
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct Metadata {
+    pub key: u8,
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    pub token_standard: Option<u8>,
+    pub collection: Option<Collection>,
+}
+
+impl Metadata {
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let metadata =
+            Self::deserialize(&mut &data[..]).map_err(|_| ProgramError::InvalidAccountData)?;
+        if metadata.key != METADATA_V1_KEY {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(metadata)
+    }
+
+    /// The collection this NFT is a verified member of, if any.
+    pub fn verified_collection(&self) -> Option<&Pubkey> {
+        self.collection
+            .as_ref()
+            .filter(|collection| collection.verified)
+            .map(|collection| &collection.key)
+    }
+}
+
+#[derive(BorshSerialize)]
+struct DataV2<'a> {
+    name: &'a str,
+    symbol: &'a str,
+    uri: &'a str,
+    seller_fee_basis_points: u16,
+    creators: Option<Vec<Creator>>,
+    collection: Option<Collection>,
+    // Always `None`, which Borsh writes as a single zero byte whatever the
+    // type, so the Metaplex `Uses` and `CollectionDetails` aren't mirrored.
+    uses: Option<()>,
+}
+
+#[derive(BorshSerialize)]
+struct CreateMetadataAccountArgsV3<'a> {
+    data: DataV2<'a>,
+    is_mutable: bool,
+    collection_details: Option<()>,
+}
+
+/// `CreateMetadataAccountV3` for an immutable NFT without creators,
+/// collection or royalties.
+#[allow(clippy::too_many_arguments)]
+pub fn create_metadata_account_v3(
+    metadata: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    payer: &Pubkey,
+    update_authority: &Pubkey,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Instruction {
+    let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+    CreateMetadataAccountArgsV3 {
+        data: DataV2 {
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        },
+        is_mutable: false,
+        collection_details: None,
+    }
+    .serialize(&mut data)
+    .expect("serializing into a Vec");
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*update_authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// `CreateMasterEditionV3` capping prints at `max_supply`. Token Metadata
+/// takes over the mint and freeze authorities.
+pub fn create_master_edition_v3(
+    edition: &Pubkey,
+    mint: &Pubkey,
+    update_authority: &Pubkey,
+    mint_authority: &Pubkey,
+    payer: &Pubkey,
+    metadata: &Pubkey,
+    max_supply: Option<u64>,
+) -> Instruction {
+    let mut data = vec![CREATE_MASTER_EDITION_V3];
+    max_supply
+        .serialize(&mut data)
+        .expect("serializing into a Vec");
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*edition, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*update_authority, true),
+            AccountMeta::new_readonly(*mint_authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn metadata(collection: Option<Collection>) -> Metadata {
+        Metadata {
+            key: METADATA_V1_KEY,
+            update_authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            name: "Counter #1".to_string(),
+            symbol: "CNT".to_string(),
+            uri: "https://example.com/1.json".to_string(),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![Creator {
+                address: Pubkey::new_unique(),
+                verified: true,
+                share: 100,
+            }]),
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: Some(255),
+            token_standard: Some(0),
+            collection,
+        }
+    }
+
+    #[test]
+    fn test_unpack_ignores_trailing_fields() {
+        let key = Pubkey::new_unique();
+        let expected = metadata(Some(Collection {
+            verified: true,
+            key,
+        }));
+        let mut data = borsh::to_vec(&expected).unwrap();
+        data.extend([0; 64]);
+        let unpacked = Metadata::unpack(&data).unwrap();
+        assert_eq!(unpacked, expected);
+        assert_eq!(unpacked.verified_collection(), Some(&key));
+    }
+
+    #[test]
+    fn test_unverified_collection() {
+        let unverified = metadata(Some(Collection {
+            verified: false,
+            key: Pubkey::new_unique(),
+        }));
+        assert_eq!(unverified.verified_collection(), None);
+        assert_eq!(metadata(None).verified_collection(), None);
+    }
+
+    #[test]
+    fn test_unpack_rejects_other_accounts() {
+        let mut data = borsh::to_vec(&metadata(None)).unwrap();
+        data[0] = 6;
+        assert_eq!(
+            Metadata::unpack(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(Metadata::unpack(&[]), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_create_metadata_account_v3_data() {
+        let key = Pubkey::new_unique();
+        let instruction =
+            create_metadata_account_v3(&key, &key, &key, &key, &key, "Counter", "CNT", "uri");
+        let mut expected = vec![CREATE_METADATA_ACCOUNT_V3];
+        for field in ["Counter", "CNT", "uri"] {
+            expected.extend((field.len() as u32).to_le_bytes());
+            expected.extend(field.as_bytes());
+        }
+        // Royalties, then no creators, collection or uses, immutable, and no
+        // collection details.
+        expected.extend([0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(instruction.data, expected);
+    }
+}
@@ -0,0 +1,62 @@
+//! Upgrades of counter accounts from the layouts older builds wrote.
+//!
+//! A counter records its layout in [`CounterAccount::layout_version`].
+//! `MigrateAccount` runs the migrations from that version up to
+//! [`COUNTER_LAYOUT_VERSION`] in order, first growing the account to the
+//! length each needs, zero-filled. A layout change bumps the version and
+//! appends its migration to [`MIGRATIONS`].
+
+use solana_program::program_error::ProgramError;
+
+use crate::state::{CounterAccount, COUNTER_DISCRIMINATOR, COUNTER_LAYOUT_VERSION};
+
+/// The upgrade of a counter from one layout to the next.
+pub struct Migration {
+    /// Bytes the account needs in the next layout.
+    pub len: usize,
+    /// Rewrites the data, already at least `len` long, in the next layout.
+    pub upgrade: fn(&mut [u8]) -> Result<(), ProgramError>,
+}
+
+/// `MIGRATIONS[v]` upgrades layout `v` to `v + 1`.
+pub const MIGRATIONS: [Migration; COUNTER_LAYOUT_VERSION as usize] = [Migration {
+    len: CounterAccount::LEN,
+    upgrade: mark_discriminator,
+}];
+
+/// The layout `data` was written in.
+pub fn layout_version(data: &[u8]) -> u8 {
+    data.get(CounterAccount::LAYOUT_VERSION_OFFSET)
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Layout 0 covers every counter from before layouts were versioned. Fields
+/// only ever went at the end, so those the shorter ones lack read as unset
+/// once zero-filled; what they may lack is the discriminator scans match on.
+fn mark_discriminator(data: &mut [u8]) -> Result<(), ProgramError> {
+    CounterAccount::load_mut(data)?.discriminator = COUNTER_DISCRIMINATOR;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_migrate_layout_0() {
+        // A counter from an early, shorter layout, holding 7.
+        let mut data = vec![0; 512];
+        data[0] = 7;
+        assert_eq!(layout_version(&data), 0);
+
+        for migration in &MIGRATIONS[usize::from(layout_version(&data))..] {
+            data.resize(data.len().max(migration.len), 0);
+            (migration.upgrade)(&mut data).unwrap();
+        }
+        let account = CounterAccount::unpack(&data).unwrap();
+        assert_eq!(account.counter, 7);
+        assert_eq!(account.discriminator, COUNTER_DISCRIMINATOR);
+        assert_eq!(account.history_capacity, 0);
+    }
+}
@@ -0,0 +1,489 @@
+//! Program entrypoint used with the `pinocchio` feature.
+//!
+//! Increment and Decrement, the instructions nearly every transaction sends,
+//! are served from Pinocchio's allocation-free view of the accounts. Anything
+//! else goes through `solana_program` and [`crate::process_instruction`]
+//! exactly as with the default entrypoint.
+//!
+//! Pinocchio rewrites the duplicate-account markers in the input while it
+//! parses, so the input can only be deserialized once. The entrypoint first
+//! walks it read-only to find the instruction data and then picks a side.
+//! Calls passing more than [`MAX_ACCOUNTS`], such as those paying a fee, and
+//! counters in older layouts shorter than [`CounterAccount::LEN`] take the
+//! regular path.
+
+use core::mem::{size_of, MaybeUninit};
+
+use pinocchio::account_info::AccountInfo;
+use solana_program::{
+    clock::Clock,
+    entrypoint::{
+        ProgramResult, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER, SUCCESS,
+    },
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::logging::{log_compute_units, verbose};
+use crate::state::{OVERFLOW_CHECKED, OVERFLOW_SATURATING};
+use crate::{error::CounterError, events::CounterEvent, CounterAccount};
+
+/// The counter, the optional signer after it and the caller's quota for
+/// counters with a daily cap, the history for counters keeping one, the
+/// config for governed counters, the referrer of a referred increment, the
+/// lease of a listed counter, the signer's credit balance in credit mode or
+/// cooldown account on a counter with a cooldown, or the boost escrow or
+/// stake of a boosted or stake-weighted one.
+const MAX_ACCOUNTS: usize = 3;
+
+/// Tag byte plus a little-endian `u32` and the overflow mode, the Borsh
+/// encoding of `IncrementArgs`/`DecrementArgs`.
+const FAST_PATH_DATA_LEN: usize = 1 + size_of::<u32>() + 1;
+
+/// # Safety
+///
+/// Called by the runtime with its serialized input.
+#[no_mangle]
+pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
+    let (num_accounts, counter_len, instruction_data) = peek_instruction_data(input);
+    if num_accounts <= MAX_ACCOUNTS
+        && counter_len >= CounterAccount::LEN
+        && is_fast_path(instruction_data)
+    {
+        const UNINIT: MaybeUninit<AccountInfo> = MaybeUninit::uninit();
+        let mut accounts = [UNINIT; MAX_ACCOUNTS];
+        let (program_id, count, instruction_data) =
+            pinocchio::entrypoint::deserialize::<MAX_ACCOUNTS>(input, &mut accounts);
+        let accounts = core::slice::from_raw_parts(accounts.as_ptr() as *const AccountInfo, count);
+        log_compute_units!("Counter program start");
+        let result = process_fast_path(program_id, accounts, instruction_data);
+        log_compute_units!("Counter program end");
+        return match result {
+            Ok(()) => SUCCESS,
+            Err(error) => error.into(),
+        };
+    }
+
+    let (program_id, accounts, instruction_data) = solana_program::entrypoint::deserialize(input);
+    match crate::process_instruction(program_id, &accounts, instruction_data) {
+        Ok(()) => SUCCESS,
+        Err(error) => error.into(),
+    }
+}
+
+solana_program::custom_heap_default!();
+solana_program::custom_panic_default!();
+
+/// Reads the number of accounts and the data length of the first, and
+/// locates the instruction data without modifying the input.
+unsafe fn peek_instruction_data<'a>(input: *const u8) -> (usize, usize, &'a [u8]) {
+    let mut offset = 0;
+    #[allow(clippy::cast_ptr_alignment)]
+    let num_accounts = *(input as *const u64) as usize;
+    offset += size_of::<u64>();
+
+    let mut first_data_len = 0;
+    for index in 0..num_accounts {
+        if *input.add(offset) == NON_DUP_MARKER {
+            // Duplicate flag, is_signer, is_writable, executable, original
+            // data length, key and owner, lamports.
+            offset += 4 + size_of::<u32>() + 2 * size_of::<Pubkey>() + size_of::<u64>();
+            #[allow(clippy::cast_ptr_alignment)]
+            let data_len = *(input.add(offset) as *const u64) as usize;
+            if index == 0 {
+                first_data_len = data_len;
+            }
+            offset += size_of::<u64>() + data_len + MAX_PERMITTED_DATA_INCREASE;
+            offset += input.add(offset).align_offset(BPF_ALIGN_OF_U128);
+            // Rent epoch.
+            offset += size_of::<u64>();
+        } else {
+            offset += size_of::<u64>();
+        }
+    }
+
+    #[allow(clippy::cast_ptr_alignment)]
+    let instruction_data_len = *(input.add(offset) as *const u64) as usize;
+    offset += size_of::<u64>();
+    (
+        num_accounts,
+        first_data_len,
+        core::slice::from_raw_parts(input.add(offset), instruction_data_len),
+    )
+}
+
+/// Only plain one-byte-tag Increment/Decrement, and IncrementOne and
+/// DecrementOne (109 and 110) with no arguments at all, take the fast path;
+/// Anchor discriminators are eight bytes and can never match.
+fn is_fast_path(instruction_data: &[u8]) -> bool {
+    match instruction_data {
+        [109 | 110] => true,
+        [0 | 1, ..] => instruction_data.len() == FAST_PATH_DATA_LEN,
+        _ => false,
+    }
+}
+
+/// The tag, 0 for an increment or 1 for a decrement, the value and the mode.
+fn parse_fast_path(instruction_data: &[u8]) -> Result<(u8, u32, u8), ProgramError> {
+    // Tag-only forms step by one, checked up or saturating down as
+    // `sdk::increment` and `sdk::decrement` do.
+    match instruction_data {
+        [109] => return Ok((0, 1, OVERFLOW_CHECKED)),
+        [110] => return Ok((1, 1, OVERFLOW_SATURATING)),
+        _ => {}
+    }
+    let (&tag, args) = instruction_data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let (&mode, value) = args
+        .split_last()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let value = u32::from_le_bytes(
+        value
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+    Ok((tag, value, mode))
+}
+
+fn process_fast_path(
+    program_id: &pinocchio::pubkey::Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    verbose!("Counter program entry point");
+
+    let (tag, value, mode) = parse_fast_path(instruction_data)?;
+
+    let (account, rest) = accounts
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !account.is_owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !account.is_writable() {
+        return Err(CounterError::CounterNotWritable.into());
+    }
+    let mut data = account
+        .try_borrow_mut_data()
+        .map_err(|_| ProgramError::AccountBorrowFailed)?;
+    let counter_account = CounterAccount::load_mut(&mut data)?;
+    if counter_account.is_renounced() {
+        return Err(CounterError::Renounced.into());
+    }
+    if counter_account.is_paused() {
+        return Err(CounterError::Paused.into());
+    }
+    if counter_account.is_frozen() {
+        return Err(CounterError::Frozen.into());
+    }
+    if counter_account.has_expiry() && counter_account.is_expired(Clock::get()?.slot) {
+        return Err(CounterError::Expired.into());
+    }
+    crate::processor::check_countdown(counter_account, tag == 1)?;
+    if counter_account.fee_lamports != 0
+        || counter_account.is_nft_gated()
+        || (tag == 0
+            && (counter_account.requires_payment()
+                || counter_account.has_milestone_reward()
+                || (counter_account.has_boost() && counter_account.is_stake_weighted())))
+    {
+        // The fee, payment, NFT gate or reward accounts, or a boost escrow
+        // next to a stake account, would have sent this call down the
+        // regular path.
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    // Governed counters pass the config after everything else.
+    let rest = if counter_account.is_governed() {
+        let (config, rest) = rest
+            .split_last()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        crate::processor::check_config(
+            bytemuck::cast_ref(program_id),
+            bytemuck::cast_ref(config.key()),
+            config.is_owned_by(program_id),
+            &config
+                .try_borrow_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+        rest
+    } else {
+        rest
+    };
+    // The history goes after any accounts the checks below take.
+    let (rest, history) = if counter_account.has_history() {
+        let (history, rest) = rest
+            .split_last()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        (rest, Some(history))
+    } else {
+        (rest, None)
+    };
+    // Increments then pass the referrer, ahead of the history.
+    let (rest, referrer) = if tag == 0 && counter_account.has_referrals() {
+        let (referrer, rest) = rest
+            .split_last()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        (
+            rest,
+            Some(referrer).filter(|referrer| referrer.key() != program_id),
+        )
+    } else {
+        (rest, None)
+    };
+    // Listed counters then pass the lease, ahead of any referrer, and a
+    // running lease's lessee stands in for the authority.
+    let (rest, owner) = if counter_account.is_listed_for_lease() {
+        let (lease, rest) = rest
+            .split_last()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let lessee = crate::processor::active_lessee(
+            bytemuck::cast_ref(program_id),
+            bytemuck::cast_ref(account.key()),
+            bytemuck::cast_ref(lease.key()),
+            lease.is_owned_by(program_id),
+            &lease
+                .try_borrow_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let owner = lessee.map(|lessee| core::mem::replace(&mut counter_account.authority, lessee));
+        (rest, owner)
+    } else {
+        (rest, None)
+    };
+    // Credit mode then takes the signer's credit balance, ahead of any lease.
+    let (rest, credits) = if counter_account.has_credits() {
+        let (credits, rest) = rest
+            .split_last()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        (rest, Some(credits))
+    } else {
+        (rest, None)
+    };
+    // A cooldown then takes the signer's cooldown account, ahead of any
+    // credit balance.
+    let (rest, cooldown) = if counter_account.has_cooldown() {
+        let (cooldown, rest) = rest
+            .split_last()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        (rest, Some(cooldown))
+    } else {
+        (rest, None)
+    };
+    // Pinocchio's `Pubkey` is a bare `[u8; 32]`, which `Pubkey` wraps.
+    let signer = rest.first().map(|signer| {
+        (
+            bytemuck::cast_ref::<_, Pubkey>(signer.key()),
+            signer.is_signer(),
+        )
+    });
+    if let Some(cooldown) = cooldown {
+        crate::processor::record_cooldown(
+            bytemuck::cast_ref(account.key()),
+            counter_account,
+            signer,
+            cooldown.is_owned_by(program_id),
+            &mut cooldown
+                .try_borrow_mut_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+    }
+    if let Some(credits) = credits {
+        crate::processor::spend_credit(
+            bytemuck::cast_ref(program_id),
+            bytemuck::cast_ref(account.key()),
+            signer,
+            bytemuck::cast_ref(credits.key()),
+            credits.is_owned_by(program_id),
+            &mut credits
+                .try_borrow_mut_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+    }
+    if let Some(referrer) = referrer {
+        crate::processor::record_referral(
+            bytemuck::cast_ref(program_id),
+            bytemuck::cast_ref(account.key()),
+            counter_account,
+            signer,
+            bytemuck::cast_ref(referrer.key()),
+            referrer.is_owned_by(program_id),
+            &mut referrer
+                .try_borrow_mut_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+            value,
+        )?;
+    }
+    if counter_account.has_blocklist() {
+        // The ban entry address follows the signer and any quota.
+        let entry = rest
+            .get(1..)
+            .and_then(<[_]>::last)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        crate::processor::check_not_banned(
+            bytemuck::cast_ref(program_id),
+            bytemuck::cast_ref(account.key()),
+            signer,
+            bytemuck::cast_ref(entry.key()),
+            entry.is_owned_by(program_id),
+            &entry
+                .try_borrow_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+    }
+    if counter_account.is_allowlisted() {
+        // The entry follows the signer and any quota.
+        let entry = rest
+            .get(1..)
+            .and_then(<[_]>::last)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        crate::processor::check_allowlisted(
+            bytemuck::cast_ref(account.key()),
+            signer,
+            entry.is_owned_by(program_id),
+            &entry
+                .try_borrow_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+    }
+    if counter_account.has_eth_authority() {
+        // The Instructions sysvar is the last account.
+        let instructions = rest.last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        crate::processor::check_eth_signature(
+            bytemuck::cast_ref(account.key()),
+            counter_account,
+            bytemuck::cast_ref(instructions.key()),
+            &instructions
+                .try_borrow_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+    }
+    if counter_account.requires_memo() {
+        // The Instructions sysvar is the last account.
+        let instructions = rest.last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        crate::processor::check_memo_present(
+            bytemuck::cast_ref(instructions.key()),
+            &instructions
+                .try_borrow_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+    }
+    if tag == 0 && counter_account.has_offchain_signer() {
+        // The Instructions sysvar follows the signer.
+        let instructions = rest.last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        crate::processor::check_offchain_signature(
+            bytemuck::cast_ref(account.key()),
+            counter_account,
+            bytemuck::cast_ref(instructions.key()),
+            &instructions
+                .try_borrow_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+    }
+    let amount = if tag == 0 && counter_account.has_boost() {
+        // The escrow, or the program ID for none, follows the signer.
+        let escrow = rest.last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let factor = if escrow.key() == program_id {
+            1
+        } else {
+            crate::processor::boost_factor(
+                bytemuck::cast_ref(program_id),
+                bytemuck::cast_ref(account.key()),
+                counter_account,
+                signer,
+                bytemuck::cast_ref(escrow.key()),
+                escrow.is_owned_by(program_id),
+                &escrow
+                    .try_borrow_data()
+                    .map_err(|_| ProgramError::AccountBorrowFailed)?,
+                Clock::get()?.unix_timestamp,
+            )?
+        };
+        u64::from(value)
+            .checked_mul(factor)
+            .ok_or(CounterError::Overflow)?
+    } else if tag == 0 && counter_account.is_stake_weighted() {
+        // The stake account follows the signer.
+        let stake = rest.last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let weight = crate::processor::stake_weight(
+            stake.is_owned_by(bytemuck::cast_ref(&solana_program::stake::program::ID)),
+            &stake
+                .try_borrow_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+            signer,
+            Clock::get()?.epoch,
+        )?;
+        u64::from(value)
+            .checked_mul(weight)
+            .ok_or(CounterError::Overflow)?
+    } else {
+        value.into()
+    };
+    let writer = match signer {
+        Some((key, true)) => *key,
+        _ => Pubkey::default(),
+    };
+    crate::processor::prepare_write(counter_account, &writer)?;
+    let (old, old_value) = (counter_account.counter, counter_account.value());
+    let authority = counter_account.authority;
+    let mutator = crate::processor::mutator(counter_account, &authority, signer);
+    let event = match tag {
+        0 => {
+            counter_account.increment(mutator, amount, mode)?;
+            if counter_account.has_daily_cap() {
+                let quota = rest.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                crate::processor::charge_daily_cap(
+                    bytemuck::cast_ref(account.key()),
+                    counter_account,
+                    signer,
+                    quota.is_owned_by(program_id),
+                    &mut quota
+                        .try_borrow_mut_data()
+                        .map_err(|_| ProgramError::AccountBorrowFailed)?,
+                    amount,
+                )?;
+            }
+            CounterEvent::Incremented {
+                old,
+                new: counter_account.counter,
+                by: u32::try_from(amount).unwrap_or(u32::MAX),
+            }
+        }
+        _ => {
+            counter_account.decrement(mutator, value.into(), mode)?;
+            CounterEvent::Decremented {
+                old,
+                new: counter_account.counter,
+                by: value,
+            }
+        }
+    };
+    if let Some(owner) = owner {
+        counter_account.authority = owner;
+    }
+    crate::processor::typed_event(counter_account, old_value, event).emit();
+    if tag == 1 {
+        crate::processor::complete_countdown(counter_account);
+    }
+    #[cfg(feature = "kv-logs")]
+    crate::processor::log_kv(
+        bytemuck::cast_ref(program_id),
+        bytemuck::cast_ref(account.key()),
+        if tag == 0 { "increment" } else { "decrement" },
+        old_value,
+        counter_account,
+    );
+    if let Some(history) = history {
+        crate::processor::record_history(
+            bytemuck::cast_ref(account.key()),
+            counter_account,
+            history.is_owned_by(program_id),
+            &mut history
+                .try_borrow_mut_data()
+                .map_err(|_| ProgramError::AccountBorrowFailed)?,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,5903 @@
+use std::cell::RefMut;
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    native_token::LAMPORTS_PER_SOL,
+    program::{invoke, invoke_signed, set_return_data},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::{Pubkey, MAX_SEED_LEN},
+    rent::Rent,
+    stake::{self, state::StakeStateV2},
+    system_instruction,
+    sysvar::{self, Sysvar},
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::compression;
+use crate::ed25519;
+use crate::error::CounterError;
+use crate::events::CounterEvent;
+use crate::governance;
+use crate::instructions::{
+    AddToAllowlistArgs, ApproveArgs, BanCallerArgs, BuyCreditsArgs, ClaimMilestoneNftArgs,
+    CloneArgs, ConfigureCrankArgs, ConfigureMilestoneNftArgs, ConfigureMilestoneRewardArgs,
+    ConfigureMultisigArgs, ConfigureOracleArgs, ConfigurePaymentArgs, ConfigureWormholeArgs,
+    CounterInstructions, DecrementArgs, IncrementArgs, IncrementIfBelowArgs, InitializeArrayArgs,
+    InitializeCompressedTreeArgs, InitializeConfigArgs, InitializeHistoryArgs, InitializeKeyedArgs,
+    InitializeLeaderboardArgs, InitializeNamedArgs, ListForLeaseArgs, LockBoostArgs,
+    ProposeAuthorityArgs, ReallocArgs, RemoveFromAllowlistArgs, RequestRandomIncrementArgs,
+    ResizeKeyedArgs, SetAllowlistEnabledArgs, SetAuthorityArgs, SetBlocklistEnabledArgs,
+    SetBoostArgs, SetCooldownArgs, SetCountdownArgs, SetCreditPriceArgs, SetDailyCapArgs,
+    SetDecayArgs, SetDecimalsArgs, SetEpochScopedArgs, SetEthAuthorityArgs, SetExpiryArgs,
+    SetFeeArgs, SetGovernanceArgs, SetKindArgs, SetMemoRequiredArgs, SetModulusArgs,
+    SetNftGateArgs, SetOffchainSignerArgs, SetRateLimitArgs, SetReferralsArgs,
+    SetStakeWeightedArgs, SetStepArgs, SplitArgs, TransferArgs, UnbanCallerArgs, UpdateArgs,
+    UpdateConfigArgs, WithdrawVaultArgs,
+};
+use crate::logging::{log_compute_units, verbose};
+use crate::memo;
+use crate::metadata::{self, Metadata};
+use crate::migration::{self, MIGRATIONS};
+use crate::pyth::{self, PriceUpdateV2, VerificationLevel};
+use crate::secp256k1;
+use crate::state::{
+    eth_message, find_allowlist_address, find_ban_address, find_boost_address,
+    find_checkpoint_address, find_config_address, find_cooldown_address, find_counter_address,
+    find_credits_address, find_history_address, find_leaderboard_address, find_lease_address,
+    find_nft_authority_address, find_quota_address, find_received_address, find_referrer_address,
+    find_registry_address, find_reward_authority_address, find_tree_authority_address,
+    find_vault_address, find_vault_authority_address, offchain_message, rescale, AllowlistEntry,
+    BanEntry, BoostEscrow, CallerCooldown, CallerQuota, Checkpoint, CounterAccount,
+    CounterArrayHeader, CounterValue, CreditBalance, HistoryEntry, HistoryHeader,
+    KeyedCounterHeader, LeaderboardHeader, Lease, ProgramConfig, ReceivedVaa, ReferrerEntry,
+    RegistryEntry, RegistryHeader, ALLOWLIST_SEED, BAN_SEED, BOOST_SEED, CHECKPOINT_SEED,
+    CONFIG_SEED, COOLDOWN_SECONDS, COOLDOWN_SEED, COOLDOWN_SLOTS, COUNTDOWN_COMPLETED,
+    COUNTDOWN_OFF, COUNTDOWN_RUNNING, COUNTER_LAYOUT_VERSION, COUNTER_SEED, CREDITS_SEED,
+    HISTORY_SEED, KIND_U128, LEADERBOARD_SEED, LEASE_SEED, MAX_SIGNERS, NFT_SEED, OVERFLOW_CHECKED,
+    OVERFLOW_SATURATING, QUOTA_SEED, REAPER_SHARE_BPS, RECEIVED_SEED, REFERRER_SEED, REGISTRY_SEED,
+    REWARD_SEED, TREASURY_SEED, TREE_AUTHORITY_SEED, VAULT_SEED,
+};
+use crate::switchboard::{self, RandomnessAccount};
+use crate::token;
+use crate::wormhole::{self, CounterMessage, PostedVaa};
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instructions_data: &[u8],
+) -> ProgramResult {
+    verbose!("Counter program entry point");
+    log_compute_units!("Counter program start");
+    let result = dispatch(program_id, accounts, instructions_data);
+    log_compute_units!("Counter program end");
+    result
+}
+
+fn dispatch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instructions_data: &[u8],
+) -> ProgramResult {
+    let instruction: CounterInstructions = CounterInstructions::unpack(instructions_data)?;
+
+    let (account, accounts) = accounts
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    // Named counters don't exist yet, and the config, counter arrays and
+    // keyed counters aren't counters, so there is no counter state to load.
+    let instruction = match instruction {
+        CounterInstructions::InitializeNamed(args) => {
+            return process_initialize_named(program_id, account, accounts, args)
+        }
+        CounterInstructions::InitializeConfig(args) => {
+            return process_initialize_config(program_id, account, accounts, args)
+        }
+        CounterInstructions::UpdateConfig(args) => {
+            return process_update_config(program_id, account, accounts, args)
+        }
+        CounterInstructions::InitializeArray(args) => {
+            return process_initialize_array(program_id, account, accounts, args)
+        }
+        CounterInstructions::IncrementAt(args) => {
+            return process_array_write(program_id, account, accounts, args.index, |old| {
+                old.checked_add(args.value).ok_or(CounterError::Overflow)
+            })
+        }
+        CounterInstructions::DecrementAt(args) => {
+            return process_array_write(program_id, account, accounts, args.index, |old| {
+                old.checked_sub(args.value).ok_or(CounterError::Underflow)
+            })
+        }
+        CounterInstructions::UpdateAt(args) => {
+            return process_array_write(program_id, account, accounts, args.index, |_| {
+                Ok(args.value)
+            })
+        }
+        CounterInstructions::InitializeKeyed(args) => {
+            return process_initialize_keyed(program_id, account, accounts, args)
+        }
+        CounterInstructions::IncrementKeyed(args) => {
+            return process_keyed_write(program_id, account, accounts, &args.key, |old| {
+                old.unwrap_or(0)
+                    .checked_add(args.value)
+                    .map(Some)
+                    .ok_or(CounterError::Overflow)
+            })
+        }
+        CounterInstructions::DecrementKeyed(args) => {
+            return process_keyed_write(program_id, account, accounts, &args.key, |old| {
+                old.ok_or(CounterError::KeyNotFound)?
+                    .checked_sub(args.value)
+                    .map(Some)
+                    .ok_or(CounterError::Underflow)
+            })
+        }
+        CounterInstructions::RemoveKeyed(args) => {
+            return process_keyed_write(program_id, account, accounts, &args.key, |old| {
+                old.map(|_| None).ok_or(CounterError::KeyNotFound)
+            })
+        }
+        CounterInstructions::ResizeKeyed(args) => {
+            return process_resize_keyed(program_id, account, accounts, args)
+        }
+        CounterInstructions::InitializeCompressedTree(args) => {
+            return process_initialize_compressed_tree(program_id, account, accounts, args)
+        }
+        CounterInstructions::CreateCompressed => {
+            return process_create_compressed(program_id, account, accounts)
+        }
+        CounterInstructions::IncrementCompressed(args) => {
+            return process_compressed_write(
+                program_id,
+                account,
+                accounts,
+                &args.root,
+                args.index,
+                args.value,
+                |value| value.checked_add(args.by).ok_or(CounterError::Overflow),
+            )
+        }
+        CounterInstructions::DecrementCompressed(args) => {
+            return process_compressed_write(
+                program_id,
+                account,
+                accounts,
+                &args.root,
+                args.index,
+                args.value,
+                |value| value.checked_sub(args.by).ok_or(CounterError::Underflow),
+            )
+        }
+        // Counters in older layouts may be too short to load.
+        CounterInstructions::MigrateAccount => return process_migrate_account(account, accounts),
+        // Referrers keep their fees whatever became of the counter.
+        CounterInstructions::WithdrawReferralFees => {
+            return process_withdraw_referral_fees(program_id, account.key, accounts)
+        }
+        CounterInstructions::InitializeLeaderboard(args) => {
+            return process_initialize_leaderboard(program_id, account, accounts, args)
+        }
+        // Owners get their lamports back whatever became of the counter.
+        CounterInstructions::UnlockBoost => {
+            return process_unlock_boost(program_id, account.key, accounts)
+        }
+        // The one-byte forms are plain Increments and Decrements from here on,
+        // with the modes `sdk::increment` and `sdk::decrement` use.
+        CounterInstructions::IncrementOne => CounterInstructions::Increment(IncrementArgs {
+            value: 1,
+            mode: OVERFLOW_CHECKED,
+        }),
+        CounterInstructions::DecrementOne => CounterInstructions::Decrement(DecrementArgs {
+            value: 1,
+            mode: OVERFLOW_SATURATING,
+        }),
+        instruction => instruction,
+    };
+
+    // Anyone can lay out a counter in an account of their own program, and
+    // nothing in the data tells it apart.
+    if account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    // Initialize would write the authority past the end of a legacy account,
+    // which is stored back at its own length.
+    if matches!(instruction, CounterInstructions::Initialize)
+        && account.data_len() < CounterAccount::LEN
+    {
+        return Err(CounterError::MigrationRequired.into());
+    }
+    // The runtime would only reject the write once the instruction is done.
+    if writes_counter(&instruction) && !account.is_writable {
+        return Err(CounterError::CounterNotWritable.into());
+    }
+    let mut data = account.data.borrow_mut();
+    // Counters in older, shorter layouts are served from a copy, stored back
+    // once the instruction succeeds.
+    let mut legacy = if data.len() < CounterAccount::LEN {
+        Some(CounterAccount::unpack(&data)?)
+    } else {
+        None
+    };
+    let counter_account = match legacy.as_mut() {
+        Some(counter_account) => counter_account,
+        None => CounterAccount::load_mut(&mut data)?,
+    };
+    if counter_account.is_renounced()
+        && !matches!(
+            instruction,
+            CounterInstructions::Get
+                | CounterInstructions::GetStats
+                | CounterInstructions::Log
+                | CounterInstructions::Checkpoint
+                | CounterInstructions::SubmitScore
+        )
+    {
+        return Err(CounterError::Renounced.into());
+    }
+
+    if writes_value(&instruction)
+        && counter_account.has_expiry()
+        && counter_account.is_expired(Clock::get()?.slot)
+    {
+        return Err(CounterError::Expired.into());
+    }
+
+    // SetCountdown is what re-arms a completed countdown.
+    if writes_value(&instruction) && !matches!(instruction, CounterInstructions::SetCountdown(_)) {
+        check_countdown(counter_account, decrements(&instruction))?;
+    }
+
+    #[cfg(feature = "kv-logs")]
+    let kv_log = value_op(&instruction).map(|op| (op, counter_account.value()));
+
+    // Governed counters pass the config after everything else.
+    let accounts = if counter_account.is_governed() && writes_value(&instruction) {
+        let (rest, [config]) = split_trailing(accounts)?;
+        check_config(
+            program_id,
+            config.key,
+            config.owner == program_id,
+            &config.try_borrow_data()?,
+        )?;
+        rest
+    } else {
+        accounts
+    };
+
+    // Writes pass the history after any accounts the gates below take.
+    let (accounts, history) = if counter_account.has_history() && writes_value(&instruction) {
+        let (rest, [history]) = split_trailing(accounts)?;
+        (rest, Some(history))
+    } else {
+        (accounts, None)
+    };
+
+    // Increments then pass the referrer, ahead of the history.
+    let (accounts, referrer) = match &instruction {
+        CounterInstructions::Increment(args) if counter_account.has_referrals() => {
+            let (rest, [referrer]) = split_trailing(accounts)?;
+            let referrer = (referrer.key != program_id).then_some(referrer);
+            if let Some(referrer) = referrer {
+                record_referral(
+                    program_id,
+                    account.key,
+                    counter_account,
+                    next_signer(rest),
+                    referrer.key,
+                    referrer.owner == program_id,
+                    &mut referrer.try_borrow_mut_data()?,
+                    args.value,
+                )?;
+            }
+            (rest, referrer)
+        }
+        _ => (accounts, None),
+    };
+
+    // Mutations of listed counters then pass the lease, ahead of any referrer.
+    // While a lease runs its lessee stands in for the authority until the
+    // instruction is done.
+    let (accounts, owner) = if counter_account.is_listed_for_lease() && is_mutation(&instruction) {
+        let (rest, [lease]) = split_trailing(accounts)?;
+        let lessee = active_lessee(
+            program_id,
+            account.key,
+            lease.key,
+            lease.owner == program_id,
+            &lease.try_borrow_data()?,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let owner = lessee.map(|lessee| std::mem::replace(&mut counter_account.authority, lessee));
+        (rest, owner)
+    } else {
+        (accounts, None)
+    };
+
+    // Nor can the owner take away what a running lease was paid for; ending
+    // it early refunds the lessee.
+    let accounts = if counter_account.is_listed_for_lease() && overrides_lease(&instruction) {
+        let (rest, [lease]) = split_trailing(accounts)?;
+        if active_lessee(
+            program_id,
+            account.key,
+            lease.key,
+            lease.owner == program_id,
+            &lease.try_borrow_data()?,
+            Clock::get()?.unix_timestamp,
+        )?
+        .is_some()
+        {
+            return Err(CounterError::LeaseActive.into());
+        }
+        rest
+    } else {
+        accounts
+    };
+
+    let accounts = if is_mutation(&instruction) {
+        let accounts = check_cooldown(program_id, account.key, counter_account, accounts)?;
+        let accounts = charge_credit(program_id, account.key, counter_account, accounts)?;
+        let accounts = charge_fee(program_id, account.key, counter_account, accounts, referrer)?;
+        let accounts = check_blocklist(program_id, account.key, counter_account, accounts)?;
+        let accounts = check_allowlist(program_id, account.key, counter_account, accounts)?;
+        let accounts = check_eth_authorization(account.key, counter_account, accounts)?;
+        check_memo(counter_account, accounts)?
+    } else {
+        accounts
+    };
+
+    match instruction {
+        CounterInstructions::Increment(_)
+        | CounterInstructions::Decrement(_)
+        | CounterInstructions::Update(_)
+        | CounterInstructions::Reset
+        | CounterInstructions::UpdateFromOracle
+        | CounterInstructions::RequestRandomIncrement(_)
+        | CounterInstructions::SettleRandomIncrement
+        | CounterInstructions::ReceiveWormholeMessage
+        | CounterInstructions::Crank
+        | CounterInstructions::Merge
+        | CounterInstructions::Split(_)
+        | CounterInstructions::Transfer(_)
+        | CounterInstructions::IncrementWide(_)
+        | CounterInstructions::DecrementWide(_)
+        | CounterInstructions::IncrementScaled(_)
+        | CounterInstructions::DecrementScaled(_)
+        | CounterInstructions::IncrementIfBelow(_)
+        | CounterInstructions::And(_)
+        | CounterInstructions::Or(_)
+        | CounterInstructions::Xor(_)
+        | CounterInstructions::ShiftLeft(_)
+        | CounterInstructions::ShiftRight(_)
+            if counter_account.is_paused() =>
+        {
+            Err(CounterError::Paused.into())
+        }
+        _ if counter_account.is_frozen() && writes_value(&instruction) => {
+            Err(CounterError::Frozen.into())
+        }
+        _ if !counter_account.is_u32() && requires_u32(&instruction) => {
+            Err(CounterError::UnsupportedKind.into())
+        }
+        CounterInstructions::Increment(args) => process_increment(
+            program_id,
+            account.key,
+            counter_account,
+            accounts,
+            args.value.into(),
+            args.mode,
+        ),
+        CounterInstructions::Decrement(args) => {
+            process_decrement(counter_account, accounts, args.value.into(), args.mode)
+        }
+        CounterInstructions::Update(args) => process_update(counter_account, accounts, args),
+        CounterInstructions::Reset => process_reset(counter_account, accounts),
+        CounterInstructions::Initialize => {
+            process_initialize(program_id, account, counter_account, accounts)
+        }
+        CounterInstructions::Pause => process_set_paused(counter_account, accounts, true),
+        CounterInstructions::Unpause => process_set_paused(counter_account, accounts, false),
+        CounterInstructions::ProposeAuthority(args) => {
+            process_propose_authority(counter_account, accounts, args)
+        }
+        CounterInstructions::AcceptAuthority => process_accept_authority(counter_account, accounts),
+        CounterInstructions::Close => return process_close(account, &mut data, accounts),
+        CounterInstructions::ConfigureMultisig(args) => {
+            process_configure_multisig(counter_account, accounts, args)
+        }
+        CounterInstructions::Approve(args) => process_approve(counter_account, accounts, args),
+        CounterInstructions::Revoke => process_revoke(counter_account, accounts),
+        CounterInstructions::Get => process_get(counter_account),
+        CounterInstructions::GetStats => process_get_stats(counter_account),
+        CounterInstructions::Log => process_log(counter_account),
+        CounterInstructions::IncrementIfBelow(args) => {
+            process_increment_if_below(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::And(args) => {
+            process_bitwise(counter_account, accounts, |value| value & args.value)
+        }
+        CounterInstructions::Or(args) => {
+            process_bitwise(counter_account, accounts, |value| value | args.value)
+        }
+        CounterInstructions::Xor(args) => {
+            process_bitwise(counter_account, accounts, |value| value ^ args.value)
+        }
+        CounterInstructions::ShiftLeft(args) => {
+            let bits = shift_bits(args.bits)?;
+            process_bitwise(counter_account, accounts, |value| value << bits)
+        }
+        CounterInstructions::ShiftRight(args) => {
+            let bits = shift_bits(args.bits)?;
+            process_bitwise(counter_account, accounts, |value| value >> bits)
+        }
+        CounterInstructions::SetModulus(args) => {
+            process_set_modulus(counter_account, accounts, args)
+        }
+        CounterInstructions::SetCountdown(args) => {
+            process_set_countdown(counter_account, accounts, args)
+        }
+        CounterInstructions::Realloc(args) => {
+            return process_realloc(account, data, accounts, args)
+        }
+        CounterInstructions::InitializeNamed(_)
+        | CounterInstructions::InitializeConfig(_)
+        | CounterInstructions::UpdateConfig(_)
+        | CounterInstructions::InitializeArray(_)
+        | CounterInstructions::IncrementAt(_)
+        | CounterInstructions::DecrementAt(_)
+        | CounterInstructions::UpdateAt(_)
+        | CounterInstructions::InitializeKeyed(_)
+        | CounterInstructions::IncrementKeyed(_)
+        | CounterInstructions::DecrementKeyed(_)
+        | CounterInstructions::RemoveKeyed(_)
+        | CounterInstructions::ResizeKeyed(_)
+        | CounterInstructions::InitializeCompressedTree(_)
+        | CounterInstructions::CreateCompressed
+        | CounterInstructions::IncrementCompressed(_)
+        | CounterInstructions::DecrementCompressed(_)
+        | CounterInstructions::MigrateAccount
+        | CounterInstructions::WithdrawReferralFees
+        | CounterInstructions::InitializeLeaderboard(_)
+        | CounterInstructions::UnlockBoost => unreachable!("dispatched before loading"),
+        CounterInstructions::IncrementOne | CounterInstructions::DecrementOne => {
+            unreachable!("expanded before loading")
+        }
+        CounterInstructions::SetEpochScoped(args) => {
+            process_set_epoch_scoped(counter_account, accounts, args)
+        }
+        CounterInstructions::SetDecay(args) => process_set_decay(counter_account, accounts, args),
+        CounterInstructions::SetRateLimit(args) => {
+            process_set_rate_limit(counter_account, accounts, args)
+        }
+        CounterInstructions::CreateQuota => process_create_quota(program_id, account.key, accounts),
+        CounterInstructions::SetDailyCap(args) => {
+            process_set_daily_cap(counter_account, accounts, args)
+        }
+        CounterInstructions::SetFee(args) => {
+            process_set_fee(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::WithdrawTreasury => {
+            process_withdraw_treasury(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::ConfigurePayment(args) => {
+            process_configure_payment(counter_account, accounts, args)
+        }
+        CounterInstructions::InitializeVault => {
+            process_initialize_vault(program_id, account.key, accounts)
+        }
+        CounterInstructions::WithdrawVault(args) => {
+            process_withdraw_vault(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::SetNftGate(args) => {
+            process_set_nft_gate(counter_account, accounts, args)
+        }
+        CounterInstructions::ConfigureMilestoneReward(args) => process_configure_milestone_reward(
+            program_id,
+            account.key,
+            counter_account,
+            accounts,
+            args,
+        ),
+        CounterInstructions::ConfigureMilestoneNft(args) => {
+            process_configure_milestone_nft(counter_account, accounts, args)
+        }
+        CounterInstructions::ConfigureOracle(args) => {
+            process_configure_oracle(counter_account, accounts, args)
+        }
+        CounterInstructions::UpdateFromOracle => {
+            process_update_from_oracle(counter_account, accounts)
+        }
+        CounterInstructions::RequestRandomIncrement(args) => {
+            process_request_random_increment(counter_account, accounts, args)
+        }
+        CounterInstructions::SettleRandomIncrement => {
+            process_settle_random_increment(counter_account, accounts)
+        }
+        CounterInstructions::SetAllowlistEnabled(args) => {
+            process_set_allowlist_enabled(counter_account, accounts, args)
+        }
+        CounterInstructions::AddToAllowlist(args) => {
+            process_add_to_allowlist(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::RemoveFromAllowlist(args) => {
+            process_remove_from_allowlist(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::SetBlocklistEnabled(args) => {
+            process_set_blocklist_enabled(counter_account, accounts, args)
+        }
+        CounterInstructions::SetMemoRequired(args) => {
+            process_set_memo_required(counter_account, accounts, args)
+        }
+        CounterInstructions::SetReferrals(args) => {
+            process_set_referrals(counter_account, accounts, args)
+        }
+        CounterInstructions::CreateReferrer => {
+            process_create_referrer(program_id, account.key, accounts)
+        }
+        CounterInstructions::BanCaller(args) => {
+            process_ban_caller(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::UnbanCaller(args) => {
+            process_unban_caller(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::SetOffchainSigner(args) => {
+            process_set_offchain_signer(counter_account, accounts, args)
+        }
+        CounterInstructions::SetEthAuthority(args) => {
+            process_set_eth_authority(counter_account, accounts, args)
+        }
+        CounterInstructions::ConfigureWormhole(args) => {
+            process_configure_wormhole(counter_account, accounts, args)
+        }
+        CounterInstructions::ReceiveWormholeMessage => {
+            process_receive_wormhole_message(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::SetGovernance(args) => {
+            process_set_governance(counter_account, accounts, args)
+        }
+        CounterInstructions::ConfigureCrank(args) => {
+            process_configure_crank(counter_account, accounts, args)
+        }
+        CounterInstructions::Crank => {
+            process_crank(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::ClaimMilestoneNft(args) => {
+            process_claim_milestone_nft(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::InitializeHistory(args) => {
+            process_initialize_history(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::Checkpoint => {
+            process_checkpoint(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::Merge => {
+            process_merge(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::Split(args) => {
+            process_split(program_id, counter_account, accounts, args)
+        }
+        CounterInstructions::Transfer(args) => {
+            process_transfer(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::Clone(args) => {
+            process_clone(program_id, counter_account, accounts, args)
+        }
+        CounterInstructions::SetAuthority(args) => {
+            process_set_authority(counter_account, accounts, args)
+        }
+        CounterInstructions::SetExpiry(args) => process_set_expiry(counter_account, accounts, args),
+        CounterInstructions::Reap => return process_reap(account, &mut data, accounts),
+        CounterInstructions::Freeze => process_set_frozen(counter_account, accounts, true),
+        CounterInstructions::Thaw => process_set_frozen(counter_account, accounts, false),
+        CounterInstructions::SetKind(args) => process_set_kind(counter_account, accounts, args),
+        CounterInstructions::IncrementWide(args) => process_increment(
+            program_id,
+            account.key,
+            counter_account,
+            accounts,
+            args.amount,
+            args.mode,
+        ),
+        CounterInstructions::DecrementWide(args) => {
+            process_decrement(counter_account, accounts, args.amount, args.mode)
+        }
+        CounterInstructions::SetDecimals(args) => {
+            process_set_decimals(counter_account, accounts, args)
+        }
+        CounterInstructions::SetStep(args) => process_set_step(counter_account, accounts, args),
+        CounterInstructions::IncrementScaled(args) => {
+            let amount = rescale(
+                args.amount,
+                args.decimals,
+                counter_account.decimals,
+                args.rounding,
+            )?;
+            process_increment(
+                program_id,
+                account.key,
+                counter_account,
+                accounts,
+                amount,
+                args.mode,
+            )
+        }
+        CounterInstructions::DecrementScaled(args) => {
+            let amount = rescale(
+                args.amount,
+                args.decimals,
+                counter_account.decimals,
+                args.rounding,
+            )?;
+            process_decrement(counter_account, accounts, amount, args.mode)
+        }
+        CounterInstructions::SubmitScore => {
+            process_submit_score(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::SetStakeWeighted(args) => {
+            process_set_stake_weighted(counter_account, accounts, args)
+        }
+        CounterInstructions::SetBoost(args) => process_set_boost(counter_account, accounts, args),
+        CounterInstructions::LockBoost(args) => {
+            process_lock_boost(program_id, account.key, accounts, args)
+        }
+        CounterInstructions::ListForLease(args) => {
+            process_list_for_lease(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::TakeLease => {
+            process_take_lease(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::EndLease => {
+            process_end_lease(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::Delist => {
+            process_delist(program_id, account.key, counter_account, accounts)
+        }
+        CounterInstructions::SetCreditPrice(args) => {
+            process_set_credit_price(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::BuyCredits(args) => {
+            process_buy_credits(program_id, account.key, counter_account, accounts, args)
+        }
+        CounterInstructions::SetCooldown(args) => {
+            process_set_cooldown(counter_account, accounts, args)
+        }
+        CounterInstructions::CreateCooldown => {
+            process_create_cooldown(program_id, account.key, accounts)
+        }
+    }?;
+
+    if let Some(owner) = owner {
+        counter_account.authority = owner;
+    }
+
+    if let Some(history) = history {
+        record_history(
+            account.key,
+            counter_account,
+            history.owner == program_id,
+            &mut history.try_borrow_mut_data()?,
+        )?;
+    }
+    #[cfg(feature = "kv-logs")]
+    if let Some((op, old)) = kv_log {
+        log_kv(program_id, account.key, op, old, counter_account);
+    }
+    if let Some(legacy) = legacy {
+        legacy.store(&mut data);
+    }
+    Ok(())
+}
+
+/// Whether `instruction` writes the loaded counter, which must then be
+/// writable. The rest read it, or write only the accounts next to it.
+fn writes_counter(instruction: &CounterInstructions) -> bool {
+    !matches!(
+        instruction,
+        CounterInstructions::Get
+            | CounterInstructions::GetStats
+            | CounterInstructions::Log
+            | CounterInstructions::CreateQuota
+            | CounterInstructions::CreateCooldown
+            | CounterInstructions::CreateReferrer
+            | CounterInstructions::SubmitScore
+            | CounterInstructions::WithdrawTreasury
+            | CounterInstructions::InitializeVault
+            | CounterInstructions::WithdrawVault(_)
+            | CounterInstructions::AddToAllowlist(_)
+            | CounterInstructions::RemoveFromAllowlist(_)
+            | CounterInstructions::BanCaller(_)
+            | CounterInstructions::UnbanCaller(_)
+            | CounterInstructions::LockBoost(_)
+            | CounterInstructions::TakeLease
+            | CounterInstructions::EndLease
+            | CounterInstructions::BuyCredits(_)
+            | CounterInstructions::Checkpoint
+            | CounterInstructions::Clone(_)
+    )
+}
+
+/// Whether `instruction` is one of the mutations the cooldown, credits, fee,
+/// blocklist, allowlist, Ethereum authority, memo and lease apply to.
+fn is_mutation(instruction: &CounterInstructions) -> bool {
+    matches!(
+        instruction,
+        CounterInstructions::Increment(_)
+            | CounterInstructions::Decrement(_)
+            | CounterInstructions::Update(_)
+            | CounterInstructions::Reset
+            | CounterInstructions::IncrementWide(_)
+            | CounterInstructions::DecrementWide(_)
+            | CounterInstructions::IncrementScaled(_)
+            | CounterInstructions::DecrementScaled(_)
+            | CounterInstructions::IncrementIfBelow(_)
+            | CounterInstructions::And(_)
+            | CounterInstructions::Or(_)
+            | CounterInstructions::Xor(_)
+            | CounterInstructions::ShiftLeft(_)
+            | CounterInstructions::ShiftRight(_)
+    )
+}
+
+/// Whether `instruction` is an authority's that would close, stop or hand
+/// over the counter from under its lessee.
+fn overrides_lease(instruction: &CounterInstructions) -> bool {
+    matches!(
+        instruction,
+        CounterInstructions::Close
+            | CounterInstructions::Pause
+            | CounterInstructions::Freeze
+            | CounterInstructions::SetAuthority(_)
+            | CounterInstructions::ProposeAuthority(_)
+            | CounterInstructions::AcceptAuthority
+    )
+}
+
+/// Whether `instruction` decrements the loaded counter, the only write a
+/// running countdown takes.
+fn decrements(instruction: &CounterInstructions) -> bool {
+    matches!(
+        instruction,
+        CounterInstructions::Decrement(_)
+            | CounterInstructions::DecrementWide(_)
+            | CounterInstructions::DecrementScaled(_)
+    )
+}
+
+/// Whether `instruction` sets the loaded counter's value, which is what
+/// a history records and a freeze prevents.
+fn writes_value(instruction: &CounterInstructions) -> bool {
+    value_op(instruction).is_some()
+}
+
+/// The `op` [`kv_log_line`](crate::events::kv_log_line)s name `instruction` by, if it sets the loaded
+/// counter's value.
+fn value_op(instruction: &CounterInstructions) -> Option<&'static str> {
+    Some(match instruction {
+        CounterInstructions::Increment(_) => "increment",
+        CounterInstructions::Decrement(_) => "decrement",
+        CounterInstructions::Update(_) => "update",
+        CounterInstructions::Reset => "reset",
+        CounterInstructions::UpdateFromOracle => "update_from_oracle",
+        CounterInstructions::SettleRandomIncrement => "settle_random_increment",
+        CounterInstructions::ReceiveWormholeMessage => "receive_wormhole_message",
+        CounterInstructions::Crank => "crank",
+        CounterInstructions::Merge => "merge",
+        CounterInstructions::Split(_) => "split",
+        CounterInstructions::Transfer(_) => "transfer",
+        CounterInstructions::IncrementWide(_) => "increment_wide",
+        CounterInstructions::DecrementWide(_) => "decrement_wide",
+        CounterInstructions::IncrementScaled(_) => "increment_scaled",
+        CounterInstructions::DecrementScaled(_) => "decrement_scaled",
+        CounterInstructions::IncrementIfBelow(_) => "increment_if_below",
+        CounterInstructions::And(_) => "and",
+        CounterInstructions::Or(_) => "or",
+        CounterInstructions::Xor(_) => "xor",
+        CounterInstructions::ShiftLeft(_) => "shift_left",
+        CounterInstructions::ShiftRight(_) => "shift_right",
+        CounterInstructions::SetCountdown(_) => "set_countdown",
+        _ => return None,
+    })
+}
+
+/// Logs the [`kv_log_line`](crate::events::kv_log_line) for a write of `op` that took the counter from
+/// `old`.
+#[cfg(feature = "kv-logs")]
+pub(crate) fn log_kv(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    op: &str,
+    old: CounterValue,
+    counter_account: &CounterAccount,
+) {
+    solana_program::log::sol_log(&crate::events::kv_log_line(
+        program_id,
+        counter,
+        op,
+        old,
+        counter_account.value(),
+        &counter_account.last_writer,
+    ));
+}
+
+/// Whether `instruction` reads or sets the `u32` value or configures a
+/// feature built on it, which other kinds of counter don't support.
+fn requires_u32(instruction: &CounterInstructions) -> bool {
+    matches!(
+        instruction,
+        CounterInstructions::SetEpochScoped(_)
+            | CounterInstructions::SetDecay(_)
+            | CounterInstructions::ConfigureMilestoneReward(_)
+            | CounterInstructions::ConfigureMilestoneNft(_)
+            | CounterInstructions::UpdateFromOracle
+            | CounterInstructions::RequestRandomIncrement(_)
+            | CounterInstructions::SettleRandomIncrement
+            | CounterInstructions::ReceiveWormholeMessage
+            | CounterInstructions::ConfigureCrank(_)
+            | CounterInstructions::Crank
+            | CounterInstructions::InitializeHistory(_)
+            | CounterInstructions::Checkpoint
+            | CounterInstructions::Merge
+            | CounterInstructions::Split(_)
+            | CounterInstructions::Transfer(_)
+            | CounterInstructions::SubmitScore
+            | CounterInstructions::IncrementIfBelow(_)
+            | CounterInstructions::And(_)
+            | CounterInstructions::Or(_)
+            | CounterInstructions::Xor(_)
+            | CounterInstructions::ShiftLeft(_)
+            | CounterInstructions::ShiftRight(_)
+            | CounterInstructions::SetModulus(_)
+            | CounterInstructions::SetCountdown(_)
+    )
+}
+
+/// `event`, describing a write to a `u32` counter, or its equivalent for
+/// a counter of another kind that held `old`.
+pub(crate) fn typed_event(
+    counter_account: &CounterAccount,
+    old: CounterValue,
+    event: CounterEvent,
+) -> CounterEvent {
+    match (old, counter_account.value()) {
+        (CounterValue::I64(old), CounterValue::I64(new)) => {
+            CounterEvent::SignedChanged { old, new }
+        }
+        (CounterValue::U128(old), CounterValue::U128(new)) => {
+            CounterEvent::WideChanged { old, new }
+        }
+        _ => event,
+    }
+}
+
+/// Brings clock-driven counters up to date: epoch-scoped counters start from
+/// zero in a new epoch and decaying counters lose `decay_per_slot` for every
+/// slot since they were last updated. Returns the events describing what
+/// changed. Other counters, and frozen ones, never read the clock.
+fn apply_clock(counter_account: &mut CounterAccount) -> Result<Vec<CounterEvent>, ProgramError> {
+    let mut events = Vec::new();
+    // A frozen value doesn't decay or roll over either.
+    if counter_account.is_frozen()
+        || (!counter_account.is_epoch_scoped() && !counter_account.is_decaying())
+    {
+        return Ok(events);
+    }
+    let clock = Clock::get()?;
+    if counter_account.is_epoch_scoped() {
+        if let Some(old) = counter_account.roll_epoch(clock.epoch) {
+            events.push(CounterEvent::EpochReset {
+                old,
+                epoch: clock.epoch,
+            });
+        }
+    }
+    if counter_account.is_decaying() {
+        if let Some(old) = counter_account.apply_decay(clock.slot) {
+            events.push(CounterEvent::Decayed {
+                old,
+                new: counter_account.counter,
+            });
+        }
+    }
+    Ok(events)
+}
+
+/// Runs [`apply_clock`] ahead of a write and logs its events, then counts the
+/// write against the rate limit and records it as `writer`'s.
+pub(crate) fn prepare_write(
+    counter_account: &mut CounterAccount,
+    writer: &Pubkey,
+) -> ProgramResult {
+    apply_clock(counter_account)?
+        .iter()
+        .for_each(CounterEvent::emit);
+    let clock = Clock::get()?;
+    if counter_account.is_rate_limited() {
+        counter_account.record_op(clock.slot)?;
+    }
+    counter_account.record_write(&clock, writer);
+    Ok(())
+}
+
+/// The first signer in `accounts`, whom a write is attributed to; the
+/// default pubkey for writes nobody signed.
+fn first_signer(accounts: &[AccountInfo]) -> Pubkey {
+    accounts
+        .iter()
+        .find(|account| account.is_signer)
+        .map_or_else(Pubkey::default, |account| *account.key)
+}
+
+/// Update, Reset and the bitwise instructions overwrite the value, so
+/// somebody must sign them even on counters nothing else restricts.
+fn check_signed(accounts: &[AccountInfo]) -> ProgramResult {
+    if !accounts.iter().any(|account| account.is_signer) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Splits off the last `N` accounts, where optional account groups such as
+/// the fee payer's go after the accounts an instruction always takes.
+fn split_trailing<'b, 'a, const N: usize>(
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<(&'b [AccountInfo<'a>], &'b [AccountInfo<'a>; N]), ProgramError> {
+    let start = accounts
+        .len()
+        .checked_sub(N)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (rest, trailing) = accounts.split_at(start);
+    Ok((rest, trailing.try_into().expect("N trailing accounts")))
+}
+
+/// Moves the counter's fee from the payer to its treasury. The payer,
+/// treasury and system program are the last three accounts; the rest are
+/// returned for the instruction itself.
+fn charge_fee<'b, 'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+    referrer: Option<&AccountInfo<'a>>,
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if counter_account.fee_lamports == 0 {
+        return Ok(accounts);
+    }
+    let (rest, [payer, treasury, system_program]) = split_trailing(accounts)?;
+    check_treasury(program_id, counter, counter_account, treasury)?;
+    let mut fee = counter_account.fee_lamports;
+    if let Some(referrer) = referrer {
+        let share = counter_account.referral_fee();
+        invoke(
+            &system_instruction::transfer(payer.key, referrer.key, share),
+            &[payer.clone(), referrer.clone(), system_program.clone()],
+        )?;
+        fee -= share;
+    }
+    invoke(
+        &system_instruction::transfer(payer.key, treasury.key, fee),
+        &[payer.clone(), treasury.clone(), system_program.clone()],
+    )?;
+    Ok(rest)
+}
+
+/// Holds back a signer still cooling down from its last mutation. The
+/// signer's cooldown account is the last account, after the credit balance;
+/// the rest are returned for the instruction itself.
+fn check_cooldown<'b, 'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.has_cooldown() {
+        return Ok(accounts);
+    }
+    let (rest, [cooldown]) = split_trailing(accounts)?;
+    record_cooldown(
+        counter,
+        counter_account,
+        next_signer(rest),
+        cooldown.owner == program_id,
+        &mut cooldown.try_borrow_mut_data()?,
+    )?;
+    Ok(rest)
+}
+
+/// Records a mutation by `caller` in its cooldown account, failing while the
+/// counter's cooldown since its last one runs. `cooldown_owned` says whether
+/// the account is owned by this program.
+pub(crate) fn record_cooldown(
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    caller: Option<(&Pubkey, bool)>,
+    cooldown_owned: bool,
+    cooldown_data: &mut [u8],
+) -> ProgramResult {
+    let Some((caller, true)) = caller else {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+    if !cooldown_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let cooldown = CallerCooldown::load_mut(cooldown_data)?;
+    if cooldown.counter != *counter || cooldown.caller != *caller {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let clock = Clock::get()?;
+    cooldown.record(
+        clock.slot,
+        clock.unix_timestamp,
+        counter_account.cooldown,
+        counter_account.cooldown_unit,
+    )
+}
+
+/// Spends one of the signer's credits in credit mode. The signer's credit
+/// balance is the last account, after the fee accounts; the rest are returned
+/// for the instruction itself.
+fn charge_credit<'b, 'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.has_credits() {
+        return Ok(accounts);
+    }
+    let (rest, [credits]) = split_trailing(accounts)?;
+    spend_credit(
+        program_id,
+        counter,
+        next_signer(rest),
+        credits.key,
+        credits.owner == program_id,
+        &mut credits.try_borrow_mut_data()?,
+    )?;
+    Ok(rest)
+}
+
+/// Takes one credit off `caller`'s balance at `credits`. `caller` is the
+/// `(key, is_signer)` of the signer, who must have signed, and
+/// `credits_owned` says whether the balance is owned by this program.
+pub(crate) fn spend_credit(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    caller: Option<(&Pubkey, bool)>,
+    credits: &Pubkey,
+    credits_owned: bool,
+    credits_data: &mut [u8],
+) -> ProgramResult {
+    let Some((caller, true)) = caller else {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+    if !credits_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let balance = CreditBalance::load_mut(credits_data)?;
+    check_pda(
+        program_id,
+        credits,
+        &[CREDITS_SEED, counter.as_ref(), caller.as_ref()],
+        balance.bump,
+    )?;
+    balance.credits = balance
+        .credits
+        .checked_sub(1)
+        .ok_or(CounterError::NoCredits)?;
+    Ok(())
+}
+
+/// Credits the referrer entry at `entry` with an Increment by `value` from
+/// `caller`, and with the referrer's share of the fee, which the fee
+/// accounts pay it. `entry_owned` says whether the entry is owned by this
+/// program.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn record_referral(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    caller: Option<(&Pubkey, bool)>,
+    entry: &Pubkey,
+    entry_owned: bool,
+    entry_data: &mut [u8],
+    value: u32,
+) -> ProgramResult {
+    if !entry_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let referrer_entry = ReferrerEntry::load_mut(entry_data)?;
+    check_pda(
+        program_id,
+        entry,
+        &[
+            REFERRER_SEED,
+            counter.as_ref(),
+            referrer_entry.referrer.as_ref(),
+        ],
+        referrer_entry.bump,
+    )?;
+    if caller.is_some_and(|(caller, _)| *caller == referrer_entry.referrer) {
+        return Err(CounterError::SelfReferral.into());
+    }
+    let fee_lamports = counter_account.referral_fee();
+    referrer_entry.referrals = referrer_entry.referrals.saturating_add(1);
+    referrer_entry.referred_value = referrer_entry.referred_value.saturating_add(value.into());
+    referrer_entry.fees_earned = referrer_entry.fees_earned.saturating_add(fee_lamports);
+    CounterEvent::Referred {
+        referrer: referrer_entry.referrer,
+        value,
+        fee_lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Moves the counter's token price from the caller's token account to the
+/// vault. The source, mint, vault and token program are the last four
+/// accounts; the rest are returned for the increment itself.
+///
+/// Token-2022 mints with a transfer fee are charged enough on top that the
+/// vault still nets the full price.
+fn charge_payment<'b, 'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.requires_payment() {
+        return Ok(accounts);
+    }
+    let (rest, [source, mint, vault, token_program]) = split_trailing(accounts)?;
+    let caller = rest.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if *mint.key != counter_account.payment_mint {
+        return Err(CounterError::InvalidVault.into());
+    }
+    token::check_token_program(mint, token_program)?;
+    if *vault.key != find_vault_address(program_id, counter, mint.key, token_program.key) {
+        return Err(CounterError::InvalidVault.into());
+    }
+    let amount = token::gross_amount(mint, counter_account.payment_price)?;
+    token::transfer_checked(token_program, source, mint, vault, caller, amount, &[])?;
+    Ok(rest)
+}
+
+/// Checks that the signing caller holds an NFT of the counter's gate
+/// collection. The caller's token account and the NFT's metadata account are
+/// the last two accounts; the rest are returned for the instruction itself.
+fn check_nft_gate<'b, 'a>(
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.is_nft_gated() {
+        return Ok(accounts);
+    }
+    let (rest, [token_account, nft_metadata]) = split_trailing(accounts)?;
+    let caller = rest.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let nft = token::unpack_account(token_account)?;
+    if nft.owner != *caller.key || nft.amount != 1 {
+        return Err(CounterError::NftRequired.into());
+    }
+    if *nft_metadata.owner != metadata::ID
+        || *nft_metadata.key != metadata::find_metadata_address(&nft.mint).0
+    {
+        return Err(CounterError::NftRequired.into());
+    }
+    let metadata = Metadata::unpack(&nft_metadata.try_borrow_data()?)?;
+    if metadata.verified_collection() != Some(&counter_account.nft_gate_collection) {
+        return Err(CounterError::NftRequired.into());
+    }
+    Ok(rest)
+}
+
+/// Checks that the signer after the counter has an allow-list entry, for
+/// counters in allow-list mode. The entry is the last account once any fee
+/// accounts are gone; the rest are returned for the instruction itself.
+fn check_allowlist<'b, 'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.is_allowlisted() {
+        return Ok(accounts);
+    }
+    let (rest, [entry]) = split_trailing(accounts)?;
+    check_allowlisted(
+        counter,
+        next_signer(rest),
+        entry.owner == program_id,
+        &entry.try_borrow_data()?,
+    )?;
+    Ok(rest)
+}
+
+/// Checks that `caller` signed and that `entry_data`, from an account this
+/// program owns iff `entry_owned`, is its allow-list entry for `counter`.
+pub(crate) fn check_allowlisted(
+    counter: &Pubkey,
+    caller: Option<(&Pubkey, bool)>,
+    entry_owned: bool,
+    entry_data: &[u8],
+) -> ProgramResult {
+    let Some((caller, true)) = caller else {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+    // A removed entry is closed, so it's no longer ours.
+    if !entry_owned {
+        return Err(CounterError::NotAllowlisted.into());
+    }
+    let entry = AllowlistEntry::unpack(entry_data)?;
+    if entry.counter != *counter || entry.member != *caller {
+        return Err(CounterError::NotAllowlisted.into());
+    }
+    Ok(())
+}
+
+/// Checks that the signer after the counter is not banned, for counters with
+/// a block-list. Its ban entry address is the last account once any fee
+/// accounts are gone; the rest are returned for the instruction itself.
+fn check_blocklist<'b, 'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.has_blocklist() {
+        return Ok(accounts);
+    }
+    let (rest, [entry]) = split_trailing(accounts)?;
+    check_not_banned(
+        program_id,
+        counter,
+        next_signer(rest),
+        entry.key,
+        entry.owner == program_id,
+        &entry.try_borrow_data()?,
+    )?;
+    Ok(rest)
+}
+
+/// Checks that `caller` signed and that `entry`, the account at its ban
+/// entry address, is not a live ban. `entry_owned` says whether this program
+/// owns it.
+pub(crate) fn check_not_banned(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    caller: Option<(&Pubkey, bool)>,
+    entry: &Pubkey,
+    entry_owned: bool,
+    entry_data: &[u8],
+) -> ProgramResult {
+    let Some((caller, true)) = caller else {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+    if *entry != find_ban_address(program_id, counter, caller).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // An entry closed earlier in the transaction is still ours but zeroed.
+    if entry_owned && BanEntry::unpack(entry_data).is_ok_and(|ban| ban.counter == *counter) {
+        return Err(CounterError::CallerBanned.into());
+    }
+    Ok(())
+}
+
+/// Checks the off-chain signer's authorization of an Increment, for counters
+/// that have one. The Instructions sysvar is the last account once the
+/// reward, NFT gate and payment accounts are gone; the rest are returned for
+/// the instruction itself.
+fn check_offchain_authorization<'b, 'a>(
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.has_offchain_signer() {
+        return Ok(accounts);
+    }
+    let (rest, [instructions]) = split_trailing(accounts)?;
+    check_offchain_signature(
+        counter,
+        counter_account,
+        instructions.key,
+        &instructions.try_borrow_data()?,
+    )?;
+    Ok(rest)
+}
+
+/// Checks that the instruction before this one is an Ed25519 program
+/// instruction in which the off-chain signer signed [`offchain_message`] for
+/// the current nonce, and advances the nonce so it can't be replayed.
+/// `instructions` is the address of the account holding `instructions_data`.
+pub(crate) fn check_offchain_signature(
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    instructions: &Pubkey,
+    instructions_data: &[u8],
+) -> ProgramResult {
+    if *instructions != sysvar::instructions::ID {
+        return Err(ProgramError::UnsupportedSysvar);
+    }
+    // The checked variants take an `AccountInfo`, which the Pinocchio
+    // entrypoint doesn't have.
+    #[allow(deprecated)]
+    let current = sysvar::instructions::load_current_index(instructions_data);
+    let previous = current
+        .checked_sub(1)
+        .ok_or(CounterError::InvalidOffchainSignature)?;
+    #[allow(deprecated)]
+    let previous = sysvar::instructions::load_instruction_at(previous.into(), instructions_data)
+        .map_err(|_| CounterError::InvalidOffchainSignature)?;
+    let message = offchain_message(counter, counter_account.offchain_nonce);
+    let authorized = previous.program_id == ed25519::ID
+        && ed25519::signed_message(&previous.data).is_some_and(|(signer, signed)| {
+            signer == counter_account.offchain_signer && signed == message
+        });
+    if !authorized {
+        return Err(CounterError::InvalidOffchainSignature.into());
+    }
+    counter_account.offchain_nonce = counter_account
+        .offchain_nonce
+        .checked_add(1)
+        .ok_or(CounterError::Overflow)?;
+    Ok(())
+}
+
+/// Checks the Ethereum authority's authorization of a mutation, for counters
+/// that have one. The Instructions sysvar is the last account once any
+/// allow-list, ban and fee accounts are gone; the rest are returned for the
+/// instruction itself.
+fn check_eth_authorization<'b, 'a>(
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.has_eth_authority() {
+        return Ok(accounts);
+    }
+    let (rest, [instructions]) = split_trailing(accounts)?;
+    check_eth_signature(
+        counter,
+        counter_account,
+        instructions.key,
+        &instructions.try_borrow_data()?,
+    )?;
+    Ok(rest)
+}
+
+/// Checks that the instruction before this one is a secp256k1 program
+/// instruction in which the Ethereum authority signed [`eth_message`] for
+/// the current nonce, and advances the nonce so it can't be replayed.
+/// `instructions` is the address of the account holding `instructions_data`.
+pub(crate) fn check_eth_signature(
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    instructions: &Pubkey,
+    instructions_data: &[u8],
+) -> ProgramResult {
+    if *instructions != sysvar::instructions::ID {
+        return Err(ProgramError::UnsupportedSysvar);
+    }
+    // See `check_offchain_signature`.
+    #[allow(deprecated)]
+    let current = sysvar::instructions::load_current_index(instructions_data);
+    let previous = current
+        .checked_sub(1)
+        .and_then(|previous| u8::try_from(previous).ok())
+        .ok_or(CounterError::InvalidEthSignature)?;
+    #[allow(deprecated)]
+    let instruction = sysvar::instructions::load_instruction_at(previous.into(), instructions_data)
+        .map_err(|_| CounterError::InvalidEthSignature)?;
+    let message = eth_message(counter, counter_account.eth_nonce);
+    let authorized = instruction.program_id == secp256k1::ID
+        && secp256k1::signed_message(&instruction.data, previous).is_some_and(
+            |(eth_address, signed)| {
+                eth_address == counter_account.eth_authority && signed == message
+            },
+        );
+    if !authorized {
+        return Err(CounterError::InvalidEthSignature.into());
+    }
+    counter_account.eth_nonce = counter_account
+        .eth_nonce
+        .checked_add(1)
+        .ok_or(CounterError::Overflow)?;
+    Ok(())
+}
+
+/// Checks that a mutation's transaction holds a memo, for counters that
+/// require one. The Instructions sysvar is the last account once any
+/// Ethereum authorization is gone; the rest are returned for the instruction
+/// itself.
+fn check_memo<'b, 'a>(
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<&'b [AccountInfo<'a>], ProgramError> {
+    if !counter_account.requires_memo() {
+        return Ok(accounts);
+    }
+    let (rest, [instructions]) = split_trailing(accounts)?;
+    check_memo_present(instructions.key, &instructions.try_borrow_data()?)?;
+    Ok(rest)
+}
+
+/// Checks that some instruction of the transaction, before or after this
+/// one, is an SPL Memo instruction. `instructions` is the address of the
+/// account holding `instructions_data`.
+pub(crate) fn check_memo_present(instructions: &Pubkey, instructions_data: &[u8]) -> ProgramResult {
+    if *instructions != sysvar::instructions::ID {
+        return Err(ProgramError::UnsupportedSysvar);
+    }
+    // See `check_offchain_signature`.
+    #[allow(deprecated)]
+    let has_memo = (0..)
+        .map_while(|index| sysvar::instructions::load_instruction_at(index, instructions_data).ok())
+        .any(|instruction| memo::is_memo_program(&instruction.program_id));
+    if !has_memo {
+        return Err(CounterError::MemoRequired.into());
+    }
+    Ok(())
+}
+
+/// The lessee of a listed counter's running lease at `now`, who holds the
+/// authority over its mutations; `None` between leases. `lease_owned` says
+/// whether the lease account is owned by this program.
+pub(crate) fn active_lessee(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    lease: &Pubkey,
+    lease_owned: bool,
+    lease_data: &[u8],
+    now: i64,
+) -> Result<Option<Pubkey>, ProgramError> {
+    if !lease_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let terms = Lease::unpack(lease_data)?;
+    check_pda(
+        program_id,
+        lease,
+        &[LEASE_SEED, counter.as_ref()],
+        terms.bump,
+    )?;
+    Ok(terms.is_active(now).then_some(terms.lessee))
+}
+
+/// The signer Increment and Decrement check against the authority and
+/// delegate. An Ethereum authority's signature was checked before the
+/// instruction ran and stands in for the authority's.
+pub(crate) fn mutator<'a>(
+    counter_account: &CounterAccount,
+    authority: &'a Pubkey,
+    signer: Option<(&'a Pubkey, bool)>,
+) -> Option<(&'a Pubkey, bool)> {
+    match counter_account.has_eth_authority() {
+        true => Some((authority, true)),
+        false => signer,
+    }
+}
+
+/// Checks that `address` is the PDA of `seeds` and returns its bump. With a
+/// stored canonical `bump` the address is recomputed from it alone; zero,
+/// for accounts that predate stored bumps, searches for the bump instead.
+fn check_pda(
+    program_id: &Pubkey,
+    address: &Pubkey,
+    seeds: &[&[u8]],
+    bump: u8,
+) -> Result<u8, ProgramError> {
+    let (expected, bump) = match bump {
+        0 => Pubkey::find_program_address(seeds, program_id),
+        bump => (
+            Pubkey::create_program_address(&[seeds, &[&[bump]]].concat(), program_id)
+                .map_err(|_| ProgramError::InvalidSeeds)?,
+            bump,
+        ),
+    };
+    if *address != expected {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+fn check_treasury(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    treasury: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    check_pda(
+        program_id,
+        treasury.key,
+        &[TREASURY_SEED, counter.as_ref()],
+        counter_account.treasury_bump,
+    )
+    .map_err(|_| CounterError::InvalidTreasury.into())
+}
+
+/// Appends the value and slot a write left the counter at to its history.
+/// `history_owned` says whether the history account is owned by this
+/// program.
+pub(crate) fn record_history(
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    history_owned: bool,
+    history_data: &mut [u8],
+) -> ProgramResult {
+    if !history_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if HistoryHeader::unpack(history_data)?.counter != *counter {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    HistoryHeader::append(
+        history_data,
+        HistoryEntry {
+            slot: counter_account.last_updated_slot,
+            value: counter_account.counter,
+            _padding: [0; 4],
+        },
+    )
+}
+
+fn next_signer<'a>(accounts: &'a [AccountInfo]) -> Option<(&'a Pubkey, bool)> {
+    accounts
+        .first()
+        .map(|account| (account.key, account.is_signer))
+}
+
+/// Charges an increment of `amount` by `caller` to its quota, for counters
+/// with a daily cap. `quota_owned` says whether the quota account is owned by
+/// this program.
+pub(crate) fn charge_daily_cap(
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    caller: Option<(&Pubkey, bool)>,
+    quota_owned: bool,
+    quota_data: &mut [u8],
+    amount: u64,
+) -> ProgramResult {
+    let Some((caller, true)) = caller else {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+    if !quota_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let quota = CallerQuota::load_mut(quota_data)?;
+    if quota.counter != *counter || quota.caller != *caller {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    quota.record(
+        Clock::get()?.unix_timestamp,
+        amount,
+        counter_account.daily_cap,
+    )
+}
+
+fn process_increment(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    value: u64,
+    mode: u8,
+) -> ProgramResult {
+    let (accounts, value) = weigh_by_stake(counter_account, accounts, value)?;
+    let (accounts, value) = apply_boost(program_id, counter, counter_account, accounts, value)?;
+    let accounts = charge_payment(program_id, counter, counter_account, accounts)?;
+    let accounts = check_nft_gate(counter_account, accounts)?;
+    let (accounts, reward) = split_reward_accounts(counter_account, accounts)?;
+    let accounts = check_offchain_authorization(counter, counter_account, accounts)?;
+    prepare_write(counter_account, &first_signer(accounts))?;
+    let (old, old_value) = (counter_account.counter, counter_account.value());
+    let authority = counter_account.authority;
+    let signer = mutator(counter_account, &authority, next_signer(accounts));
+    counter_account.increment(signer, value, mode)?;
+    if counter_account.has_daily_cap() {
+        let quota = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        charge_daily_cap(
+            counter,
+            counter_account,
+            next_signer(accounts),
+            quota.owner == program_id,
+            &mut quota.try_borrow_mut_data()?,
+            value,
+        )?;
+    }
+    typed_event(
+        counter_account,
+        old_value,
+        CounterEvent::Incremented {
+            old,
+            new: counter_account.counter,
+            // Only a wide amount on a saturating or wrapping `u32` counter
+            // outgrows this.
+            by: u32::try_from(value).unwrap_or(u32::MAX),
+        },
+    )
+    .emit();
+    if let Some(reward) = reward {
+        reward_milestones(program_id, counter, counter_account, reward)?;
+    }
+    Ok(())
+}
+
+/// Compares against the value the increment would start from, so a decayed
+/// or rolled-over counter can be under the threshold again. A skip still
+/// checks that the signer may increment, on a copy so no allowance is spent.
+fn process_increment_if_below(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: IncrementIfBelowArgs,
+) -> ProgramResult {
+    let mut current = *counter_account;
+    apply_clock(&mut current)?;
+    if current.counter < args.threshold {
+        return process_increment(
+            program_id,
+            counter,
+            counter_account,
+            accounts,
+            args.value.into(),
+            args.mode,
+        );
+    }
+    if !args.skip {
+        return Err(CounterError::ThresholdReached.into());
+    }
+    let authority = current.authority;
+    current.check_mutator(mutator(&current, &authority, next_signer(accounts)))
+}
+
+/// Multiplies an Increment's `value` by the stake its signer lends it, for
+/// stake-weighted counters. The stake account is the last account once the
+/// checks in `dispatch` took theirs; the rest are returned for the increment
+/// itself.
+fn weigh_by_stake<'b, 'a>(
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+    value: u64,
+) -> Result<(&'b [AccountInfo<'a>], u64), ProgramError> {
+    if !counter_account.is_stake_weighted() {
+        return Ok((accounts, value));
+    }
+    let (rest, [stake]) = split_trailing(accounts)?;
+    let weight = stake_weight(
+        stake.owner == &stake::program::ID,
+        &stake.try_borrow_data()?,
+        next_signer(rest),
+        Clock::get()?.epoch,
+    )?;
+    let value = value.checked_mul(weight).ok_or(CounterError::Overflow)?;
+    Ok((rest, value))
+}
+
+/// Multiplies an Increment's `value` by the counter's boost factor while the
+/// signer's escrow boosts it. The escrow, or the program ID for none, is the
+/// last account once the stake account is gone; the rest are returned for
+/// the increment itself.
+fn apply_boost<'b, 'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+    value: u64,
+) -> Result<(&'b [AccountInfo<'a>], u64), ProgramError> {
+    if !counter_account.has_boost() {
+        return Ok((accounts, value));
+    }
+    let (rest, [escrow]) = split_trailing(accounts)?;
+    if escrow.key == program_id {
+        return Ok((rest, value));
+    }
+    let factor = boost_factor(
+        program_id,
+        counter,
+        counter_account,
+        next_signer(rest),
+        escrow.key,
+        escrow.owner == program_id,
+        &escrow.try_borrow_data()?,
+        Clock::get()?.unix_timestamp,
+    )?;
+    let value = value.checked_mul(factor).ok_or(CounterError::Overflow)?;
+    Ok((rest, value))
+}
+
+/// What the boost escrow at `escrow` multiplies an Increment by `caller`
+/// by at `now`: the counter's boost factor while it locks enough, one
+/// otherwise. Only the escrow's owner may pass it. `escrow_owned` says
+/// whether the escrow is owned by this program.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn boost_factor(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    caller: Option<(&Pubkey, bool)>,
+    escrow: &Pubkey,
+    escrow_owned: bool,
+    escrow_data: &[u8],
+    now: i64,
+) -> Result<u64, ProgramError> {
+    if !escrow_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let boost = BoostEscrow::unpack(escrow_data)?;
+    check_pda(
+        program_id,
+        escrow,
+        &[BOOST_SEED, counter.as_ref(), boost.owner.as_ref()],
+        boost.bump,
+    )?;
+    match caller {
+        Some((caller, true)) if *caller == boost.owner => {}
+        _ => return Err(CounterError::Unauthorized.into()),
+    }
+    Ok(if boost.is_boosting(now, counter_account.boost_min_sol) {
+        counter_account.boost_factor.into()
+    } else {
+        1
+    })
+}
+
+/// The weight a native stake account lends an increment by `caller`: the
+/// stake it delegates in whole SOL. Only its staker or withdrawer may lend
+/// it, and only while the delegation is active in `epoch`.
+pub(crate) fn stake_weight(
+    stake_owned: bool,
+    stake_data: &[u8],
+    caller: Option<(&Pubkey, bool)>,
+    epoch: u64,
+) -> Result<u64, ProgramError> {
+    if !stake_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let StakeStateV2::Stake(meta, stake, _) = StakeStateV2::deserialize(&mut &stake_data[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?
+    else {
+        return Err(CounterError::StakeNotActive.into());
+    };
+    let caller = match caller {
+        Some((caller, true)) => caller,
+        _ => return Err(ProgramError::MissingRequiredSignature),
+    };
+    if *caller != meta.authorized.staker && *caller != meta.authorized.withdrawer {
+        return Err(CounterError::Unauthorized.into());
+    }
+    let delegation = stake.delegation;
+    let activated = delegation.is_bootstrap() || delegation.activation_epoch < epoch;
+    if !activated || delegation.deactivation_epoch != u64::MAX {
+        return Err(CounterError::StakeNotActive.into());
+    }
+    Ok(delegation.stake / LAMPORTS_PER_SOL)
+}
+
+/// Splits off the destination, mint, reward authority and token program an
+/// Increment passes while milestone rewards are on. They are the last four
+/// accounts once the NFT gate, payment and fee accounts are gone.
+fn split_reward_accounts<'b, 'a>(
+    counter_account: &CounterAccount,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<(&'b [AccountInfo<'a>], Option<&'b [AccountInfo<'a>; 4]>), ProgramError> {
+    if !counter_account.has_milestone_reward() {
+        return Ok((accounts, None));
+    }
+    let (rest, reward) = split_trailing(accounts)?;
+    Ok((rest, Some(reward)))
+}
+
+/// Mints the reward for every milestone the counter reached for the first
+/// time. The reward accounts are only checked when there is something to
+/// mint.
+fn reward_milestones<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    [destination, mint, reward_authority, token_program]: &[AccountInfo<'a>; 4],
+) -> ProgramResult {
+    let milestones = counter_account.take_milestones();
+    if milestones == 0 {
+        return Ok(());
+    }
+    if *mint.key != counter_account.reward_mint {
+        return Err(CounterError::InvalidRewardMint.into());
+    }
+    token::check_token_program(mint, token_program)?;
+    let bump = check_pda(
+        program_id,
+        reward_authority.key,
+        &[REWARD_SEED, counter.as_ref()],
+        counter_account.reward_authority_bump,
+    )?;
+    let amount = counter_account
+        .reward_amount
+        .checked_mul(milestones.into())
+        .ok_or(CounterError::Overflow)?;
+    token::mint_to(
+        token_program,
+        mint,
+        destination,
+        reward_authority,
+        amount,
+        &[&[REWARD_SEED, counter.as_ref(), &[bump]]],
+    )?;
+    CounterEvent::MilestoneRewarded {
+        milestone: counter_account.rewarded_milestones,
+        destination: *destination.key,
+        amount,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_decrement(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    value: u64,
+    mode: u8,
+) -> ProgramResult {
+    let accounts = check_nft_gate(counter_account, accounts)?;
+    prepare_write(counter_account, &first_signer(accounts))?;
+    let (old, old_value) = (counter_account.counter, counter_account.value());
+    let authority = counter_account.authority;
+    let signer = mutator(counter_account, &authority, next_signer(accounts));
+    counter_account.decrement(signer, value, mode)?;
+    typed_event(
+        counter_account,
+        old_value,
+        CounterEvent::Decremented {
+            old,
+            new: counter_account.counter,
+            by: u32::try_from(value).unwrap_or(u32::MAX),
+        },
+    )
+    .emit();
+    complete_countdown(counter_account);
+    Ok(())
+}
+
+/// Countdown counters take only decrements while running, and no writes at
+/// all once completed.
+pub(crate) fn check_countdown(counter_account: &CounterAccount, decrement: bool) -> ProgramResult {
+    if counter_account.is_countdown_completed() {
+        return Err(CounterError::CountdownCompleted.into());
+    }
+    if counter_account.has_countdown() && !decrement {
+        return Err(CounterError::CountdownOnly.into());
+    }
+    Ok(())
+}
+
+/// Locks a running countdown that a decrement brought to zero.
+pub(crate) fn complete_countdown(counter_account: &mut CounterAccount) {
+    if counter_account.has_countdown() && counter_account.counter == 0 {
+        counter_account.countdown = COUNTDOWN_COMPLETED;
+        CounterEvent::CountdownCompleted.emit();
+    }
+}
+
+fn process_update(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: UpdateArgs,
+) -> ProgramResult {
+    check_signed(accounts)?;
+    prepare_write(counter_account, &first_signer(accounts))?;
+    counter_account.check_governance(accounts)?;
+    counter_account.check_privileged(accounts)?;
+    let (old, old_value) = (counter_account.counter, counter_account.value());
+    counter_account.set_value(args.value);
+    typed_event(
+        counter_account,
+        old_value,
+        CounterEvent::Updated {
+            old,
+            new: args.value,
+        },
+    )
+    .emit();
+    counter_account.record_value();
+    Ok(())
+}
+
+fn process_reset(counter_account: &mut CounterAccount, accounts: &[AccountInfo]) -> ProgramResult {
+    check_signed(accounts)?;
+    prepare_write(counter_account, &first_signer(accounts))?;
+    counter_account.check_governance(accounts)?;
+    counter_account.check_privileged(accounts)?;
+    let (old, old_value) = (counter_account.counter, counter_account.value());
+    counter_account.set_value(0);
+    typed_event(counter_account, old_value, CounterEvent::Reset { old }).emit();
+    counter_account.record_reset();
+    Ok(())
+}
+
+/// The bitwise instructions set the value outright, so they are signed and
+/// governed like Update.
+fn process_bitwise(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    op: impl FnOnce(u32) -> u32,
+) -> ProgramResult {
+    check_signed(accounts)?;
+    prepare_write(counter_account, &first_signer(accounts))?;
+    counter_account.check_governance(accounts)?;
+    counter_account.check_privileged(accounts)?;
+    let old = counter_account.counter;
+    counter_account.counter = op(old);
+    CounterEvent::Updated {
+        old,
+        new: counter_account.counter,
+    }
+    .emit();
+    counter_account.record_value();
+    Ok(())
+}
+
+/// `bits` for a shift of a `u32`, which must move it by fewer than 32 bits.
+fn shift_bits(bits: u8) -> Result<u32, ProgramError> {
+    let bits = u32::from(bits);
+    if bits >= u32::BITS {
+        return Err(CounterError::InvalidShift.into());
+    }
+    Ok(bits)
+}
+
+fn process_initialize<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let registry = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let config = next_account_info(accounts_iter)?;
+    if counter_account.authority != Pubkey::default() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // The data is borrowed through `counter_account`, whose size is fixed.
+    check_rent_exempt(account.lamports(), CounterAccount::LEN)?;
+    append_to_registry(program_id, registry, payer, system_program, account.key)?;
+    counter_account.authority = *authority.key;
+    counter_account.record_creation(&Clock::get()?, payer.key);
+    join_config(program_id, config, counter_account)?;
+    CounterEvent::Initialized {
+        authority: *authority.key,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_initialize_named<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+    args: InitializeNamedArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let registry = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let config = next_account_info(accounts_iter)?;
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    create_named_counter(
+        program_id,
+        account,
+        authority.key,
+        &args.name,
+        registry,
+        payer,
+        system_program,
+    )?;
+    join_config(
+        program_id,
+        config,
+        CounterAccount::load_mut(&mut account.try_borrow_mut_data()?)?,
+    )?;
+    CounterEvent::Initialized {
+        authority: *authority.key,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Creates the counter `authority` names `name` at its PDA and appends it to
+/// the registry.
+fn create_named_counter<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    authority: &Pubkey,
+    name: &str,
+    registry: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    if name.len() > MAX_SEED_LEN {
+        return Err(ProgramError::MaxSeedLengthExceeded);
+    }
+    let (address, bump) = find_counter_address(program_id, authority, name);
+    if *account.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        account,
+        system_program,
+        program_id,
+        CounterAccount::LEN,
+        &[COUNTER_SEED, authority.as_ref(), name.as_bytes(), &[bump]],
+    )?;
+    append_to_registry(program_id, registry, payer, system_program, account.key)?;
+    let mut data = account.try_borrow_mut_data()?;
+    let counter_account = CounterAccount::load_mut(&mut data)?;
+    counter_account.authority = *authority;
+    counter_account.record_creation(&Clock::get()?, payer.key);
+    Ok(())
+}
+
+/// Puts a new counter under the program config, if it has been initialized,
+/// starting it with the config's default fee.
+fn join_config(
+    program_id: &Pubkey,
+    config: &AccountInfo,
+    counter_account: &mut CounterAccount,
+) -> ProgramResult {
+    if config.owner != program_id {
+        // Only the config's own address proves there is no config yet.
+        check_pda(program_id, config.key, &[CONFIG_SEED], 0)?;
+        return Ok(());
+    }
+    let program_config = ProgramConfig::unpack(&config.try_borrow_data()?)?;
+    check_pda(program_id, config.key, &[CONFIG_SEED], program_config.bump)?;
+    counter_account.governed = 1;
+    counter_account.fee_lamports = program_config.default_fee_lamports;
+    Ok(())
+}
+
+/// Fails while the program config is paused. `config` must be the config
+/// PDA, which exists for as long as any counter is governed by it.
+pub(crate) fn check_config(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    config_owned: bool,
+    config_data: &[u8],
+) -> ProgramResult {
+    if !config_owned {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let program_config = ProgramConfig::unpack(config_data)?;
+    check_pda(program_id, config, &[CONFIG_SEED], program_config.bump)?;
+    if program_config.is_paused() {
+        return Err(CounterError::ProgramPaused.into());
+    }
+    Ok(())
+}
+
+fn process_initialize_config<'a>(
+    program_id: &Pubkey,
+    config: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+    args: InitializeConfigArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let (address, bump) = find_config_address(program_id);
+    if *config.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if config.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        config,
+        system_program,
+        program_id,
+        ProgramConfig::LEN,
+        &[CONFIG_SEED, &[bump]],
+    )?;
+    config
+        .try_borrow_mut_data()?
+        .copy_from_slice(bytemuck::bytes_of(&ProgramConfig {
+            super_admin: args.super_admin,
+            default_fee_lamports: args.default_fee_lamports,
+            paused: 0,
+            bump,
+            _padding: [0; 6],
+        }));
+    CounterEvent::ConfigInitialized {
+        super_admin: args.super_admin,
+        default_fee_lamports: args.default_fee_lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_update_config(
+    program_id: &Pubkey,
+    config: &AccountInfo,
+    accounts: &[AccountInfo],
+    args: UpdateConfigArgs,
+) -> ProgramResult {
+    let super_admin = next_account_info(&mut accounts.iter())?;
+    if config.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mut data = config.try_borrow_mut_data()?;
+    let program_config = ProgramConfig::load_mut(&mut data)?;
+    check_pda(program_id, config.key, &[CONFIG_SEED], program_config.bump)?;
+    if !super_admin.is_signer || *super_admin.key != program_config.super_admin {
+        return Err(CounterError::Unauthorized.into());
+    }
+    program_config.super_admin = args.super_admin;
+    program_config.default_fee_lamports = args.default_fee_lamports;
+    program_config.paused = args.paused.into();
+    CounterEvent::ConfigUpdated {
+        super_admin: args.super_admin,
+        default_fee_lamports: args.default_fee_lamports,
+        paused: args.paused,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_initialize_array(
+    program_id: &Pubkey,
+    array: &AccountInfo,
+    accounts: &[AccountInfo],
+    args: InitializeArrayArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if array.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if args.len == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut data = array.try_borrow_mut_data()?;
+    if data.len() != CounterArrayHeader::account_len(args.len) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if CounterArrayHeader::unpack(&data)?.authority != Pubkey::default() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    check_rent_exempt(array.lamports(), data.len())?;
+    data[..CounterArrayHeader::LEN].copy_from_slice(bytemuck::bytes_of(&CounterArrayHeader {
+        authority: *authority.key,
+        len: args.len,
+        _padding: [0; 6],
+    }));
+    CounterEvent::ArrayInitialized { len: args.len }.emit();
+    Ok(())
+}
+
+/// Replaces the counter at `index` of `array` with what `write` makes of
+/// it. Only the array's authority writes to it.
+fn process_array_write(
+    program_id: &Pubkey,
+    array: &AccountInfo,
+    accounts: &[AccountInfo],
+    index: u16,
+    write: impl FnOnce(u32) -> Result<u32, CounterError>,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    if array.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut data = array.try_borrow_mut_data()?;
+    let header = CounterArrayHeader::unpack(&data)?;
+    if header.authority == Pubkey::default() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if !authority.is_signer || *authority.key != header.authority {
+        return Err(CounterError::Unauthorized.into());
+    }
+    let old = CounterArrayHeader::value(&data, index)?;
+    let new = write(old)?;
+    CounterArrayHeader::set_value(&mut data, index, new)?;
+    CounterEvent::ArrayValueChanged { index, old, new }.emit();
+    Ok(())
+}
+
+fn process_initialize_keyed(
+    program_id: &Pubkey,
+    keyed: &AccountInfo,
+    accounts: &[AccountInfo],
+    args: InitializeKeyedArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if keyed.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut data = keyed.try_borrow_mut_data()?;
+    if data.len() != KeyedCounterHeader::account_len(args.capacity) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if KeyedCounterHeader::unpack(&data)?.authority != Pubkey::default() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    check_rent_exempt(keyed.lamports(), data.len())?;
+    *KeyedCounterHeader::load_mut(&mut data)? = KeyedCounterHeader {
+        authority: *authority.key,
+        len: 0,
+        capacity: args.capacity,
+    };
+    CounterEvent::KeyedInitialized {
+        capacity: args.capacity,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Checks that `authority` signed for the initialized keyed counter account
+/// whose data is `data`.
+fn check_keyed_authority(authority: &AccountInfo, data: &[u8]) -> ProgramResult {
+    let header = KeyedCounterHeader::unpack(data)?;
+    if header.authority == Pubkey::default() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if !authority.is_signer || *authority.key != header.authority {
+        return Err(CounterError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+fn process_keyed_write(
+    program_id: &Pubkey,
+    keyed: &AccountInfo,
+    accounts: &[AccountInfo],
+    key: &[u8; 32],
+    write: impl FnOnce(Option<u64>) -> Result<Option<u64>, CounterError>,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    if keyed.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut data = keyed.try_borrow_mut_data()?;
+    check_keyed_authority(authority, &data)?;
+    let (old, new) = KeyedCounterHeader::write(&mut data, key, write)?;
+    CounterEvent::KeyedValueChanged {
+        key: *key,
+        old,
+        new,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_resize_keyed<'a>(
+    program_id: &Pubkey,
+    keyed: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+    args: ResizeKeyedArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if keyed.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    {
+        let data = keyed.try_borrow_data()?;
+        check_keyed_authority(authority, &data)?;
+        if args.capacity < KeyedCounterHeader::unpack(&data)?.len {
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+    resize_account(
+        keyed,
+        payer,
+        system_program,
+        KeyedCounterHeader::account_len(args.capacity),
+    )?;
+    KeyedCounterHeader::load_mut(&mut keyed.try_borrow_mut_data()?)?.capacity = args.capacity;
+    CounterEvent::KeyedResized {
+        capacity: args.capacity,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Checks the tree authority PDA and programs of a compressed counter
+/// instruction on `tree`, returning the tree authority's bump.
+fn check_compression_accounts(
+    program_id: &Pubkey,
+    tree: &AccountInfo,
+    tree_authority: &AccountInfo,
+    compression_program: &AccountInfo,
+    noop_program: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let (tree_authority_address, bump) = find_tree_authority_address(program_id, tree.key);
+    if *tree_authority.key != tree_authority_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if *compression_program.key != compression::PROGRAM_ID
+        || *noop_program.key != compression::NOOP_PROGRAM_ID
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(bump)
+}
+
+fn process_initialize_compressed_tree<'a>(
+    program_id: &Pubkey,
+    tree: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+    args: InitializeCompressedTreeArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let tree_authority = next_account_info(accounts_iter)?;
+    let compression_program = next_account_info(accounts_iter)?;
+    let noop_program = next_account_info(accounts_iter)?;
+    let bump = check_compression_accounts(
+        program_id,
+        tree,
+        tree_authority,
+        compression_program,
+        noop_program,
+    )?;
+    invoke_signed(
+        &compression::init_empty_merkle_tree(
+            tree.key,
+            tree_authority.key,
+            args.max_depth,
+            args.max_buffer_size,
+        ),
+        &[
+            tree.clone(),
+            tree_authority.clone(),
+            noop_program.clone(),
+            compression_program.clone(),
+        ],
+        &[&[TREE_AUTHORITY_SEED, tree.key.as_ref(), &[bump]]],
+    )?;
+    CounterEvent::CompressedTreeInitialized {
+        tree: *tree.key,
+        max_depth: args.max_depth,
+        max_buffer_size: args.max_buffer_size,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_create_compressed<'a>(
+    program_id: &Pubkey,
+    tree: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let tree_authority = next_account_info(accounts_iter)?;
+    let compression_program = next_account_info(accounts_iter)?;
+    let noop_program = next_account_info(accounts_iter)?;
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let bump = check_compression_accounts(
+        program_id,
+        tree,
+        tree_authority,
+        compression_program,
+        noop_program,
+    )?;
+    invoke_signed(
+        &compression::append(
+            tree.key,
+            tree_authority.key,
+            &compression::leaf(authority.key, 0),
+        ),
+        &[
+            tree.clone(),
+            tree_authority.clone(),
+            noop_program.clone(),
+            compression_program.clone(),
+        ],
+        &[&[TREE_AUTHORITY_SEED, tree.key.as_ref(), &[bump]]],
+    )?;
+    CounterEvent::CompressedCreated {
+        tree: *tree.key,
+        authority: *authority.key,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Replaces the leaf of the compressed counter at `index` holding `value`
+/// with one holding `write(value)`. The compression program rejects the
+/// proof unless the tree holds that leaf, so the authority signing is the
+/// counter's and `value` is its current value.
+fn process_compressed_write<'a>(
+    program_id: &Pubkey,
+    tree: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+    root: &[u8; 32],
+    index: u32,
+    value: u32,
+    write: impl FnOnce(u32) -> Result<u32, CounterError>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let tree_authority = next_account_info(accounts_iter)?;
+    let compression_program = next_account_info(accounts_iter)?;
+    let noop_program = next_account_info(accounts_iter)?;
+    let proof = accounts_iter.as_slice();
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let bump = check_compression_accounts(
+        program_id,
+        tree,
+        tree_authority,
+        compression_program,
+        noop_program,
+    )?;
+    let new = write(value)?;
+    let proof_keys: Vec<Pubkey> = proof.iter().map(|node| *node.key).collect();
+    let mut account_infos = vec![
+        tree.clone(),
+        tree_authority.clone(),
+        noop_program.clone(),
+        compression_program.clone(),
+    ];
+    account_infos.extend_from_slice(proof);
+    invoke_signed(
+        &compression::replace_leaf(
+            tree.key,
+            tree_authority.key,
+            root,
+            &compression::leaf(authority.key, value),
+            &compression::leaf(authority.key, new),
+            index,
+            &proof_keys,
+        ),
+        &account_infos,
+        &[&[TREE_AUTHORITY_SEED, tree.key.as_ref(), &[bump]]],
+    )?;
+    CounterEvent::CompressedChanged {
+        tree: *tree.key,
+        index,
+        authority: *authority.key,
+        old: value,
+        new,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Records `counter` in the program's registry, creating the registry on
+/// first use. The payer funds the extra rent for each entry.
+fn append_to_registry<'a>(
+    program_id: &Pubkey,
+    registry: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    counter: &Pubkey,
+) -> ProgramResult {
+    let (registry_address, bump) = find_registry_address(program_id);
+    if *registry.key != registry_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if registry.owner != program_id {
+        create_pda_account(
+            payer,
+            registry,
+            system_program,
+            program_id,
+            RegistryHeader::LEN,
+            &[REGISTRY_SEED, &[bump]],
+        )?;
+    }
+
+    let count = registry_header(&mut registry.try_borrow_mut_data()?)?.count as usize;
+    resize_account(
+        registry,
+        payer,
+        system_program,
+        RegistryHeader::account_len(count + 1),
+    )?;
+
+    let mut data = registry.try_borrow_mut_data()?;
+    let start = RegistryHeader::account_len(count);
+    let entry = data
+        .get_mut(start..start + RegistryEntry::LEN)
+        .and_then(|bytes| bytemuck::try_from_bytes_mut::<RegistryEntry>(bytes).ok())
+        .ok_or(ProgramError::InvalidAccountData)?;
+    *entry = RegistryEntry {
+        counter: *counter,
+        created_slot: Clock::get()?.slot,
+    };
+    registry_header(&mut data)?.count += 1;
+    Ok(())
+}
+
+fn registry_header(data: &mut [u8]) -> Result<&mut RegistryHeader, ProgramError> {
+    data.get_mut(..RegistryHeader::LEN)
+        .and_then(|bytes| bytemuck::try_from_bytes_mut(bytes).ok())
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// Creates a program-owned account at a PDA. Unlike `create_account`, this
+/// still works if someone has already sent lamports to the address.
+fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    owner: &Pubkey,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    top_up_rent(account, payer, system_program, space)?;
+    let accounts = [account.clone(), system_program.clone()];
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &accounts,
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &accounts,
+        &[seeds],
+    )
+}
+
+/// Grows or shrinks `account`, first funding it up to the rent-exempt
+/// minimum for `new_size` from `payer`.
+fn resize_account<'a>(
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    new_size: usize,
+) -> ProgramResult {
+    top_up_rent(account, payer, system_program, new_size)?;
+    account.realloc(new_size, true)
+}
+
+fn top_up_rent<'a>(
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    size: usize,
+) -> ProgramResult {
+    let top_up = Rent::get()?
+        .minimum_balance(size)
+        .saturating_sub(account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, top_up),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    Ok(())
+}
+
+/// Counters below the rent-exempt minimum could be garbage-collected.
+fn check_rent_exempt(lamports: u64, data_len: usize) -> ProgramResult {
+    if !Rent::get()?.is_exempt(lamports, data_len) {
+        return Err(CounterError::NotRentExempt.into());
+    }
+    Ok(())
+}
+
+fn process_set_paused(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    paused: bool,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.paused = paused as u8;
+    match paused {
+        true => CounterEvent::Paused.emit(),
+        false => CounterEvent::Unpaused.emit(),
+    }
+    Ok(())
+}
+
+/// Clock-driven counters are brought up to date when frozen and resume from
+/// the thaw, as if the frozen slots and epochs never happened.
+fn process_set_frozen(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    frozen: bool,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    if frozen && !counter_account.is_frozen() {
+        apply_clock(counter_account)?
+            .iter()
+            .for_each(CounterEvent::emit);
+    }
+    if !frozen
+        && counter_account.is_frozen()
+        && (counter_account.is_epoch_scoped() || counter_account.is_decaying())
+    {
+        let clock = Clock::get()?;
+        counter_account.last_updated_slot = clock.slot;
+        counter_account.last_write_epoch = clock.epoch;
+    }
+    counter_account.frozen = frozen as u8;
+    match frozen {
+        true => CounterEvent::Frozen.emit(),
+        false => CounterEvent::Thawed.emit(),
+    }
+    Ok(())
+}
+
+fn process_set_epoch_scoped(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetEpochScopedArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.epoch_scoped = args.enabled as u8;
+    if args.enabled {
+        // Only writes from here on count towards the current epoch.
+        counter_account.last_write_epoch = Clock::get()?.epoch;
+    }
+    CounterEvent::EpochScopeSet {
+        enabled: args.enabled,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_decay(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetDecayArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    // Settle decay at the old rate before switching to the new one.
+    prepare_write(counter_account, &first_signer(accounts))?;
+    counter_account.decay_per_slot = args.per_slot;
+    counter_account.last_updated_slot = Clock::get()?.slot;
+    CounterEvent::DecayConfigured {
+        per_slot: args.per_slot,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_rate_limit(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetRateLimitArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.max_ops_per_slot = args.max_ops_per_slot;
+    CounterEvent::RateLimitConfigured {
+        max_ops_per_slot: args.max_ops_per_slot,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_cooldown(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetCooldownArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    if !matches!(args.unit, COOLDOWN_SLOTS | COOLDOWN_SECONDS) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    counter_account.cooldown = args.cooldown;
+    counter_account.cooldown_unit = args.unit;
+    CounterEvent::CooldownSet {
+        cooldown: args.cooldown,
+        unit: args.unit,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_daily_cap(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetDailyCapArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.daily_cap = args.cap;
+    CounterEvent::DailyCapConfigured { cap: args.cap }.emit();
+    Ok(())
+}
+
+/// Also funds the treasury up to the rent-exempt minimum, so fees smaller than
+/// that can be paid into it from the first operation on.
+fn process_set_fee<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: SetFeeArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    counter_account.treasury_bump = check_treasury(program_id, counter, counter_account, treasury)?;
+    top_up_rent(treasury, payer, system_program, 0)?;
+    counter_account.fee_lamports = args.lamports;
+    CounterEvent::FeeConfigured {
+        lamports: args.lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_configure_crank(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ConfigureCrankArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.crank_every_slots = args.every_slots;
+    counter_account.crank_tip_lamports = args.tip_lamports;
+    counter_account.last_crank_slot = Clock::get()?.slot;
+    CounterEvent::CrankConfigured {
+        every_slots: args.every_slots,
+        tip_lamports: args.tip_lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+/// The next schedule starts from the slot the crank ran in, however late.
+fn process_crank<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let cranker = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !cranker.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let bump = check_treasury(program_id, counter, counter_account, treasury)?;
+    let slot = Clock::get()?.slot;
+    if !counter_account.is_crank_due(slot) {
+        return Err(CounterError::CrankNotDue.into());
+    }
+
+    prepare_write(counter_account, &first_signer(accounts))?;
+    counter_account.last_crank_slot = slot;
+    CounterEvent::Reset {
+        old: counter_account.counter,
+    }
+    .emit();
+    counter_account.counter = 0;
+    counter_account.record_reset();
+
+    let tip = treasury
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0))
+        .min(counter_account.crank_tip_lamports);
+    if tip > 0 {
+        invoke_signed(
+            &system_instruction::transfer(treasury.key, cranker.key, tip),
+            &[treasury.clone(), cranker.clone(), system_program.clone()],
+            &[&[TREASURY_SEED, counter.as_ref(), &[bump]]],
+        )?;
+    }
+    CounterEvent::Cranked {
+        cranker: *cranker.key,
+        tip,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Sends everything above the treasury's rent-exempt minimum to
+/// `destination`.
+fn process_withdraw_treasury<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    let bump = check_treasury(program_id, counter, counter_account, treasury)?;
+
+    let lamports = treasury
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    invoke_signed(
+        &system_instruction::transfer(treasury.key, destination.key, lamports),
+        &[
+            treasury.clone(),
+            destination.clone(),
+            system_program.clone(),
+        ],
+        &[&[TREASURY_SEED, counter.as_ref(), &[bump]]],
+    )?;
+    CounterEvent::TreasuryWithdrawn {
+        destination: *destination.key,
+        lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_configure_payment(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ConfigurePaymentArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.payment_mint = args.mint;
+    counter_account.payment_price = args.price;
+    CounterEvent::PaymentConfigured {
+        mint: args.mint,
+        price: args.price,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_nft_gate(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetNftGateArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.nft_gate_collection = args.collection;
+    CounterEvent::NftGateConfigured {
+        collection: args.collection,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Milestones the counter has already passed count as rewarded.
+fn process_configure_milestone_reward(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ConfigureMilestoneRewardArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.reward_authority_bump = find_reward_authority_address(program_id, counter).1;
+    counter_account.reward_mint = args.mint;
+    counter_account.reward_amount = args.amount;
+    counter_account.milestone_every = args.every;
+    counter_account.rewarded_milestones = counter_account
+        .counter
+        .checked_div(args.every)
+        .unwrap_or_default();
+    CounterEvent::MilestoneRewardConfigured {
+        mint: args.mint,
+        every: args.every,
+        amount: args.amount,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_configure_milestone_nft(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ConfigureMilestoneNftArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.nft_target = args.target;
+    counter_account.nft_claimed = 0;
+    CounterEvent::MilestoneNftConfigured {
+        target: args.target,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Creates and mints a single token of `mint` to the claimer, then has Token
+/// Metadata make it an NFT: metadata naming it, and a master edition with no
+/// prints, which takes the mint authority away for good.
+fn process_claim_milestone_nft<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: ClaimMilestoneNftArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let claimer = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let token_account = next_account_info(accounts_iter)?;
+    let metadata_account = next_account_info(accounts_iter)?;
+    let master_edition = next_account_info(accounts_iter)?;
+    let nft_authority = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+    let token_metadata_program = next_account_info(accounts_iter)?;
+    if !claimer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if counter_account.nft_target == 0 {
+        return Err(CounterError::MilestoneNotReached.into());
+    }
+    if counter_account.is_nft_claimed() {
+        return Err(CounterError::MilestoneNftClaimed.into());
+    }
+    // Judge the value a read would report, as `Get` does.
+    let mut current = *counter_account;
+    apply_clock(&mut current)?;
+    if current.counter < counter_account.nft_target {
+        return Err(CounterError::MilestoneNotReached.into());
+    }
+    if *token_program.key != spl_token::id() || *token_metadata_program.key != metadata::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (nft_authority_address, bump) = find_nft_authority_address(program_id, counter);
+    if *nft_authority.key != nft_authority_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let nft_authority_seeds: &[&[u8]] = &[NFT_SEED, counter.as_ref(), &[bump]];
+
+    let mint_len = spl_token::state::Mint::LEN;
+    invoke(
+        &system_instruction::create_account(
+            claimer.key,
+            mint.key,
+            Rent::get()?.minimum_balance(mint_len),
+            mint_len as u64,
+            token_program.key,
+        ),
+        &[claimer.clone(), mint.clone(), system_program.clone()],
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_mint2(
+            token_program.key,
+            mint.key,
+            nft_authority.key,
+            Some(nft_authority.key),
+            0,
+        )?,
+        &[mint.clone(), token_program.clone()],
+    )?;
+    invoke(
+        &create_associated_token_account_idempotent(
+            claimer.key,
+            claimer.key,
+            mint.key,
+            token_program.key,
+        ),
+        &[
+            claimer.clone(),
+            token_account.clone(),
+            claimer.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )?;
+    token::mint_to(
+        token_program,
+        mint,
+        token_account,
+        nft_authority,
+        1,
+        &[nft_authority_seeds],
+    )?;
+    invoke_signed(
+        &metadata::create_metadata_account_v3(
+            metadata_account.key,
+            mint.key,
+            nft_authority.key,
+            claimer.key,
+            nft_authority.key,
+            &args.name,
+            &args.symbol,
+            &args.uri,
+        ),
+        &[
+            metadata_account.clone(),
+            mint.clone(),
+            nft_authority.clone(),
+            claimer.clone(),
+            nft_authority.clone(),
+            system_program.clone(),
+            token_metadata_program.clone(),
+        ],
+        &[nft_authority_seeds],
+    )?;
+    invoke_signed(
+        &metadata::create_master_edition_v3(
+            master_edition.key,
+            mint.key,
+            nft_authority.key,
+            nft_authority.key,
+            claimer.key,
+            metadata_account.key,
+            Some(0),
+        ),
+        &[
+            master_edition.clone(),
+            mint.clone(),
+            nft_authority.clone(),
+            nft_authority.clone(),
+            claimer.clone(),
+            metadata_account.clone(),
+            token_program.clone(),
+            system_program.clone(),
+            token_metadata_program.clone(),
+        ],
+        &[nft_authority_seeds],
+    )?;
+
+    counter_account.nft_claimed = 1;
+    CounterEvent::MilestoneNftClaimed {
+        claimer: *claimer.key,
+        mint: *mint.key,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_configure_oracle(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ConfigureOracleArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.oracle_feed_id = args.feed_id;
+    counter_account.oracle_max_age_seconds = args.max_age_seconds;
+    counter_account.oracle_max_confidence_bps = args.max_confidence_bps;
+    counter_account.oracle_exponent = args.exponent;
+    CounterEvent::OracleConfigured {
+        feed_id: args.feed_id,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Only fully verified updates for the configured feed count; partially
+/// verified ones were checked against too few Wormhole guardians.
+fn process_update_from_oracle(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let price_update = next_account_info(&mut accounts.iter())?;
+    if !counter_account.has_oracle() || *price_update.owner != pyth::RECEIVER_ID {
+        return Err(CounterError::InvalidPriceFeed.into());
+    }
+    let update = PriceUpdateV2::unpack(&price_update.try_borrow_data()?)?;
+    let message = update.price_message;
+    if update.verification_level != VerificationLevel::Full
+        || message.feed_id != counter_account.oracle_feed_id
+    {
+        return Err(CounterError::InvalidPriceFeed.into());
+    }
+    let age = Clock::get()?
+        .unix_timestamp
+        .saturating_sub(message.publish_time);
+    if age > i64::from(counter_account.oracle_max_age_seconds) {
+        return Err(CounterError::StalePrice.into());
+    }
+    let max_conf = u128::from(message.price.unsigned_abs())
+        * u128::from(counter_account.oracle_max_confidence_bps)
+        / 10_000;
+    if u128::from(message.conf) > max_conf {
+        return Err(CounterError::PriceTooUncertain.into());
+    }
+    let new = pyth::scale_price(
+        message.price,
+        message.exponent,
+        counter_account.oracle_exponent,
+    )
+    .ok_or(CounterError::Overflow)?;
+
+    prepare_write(counter_account, &first_signer(accounts))?;
+    CounterEvent::OracleUpdated {
+        old: counter_account.counter,
+        new,
+        publish_time: message.publish_time,
+    }
+    .emit();
+    counter_account.counter = new;
+    counter_account.record_value();
+    Ok(())
+}
+
+fn process_configure_wormhole(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ConfigureWormholeArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.wormhole_emitter_chain = args.emitter_chain;
+    counter_account.wormhole_emitter_address = args.emitter_address;
+    CounterEvent::WormholeConfigured {
+        emitter_chain: args.emitter_chain,
+        emitter_address: args.emitter_address,
+    }
+    .emit();
+    Ok(())
+}
+
+/// The emitter stands in for the authority, so no signer is checked; the
+/// operations otherwise behave like their instructions.
+fn process_receive_wormhole_message<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let posted_vaa = next_account_info(accounts_iter)?;
+    let received = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !counter_account.has_wormhole_emitter() || *posted_vaa.owner != wormhole::CORE_BRIDGE_ID {
+        return Err(CounterError::InvalidVaa.into());
+    }
+    let vaa = PostedVaa::unpack(&posted_vaa.try_borrow_data()?)?;
+    let vaa_hash = vaa.hash();
+    if *posted_vaa.key != wormhole::find_posted_vaa_address(&vaa_hash).0
+        || vaa.emitter_chain != counter_account.wormhole_emitter_chain
+        || vaa.emitter_address != counter_account.wormhole_emitter_address
+    {
+        return Err(CounterError::InvalidVaa.into());
+    }
+    let message = CounterMessage::unpack(&vaa.payload)
+        .filter(|message| message.counter == *counter)
+        .ok_or(CounterError::InvalidVaa)?;
+
+    let (address, bump) = find_received_address(program_id, counter, &vaa_hash);
+    if *received.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if received.owner == program_id {
+        return Err(CounterError::VaaAlreadyConsumed.into());
+    }
+    create_pda_account(
+        payer,
+        received,
+        system_program,
+        program_id,
+        ReceivedVaa::LEN,
+        &[RECEIVED_SEED, counter.as_ref(), &vaa_hash, &[bump]],
+    )?;
+    received
+        .try_borrow_mut_data()?
+        .copy_from_slice(bytemuck::bytes_of(&ReceivedVaa {
+            counter: *counter,
+            sequence: vaa.sequence,
+        }));
+
+    prepare_write(counter_account, &first_signer(accounts))?;
+    let old = counter_account.counter;
+    let value = message.value;
+    let event = match message.op {
+        wormhole::OP_INCREMENT => {
+            counter_account.counter = old.checked_add(value).ok_or(CounterError::Overflow)?;
+            counter_account.record_increment();
+            CounterEvent::Incremented {
+                old,
+                new: counter_account.counter,
+                by: value,
+            }
+        }
+        wormhole::OP_DECREMENT => {
+            counter_account.counter = old.saturating_sub(value);
+            counter_account.record_decrement();
+            CounterEvent::Decremented {
+                old,
+                new: counter_account.counter,
+                by: value,
+            }
+        }
+        wormhole::OP_UPDATE => {
+            counter_account.counter = value;
+            counter_account.record_value();
+            CounterEvent::Updated { old, new: value }
+        }
+        wormhole::OP_RESET => {
+            counter_account.counter = 0;
+            counter_account.record_reset();
+            CounterEvent::Reset { old }
+        }
+        _ => return Err(CounterError::InvalidVaa.into()),
+    };
+    CounterEvent::WormholeMessageReceived {
+        emitter_chain: vaa.emitter_chain,
+        sequence: vaa.sequence,
+    }
+    .emit();
+    event.emit();
+    Ok(())
+}
+
+fn unpack_randomness(randomness: &AccountInfo) -> Result<RandomnessAccount, ProgramError> {
+    if *randomness.owner != switchboard::ON_DEMAND_ID {
+        return Err(CounterError::InvalidRandomness.into());
+    }
+    RandomnessAccount::unpack(&randomness.try_borrow_data()?)
+}
+
+/// Checks the signer as Increment does, spending a delegate's allowance
+/// now rather than at settlement. A pending request whose randomness account
+/// has since been committed again can never settle, so a request on that
+/// account replaces it.
+fn process_request_random_increment(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: RequestRandomIncrementArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let signer = next_account_info(accounts_iter)?;
+    let randomness_account = next_account_info(accounts_iter)?;
+    if args.max == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let randomness = unpack_randomness(randomness_account)?;
+    if counter_account.has_random_request()
+        && (*randomness_account.key != counter_account.random_account
+            || randomness.seed_slot == counter_account.random_seed_slot)
+    {
+        return Err(CounterError::RandomRequestPending.into());
+    }
+    if !randomness.is_fresh_commit(&Clock::get()?) {
+        return Err(CounterError::InvalidRandomness.into());
+    }
+    counter_account.check_mutator(Some((signer.key, signer.is_signer)))?;
+    counter_account.random_account = *randomness_account.key;
+    counter_account.random_seed_slot = randomness.seed_slot;
+    counter_account.random_max = args.max;
+    counter_account.random_pending = 1;
+    CounterEvent::RandomIncrementRequested {
+        randomness: *randomness_account.key,
+        max: args.max,
+    }
+    .emit();
+    Ok(())
+}
+
+/// The increment is the revealed value's first eight bytes, little-endian,
+/// mapped onto 1 to `random_max`.
+fn process_settle_random_increment(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let randomness_account = next_account_info(&mut accounts.iter())?;
+    if !counter_account.has_random_request() {
+        return Err(CounterError::NoRandomRequest.into());
+    }
+    let randomness = unpack_randomness(randomness_account)?;
+    if *randomness_account.key != counter_account.random_account
+        || randomness.seed_slot != counter_account.random_seed_slot
+    {
+        return Err(CounterError::InvalidRandomness.into());
+    }
+    let value = randomness
+        .value(&Clock::get()?)
+        .ok_or(CounterError::RandomnessNotRevealed)?;
+    let random = u64::from_le_bytes(value[..8].try_into().expect("eight bytes"));
+    let by = (random % u64::from(counter_account.random_max)) as u32 + 1;
+
+    prepare_write(counter_account, &first_signer(accounts))?;
+    let old = counter_account.counter;
+    counter_account.counter = old.checked_add(by).ok_or(CounterError::Overflow)?;
+    counter_account.record_increment();
+    counter_account.random_pending = 0;
+    CounterEvent::Incremented {
+        old,
+        new: counter_account.counter,
+        by,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_allowlist_enabled(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetAllowlistEnabledArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.allowlist_enabled = args.enabled.into();
+    CounterEvent::AllowlistSet {
+        enabled: args.enabled,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_add_to_allowlist<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: AddToAllowlistArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let entry = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    let (address, bump) = find_allowlist_address(program_id, counter, &args.member);
+    if *entry.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if entry.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        entry,
+        system_program,
+        program_id,
+        AllowlistEntry::LEN,
+        &[
+            ALLOWLIST_SEED,
+            counter.as_ref(),
+            args.member.as_ref(),
+            &[bump],
+        ],
+    )?;
+    entry
+        .try_borrow_mut_data()?
+        .copy_from_slice(bytemuck::bytes_of(&AllowlistEntry {
+            counter: *counter,
+            member: args.member,
+        }));
+    CounterEvent::AllowlistAdded {
+        member: args.member,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_offchain_signer(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetOffchainSignerArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.offchain_signer = args.signer;
+    CounterEvent::OffchainSignerSet {
+        signer: args.signer,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_eth_authority(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetEthAuthorityArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.eth_authority = args.eth_address;
+    CounterEvent::EthAuthoritySet {
+        eth_address: args.eth_address,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_governance(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetGovernanceArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    let native_treasury = match args.governance == Pubkey::default() {
+        true => Pubkey::default(),
+        false => {
+            governance::find_native_treasury_address(&args.governance_program, &args.governance).0
+        }
+    };
+    counter_account.governance = args.governance;
+    counter_account.governance_treasury = native_treasury;
+    CounterEvent::GovernanceSet {
+        governance: args.governance,
+        native_treasury,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_memo_required(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetMemoRequiredArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.memo_required = args.enabled.into();
+    CounterEvent::MemoRequiredSet {
+        enabled: args.enabled,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_stake_weighted(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetStakeWeightedArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.stake_weighted = args.enabled.into();
+    CounterEvent::StakeWeightedSet {
+        enabled: args.enabled,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_boost(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetBoostArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.boost_factor = args.factor;
+    counter_account.boost_min_sol = args.min_sol;
+    CounterEvent::BoostSet {
+        factor: args.factor,
+        min_sol: args.min_sol,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_lock_boost<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    args: LockBoostArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if args.lamports == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let unlocks_at = i64::try_from(args.duration)
+        .ok()
+        .and_then(|duration| Clock::get().ok()?.unix_timestamp.checked_add(duration))
+        .ok_or(CounterError::Overflow)?;
+
+    if escrow.owner != program_id {
+        let (address, bump) = find_boost_address(program_id, counter, owner.key);
+        if *escrow.key != address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        create_pda_account(
+            owner,
+            escrow,
+            system_program,
+            program_id,
+            BoostEscrow::LEN,
+            &[BOOST_SEED, counter.as_ref(), owner.key.as_ref(), &[bump]],
+        )?;
+        *BoostEscrow::load_mut(&mut escrow.try_borrow_mut_data()?)? = BoostEscrow {
+            counter: *counter,
+            owner: *owner.key,
+            locked_lamports: 0,
+            unlocks_at,
+            bump,
+            _padding: [0; 7],
+        };
+    }
+    let mut data = escrow.try_borrow_mut_data()?;
+    let boost = BoostEscrow::load_mut(&mut data)?;
+    check_pda(
+        program_id,
+        escrow.key,
+        &[BOOST_SEED, counter.as_ref(), owner.key.as_ref()],
+        boost.bump,
+    )?;
+    boost.locked_lamports = boost
+        .locked_lamports
+        .checked_add(args.lamports)
+        .ok_or(CounterError::Overflow)?;
+    boost.unlocks_at = boost.unlocks_at.max(unlocks_at);
+    let event = CounterEvent::BoostLocked {
+        owner: *owner.key,
+        locked_lamports: boost.locked_lamports,
+        unlocks_at: boost.unlocks_at,
+    };
+    drop(data);
+    invoke(
+        &system_instruction::transfer(owner.key, escrow.key, args.lamports),
+        &[owner.clone(), escrow.clone(), system_program.clone()],
+    )?;
+    event.emit();
+    Ok(())
+}
+
+fn process_unlock_boost(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    if escrow.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut data = escrow.try_borrow_mut_data()?;
+    let boost = BoostEscrow::unpack(&data)?;
+    check_pda(
+        program_id,
+        escrow.key,
+        &[BOOST_SEED, counter.as_ref(), owner.key.as_ref()],
+        boost.bump,
+    )?;
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if Clock::get()?.unix_timestamp < boost.unlocks_at {
+        return Err(CounterError::BoostStillLocked.into());
+    }
+
+    let lamports = escrow.lamports();
+    let owner_lamports = owner
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **owner.lamports.borrow_mut() = owner_lamports;
+    **escrow.lamports.borrow_mut() = 0;
+    data.fill(0);
+    CounterEvent::BoostUnlocked {
+        owner: *owner.key,
+        lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_list_for_lease<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: ListForLeaseArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let lease = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    if args.duration == 0 || i64::try_from(args.duration).is_err() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    counter_account.treasury_bump = check_treasury(program_id, counter, counter_account, treasury)?;
+    top_up_rent(treasury, payer, system_program, 0)?;
+
+    if lease.owner != program_id {
+        let (address, bump) = find_lease_address(program_id, counter);
+        if *lease.key != address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        create_pda_account(
+            payer,
+            lease,
+            system_program,
+            program_id,
+            Lease::LEN,
+            &[LEASE_SEED, counter.as_ref(), &[bump]],
+        )?;
+        *Lease::load_mut(&mut lease.try_borrow_mut_data()?)? = Lease {
+            counter: *counter,
+            lessee: Pubkey::default(),
+            price_lamports: 0,
+            duration: 0,
+            expires_at: 0,
+            bump,
+            _padding: [0; 7],
+        };
+    }
+    let mut data = lease.try_borrow_mut_data()?;
+    let terms = Lease::load_mut(&mut data)?;
+    check_pda(
+        program_id,
+        lease.key,
+        &[LEASE_SEED, counter.as_ref()],
+        terms.bump,
+    )?;
+    if terms.is_active(Clock::get()?.unix_timestamp) {
+        return Err(CounterError::LeaseActive.into());
+    }
+    terms.price_lamports = args.price_lamports;
+    terms.duration = args.duration;
+    counter_account.lease_listed = 1;
+    CounterEvent::ListedForLease {
+        price_lamports: args.price_lamports,
+        duration: args.duration,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_take_lease<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let lessee = next_account_info(accounts_iter)?;
+    let lease = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !counter_account.is_listed_for_lease() {
+        return Err(CounterError::NotForLease.into());
+    }
+    if !lessee.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_treasury(program_id, counter, counter_account, treasury)?;
+    if lease.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut data = lease.try_borrow_mut_data()?;
+    let terms = Lease::load_mut(&mut data)?;
+    check_pda(
+        program_id,
+        lease.key,
+        &[LEASE_SEED, counter.as_ref()],
+        terms.bump,
+    )?;
+    let now = Clock::get()?.unix_timestamp;
+    if terms.is_active(now) {
+        return Err(CounterError::LeaseActive.into());
+    }
+    terms.lessee = *lessee.key;
+    // Listing only accepts durations that fit an `i64`.
+    terms.expires_at = now
+        .checked_add(terms.duration as i64)
+        .ok_or(CounterError::Overflow)?;
+    let price_lamports = terms.price_lamports;
+    let event = CounterEvent::Leased {
+        lessee: *lessee.key,
+        expires_at: terms.expires_at,
+    };
+    drop(data);
+    invoke(
+        &system_instruction::transfer(lessee.key, treasury.key, price_lamports),
+        &[lessee.clone(), treasury.clone(), system_program.clone()],
+    )?;
+    event.emit();
+    Ok(())
+}
+
+/// The lessee may end its lease at any time and forfeits what it paid. The
+/// authority may end it too, refunding the lessee the share of the price for
+/// the time left out of the treasury; only that path takes the treasury,
+/// lessee and system program accounts.
+fn process_end_lease<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let signer = next_account_info(accounts_iter)?;
+    let lease = next_account_info(accounts_iter)?;
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if lease.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut data = lease.try_borrow_mut_data()?;
+    let terms = Lease::load_mut(&mut data)?;
+    check_pda(
+        program_id,
+        lease.key,
+        &[LEASE_SEED, counter.as_ref()],
+        terms.bump,
+    )?;
+    let now = Clock::get()?.unix_timestamp;
+    if !terms.is_active(now) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let lessee = terms.lessee;
+    let refund_lamports = if *signer.key == lessee {
+        0
+    } else {
+        counter_account.check_authority(signer)?;
+        terms.refund(now)
+    };
+    terms.lessee = Pubkey::default();
+    terms.expires_at = now;
+    drop(data);
+
+    if refund_lamports != 0 {
+        let treasury = next_account_info(accounts_iter)?;
+        let lessee_account = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+        if *lessee_account.key != lessee {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let bump = check_treasury(program_id, counter, counter_account, treasury)?;
+        invoke_signed(
+            &system_instruction::transfer(treasury.key, lessee_account.key, refund_lamports),
+            &[
+                treasury.clone(),
+                lessee_account.clone(),
+                system_program.clone(),
+            ],
+            &[&[TREASURY_SEED, counter.as_ref(), &[bump]]],
+        )?;
+    }
+    CounterEvent::LeaseEnded {
+        lessee,
+        refund_lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_delist(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let lease = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    if active_lessee(
+        program_id,
+        counter,
+        lease.key,
+        lease.owner == program_id,
+        &lease.try_borrow_data()?,
+        Clock::get()?.unix_timestamp,
+    )?
+    .is_some()
+    {
+        return Err(CounterError::LeaseActive.into());
+    }
+    close_entry(lease, destination)?;
+    counter_account.lease_listed = 0;
+    CounterEvent::Delisted.emit();
+    Ok(())
+}
+
+fn process_set_credit_price<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: SetCreditPriceArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    counter_account.treasury_bump = check_treasury(program_id, counter, counter_account, treasury)?;
+    top_up_rent(treasury, payer, system_program, 0)?;
+    counter_account.credit_price_lamports = args.lamports;
+    CounterEvent::CreditPriceSet {
+        lamports: args.lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_buy_credits<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: BuyCreditsArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let buyer = next_account_info(accounts_iter)?;
+    let credits = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !counter_account.has_credits() {
+        return Err(CounterError::CreditsDisabled.into());
+    }
+    if !buyer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_treasury(program_id, counter, counter_account, treasury)?;
+    let price = args
+        .count
+        .checked_mul(counter_account.credit_price_lamports.into())
+        .ok_or(CounterError::Overflow)?;
+
+    if credits.owner != program_id {
+        let (address, bump) = find_credits_address(program_id, counter, buyer.key);
+        if *credits.key != address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        create_pda_account(
+            buyer,
+            credits,
+            system_program,
+            program_id,
+            CreditBalance::LEN,
+            &[CREDITS_SEED, counter.as_ref(), buyer.key.as_ref(), &[bump]],
+        )?;
+        *CreditBalance::load_mut(&mut credits.try_borrow_mut_data()?)? = CreditBalance {
+            counter: *counter,
+            owner: *buyer.key,
+            credits: 0,
+            bump,
+            _padding: [0; 7],
+        };
+    }
+    let mut data = credits.try_borrow_mut_data()?;
+    let balance = CreditBalance::load_mut(&mut data)?;
+    check_pda(
+        program_id,
+        credits.key,
+        &[CREDITS_SEED, counter.as_ref(), buyer.key.as_ref()],
+        balance.bump,
+    )?;
+    balance.credits = balance
+        .credits
+        .checked_add(args.count)
+        .ok_or(CounterError::Overflow)?;
+    let event = CounterEvent::CreditsBought {
+        buyer: *buyer.key,
+        count: args.count,
+        credits: balance.credits,
+    };
+    drop(data);
+    invoke(
+        &system_instruction::transfer(buyer.key, treasury.key, price),
+        &[buyer.clone(), treasury.clone(), system_program.clone()],
+    )?;
+    event.emit();
+    Ok(())
+}
+
+fn process_set_referrals(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetReferralsArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    if args.share_bps > 10_000 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    counter_account.referrals_enabled = args.enabled.into();
+    counter_account.referral_share_bps = args.share_bps;
+    CounterEvent::ReferralsSet {
+        enabled: args.enabled,
+        share_bps: args.share_bps,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_create_referrer<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let referrer = next_account_info(accounts_iter)?;
+    let entry = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !referrer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (address, bump) = find_referrer_address(program_id, counter, referrer.key);
+    if *entry.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if entry.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        entry,
+        system_program,
+        program_id,
+        ReferrerEntry::LEN,
+        &[
+            REFERRER_SEED,
+            counter.as_ref(),
+            referrer.key.as_ref(),
+            &[bump],
+        ],
+    )?;
+    *ReferrerEntry::load_mut(&mut entry.try_borrow_mut_data()?)? = ReferrerEntry {
+        counter: *counter,
+        referrer: *referrer.key,
+        referrals: 0,
+        referred_value: 0,
+        fees_earned: 0,
+        bump,
+        _padding: [0; 7],
+    };
+    Ok(())
+}
+
+fn process_withdraw_referral_fees(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let referrer = next_account_info(accounts_iter)?;
+    let entry = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    if entry.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let referrer_entry = ReferrerEntry::unpack(&entry.try_borrow_data()?)?;
+    check_pda(
+        program_id,
+        entry.key,
+        &[REFERRER_SEED, counter.as_ref(), referrer.key.as_ref()],
+        referrer_entry.bump,
+    )?;
+    if !referrer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let lamports = entry
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(entry.data_len()));
+    let destination_lamports = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(CounterError::Overflow)?;
+    **entry.lamports.borrow_mut() -= lamports;
+    **destination.lamports.borrow_mut() = destination_lamports;
+    CounterEvent::ReferralFeesWithdrawn {
+        referrer: *referrer.key,
+        destination: *destination.key,
+        lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_initialize_leaderboard<'a>(
+    program_id: &Pubkey,
+    leaderboard: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+    args: InitializeLeaderboardArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if args.capacity == 0 || args.capacity > LeaderboardHeader::MAX_CAPACITY {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (address, bump) = find_leaderboard_address(program_id, authority.key);
+    if *leaderboard.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if leaderboard.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        leaderboard,
+        system_program,
+        program_id,
+        LeaderboardHeader::account_len(args.capacity),
+        &[LEADERBOARD_SEED, authority.key.as_ref(), &[bump]],
+    )?;
+    leaderboard.try_borrow_mut_data()?[..LeaderboardHeader::LEN].copy_from_slice(
+        bytemuck::bytes_of(&LeaderboardHeader {
+            authority: *authority.key,
+            capacity: args.capacity,
+            count: 0,
+            bump,
+            _padding: [0; 7],
+        }),
+    );
+    CounterEvent::LeaderboardInitialized {
+        authority: *authority.key,
+        capacity: args.capacity,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_submit_score(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let leaderboard = next_account_info(accounts_iter)?;
+    if leaderboard.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut data = leaderboard.try_borrow_mut_data()?;
+    let header = LeaderboardHeader::unpack(&data)?;
+    check_pda(
+        program_id,
+        leaderboard.key,
+        &[LEADERBOARD_SEED, header.authority.as_ref()],
+        header.bump,
+    )?;
+    if counter_account.authority != header.authority {
+        return Err(CounterError::Unauthorized.into());
+    }
+    // Like `Get`, the value the next write would start from.
+    let mut current = *counter_account;
+    apply_clock(&mut current)?;
+
+    let score = current.counter.into();
+    let rank = LeaderboardHeader::submit(&mut data, counter, score)?;
+    CounterEvent::ScoreSubmitted {
+        leaderboard: *leaderboard.key,
+        score,
+        rank,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_blocklist_enabled(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetBlocklistEnabledArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.blocklist_enabled = args.enabled.into();
+    CounterEvent::BlocklistSet {
+        enabled: args.enabled,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_ban_caller<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: BanCallerArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let entry = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    let (address, bump) = find_ban_address(program_id, counter, &args.caller);
+    if *entry.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if entry.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        entry,
+        system_program,
+        program_id,
+        BanEntry::LEN,
+        &[BAN_SEED, counter.as_ref(), args.caller.as_ref(), &[bump]],
+    )?;
+    entry
+        .try_borrow_mut_data()?
+        .copy_from_slice(bytemuck::bytes_of(&BanEntry {
+            counter: *counter,
+            caller: args.caller,
+        }));
+    CounterEvent::Banned {
+        caller: args.caller,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_unban_caller(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo],
+    args: UnbanCallerArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let entry = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    if *entry.key != find_ban_address(program_id, counter, &args.caller).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if entry.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    close_entry(entry, destination)?;
+    CounterEvent::Unbanned {
+        caller: args.caller,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Closes an allow-list or ban entry the way `Close` closes a counter: all
+/// lamports out and the data zeroed, leaving the runtime to reclaim it.
+fn close_entry(entry: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let destination_lamports = destination
+        .lamports()
+        .checked_add(entry.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **destination.lamports.borrow_mut() = destination_lamports;
+    **entry.lamports.borrow_mut() = 0;
+    entry.try_borrow_mut_data()?.fill(0);
+    Ok(())
+}
+
+fn process_remove_from_allowlist(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo],
+    args: RemoveFromAllowlistArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let entry = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    if *entry.key != find_allowlist_address(program_id, counter, &args.member).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if entry.owner != program_id {
+        return Err(CounterError::NotAllowlisted.into());
+    }
+
+    close_entry(entry, destination)?;
+    CounterEvent::AllowlistRemoved {
+        member: args.member,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Creates the vault for `mint`: the associated token account of the
+/// counter's vault authority PDA. Anyone may pay for it.
+fn process_initialize_vault<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let vault_authority = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+    if *vault_authority.key != find_vault_authority_address(program_id, counter).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    token::check_token_program(mint, token_program)?;
+
+    invoke(
+        &create_associated_token_account_idempotent(
+            payer.key,
+            vault_authority.key,
+            mint.key,
+            token_program.key,
+        ),
+        &[
+            payer.clone(),
+            vault.clone(),
+            vault_authority.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )
+}
+
+fn process_withdraw_vault<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: WithdrawVaultArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let vault_authority = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    let (vault_authority_address, bump) = find_vault_authority_address(program_id, counter);
+    if *vault_authority.key != vault_authority_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    token::check_token_program(mint, token_program)?;
+
+    // The token program checks that the vault belongs to the vault
+    // authority, so any of its vaults can be drained, not just the one for
+    // the current payment mint.
+    token::transfer_checked(
+        token_program,
+        vault,
+        mint,
+        destination,
+        vault_authority,
+        args.amount,
+        &[&[VAULT_SEED, counter.as_ref(), &[bump]]],
+    )?;
+    CounterEvent::VaultWithdrawn {
+        destination: *destination.key,
+        amount: args.amount,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_create_quota<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let caller = next_account_info(accounts_iter)?;
+    let quota = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (address, bump) = find_quota_address(program_id, counter, caller.key);
+    if *quota.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if quota.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        quota,
+        system_program,
+        program_id,
+        CallerQuota::LEN,
+        &[QUOTA_SEED, counter.as_ref(), caller.key.as_ref(), &[bump]],
+    )?;
+    *CallerQuota::load_mut(&mut quota.try_borrow_mut_data()?)? = CallerQuota {
+        counter: *counter,
+        caller: *caller.key,
+        window_start: 0,
+        total: 0,
+    };
+    Ok(())
+}
+
+fn process_create_cooldown<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let caller = next_account_info(accounts_iter)?;
+    let cooldown = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (address, bump) = find_cooldown_address(program_id, counter, caller.key);
+    if *cooldown.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if cooldown.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        cooldown,
+        system_program,
+        program_id,
+        CallerCooldown::LEN,
+        &[
+            COOLDOWN_SEED,
+            counter.as_ref(),
+            caller.key.as_ref(),
+            &[bump],
+        ],
+    )?;
+    *CallerCooldown::load_mut(&mut cooldown.try_borrow_mut_data()?)? = CallerCooldown {
+        counter: *counter,
+        caller: *caller.key,
+        last_slot: 0,
+        last_timestamp: 0,
+        operations: 0,
+    };
+    Ok(())
+}
+
+fn process_initialize_history<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: InitializeHistoryArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let history = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    if args.capacity == 0 || args.capacity > HistoryHeader::MAX_CAPACITY {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (address, bump) = find_history_address(program_id, counter);
+    if *history.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if history.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        payer,
+        history,
+        system_program,
+        program_id,
+        HistoryHeader::account_len(args.capacity),
+        &[HISTORY_SEED, counter.as_ref(), &[bump]],
+    )?;
+    history.try_borrow_mut_data()?[..HistoryHeader::LEN].copy_from_slice(bytemuck::bytes_of(
+        &HistoryHeader {
+            counter: *counter,
+            capacity: args.capacity.into(),
+            count: 0,
+        },
+    ));
+    counter_account.history_capacity = args.capacity;
+    CounterEvent::HistoryInitialized {
+        capacity: args.capacity,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_checkpoint<'a>(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let checkpoint = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let clock = Clock::get()?;
+    let (address, bump) = find_checkpoint_address(program_id, counter, clock.epoch);
+    if *checkpoint.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if checkpoint.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    // Like `Get`, the value the next write would start from.
+    let mut current = *counter_account;
+    apply_clock(&mut current)?;
+
+    create_pda_account(
+        payer,
+        checkpoint,
+        system_program,
+        program_id,
+        Checkpoint::LEN,
+        &[
+            CHECKPOINT_SEED,
+            counter.as_ref(),
+            &clock.epoch.to_le_bytes(),
+            &[bump],
+        ],
+    )?;
+    checkpoint
+        .try_borrow_mut_data()?
+        .copy_from_slice(bytemuck::bytes_of(&Checkpoint {
+            counter: *counter,
+            period_index: clock.epoch,
+            slot: clock.slot,
+            value: current.counter,
+            _padding: [0; 4],
+        }));
+    CounterEvent::Checkpointed {
+        period_index: clock.epoch,
+        value: current.counter,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Folds the source counter's value into the loaded one. The source is
+/// closed, so its decay or epoch rollover is applied to a copy.
+fn process_merge(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let source = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    let signers = accounts_iter.as_slice();
+    if source.key == counter {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if source.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut source_account = CounterAccount::unpack(&source.try_borrow_data()?)?;
+    if !source_account.is_u32() {
+        return Err(CounterError::UnsupportedKind.into());
+    }
+    if source_account.is_paused() {
+        return Err(CounterError::Paused.into());
+    }
+    if source_account.is_frozen() {
+        return Err(CounterError::Frozen.into());
+    }
+    check_countdown(&source_account, false)?;
+    counter_account.check_authority(authority)?;
+    counter_account.check_privileged(signers)?;
+    source_account.check_authority(authority)?;
+    source_account.check_privileged(signers)?;
+    apply_clock(&mut source_account)?;
+
+    prepare_write(counter_account, authority.key)?;
+    counter_account.counter = counter_account
+        .counter
+        .checked_add(source_account.counter)
+        .ok_or(CounterError::Overflow)?;
+    counter_account.record_value();
+    close_entry(source, destination)?;
+    CounterEvent::Merged {
+        source: *source.key,
+        value: source_account.counter,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_split<'a>(
+    program_id: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: SplitArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let new_counter = next_account_info(accounts_iter)?;
+    let registry = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    counter_account.check_privileged(accounts_iter.as_slice())?;
+
+    prepare_write(counter_account, authority.key)?;
+    counter_account.counter = counter_account
+        .counter
+        .checked_sub(args.amount)
+        .ok_or(CounterError::InsufficientValue)?;
+    counter_account.record_value();
+    create_named_counter(
+        program_id,
+        new_counter,
+        authority.key,
+        &args.name,
+        registry,
+        payer,
+        system_program,
+    )?;
+    let mut data = new_counter.try_borrow_mut_data()?;
+    let new_counter_account = CounterAccount::load_mut(&mut data)?;
+    new_counter_account.counter = args.amount;
+    new_counter_account.governed = counter_account.governed;
+    new_counter_account.record_value();
+    CounterEvent::Split {
+        new_counter: *new_counter.key,
+        amount: args.amount,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_transfer(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: TransferArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    if destination.key == counter {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if destination.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut destination_data = destination.try_borrow_mut_data()?;
+    let destination_account = CounterAccount::load_mut(&mut destination_data)?;
+    if !destination_account.is_u32() {
+        return Err(CounterError::UnsupportedKind.into());
+    }
+    if destination_account.is_paused() {
+        return Err(CounterError::Paused.into());
+    }
+    if destination_account.is_frozen() {
+        return Err(CounterError::Frozen.into());
+    }
+    // The destination is written like the counter, so it passes the same
+    // gates dispatch puts the counter through.
+    if destination_account.is_renounced() {
+        return Err(CounterError::Renounced.into());
+    }
+    if destination_account.has_expiry() && destination_account.is_expired(Clock::get()?.slot) {
+        return Err(CounterError::Expired.into());
+    }
+    check_countdown(destination_account, false)?;
+    let history = match destination_account.has_history() {
+        true => Some(next_account_info(accounts_iter)?),
+        false => None,
+    };
+    // There is one config, which a governed counter already passed.
+    let signers = if destination_account.is_governed() && !counter_account.is_governed() {
+        let (rest, [config]) = split_trailing(accounts_iter.as_slice())?;
+        check_config(
+            program_id,
+            config.key,
+            config.owner == program_id,
+            &config.try_borrow_data()?,
+        )?;
+        rest
+    } else {
+        accounts_iter.as_slice()
+    };
+    counter_account.check_authority(authority)?;
+    counter_account.check_privileged(signers)?;
+
+    prepare_write(counter_account, authority.key)?;
+    prepare_write(destination_account, authority.key)?;
+    counter_account.counter = counter_account
+        .counter
+        .checked_sub(args.amount)
+        .ok_or(CounterError::InsufficientValue)?;
+    // Wrapping would lose what was moved, so a transfer has to fit.
+    destination_account.counter = destination_account
+        .counter
+        .checked_add(args.amount)
+        .filter(|&value| !destination_account.has_modulus() || value < destination_account.modulus)
+        .ok_or(CounterError::Overflow)?;
+    counter_account.record_value();
+    destination_account.record_value();
+    if let Some(history) = history {
+        record_history(
+            destination.key,
+            destination_account,
+            history.owner == program_id,
+            &mut history.try_borrow_mut_data()?,
+        )?;
+    }
+    CounterEvent::Transferred {
+        destination: *destination.key,
+        amount: args.amount,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_clone<'a>(
+    program_id: &Pubkey,
+    counter_account: &CounterAccount,
+    accounts: &[AccountInfo<'a>],
+    args: CloneArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let new_counter = next_account_info(accounts_iter)?;
+    let registry = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+
+    create_named_counter(
+        program_id,
+        new_counter,
+        authority.key,
+        &args.name,
+        registry,
+        payer,
+        system_program,
+    )?;
+    let mut data = new_counter.try_borrow_mut_data()?;
+    let new_counter_account = CounterAccount::load_mut(&mut data)?;
+    *new_counter_account = *counter_account;
+    // These live in, or are the bumps of, accounts derived from the
+    // original's address.
+    new_counter_account.history_capacity = 0;
+    new_counter_account.random_pending = 0;
+    new_counter_account.lease_listed = 0;
+    new_counter_account.treasury_bump = 0;
+    new_counter_account.reward_authority_bump = 0;
+    new_counter_account.record_creation(&Clock::get()?, payer.key);
+    CounterEvent::Cloned {
+        new_counter: *new_counter.key,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_propose_authority(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ProposeAuthorityArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.pending_authority = args.new_authority;
+    CounterEvent::AuthorityProposed {
+        new_authority: args.new_authority,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_accept_authority(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let new_authority = next_account_info(&mut accounts.iter())?;
+    if counter_account.pending_authority == Pubkey::default()
+        || !new_authority.is_signer
+        || *new_authority.key != counter_account.pending_authority
+    {
+        return Err(CounterError::Unauthorized.into());
+    }
+    CounterEvent::AuthorityAccepted {
+        old_authority: counter_account.authority,
+        new_authority: counter_account.pending_authority,
+    }
+    .emit();
+    counter_account.authority = counter_account.pending_authority;
+    counter_account.pending_authority = Pubkey::default();
+    Ok(())
+}
+
+fn process_set_authority(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetAuthorityArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    CounterEvent::AuthoritySet {
+        old_authority: counter_account.authority,
+        new_authority: args.new_authority,
+    }
+    .emit();
+    counter_account.pending_authority = Pubkey::default();
+    match args.new_authority {
+        Some(new_authority) => {
+            counter_account.authority = new_authority;
+        }
+        None => {
+            counter_account.authority = Pubkey::default();
+            counter_account.delegate = Pubkey::default();
+            counter_account.delegated_allowance = 0;
+            counter_account.renounced = 1;
+        }
+    }
+    Ok(())
+}
+
+/// Takes the raw account data rather than the loaded counter because it
+/// zeroes the data once the lamports have moved.
+fn process_close(
+    account: &AccountInfo,
+    data: &mut [u8],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let counter_account = CounterAccount::unpack(data)?;
+    let accounts_iter = &mut accounts.iter();
+    let destination = next_account_info(accounts_iter)?;
+    if counter_account.multisig_threshold > 0 {
+        counter_account.check_multisig(accounts_iter.as_slice())?;
+    } else {
+        counter_account.check_authority(next_account_info(accounts_iter)?)?;
+    }
+
+    let destination_lamports = destination
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    CounterEvent::Closed {
+        destination: *destination.key,
+        lamports: account.lamports(),
+    }
+    .emit();
+    **destination.lamports.borrow_mut() = destination_lamports;
+    **account.lamports.borrow_mut() = 0;
+    data.fill(0);
+    Ok(())
+}
+
+fn process_set_expiry(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetExpiryArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.expires_at_slot = args.expires_at_slot;
+    counter_account.expiry_recipient = args.recipient;
+    CounterEvent::ExpirySet {
+        expires_at_slot: args.expires_at_slot,
+        recipient: args.recipient,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Like [`process_close`], but for anyone once the counter has expired or
+/// been abandoned. The reaper keeps [`REAPER_SHARE_BPS`] of the lamports and
+/// the rest go to [`CounterAccount::reap_recipient`].
+fn process_reap(account: &AccountInfo, data: &mut [u8], accounts: &[AccountInfo]) -> ProgramResult {
+    let counter_account = CounterAccount::unpack(data)?;
+    let accounts_iter = &mut accounts.iter();
+    let recipient = next_account_info(accounts_iter)?;
+    let reaper = next_account_info(accounts_iter)?;
+    if !reaper.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let expected = counter_account
+        .reap_recipient(&Clock::get()?)
+        .ok_or(CounterError::NotExpired)?;
+    if *recipient.key != expected {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let lamports = account.lamports();
+    // At most `lamports`, so it fits back in a `u64`.
+    let reaper_share = (u128::from(lamports) * u128::from(REAPER_SHARE_BPS) / 10_000) as u64;
+    let recipient_share = lamports - reaper_share;
+    CounterEvent::Reaped {
+        recipient: *recipient.key,
+        lamports: recipient_share,
+        reaper: *reaper.key,
+        reaper_lamports: reaper_share,
+    }
+    .emit();
+    **account.lamports.borrow_mut() = 0;
+    for (to, share) in [(recipient, recipient_share), (reaper, reaper_share)] {
+        let balance = to
+            .lamports()
+            .checked_add(share)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **to.lamports.borrow_mut() = balance;
+    }
+    data.fill(0);
+    Ok(())
+}
+
+fn process_set_kind(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetKindArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    if args.kind > KIND_U128 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    // Every feature `requires_u32` rejects later must be off already.
+    if !counter_account.is_u32()
+        || counter_account.values_seen != 0
+        || counter_account.counter != 0
+        || counter_account.is_epoch_scoped()
+        || counter_account.is_decaying()
+        || counter_account.has_milestone_reward()
+        || counter_account.nft_target != 0
+        || counter_account.crank_every_slots != 0
+        || counter_account.has_history()
+    {
+        return Err(CounterError::KindLocked.into());
+    }
+    counter_account.kind = args.kind;
+    CounterEvent::KindSet { kind: args.kind }.emit();
+    Ok(())
+}
+
+fn process_set_decimals(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetDecimalsArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    if !counter_account.value().is_zero() {
+        return Err(CounterError::NonZeroValue.into());
+    }
+    counter_account.decimals = args.decimals;
+    CounterEvent::DecimalsSet {
+        decimals: args.decimals,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_set_step(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetStepArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.step = args.step;
+    CounterEvent::StepSet { step: args.step }.emit();
+    Ok(())
+}
+
+/// The value isn't reduced here; the next Increment or Decrement brings it
+/// into range.
+fn process_set_modulus(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetModulusArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.modulus = args.modulus;
+    CounterEvent::ModulusSet {
+        modulus: args.modulus,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Arming sets the value, so unlike leaving countdown mode it counts as a
+/// write for the stats.
+fn process_set_countdown(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: SetCountdownArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    let old = counter_account.counter;
+    if args.target == 0 {
+        counter_account.countdown = COUNTDOWN_OFF;
+    } else {
+        prepare_write(counter_account, authority.key)?;
+        counter_account.counter = args.target;
+        counter_account.countdown = COUNTDOWN_RUNNING;
+        counter_account.record_value();
+    }
+    CounterEvent::CountdownSet {
+        old,
+        target: args.target,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_configure_multisig(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ConfigureMultisigArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    counter_account.check_authority(authority)?;
+    // Otherwise the authority alone could lift the multisig it is bound by.
+    if counter_account.multisig_threshold > 0 {
+        counter_account.check_multisig(accounts_iter.as_slice())?;
+    }
+    // A repeated key only counts once, so it could leave the threshold out
+    // of reach.
+    let duplicated =
+        (1..args.signers.len()).any(|index| args.signers[..index].contains(&args.signers[index]));
+    if args.signers.len() > MAX_SIGNERS
+        || args.threshold as usize > args.signers.len()
+        || (args.threshold == 0) != args.signers.is_empty()
+        || duplicated
+    {
+        return Err(CounterError::InvalidMultisigConfig.into());
+    }
+    counter_account.multisig_threshold = args.threshold;
+    counter_account.multisig_signer_count = args.signers.len() as u8;
+    counter_account.multisig_signers = [Pubkey::default(); MAX_SIGNERS];
+    counter_account.multisig_signers[..args.signers.len()].copy_from_slice(&args.signers);
+    CounterEvent::MultisigConfigured {
+        threshold: args.threshold,
+        signer_count: counter_account.multisig_signer_count,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_approve(
+    counter_account: &mut CounterAccount,
+    accounts: &[AccountInfo],
+    args: ApproveArgs,
+) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.delegate = args.delegate;
+    counter_account.delegated_allowance = args.allowance;
+    CounterEvent::DelegateApproved {
+        delegate: args.delegate,
+        allowance: args.allowance,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_revoke(counter_account: &mut CounterAccount, accounts: &[AccountInfo]) -> ProgramResult {
+    let authority = next_account_info(&mut accounts.iter())?;
+    counter_account.check_authority(authority)?;
+    counter_account.delegate = Pubkey::default();
+    counter_account.delegated_allowance = 0;
+    CounterEvent::DelegateRevoked.emit();
+    Ok(())
+}
+
+/// Read-only, so it also works on paused counters.
+fn process_get(counter_account: &CounterAccount) -> ProgramResult {
+    // Report the value the next write would start from. The counter is
+    // read-only here, so the update happens on a copy.
+    let mut current = *counter_account;
+    apply_clock(&mut current)?;
+    set_return_data(&current.value().to_le_bytes());
+    Ok(())
+}
+
+fn process_get_stats(counter_account: &CounterAccount) -> ProgramResult {
+    set_return_data(bytemuck::bytes_of(&counter_account.stats()));
+    Ok(())
+}
+
+/// Logs whatever the build's features, unlike `verbose!`, since the logs are
+/// all it produces. The value is the one [`process_get`] returns.
+fn process_log(counter_account: &CounterAccount) -> ProgramResult {
+    let mut current = *counter_account;
+    apply_clock(&mut current)?;
+    msg!("value: {}", current.value());
+    msg!("authority: {}", counter_account.authority);
+    msg!("stats: {:?}", counter_account.stats());
+    msg!("state: {:?}", counter_account);
+    Ok(())
+}
+
+/// Takes the data borrow by value: the rent top-up CPI and the resize both
+/// borrow the account again, so it has to be released first.
+fn process_realloc<'a>(
+    account: &AccountInfo<'a>,
+    data: RefMut<&mut [u8]>,
+    accounts: &[AccountInfo<'a>],
+    args: ReallocArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    CounterAccount::unpack(&data)?.check_authority(authority)?;
+    let old_size = data.len();
+    drop(data);
+
+    let new_size = usize::try_from(args.new_size).map_err(|_| ProgramError::InvalidRealloc)?;
+    if new_size < CounterAccount::LEN {
+        return Err(ProgramError::InvalidRealloc);
+    }
+
+    resize_account(account, payer, system_program, new_size)?;
+    check_rent_exempt(account.lamports(), new_size)?;
+
+    CounterEvent::Reallocated {
+        old_size: old_size as u64,
+        new_size: args.new_size,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_migrate_account<'a>(
+    account: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !account.is_writable {
+        return Err(CounterError::CounterNotWritable.into());
+    }
+
+    let from = migration::layout_version(&account.try_borrow_data()?);
+    let migrations = MIGRATIONS
+        .get(usize::from(from)..)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    for migration in migrations {
+        if account.data_len() < migration.len {
+            resize_account(account, payer, system_program, migration.len)?;
+        }
+        (migration.upgrade)(&mut account.try_borrow_mut_data()?)?;
+    }
+
+    // An authority that doesn't sign fails the transaction, which undoes the
+    // migrations too.
+    let mut data = account.try_borrow_mut_data()?;
+    let counter_account = CounterAccount::load_mut(&mut data)?;
+    // The first counters had no authority; like their writes, their
+    // migration is open to any signer.
+    if counter_account.authority == Pubkey::default() {
+        if !authority.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+    } else {
+        counter_account.check_authority(authority)?;
+    }
+    counter_account.layout_version = COUNTER_LAYOUT_VERSION;
+    drop(data);
+    check_rent_exempt(account.lamports(), account.data_len())?;
+
+    CounterEvent::Migrated {
+        from,
+        to: COUNTER_LAYOUT_VERSION,
+    }
+    .emit();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::{OVERFLOW_CHECKED, OVERFLOW_SATURATING, OVERFLOW_WRAPPING};
+    use solana_program::{
+        clock::Epoch,
+        entrypoint::SUCCESS,
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+        pubkey::Pubkey,
+    };
+
+    /// Writes read the clock, which only the runtime provides; this one stays
+    /// at slot zero.
+    struct ClockStub;
+
+    impl SyscallStubs for ClockStub {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            // SAFETY: `Clock::get` passes a pointer to a `Clock`.
+            unsafe { var_addr.cast::<Clock>().write(Clock::default()) };
+            SUCCESS
+        }
+    }
+
+    fn stub_clock() {
+        static STUB: std::sync::Once = std::sync::Once::new();
+        STUB.call_once(|| {
+            set_syscall_stubs(Box::new(ClockStub));
+        });
+    }
+
+    /// Counter data as left by Initialize, which needs the runtime for its
+    /// registry CPIs.
+    fn owned_counter_data(authority: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0; CounterAccount::LEN];
+        CounterAccount::load_mut(&mut data).unwrap().authority = *authority;
+        data
+    }
+
+    #[test]
+    fn test_increment() {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0; CounterAccount::LEN];
+        let owner = Pubkey::default();
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let accounts = vec![account];
+
+        let mut increment_instruction_data: Vec<u8> = vec![0];
+        let increment_value = 48u32;
+        increment_instruction_data.extend_from_slice(&increment_value.to_le_bytes());
+        process_instruction(&program_id, &accounts, &increment_instruction_data).unwrap();
+
+        let increment_result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(increment_result, 48);
+    }
+
+    #[test]
+    fn test_decrement() {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0; CounterAccount::LEN];
+        let owner = Pubkey::default();
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let accounts = vec![account];
+
+        let mut increment_instruction_data: Vec<u8> = vec![0];
+        let increment_value = 48u32;
+        increment_instruction_data.extend_from_slice(&increment_value.to_le_bytes());
+        process_instruction(&program_id, &accounts, &increment_instruction_data).unwrap();
+
+        let increment_result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(increment_result, 48);
+
+        let mut decrement_instruction_data: Vec<u8> = vec![1];
+        let value = 16u32;
+        decrement_instruction_data.extend_from_slice(&value.to_le_bytes());
+        process_instruction(&program_id, &accounts, &decrement_instruction_data).unwrap();
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 32);
+
+        let mut big_decrement_instruction_data: Vec<u8> = vec![1];
+        big_decrement_instruction_data.extend_from_slice(&100u32.to_le_bytes());
+        process_instruction(&program_id, &accounts, &big_decrement_instruction_data).unwrap();
+
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 0);
+    }
+
+    #[test]
+    fn test_update_counter() {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0; CounterAccount::LEN];
+        let owner = Pubkey::default();
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let signer_key = Pubkey::new_unique();
+        let mut signer_lamports = 0;
+        let mut signer_data = vec![];
+        let signer = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut signer_lamports,
+            &mut signer_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let accounts = vec![account, signer];
+
+        let mut increment_instruction_data: Vec<u8> = vec![0];
+        let increment_value = 48u32;
+        increment_instruction_data.extend_from_slice(&increment_value.to_le_bytes());
+        process_instruction(&program_id, &accounts, &increment_instruction_data).unwrap();
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 48);
+
+        let mut instruction_data: Vec<u8> = vec![2];
+        instruction_data.extend_from_slice(&33u32.to_le_bytes());
+        // Nobody signing can't overwrite the counter.
+        assert_eq!(
+            process_instruction(&program_id, &accounts[..1], &instruction_data),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 33);
+    }
+
+    #[test]
+    fn test_reset_counter() {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0; CounterAccount::LEN];
+        let owner = Pubkey::default();
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let signer_key = Pubkey::new_unique();
+        let mut signer_lamports = 0;
+        let mut signer_data = vec![];
+        let signer = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut signer_lamports,
+            &mut signer_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let accounts = vec![account, signer];
+
+        let mut increment_instruction_data: Vec<u8> = vec![0];
+        let increment_value = 48u32;
+        increment_instruction_data.extend_from_slice(&increment_value.to_le_bytes());
+        process_instruction(&program_id, &accounts, &increment_instruction_data).unwrap();
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 48);
+
+        let reset_instruction_data: Vec<u8> = vec![3];
+        assert_eq!(
+            process_instruction(&program_id, &accounts[..1], &reset_instruction_data),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        process_instruction(&program_id, &accounts, &reset_instruction_data).unwrap();
+
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 0);
+    }
+
+    #[test]
+    fn test_pause_unpause() {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let owner = Pubkey::default();
+        let authority_key = Pubkey::new_unique();
+        let mut data = owned_counter_data(&authority_key);
+        let mut authority_lamports = 0;
+        let mut authority_data = vec![];
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let authority = AccountInfo::new(
+            &authority_key,
+            true,
+            false,
+            &mut authority_lamports,
+            &mut authority_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let accounts = vec![account, authority];
+
+        process_instruction(&program_id, &accounts, &[5]).unwrap();
+
+        let mut increment_instruction_data: Vec<u8> = vec![0];
+        increment_instruction_data.extend_from_slice(&48u32.to_le_bytes());
+        let err =
+            process_instruction(&program_id, &accounts, &increment_instruction_data).unwrap_err();
+        assert_eq!(err, CounterError::Paused.into());
+
+        let err = process_instruction(&program_id, &accounts, &[3]).unwrap_err();
+        assert_eq!(err, CounterError::Paused.into());
+
+        process_instruction(&program_id, &accounts, &[6]).unwrap();
+        process_instruction(&program_id, &accounts, &increment_instruction_data).unwrap();
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 48);
+    }
+
+    #[test]
+    fn test_pause_requires_authority() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let owner = Pubkey::default();
+        let authority_key = Pubkey::new_unique();
+        let mut data = owned_counter_data(&authority_key);
+        let other_key = Pubkey::new_unique();
+        let mut other_lamports = 0;
+        let mut other_data = vec![];
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let other = AccountInfo::new(
+            &other_key,
+            true,
+            false,
+            &mut other_lamports,
+            &mut other_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let err = process_instruction(&program_id, &[account, other], &[5]).unwrap_err();
+        assert_eq!(err, CounterError::Unauthorized.into());
+    }
+
+    #[test]
+    fn test_two_step_authority_transfer() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let owner = Pubkey::default();
+        let authority_key = Pubkey::new_unique();
+        let mut data = owned_counter_data(&authority_key);
+        let mut authority_lamports = 0;
+        let mut authority_data = vec![];
+        let new_authority_key = Pubkey::new_unique();
+        let mut new_authority_lamports = 0;
+        let mut new_authority_data = vec![];
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let authority = AccountInfo::new(
+            &authority_key,
+            true,
+            false,
+            &mut authority_lamports,
+            &mut authority_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let new_authority = AccountInfo::new(
+            &new_authority_key,
+            true,
+            false,
+            &mut new_authority_lamports,
+            &mut new_authority_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let mut propose_instruction_data: Vec<u8> = vec![7];
+        propose_instruction_data.extend_from_slice(new_authority_key.as_ref());
+        process_instruction(
+            &program_id,
+            &[account.clone(), authority.clone()],
+            &propose_instruction_data,
+        )
+        .unwrap();
+
+        let err = process_instruction(&program_id, &[account.clone(), authority.clone()], &[8])
+            .unwrap_err();
+        assert_eq!(err, CounterError::Unauthorized.into());
+
+        process_instruction(&program_id, &[account.clone(), new_authority.clone()], &[8]).unwrap();
+        let counter_account = CounterAccount::unpack(&account.data.borrow()).unwrap();
+        assert_eq!(counter_account.authority, new_authority_key);
+        assert_eq!(counter_account.pending_authority, Pubkey::default());
+
+        let err =
+            process_instruction(&program_id, &[account.clone(), authority], &[5]).unwrap_err();
+        assert_eq!(err, CounterError::Unauthorized.into());
+        process_instruction(&program_id, &[account, new_authority], &[5]).unwrap();
+    }
+
+    #[test]
+    fn test_multisig_update() {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let owner = Pubkey::default();
+        let authority_key = Pubkey::new_unique();
+        let mut data = owned_counter_data(&authority_key);
+        let mut authority_lamports = 0;
+        let mut authority_data = vec![];
+        let signer_keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let mut signer_lamports = [0; 3];
+        let mut signer_data = [vec![], vec![], vec![]];
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let authority = AccountInfo::new(
+            &authority_key,
+            true,
+            false,
+            &mut authority_lamports,
+            &mut authority_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let signers: Vec<AccountInfo> = signer_keys
+            .iter()
+            .zip(signer_lamports.iter_mut())
+            .zip(signer_data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(
+                    key,
+                    true,
+                    false,
+                    lamports,
+                    data,
+                    &owner,
+                    false,
+                    Epoch::default(),
+                )
+            })
+            .collect();
+
+        let mut configure_instruction_data: Vec<u8> = vec![10, 2];
+        configure_instruction_data.extend_from_slice(&3u32.to_le_bytes());
+        for signer_key in &signer_keys {
+            configure_instruction_data.extend_from_slice(signer_key.as_ref());
+        }
+        process_instruction(
+            &program_id,
+            &[account.clone(), authority],
+            &configure_instruction_data,
+        )
+        .unwrap();
+
+        let mut update_instruction_data: Vec<u8> = vec![2];
+        update_instruction_data.extend_from_slice(&33u32.to_le_bytes());
+
+        let err = process_instruction(
+            &program_id,
+            &[account.clone(), signers[0].clone()],
+            &update_instruction_data,
+        )
+        .unwrap_err();
+        assert_eq!(err, CounterError::NotEnoughSigners.into());
+
+        let err = process_instruction(
+            &program_id,
+            &[account.clone(), signers[0].clone(), signers[0].clone()],
+            &update_instruction_data,
+        )
+        .unwrap_err();
+        assert_eq!(err, CounterError::NotEnoughSigners.into());
+
+        process_instruction(
+            &program_id,
+            &[account.clone(), signers[0].clone(), signers[2].clone()],
+            &update_instruction_data,
+        )
+        .unwrap();
+        let result = CounterAccount::unpack(&account.data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 33);
+    }
+
+    #[test]
+    fn test_close() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 100;
+        let owner = Pubkey::default();
+        let authority_key = Pubkey::new_unique();
+        let mut data = owned_counter_data(&authority_key);
+        let mut authority_lamports = 0;
+        let mut authority_data = vec![];
+        let destination_key = Pubkey::new_unique();
+        let mut destination_lamports = 5;
+        let mut destination_data = vec![];
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let authority = AccountInfo::new(
+            &authority_key,
+            true,
+            false,
+            &mut authority_lamports,
+            &mut authority_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let destination = AccountInfo::new(
+            &destination_key,
+            false,
+            true,
+            &mut destination_lamports,
+            &mut destination_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let err = process_instruction(
+            &program_id,
+            &[account.clone(), destination.clone(), destination.clone()],
+            &[9],
+        )
+        .unwrap_err();
+        assert_eq!(err, CounterError::Unauthorized.into());
+
+        process_instruction(
+            &program_id,
+            &[account.clone(), destination.clone(), authority],
+            &[9],
+        )
+        .unwrap();
+        assert_eq!(account.lamports(), 0);
+        assert_eq!(destination.lamports(), 105);
+        assert!(account.data.borrow().iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_delegate_allowance() {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let owner = Pubkey::default();
+        let authority_key = Pubkey::new_unique();
+        let mut data = owned_counter_data(&authority_key);
+        let mut authority_lamports = 0;
+        let mut authority_data = vec![];
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_lamports = 0;
+        let mut delegate_data = vec![];
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let authority = AccountInfo::new(
+            &authority_key,
+            true,
+            false,
+            &mut authority_lamports,
+            &mut authority_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let delegate = AccountInfo::new(
+            &delegate_key,
+            true,
+            false,
+            &mut delegate_lamports,
+            &mut delegate_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let mut increment_instruction_data: Vec<u8> = vec![0];
+        increment_instruction_data.extend_from_slice(&5u32.to_le_bytes());
+        let err = process_instruction(
+            &program_id,
+            &[account.clone(), delegate.clone()],
+            &increment_instruction_data,
+        )
+        .unwrap_err();
+        assert_eq!(err, CounterError::Unauthorized.into());
+
+        let mut approve_instruction_data: Vec<u8> = vec![11];
+        approve_instruction_data.extend_from_slice(delegate_key.as_ref());
+        approve_instruction_data.extend_from_slice(&2u64.to_le_bytes());
+        process_instruction(
+            &program_id,
+            &[account.clone(), authority.clone()],
+            &approve_instruction_data,
+        )
+        .unwrap();
+
+        let mut decrement_instruction_data: Vec<u8> = vec![1];
+        decrement_instruction_data.extend_from_slice(&1u32.to_le_bytes());
+        process_instruction(
+            &program_id,
+            &[account.clone(), delegate.clone()],
+            &increment_instruction_data,
+        )
+        .unwrap();
+        process_instruction(
+            &program_id,
+            &[account.clone(), delegate.clone()],
+            &decrement_instruction_data,
+        )
+        .unwrap();
+        let err = process_instruction(
+            &program_id,
+            &[account.clone(), delegate.clone()],
+            &increment_instruction_data,
+        )
+        .unwrap_err();
+        assert_eq!(err, CounterError::AllowanceExceeded.into());
+
+        let counter_account = CounterAccount::unpack(&account.data.borrow()).unwrap();
+        assert_eq!(counter_account.counter, 4);
+        assert_eq!(counter_account.delegated_allowance, 0);
+
+        process_instruction(
+            &program_id,
+            &[account.clone(), authority.clone()],
+            &approve_instruction_data,
+        )
+        .unwrap();
+        process_instruction(&program_id, &[account.clone(), authority], &[12]).unwrap();
+        let err = process_instruction(
+            &program_id,
+            &[account, delegate],
+            &increment_instruction_data,
+        )
+        .unwrap_err();
+        assert_eq!(err, CounterError::Unauthorized.into());
+    }
+
+    #[test]
+    fn test_increment_overflow() {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0; CounterAccount::LEN];
+        let owner = Pubkey::default();
+
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let accounts = vec![account];
+
+        let mut increment_instruction_data: Vec<u8> = vec![0];
+        increment_instruction_data.extend_from_slice(&u32::MAX.to_le_bytes());
+        process_instruction(&program_id, &accounts, &increment_instruction_data).unwrap();
+
+        let mut increment_one_instruction_data: Vec<u8> = vec![0];
+        increment_one_instruction_data.extend_from_slice(&1u32.to_le_bytes());
+        let err = process_instruction(&program_id, &accounts, &increment_one_instruction_data)
+            .unwrap_err();
+        assert_eq!(err, CounterError::Overflow.into());
+
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, u32::MAX);
+
+        // Saturating stays put and wrapping comes around to zero.
+        increment_one_instruction_data.push(OVERFLOW_SATURATING);
+        process_instruction(&program_id, &accounts, &increment_one_instruction_data).unwrap();
+        increment_one_instruction_data[5] = OVERFLOW_WRAPPING;
+        process_instruction(&program_id, &accounts, &increment_one_instruction_data).unwrap();
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, 0);
+
+        let mut decrement_one_instruction_data: Vec<u8> = vec![1];
+        decrement_one_instruction_data.extend_from_slice(&1u32.to_le_bytes());
+        decrement_one_instruction_data.push(OVERFLOW_CHECKED);
+        let err = process_instruction(&program_id, &accounts, &decrement_one_instruction_data)
+            .unwrap_err();
+        assert_eq!(err, CounterError::Underflow.into());
+        decrement_one_instruction_data[5] = OVERFLOW_WRAPPING;
+        process_instruction(&program_id, &accounts, &decrement_one_instruction_data).unwrap();
+        let result = CounterAccount::unpack(&accounts[0].data.borrow())
+            .unwrap()
+            .counter;
+        assert_eq!(result, u32::MAX);
+
+        decrement_one_instruction_data[5] = 3;
+        let err = process_instruction(&program_id, &accounts, &decrement_one_instruction_data)
+            .unwrap_err();
+        assert_eq!(err, ProgramError::InvalidInstructionData);
+    }
+
+    #[test]
+    fn test_check_pda() {
+        let program_id = Pubkey::new_unique();
+        let counter = Pubkey::new_unique();
+        let seeds: &[&[u8]] = &[TREASURY_SEED, counter.as_ref()];
+        let (address, bump) = Pubkey::find_program_address(seeds, &program_id);
+        assert_eq!(check_pda(&program_id, &address, seeds, bump), Ok(bump));
+        // Zero means no stored bump, so the canonical one is searched for.
+        assert_eq!(check_pda(&program_id, &address, seeds, 0), Ok(bump));
+        assert_eq!(
+            check_pda(&program_id, &address, seeds, bump - 1),
+            Err(ProgramError::InvalidSeeds)
+        );
+        assert_eq!(
+            check_pda(&program_id, &counter, seeds, 0),
+            Err(ProgramError::InvalidSeeds)
+        );
+    }
+}
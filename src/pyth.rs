@@ -0,0 +1,118 @@
+//! Just enough of the Pyth Solana receiver to read a verified price, without
+//! depending on the Pyth crates.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey, pubkey::Pubkey};
+
+/// The Pyth Solana receiver program, owner of `PriceUpdateV2` accounts.
+pub const RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Anchor discriminator of `PriceUpdateV2`, `sha256("account:PriceUpdateV2")[..8]`.
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum VerificationLevel {
+    /// Checked against only some of the Wormhole guardians.
+    Partial { num_signatures: u8 },
+    /// Checked against a quorum of the Wormhole guardians.
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+/// A `PriceUpdateV2` account, after its discriminator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+impl PriceUpdateV2 {
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let rest = data
+            .strip_prefix(&PRICE_UPDATE_V2_DISCRIMINATOR)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Self::deserialize(&mut &rest[..]).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Packs the account the way the receiver stores it, discriminator first.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = PRICE_UPDATE_V2_DISCRIMINATOR.to_vec();
+        self.serialize(&mut data).expect("serializing into a Vec");
+        data
+    }
+}
+
+/// `price * 10^exponent` in units of `10^target_exponent`, truncated, if it is
+/// non-negative and fits a `u32`.
+pub fn scale_price(price: i64, exponent: i32, target_exponent: i32) -> Option<u32> {
+    let price = u64::try_from(price).ok()?;
+    let shift = exponent.checked_sub(target_exponent)?;
+    let factor = 10u64.checked_pow(shift.unsigned_abs());
+    let scaled = if shift >= 0 {
+        price.checked_mul(factor?)?
+    } else {
+        // Dividing by more than `u64::MAX` leaves nothing.
+        factor.map_or(0, |factor| price / factor)
+    };
+    u32::try_from(scaled).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scale_price() {
+        // $65,123.45678 with Pyth's usual exponent, in cents.
+        assert_eq!(scale_price(6_512_345_678_000, -8, -2), Some(6_512_345));
+        assert_eq!(scale_price(12, 0, -3), Some(12_000));
+        assert_eq!(scale_price(12, -30, 0), Some(0));
+        assert_eq!(scale_price(-1, 0, 0), None);
+        assert_eq!(scale_price(i64::from(u32::MAX) + 1, 0, 0), None);
+        assert_eq!(scale_price(1, 30, 0), None);
+    }
+
+    #[test]
+    fn test_price_update_round_trip() {
+        let update = PriceUpdateV2 {
+            write_authority: Pubkey::new_unique(),
+            verification_level: VerificationLevel::Partial { num_signatures: 5 },
+            price_message: PriceFeedMessage {
+                feed_id: [7; 32],
+                price: 6_512_345_678,
+                conf: 1_234_567,
+                exponent: -8,
+                publish_time: 1_700_000_000,
+                prev_publish_time: 1_699_999_999,
+                ema_price: 6_500_000_000,
+                ema_conf: 1_000_000,
+            },
+            posted_slot: 42,
+        };
+        let data = update.pack();
+        // Discriminator, authority, two-byte verification level, the message
+        // and the slot.
+        assert_eq!(data.len(), 8 + 32 + 2 + 84 + 8);
+        assert_eq!(PriceUpdateV2::unpack(&data).unwrap(), update);
+
+        let mut wrong = data;
+        wrong[0] ^= 1;
+        assert_eq!(
+            PriceUpdateV2::unpack(&wrong),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
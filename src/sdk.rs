@@ -0,0 +1,2712 @@
+//! Instruction builders for clients and CPI callers.
+//!
+//! Privileged instructions take a `signers` slice: the authority alone when no
+//! multisig is configured, otherwise the multisig members signing the call.
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use crate::compression;
+use crate::ed25519;
+use crate::instructions::{
+    AddToAllowlistArgs, AndArgs, ApproveArgs, BanCallerArgs, BuyCreditsArgs, ClaimMilestoneNftArgs,
+    CloneArgs, ConfigureCrankArgs, ConfigureMilestoneNftArgs, ConfigureMilestoneRewardArgs,
+    ConfigureMultisigArgs, ConfigureOracleArgs, ConfigurePaymentArgs, ConfigureWormholeArgs,
+    CounterInstructions, DecrementArgs, DecrementAtArgs, DecrementCompressedArgs,
+    DecrementKeyedArgs, DecrementScaledArgs, DecrementWideArgs, IncrementArgs, IncrementAtArgs,
+    IncrementCompressedArgs, IncrementIfBelowArgs, IncrementKeyedArgs, IncrementScaledArgs,
+    IncrementWideArgs, InitializeArrayArgs, InitializeCompressedTreeArgs, InitializeConfigArgs,
+    InitializeHistoryArgs, InitializeKeyedArgs, InitializeLeaderboardArgs, InitializeNamedArgs,
+    ListForLeaseArgs, LockBoostArgs, OrArgs, ProposeAuthorityArgs, ReallocArgs,
+    RemoveFromAllowlistArgs, RemoveKeyedArgs, RequestRandomIncrementArgs, ResizeKeyedArgs,
+    SetAllowlistEnabledArgs, SetAuthorityArgs, SetBlocklistEnabledArgs, SetBoostArgs,
+    SetCooldownArgs, SetCountdownArgs, SetCreditPriceArgs, SetDailyCapArgs, SetDecayArgs,
+    SetDecimalsArgs, SetEpochScopedArgs, SetEthAuthorityArgs, SetExpiryArgs, SetFeeArgs,
+    SetGovernanceArgs, SetKindArgs, SetMemoRequiredArgs, SetModulusArgs, SetNftGateArgs,
+    SetOffchainSignerArgs, SetRateLimitArgs, SetReferralsArgs, SetStakeWeightedArgs, SetStepArgs,
+    ShiftLeftArgs, ShiftRightArgs, SplitArgs, TransferArgs, UnbanCallerArgs, UpdateArgs,
+    UpdateAtArgs, UpdateConfigArgs, WithdrawVaultArgs, XorArgs,
+};
+use crate::metadata::{self, find_master_edition_address, find_metadata_address};
+use crate::secp256k1;
+use crate::state::{
+    eth_message, find_allowlist_address, find_ban_address, find_boost_address,
+    find_checkpoint_address, find_config_address, find_cooldown_address, find_counter_address,
+    find_credits_address, find_history_address, find_leaderboard_address, find_lease_address,
+    find_nft_authority_address, find_quota_address, find_received_address, find_referrer_address,
+    find_registry_address, find_reward_authority_address, find_treasury_address,
+    find_tree_authority_address, find_vault_address, find_vault_authority_address,
+    offchain_message, OVERFLOW_CHECKED, OVERFLOW_SATURATING,
+};
+use crate::wormhole;
+
+fn with_signers(mut accounts: Vec<AccountMeta>, signers: &[&Pubkey]) -> Vec<AccountMeta> {
+    accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(**signer, true)),
+    );
+    accounts
+}
+
+/// Appends the accounts that pay a counter's fee to an Increment, Decrement,
+/// Update or Reset built for `counter`. Needed once [`set_fee`] is in effect.
+pub fn with_fee(mut instruction: Instruction, counter: &Pubkey, payer: &Pubkey) -> Instruction {
+    let (treasury, _) = find_treasury_address(&instruction.program_id, counter);
+    instruction.accounts.extend([
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(treasury, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+    instruction
+}
+
+/// Appends the counter's history to a write built for `counter`: Increment,
+/// Decrement, Update, Reset, UpdateFromOracle, SettleRandomIncrement,
+/// ReceiveWormholeMessage, Crank, Merge, Split or Transfer. Needed once
+/// [`initialize_history`] has run; apply it after every other `with_*`
+/// builder but [`with_config`].
+pub fn with_history(mut instruction: Instruction, counter: &Pubkey) -> Instruction {
+    let (history, _) = find_history_address(&instruction.program_id, counter);
+    instruction.accounts.push(AccountMeta::new(history, false));
+    instruction
+}
+
+/// Appends the entry of `referrer`, or the program ID for none, to an
+/// Increment built for `counter`. Needed once [`set_referrals`] is in effect;
+/// apply it after every other `with_*` builder but [`with_history`] and
+/// [`with_config`]. The entry must exist; see [`create_referrer`].
+pub fn with_referrer(
+    mut instruction: Instruction,
+    counter: &Pubkey,
+    referrer: Option<&Pubkey>,
+) -> Instruction {
+    let entry = match referrer {
+        Some(referrer) => find_referrer_address(&instruction.program_id, counter, referrer).0,
+        None => instruction.program_id,
+    };
+    instruction.accounts.push(match referrer {
+        Some(_) => AccountMeta::new(entry, false),
+        None => AccountMeta::new_readonly(entry, false),
+    });
+    instruction
+}
+
+/// Appends `owner`'s credit balance to an Increment, Decrement, Update or
+/// Reset built for `counter`, where `owner` is the instruction's signer.
+/// Needed once [`set_credit_price`] is in effect; apply it after every other
+/// `with_*` builder but [`with_cooldown`], [`with_lease`], [`with_referrer`],
+/// [`with_history`] and [`with_config`].
+pub fn with_credits(mut instruction: Instruction, counter: &Pubkey, owner: &Pubkey) -> Instruction {
+    let (credits, _) = find_credits_address(&instruction.program_id, counter, owner);
+    instruction.accounts.push(AccountMeta::new(credits, false));
+    instruction
+}
+
+/// Appends `caller`'s cooldown account to an Increment, Decrement, Update or
+/// Reset built for `counter`, where `caller` is the instruction's signer.
+/// Needed once [`set_cooldown`] is in effect; apply it after every other
+/// `with_*` builder but [`with_lease`], [`with_referrer`], [`with_history`]
+/// and [`with_config`]. The account must exist; see [`create_cooldown`].
+pub fn with_cooldown(
+    mut instruction: Instruction,
+    counter: &Pubkey,
+    caller: &Pubkey,
+) -> Instruction {
+    let (cooldown, _) = find_cooldown_address(&instruction.program_id, counter, caller);
+    instruction.accounts.push(AccountMeta::new(cooldown, false));
+    instruction
+}
+
+/// Appends the lease of `counter` to an Increment, Decrement, Update or Reset
+/// built for it, or to a Close, Pause, Freeze, SetAuthority, ProposeAuthority
+/// or AcceptAuthority. Needed while the counter is listed; see [`list_for_lease`].
+/// Apply it after every other `with_*` builder but [`with_referrer`],
+/// [`with_history`] and [`with_config`].
+pub fn with_lease(mut instruction: Instruction, counter: &Pubkey) -> Instruction {
+    let (lease, _) = find_lease_address(&instruction.program_id, counter);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(lease, false));
+    instruction
+}
+
+/// Appends the program config to a write of a governed counter, the same
+/// writes as [`with_history`] takes. Apply it last.
+pub fn with_config(mut instruction: Instruction) -> Instruction {
+    let (config, _) = find_config_address(&instruction.program_id);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(config, false));
+    instruction
+}
+
+/// Appends `caller`'s ban entry address to an Increment, Decrement, Update
+/// or Reset built for `counter`, where `caller` is the instruction's signer.
+/// Needed once [`set_blocklist_enabled`] is in effect; apply it after
+/// [`with_allowlist`] and before [`with_fee`].
+pub fn with_blocklist(
+    mut instruction: Instruction,
+    counter: &Pubkey,
+    caller: &Pubkey,
+) -> Instruction {
+    let (entry, _) = find_ban_address(&instruction.program_id, counter, caller);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(entry, false));
+    instruction
+}
+
+/// Appends `member`'s allow-list entry to an Increment, Decrement, Update or
+/// Reset built for `counter`, where `member` is the instruction's signer.
+/// Needed once [`set_allowlist_enabled`] is in effect; apply
+/// [`with_blocklist`] and [`with_fee`] after this and anything else before
+/// it.
+pub fn with_allowlist(
+    mut instruction: Instruction,
+    counter: &Pubkey,
+    member: &Pubkey,
+) -> Instruction {
+    let (entry, _) = find_allowlist_address(&instruction.program_id, counter, member);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(entry, false));
+    instruction
+}
+
+/// Appends the accounts that pay a counter's token price to an Increment
+/// built for `counter`. `source` is the signer's token account for `mint`,
+/// and `token_program` is the program owning `mint`, spl-token or
+/// Token-2022. Needed once [`configure_payment`] is in effect; apply
+/// [`with_fee`] after this when both are.
+pub fn with_payment(
+    mut instruction: Instruction,
+    counter: &Pubkey,
+    mint: &Pubkey,
+    source: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let vault = find_vault_address(&instruction.program_id, counter, mint, token_program);
+    instruction.accounts.extend([
+        AccountMeta::new(*source, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*token_program, false),
+    ]);
+    instruction
+}
+
+/// Appends the Instructions sysvar to an Increment, so the counter can find
+/// the off-chain signer's authorization before it. Needed once
+/// [`set_offchain_signer`] is in effect; apply this before any other `with_*`
+/// builder, and send [`offchain_authorization`] right before the result.
+pub fn with_offchain_authorization(mut instruction: Instruction) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    instruction
+}
+
+/// The Ed25519 program instruction carrying `signature`, the off-chain
+/// `signer`'s signature over [`offchain_message`] for `counter` and `nonce`
+/// (the counter's current `offchain_nonce`).
+pub fn offchain_authorization(
+    signer: &Pubkey,
+    signature: &[u8; ed25519::SIGNATURE_LEN],
+    counter: &Pubkey,
+    nonce: u64,
+) -> Instruction {
+    ed25519::new_instruction(signer, signature, &offchain_message(counter, nonce))
+}
+
+/// Appends the Instructions sysvar to an Increment, Decrement, Update or
+/// Reset, so the counter can find its Ethereum authority's authorization
+/// before it. Needed once [`set_eth_authority`] is in effect; apply
+/// [`with_allowlist`], [`with_blocklist`] and [`with_fee`] after this and
+/// anything else before it, and send [`eth_authorization`] right before the
+/// result.
+pub fn with_eth_authorization(mut instruction: Instruction) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    instruction
+}
+
+/// Appends the Instructions sysvar to an Increment, Decrement, Update or
+/// Reset, so the counter can find the memo in its transaction. Needed once
+/// [`set_memo_required`] is in effect; apply [`with_eth_authorization`],
+/// [`with_allowlist`], [`with_blocklist`] and [`with_fee`] after this and
+/// anything else before it, and send a [`crate::memo::new_instruction`] in
+/// the same transaction.
+pub fn with_memo_required(mut instruction: Instruction) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    instruction
+}
+
+/// Appends `stake`, a native stake account the signer is the staker or
+/// withdrawer of, to an Increment. Needed once [`set_stake_weighted`] is in
+/// effect; apply [`with_memo_required`], [`with_eth_authorization`],
+/// [`with_allowlist`], [`with_blocklist`] and [`with_fee`] after this and
+/// anything else before it.
+pub fn with_stake(mut instruction: Instruction, stake: &Pubkey) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*stake, false));
+    instruction
+}
+
+/// Appends the boost escrow of `owner`, the signer, or the program ID for
+/// none, to an Increment built for `counter`. Needed once [`set_boost`] is in
+/// effect; apply [`with_stake`], [`with_memo_required`],
+/// [`with_eth_authorization`], [`with_allowlist`], [`with_blocklist`] and
+/// [`with_fee`] after this and anything else before it.
+pub fn with_boost(
+    mut instruction: Instruction,
+    counter: &Pubkey,
+    owner: Option<&Pubkey>,
+) -> Instruction {
+    let escrow = match owner {
+        Some(owner) => find_boost_address(&instruction.program_id, counter, owner).0,
+        None => instruction.program_id,
+    };
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(escrow, false));
+    instruction
+}
+
+/// The secp256k1 program instruction carrying `signature`, the Ethereum
+/// authority's signature over [`eth_message`] for `counter` and `nonce` (the
+/// counter's current `eth_nonce`). `signature` and `recovery_id` split the
+/// 65-byte signature wallets return, whose last byte is 27 or 28 more than
+/// the recovery id. `instruction_index` is where this instruction goes in
+/// the transaction.
+pub fn eth_authorization(
+    eth_address: &[u8; secp256k1::ETH_ADDRESS_LEN],
+    signature: &[u8; secp256k1::SIGNATURE_LEN],
+    recovery_id: u8,
+    counter: &Pubkey,
+    nonce: u64,
+    instruction_index: u8,
+) -> Instruction {
+    secp256k1::new_instruction(
+        eth_address,
+        signature,
+        recovery_id,
+        &eth_message(counter, nonce),
+        instruction_index,
+    )
+}
+
+/// Appends the accounts that receive a counter's milestone reward to an
+/// Increment built for `counter`: `destination`, a token account for `mint`,
+/// the reward authority and `token_program`. Needed once
+/// [`configure_milestone_reward`] is in effect; apply [`with_nft`],
+/// [`with_payment`] and [`with_fee`] after this.
+pub fn with_milestone_reward(
+    mut instruction: Instruction,
+    counter: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let (reward_authority, _) = find_reward_authority_address(&instruction.program_id, counter);
+    instruction.accounts.extend([
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new_readonly(reward_authority, false),
+        AccountMeta::new_readonly(*token_program, false),
+    ]);
+    instruction
+}
+
+/// Appends the NFT that lets the signer of `instruction` through a counter's
+/// NFT gate: the signer's `token_account` holding `mint`, and the mint's
+/// Metaplex metadata. Needed once [`set_nft_gate`] is in effect; apply
+/// [`with_payment`] and [`with_fee`] after this.
+pub fn with_nft(
+    mut instruction: Instruction,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (metadata, _) = find_metadata_address(mint);
+    instruction.accounts.extend([
+        AccountMeta::new_readonly(*token_account, false),
+        AccountMeta::new_readonly(metadata, false),
+    ]);
+    instruction
+}
+
+/// `signer` is the authority or delegate; it is ignored by counters that
+/// have no authority. Fails on overflow.
+pub fn increment(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    value: u32,
+) -> Instruction {
+    increment_with_mode(program_id, counter, signer, value, OVERFLOW_CHECKED)
+}
+
+/// [`increment`] with the given `OVERFLOW_*` policy.
+pub fn increment_with_mode(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    value: u32,
+    mode: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::Increment(IncrementArgs { value, mode }).pack(),
+    }
+}
+
+/// Increment for counters with a daily cap: also passes the signer's quota
+/// account, which must exist (see [`create_quota`]).
+pub fn increment_capped(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    value: u32,
+) -> Instruction {
+    let mut instruction = increment(program_id, counter, signer, value);
+    let (quota, _) = find_quota_address(program_id, counter, signer);
+    instruction.accounts.push(AccountMeta::new(quota, false));
+    instruction
+}
+
+/// `signer` is the authority or delegate; it is ignored by counters that
+/// have no authority. Floors at zero, or at `i64::MIN` on signed counters.
+pub fn decrement(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    value: u32,
+) -> Instruction {
+    decrement_with_mode(program_id, counter, signer, value, OVERFLOW_SATURATING)
+}
+
+/// [`decrement`] with the given `OVERFLOW_*` policy.
+pub fn decrement_with_mode(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    value: u32,
+    mode: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::Decrement(DecrementArgs { value, mode }).pack(),
+    }
+}
+
+/// [`increment`] by one in a single byte of instruction data.
+pub fn increment_one(program_id: &Pubkey, counter: &Pubkey, signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::IncrementOne.pack(),
+    }
+}
+
+/// [`decrement`] by one in a single byte of instruction data.
+pub fn decrement_one(program_id: &Pubkey, counter: &Pubkey, signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::DecrementOne.pack(),
+    }
+}
+
+pub fn update(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signers: &[&Pubkey],
+    value: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(vec![AccountMeta::new(*counter, false)], signers),
+        data: CounterInstructions::Update(UpdateArgs { value }).pack(),
+    }
+}
+
+pub fn reset(program_id: &Pubkey, counter: &Pubkey, signers: &[&Pubkey]) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(vec![AccountMeta::new(*counter, false)], signers),
+        data: CounterInstructions::Reset.pack(),
+    }
+}
+
+pub fn initialize(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(find_registry_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+        data: CounterInstructions::Initialize.pack(),
+    }
+}
+
+pub fn pause(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::Pause.pack(),
+    }
+}
+
+pub fn unpause(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::Unpause.pack(),
+    }
+}
+
+pub fn freeze(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::Freeze.pack(),
+    }
+}
+
+pub fn thaw(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::Thaw.pack(),
+    }
+}
+
+pub fn propose_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::ProposeAuthority(ProposeAuthorityArgs {
+            new_authority: *new_authority,
+        })
+        .pack(),
+    }
+}
+
+pub fn accept_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*new_authority, true),
+        ],
+        data: CounterInstructions::AcceptAuthority.pack(),
+    }
+}
+
+pub fn close(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    destination: &Pubkey,
+    signers: &[&Pubkey],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(
+            vec![
+                AccountMeta::new(*counter, false),
+                AccountMeta::new(*destination, false),
+            ],
+            signers,
+        ),
+        data: CounterInstructions::Close.pack(),
+    }
+}
+
+pub fn configure_multisig(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    threshold: u8,
+    signers: &[Pubkey],
+) -> Instruction {
+    reconfigure_multisig(program_id, counter, authority, &[], threshold, signers)
+}
+
+/// Replaces a configured multisig, which takes `threshold` of its current
+/// `members` as well as the authority.
+pub fn reconfigure_multisig(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    members: &[&Pubkey],
+    threshold: u8,
+    signers: &[Pubkey],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(
+            vec![
+                AccountMeta::new(*counter, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            members,
+        ),
+        data: CounterInstructions::ConfigureMultisig(ConfigureMultisigArgs {
+            threshold,
+            signers: signers.to_vec(),
+        })
+        .pack(),
+    }
+}
+
+pub fn approve(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    delegate: &Pubkey,
+    allowance: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::Approve(ApproveArgs {
+            delegate: *delegate,
+            allowance,
+        })
+        .pack(),
+    }
+}
+
+pub fn revoke(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::Revoke.pack(),
+    }
+}
+
+pub fn get(program_id: &Pubkey, counter: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*counter, false)],
+        data: CounterInstructions::Get.pack(),
+    }
+}
+
+pub fn get_stats(program_id: &Pubkey, counter: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*counter, false)],
+        data: CounterInstructions::GetStats.pack(),
+    }
+}
+
+pub fn log(program_id: &Pubkey, counter: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*counter, false)],
+        data: CounterInstructions::Log.pack(),
+    }
+}
+
+/// [`increment`] if the counter is below `threshold`. At or above it the
+/// instruction fails with `ThresholdReached`, or succeeds without writing when
+/// `skip` is set.
+pub fn increment_if_below(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    value: u32,
+    threshold: u32,
+    skip: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::IncrementIfBelow(IncrementIfBelowArgs {
+            value,
+            threshold,
+            mode: OVERFLOW_CHECKED,
+            skip,
+        })
+        .pack(),
+    }
+}
+
+/// Sets the counter to its bitwise AND with `value`. `signers` are as for
+/// [`update`].
+pub fn and(program_id: &Pubkey, counter: &Pubkey, signers: &[&Pubkey], value: u32) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(vec![AccountMeta::new(*counter, false)], signers),
+        data: CounterInstructions::And(AndArgs { value }).pack(),
+    }
+}
+
+/// Sets the counter to its bitwise OR with `value`. `signers` are as for
+/// [`update`].
+pub fn or(program_id: &Pubkey, counter: &Pubkey, signers: &[&Pubkey], value: u32) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(vec![AccountMeta::new(*counter, false)], signers),
+        data: CounterInstructions::Or(OrArgs { value }).pack(),
+    }
+}
+
+/// Sets the counter to its bitwise XOR with `value`. `signers` are as for
+/// [`update`].
+pub fn xor(program_id: &Pubkey, counter: &Pubkey, signers: &[&Pubkey], value: u32) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(vec![AccountMeta::new(*counter, false)], signers),
+        data: CounterInstructions::Xor(XorArgs { value }).pack(),
+    }
+}
+
+/// Shifts the counter left by `bits`, which must be below 32. `signers` are
+/// as for [`update`].
+pub fn shift_left(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signers: &[&Pubkey],
+    bits: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(vec![AccountMeta::new(*counter, false)], signers),
+        data: CounterInstructions::ShiftLeft(ShiftLeftArgs { bits }).pack(),
+    }
+}
+
+/// Shifts the counter right by `bits`, which must be below 32. `signers`
+/// are as for [`update`].
+pub fn shift_right(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signers: &[&Pubkey],
+    bits: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: with_signers(vec![AccountMeta::new(*counter, false)], signers),
+        data: CounterInstructions::ShiftRight(ShiftRightArgs { bits }).pack(),
+    }
+}
+
+pub fn realloc(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    new_size: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::Realloc(ReallocArgs { new_size }).pack(),
+    }
+}
+
+/// Creates the counter named `name` owned by `authority`; its address is
+/// [`find_counter_address`]. Panics if `name` is longer than 32 bytes.
+pub fn initialize_named(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    name: &str,
+) -> Instruction {
+    let (counter, _) = find_counter_address(program_id, authority, name);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(find_registry_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+        data: CounterInstructions::InitializeNamed(InitializeNamedArgs {
+            name: name.to_string(),
+        })
+        .pack(),
+    }
+}
+
+pub fn set_epoch_scoped(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetEpochScoped(SetEpochScopedArgs { enabled }).pack(),
+    }
+}
+
+pub fn set_decay(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    per_slot: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetDecay(SetDecayArgs { per_slot }).pack(),
+    }
+}
+
+pub fn set_rate_limit(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    max_ops_per_slot: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetRateLimit(SetRateLimitArgs { max_ops_per_slot }).pack(),
+    }
+}
+
+pub fn create_quota(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    caller: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (quota, _) = find_quota_address(program_id, counter, caller);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*caller, true),
+            AccountMeta::new(quota, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::CreateQuota.pack(),
+    }
+}
+
+/// `unit` is [`crate::state::COOLDOWN_SLOTS`] or
+/// [`crate::state::COOLDOWN_SECONDS`].
+pub fn set_cooldown(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    cooldown: u32,
+    unit: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetCooldown(SetCooldownArgs { cooldown, unit }).pack(),
+    }
+}
+
+pub fn create_cooldown(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    caller: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (cooldown, _) = find_cooldown_address(program_id, counter, caller);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*caller, true),
+            AccountMeta::new(cooldown, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::CreateCooldown.pack(),
+    }
+}
+
+pub fn create_referrer(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    referrer: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (entry, _) = find_referrer_address(program_id, counter, referrer);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*referrer, true),
+            AccountMeta::new(entry, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::CreateReferrer.pack(),
+    }
+}
+
+pub fn set_referrals(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    enabled: bool,
+    share_bps: u16,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetReferrals(SetReferralsArgs { enabled, share_bps }).pack(),
+    }
+}
+
+pub fn withdraw_referral_fees(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    referrer: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let (entry, _) = find_referrer_address(program_id, counter, referrer);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*referrer, true),
+            AccountMeta::new(entry, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: CounterInstructions::WithdrawReferralFees.pack(),
+    }
+}
+
+pub fn initialize_leaderboard(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    capacity: u32,
+) -> Instruction {
+    let (leaderboard, _) = find_leaderboard_address(program_id, authority);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(leaderboard, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::InitializeLeaderboard(InitializeLeaderboardArgs { capacity })
+            .pack(),
+    }
+}
+
+/// Ranks `counter` on the leaderboard of `authority`, its authority.
+pub fn submit_score(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    let (leaderboard, _) = find_leaderboard_address(program_id, authority);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new(leaderboard, false),
+        ],
+        data: CounterInstructions::SubmitScore.pack(),
+    }
+}
+
+pub fn set_daily_cap(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    cap: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetDailyCap(SetDailyCapArgs { cap }).pack(),
+    }
+}
+
+pub fn set_fee(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let (treasury, _) = find_treasury_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::SetFee(SetFeeArgs { lamports }).pack(),
+    }
+}
+
+pub fn withdraw_treasury(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let (treasury, _) = find_treasury_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::WithdrawTreasury.pack(),
+    }
+}
+
+pub fn configure_payment(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    price: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::ConfigurePayment(ConfigurePaymentArgs { mint: *mint, price })
+            .pack(),
+    }
+}
+
+pub fn initialize_vault(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let (vault_authority, _) = find_vault_authority_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(
+                find_vault_address(program_id, counter, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+        data: CounterInstructions::InitializeVault.pack(),
+    }
+}
+
+pub fn withdraw_vault(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (vault_authority, _) = find_vault_authority_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(
+                find_vault_address(program_id, counter, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: CounterInstructions::WithdrawVault(WithdrawVaultArgs { amount }).pack(),
+    }
+}
+
+pub fn set_nft_gate(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    collection: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetNftGate(SetNftGateArgs {
+            collection: *collection,
+        })
+        .pack(),
+    }
+}
+
+pub fn configure_milestone_reward(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    every: u32,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::ConfigureMilestoneReward(ConfigureMilestoneRewardArgs {
+            mint: *mint,
+            every,
+            amount,
+        })
+        .pack(),
+    }
+}
+
+pub fn configure_milestone_nft(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    target: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::ConfigureMilestoneNft(ConfigureMilestoneNftArgs { target })
+            .pack(),
+    }
+}
+
+/// `mint` is a fresh keypair's address; it signs alongside `claimer`.
+pub fn claim_milestone_nft(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    claimer: &Pubkey,
+    mint: &Pubkey,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Instruction {
+    let (nft_authority, _) = find_nft_authority_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new(*claimer, true),
+            AccountMeta::new(*mint, true),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(claimer, mint),
+                false,
+            ),
+            AccountMeta::new(find_metadata_address(mint).0, false),
+            AccountMeta::new(find_master_edition_address(mint).0, false),
+            AccountMeta::new_readonly(nft_authority, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(metadata::ID, false),
+        ],
+        data: CounterInstructions::ClaimMilestoneNft(ClaimMilestoneNftArgs {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+        })
+        .pack(),
+    }
+}
+
+pub fn configure_oracle(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    feed_id: [u8; 32],
+    max_age_seconds: u32,
+    max_confidence_bps: u16,
+    exponent: i32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::ConfigureOracle(ConfigureOracleArgs {
+            feed_id,
+            max_age_seconds,
+            max_confidence_bps,
+            exponent,
+        })
+        .pack(),
+    }
+}
+
+pub fn update_from_oracle(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    price_update: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*price_update, false),
+        ],
+        data: CounterInstructions::UpdateFromOracle.pack(),
+    }
+}
+
+/// `signer` is the authority or delegate; it is ignored by counters that
+/// have no authority.
+pub fn request_random_increment(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    randomness: &Pubkey,
+    max: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(*randomness, false),
+        ],
+        data: CounterInstructions::RequestRandomIncrement(RequestRandomIncrementArgs { max })
+            .pack(),
+    }
+}
+
+pub fn settle_random_increment(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    randomness: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*randomness, false),
+        ],
+        data: CounterInstructions::SettleRandomIncrement.pack(),
+    }
+}
+
+pub fn set_allowlist_enabled(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetAllowlistEnabled(SetAllowlistEnabledArgs { enabled }).pack(),
+    }
+}
+
+pub fn add_to_allowlist(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    member: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (entry, _) = find_allowlist_address(program_id, counter, member);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(entry, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::AddToAllowlist(AddToAllowlistArgs { member: *member }).pack(),
+    }
+}
+
+pub fn remove_from_allowlist(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    member: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let (entry, _) = find_allowlist_address(program_id, counter, member);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(entry, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: CounterInstructions::RemoveFromAllowlist(RemoveFromAllowlistArgs { member: *member })
+            .pack(),
+    }
+}
+
+pub fn set_blocklist_enabled(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetBlocklistEnabled(SetBlocklistEnabledArgs { enabled }).pack(),
+    }
+}
+
+pub fn set_memo_required(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetMemoRequired(SetMemoRequiredArgs { enabled }).pack(),
+    }
+}
+
+pub fn set_stake_weighted(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetStakeWeighted(SetStakeWeightedArgs { enabled }).pack(),
+    }
+}
+
+pub fn set_boost(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    factor: u8,
+    min_sol: u16,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetBoost(SetBoostArgs { factor, min_sol }).pack(),
+    }
+}
+
+/// Locks `lamports` of `owner` for its Increments of `counter`, for at
+/// least `duration` seconds.
+pub fn lock_boost(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    owner: &Pubkey,
+    lamports: u64,
+    duration: u64,
+) -> Instruction {
+    let (escrow, _) = find_boost_address(program_id, counter, owner);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::LockBoost(LockBoostArgs { lamports, duration }).pack(),
+    }
+}
+
+pub fn unlock_boost(program_id: &Pubkey, counter: &Pubkey, owner: &Pubkey) -> Instruction {
+    let (escrow, _) = find_boost_address(program_id, counter, owner);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(escrow, false),
+        ],
+        data: CounterInstructions::UnlockBoost.pack(),
+    }
+}
+
+/// Lists `counter` for lease at `price_lamports` per `duration` seconds,
+/// paid into its treasury.
+pub fn list_for_lease(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    price_lamports: u64,
+    duration: u64,
+) -> Instruction {
+    let (lease, _) = find_lease_address(program_id, counter);
+    let (treasury, _) = find_treasury_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(lease, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::ListForLease(ListForLeaseArgs {
+            price_lamports,
+            duration,
+        })
+        .pack(),
+    }
+}
+
+pub fn take_lease(program_id: &Pubkey, counter: &Pubkey, lessee: &Pubkey) -> Instruction {
+    let (lease, _) = find_lease_address(program_id, counter);
+    let (treasury, _) = find_treasury_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new(*lessee, true),
+            AccountMeta::new(lease, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::TakeLease.pack(),
+    }
+}
+
+/// Ends the running lease of `counter`, signed by `signer`, the lessee or the
+/// authority; an authority refunds `lessee`.
+pub fn end_lease(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    lessee: &Pubkey,
+) -> Instruction {
+    let (lease, _) = find_lease_address(program_id, counter);
+    let (treasury, _) = find_treasury_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(lease, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(*lessee, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::EndLease.pack(),
+    }
+}
+
+pub fn delist(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let (lease, _) = find_lease_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(lease, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: CounterInstructions::Delist.pack(),
+    }
+}
+
+/// Puts `counter` in credit mode at `lamports` per credit, or takes it out
+/// with zero. `payer` tops up the treasury's rent.
+pub fn set_credit_price(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    lamports: u32,
+) -> Instruction {
+    let (treasury, _) = find_treasury_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::SetCreditPrice(SetCreditPriceArgs { lamports }).pack(),
+    }
+}
+
+pub fn buy_credits(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    buyer: &Pubkey,
+    count: u64,
+) -> Instruction {
+    let (credits, _) = find_credits_address(program_id, counter, buyer);
+    let (treasury, _) = find_treasury_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new(*buyer, true),
+            AccountMeta::new(credits, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::BuyCredits(BuyCreditsArgs { count }).pack(),
+    }
+}
+
+pub fn ban_caller(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    caller: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (entry, _) = find_ban_address(program_id, counter, caller);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(entry, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::BanCaller(BanCallerArgs { caller: *caller }).pack(),
+    }
+}
+
+pub fn unban_caller(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    caller: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let (entry, _) = find_ban_address(program_id, counter, caller);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(entry, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: CounterInstructions::UnbanCaller(UnbanCallerArgs { caller: *caller }).pack(),
+    }
+}
+
+pub fn set_offchain_signer(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    signer: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetOffchainSigner(SetOffchainSignerArgs { signer: *signer })
+            .pack(),
+    }
+}
+
+pub fn set_eth_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    eth_address: &[u8; secp256k1::ETH_ADDRESS_LEN],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetEthAuthority(SetEthAuthorityArgs {
+            eth_address: *eth_address,
+        })
+        .pack(),
+    }
+}
+
+pub fn configure_wormhole(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    emitter_chain: u16,
+    emitter_address: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::ConfigureWormhole(ConfigureWormholeArgs {
+            emitter_chain,
+            emitter_address,
+        })
+        .pack(),
+    }
+}
+
+/// Applies the VAA whose body hashes to `vaa_hash` (see
+/// [`wormhole::PostedVaa::hash`]), once the Core Bridge has posted it.
+pub fn receive_wormhole_message(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    vaa_hash: &[u8; 32],
+    payer: &Pubkey,
+) -> Instruction {
+    let (posted_vaa, _) = wormhole::find_posted_vaa_address(vaa_hash);
+    let (received, _) = find_received_address(program_id, counter, vaa_hash);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(posted_vaa, false),
+            AccountMeta::new(received, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::ReceiveWormholeMessage.pack(),
+    }
+}
+
+/// Once in effect, Update and Reset pass the native treasury (see
+/// [`crate::governance::find_native_treasury_address`]) among their
+/// `signers`, in a proposal's instructions.
+pub fn set_governance(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    governance_program: &Pubkey,
+    governance: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetGovernance(SetGovernanceArgs {
+            governance_program: *governance_program,
+            governance: *governance,
+        })
+        .pack(),
+    }
+}
+
+pub fn configure_crank(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    every_slots: u64,
+    tip_lamports: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::ConfigureCrank(ConfigureCrankArgs {
+            every_slots,
+            tip_lamports,
+        })
+        .pack(),
+    }
+}
+
+pub fn crank(program_id: &Pubkey, counter: &Pubkey, cranker: &Pubkey) -> Instruction {
+    let (treasury, _) = find_treasury_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new(*cranker, true),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::Crank.pack(),
+    }
+}
+
+/// `capacity` is at most [`crate::state::HistoryHeader::MAX_CAPACITY`].
+pub fn initialize_history(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    capacity: u32,
+) -> Instruction {
+    let (history, _) = find_history_address(program_id, counter);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(history, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::InitializeHistory(InitializeHistoryArgs { capacity }).pack(),
+    }
+}
+
+/// Checkpoints `counter` for `epoch`, which must be the current epoch.
+pub fn checkpoint(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    epoch: u64,
+) -> Instruction {
+    let (checkpoint, _) = find_checkpoint_address(program_id, counter, epoch);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new(checkpoint, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::Checkpoint.pack(),
+    }
+}
+
+/// Merges `source` into `counter`, sending `source`'s lamports to
+/// `destination`. `authority` must be the authority of both.
+pub fn merge(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    source: &Pubkey,
+    authority: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*destination, false),
+        ],
+        data: CounterInstructions::Merge.pack(),
+    }
+}
+
+/// Moves `amount` out of `counter` into a new counter `authority` names
+/// `name`, at [`find_counter_address`]. Panics if `name` is longer than 32
+/// bytes.
+pub fn split(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    amount: u32,
+    name: &str,
+) -> Instruction {
+    let (new_counter, _) = find_counter_address(program_id, authority, name);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(new_counter, false),
+            AccountMeta::new(find_registry_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::Split(SplitArgs {
+            amount,
+            name: name.to_string(),
+        })
+        .pack(),
+    }
+}
+
+/// Moves `amount` from `counter` to `destination`. Pass
+/// `destination_history` once the destination keeps a history (see
+/// [`find_history_address`]), and apply [`with_config`] when either counter
+/// is governed.
+pub fn transfer(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    destination: &Pubkey,
+    destination_history: Option<&Pubkey>,
+    amount: u32,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*counter, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*destination, false),
+    ];
+    accounts.extend(destination_history.map(|history| AccountMeta::new(*history, false)));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: CounterInstructions::Transfer(TransferArgs { amount }).pack(),
+    }
+}
+
+/// Copies `counter` into a new counter `authority` names `name`, at
+/// [`find_counter_address`]. Panics if `name` is longer than 32 bytes.
+pub fn clone(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    name: &str,
+) -> Instruction {
+    let (new_counter, _) = find_counter_address(program_id, authority, name);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(new_counter, false),
+            AccountMeta::new(find_registry_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::Clone(CloneArgs {
+            name: name.to_string(),
+        })
+        .pack(),
+    }
+}
+
+/// Hands `counter` to `new_authority` at once, unlike
+/// [`propose_authority`]; `None` renounces it for good.
+pub fn set_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    new_authority: Option<&Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetAuthority(SetAuthorityArgs {
+            new_authority: new_authority.copied(),
+        })
+        .pack(),
+    }
+}
+
+pub fn set_expiry(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    expires_at_slot: u64,
+    recipient: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetExpiry(SetExpiryArgs {
+            expires_at_slot,
+            recipient: *recipient,
+        })
+        .pack(),
+    }
+}
+
+pub fn reap(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    recipient: &Pubkey,
+    reaper: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new(*reaper, true),
+        ],
+        data: CounterInstructions::Reap.pack(),
+    }
+}
+
+/// Send right after [`initialize`] or [`initialize_named`]; see
+/// [`CounterInstructions::SetKind`].
+pub fn set_kind(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    kind: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetKind(SetKindArgs { kind }).pack(),
+    }
+}
+
+/// [`increment_with_mode`] with a `u64` amount, for wide and signed counters.
+pub fn increment_wide(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    amount: u64,
+    mode: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::IncrementWide(IncrementWideArgs { amount, mode }).pack(),
+    }
+}
+
+/// [`decrement_with_mode`] with a `u64` amount, for wide and signed counters.
+pub fn decrement_wide(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    amount: u64,
+    mode: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::DecrementWide(DecrementWideArgs { amount, mode }).pack(),
+    }
+}
+
+pub fn set_decimals(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    decimals: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetDecimals(SetDecimalsArgs { decimals }).pack(),
+    }
+}
+
+/// Increments by `amount` with `decimals` fractional digits; the program
+/// rescales it to the counter's decimals, rounding as `rounding` says.
+pub fn increment_scaled(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    rounding: u8,
+    mode: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::IncrementScaled(IncrementScaledArgs {
+            amount,
+            decimals,
+            rounding,
+            mode,
+        })
+        .pack(),
+    }
+}
+
+/// The decrementing counterpart of [`increment_scaled`].
+pub fn decrement_scaled(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    rounding: u8,
+    mode: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: CounterInstructions::DecrementScaled(DecrementScaledArgs {
+            amount,
+            decimals,
+            rounding,
+            mode,
+        })
+        .pack(),
+    }
+}
+
+pub fn set_step(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    step: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetStep(SetStepArgs { step }).pack(),
+    }
+}
+
+/// Zero turns the modulus off.
+pub fn set_modulus(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    modulus: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetModulus(SetModulusArgs { modulus }).pack(),
+    }
+}
+
+/// Arms a countdown from `target`, or with zero leaves countdown mode.
+pub fn set_countdown(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    target: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: CounterInstructions::SetCountdown(SetCountdownArgs { target }).pack(),
+    }
+}
+
+pub fn initialize_config(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    super_admin: &Pubkey,
+    default_fee_lamports: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::InitializeConfig(InitializeConfigArgs {
+            super_admin: *super_admin,
+            default_fee_lamports,
+        })
+        .pack(),
+    }
+}
+
+pub fn update_config(
+    program_id: &Pubkey,
+    super_admin: &Pubkey,
+    new_super_admin: &Pubkey,
+    default_fee_lamports: u64,
+    paused: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*super_admin, true),
+        ],
+        data: CounterInstructions::UpdateConfig(UpdateConfigArgs {
+            super_admin: *new_super_admin,
+            default_fee_lamports,
+            paused,
+        })
+        .pack(),
+    }
+}
+
+/// Sets up `array`, which the caller created with
+/// [`CounterArrayHeader::account_len`] bytes owned by the program.
+///
+/// [`CounterArrayHeader::account_len`]: crate::state::CounterArrayHeader::account_len
+pub fn initialize_array(
+    program_id: &Pubkey,
+    array: &Pubkey,
+    authority: &Pubkey,
+    len: u16,
+) -> Instruction {
+    authority_instruction(
+        program_id,
+        array,
+        authority,
+        CounterInstructions::InitializeArray(InitializeArrayArgs { len }),
+    )
+}
+
+/// `instruction` on a counter array or keyed counter account, signed by its
+/// authority.
+fn authority_instruction(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    authority: &Pubkey,
+    instruction: CounterInstructions,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: instruction.pack(),
+    }
+}
+
+pub fn increment_at(
+    program_id: &Pubkey,
+    array: &Pubkey,
+    authority: &Pubkey,
+    index: u16,
+    value: u32,
+) -> Instruction {
+    authority_instruction(
+        program_id,
+        array,
+        authority,
+        CounterInstructions::IncrementAt(IncrementAtArgs { index, value }),
+    )
+}
+
+pub fn decrement_at(
+    program_id: &Pubkey,
+    array: &Pubkey,
+    authority: &Pubkey,
+    index: u16,
+    value: u32,
+) -> Instruction {
+    authority_instruction(
+        program_id,
+        array,
+        authority,
+        CounterInstructions::DecrementAt(DecrementAtArgs { index, value }),
+    )
+}
+
+pub fn update_at(
+    program_id: &Pubkey,
+    array: &Pubkey,
+    authority: &Pubkey,
+    index: u16,
+    value: u32,
+) -> Instruction {
+    authority_instruction(
+        program_id,
+        array,
+        authority,
+        CounterInstructions::UpdateAt(UpdateAtArgs { index, value }),
+    )
+}
+
+/// Sets up `keyed`, which the caller created with
+/// [`KeyedCounterHeader::account_len`] bytes owned by the program.
+///
+/// [`KeyedCounterHeader::account_len`]: crate::state::KeyedCounterHeader::account_len
+pub fn initialize_keyed(
+    program_id: &Pubkey,
+    keyed: &Pubkey,
+    authority: &Pubkey,
+    capacity: u32,
+) -> Instruction {
+    authority_instruction(
+        program_id,
+        keyed,
+        authority,
+        CounterInstructions::InitializeKeyed(InitializeKeyedArgs { capacity }),
+    )
+}
+
+pub fn increment_keyed(
+    program_id: &Pubkey,
+    keyed: &Pubkey,
+    authority: &Pubkey,
+    key: [u8; 32],
+    value: u64,
+) -> Instruction {
+    authority_instruction(
+        program_id,
+        keyed,
+        authority,
+        CounterInstructions::IncrementKeyed(IncrementKeyedArgs { key, value }),
+    )
+}
+
+pub fn decrement_keyed(
+    program_id: &Pubkey,
+    keyed: &Pubkey,
+    authority: &Pubkey,
+    key: [u8; 32],
+    value: u64,
+) -> Instruction {
+    authority_instruction(
+        program_id,
+        keyed,
+        authority,
+        CounterInstructions::DecrementKeyed(DecrementKeyedArgs { key, value }),
+    )
+}
+
+pub fn remove_keyed(
+    program_id: &Pubkey,
+    keyed: &Pubkey,
+    authority: &Pubkey,
+    key: [u8; 32],
+) -> Instruction {
+    authority_instruction(
+        program_id,
+        keyed,
+        authority,
+        CounterInstructions::RemoveKeyed(RemoveKeyedArgs { key }),
+    )
+}
+
+pub fn resize_keyed(
+    program_id: &Pubkey,
+    keyed: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    capacity: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*keyed, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::ResizeKeyed(ResizeKeyedArgs { capacity }).pack(),
+    }
+}
+
+fn compressed_accounts(program_id: &Pubkey, tree: &Pubkey) -> [AccountMeta; 3] {
+    let (tree_authority, _) = find_tree_authority_address(program_id, tree);
+    [
+        AccountMeta::new_readonly(tree_authority, false),
+        AccountMeta::new_readonly(compression::PROGRAM_ID, false),
+        AccountMeta::new_readonly(compression::NOOP_PROGRAM_ID, false),
+    ]
+}
+
+/// Sets up `tree`, which the caller allocated for `max_depth` and
+/// `max_buffer_size` with SPL Account Compression as its owner.
+pub fn initialize_compressed_tree(
+    program_id: &Pubkey,
+    tree: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*tree, false)];
+    accounts.extend(compressed_accounts(program_id, tree));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: CounterInstructions::InitializeCompressedTree(InitializeCompressedTreeArgs {
+            max_depth,
+            max_buffer_size,
+        })
+        .pack(),
+    }
+}
+
+fn compressed_instruction(
+    program_id: &Pubkey,
+    tree: &Pubkey,
+    authority: &Pubkey,
+    proof: &[Pubkey],
+    instruction: CounterInstructions,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*tree, false),
+        AccountMeta::new_readonly(*authority, true),
+    ];
+    accounts.extend(compressed_accounts(program_id, tree));
+    accounts.extend(
+        proof
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false)),
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.pack(),
+    }
+}
+
+pub fn create_compressed(program_id: &Pubkey, tree: &Pubkey, authority: &Pubkey) -> Instruction {
+    compressed_instruction(
+        program_id,
+        tree,
+        authority,
+        &[],
+        CounterInstructions::CreateCompressed,
+    )
+}
+
+/// Adds `by` to the compressed counter at `index`, currently `value`, with
+/// `proof` its path to `root` from the leaf up.
+#[allow(clippy::too_many_arguments)]
+pub fn increment_compressed(
+    program_id: &Pubkey,
+    tree: &Pubkey,
+    authority: &Pubkey,
+    root: [u8; 32],
+    index: u32,
+    value: u32,
+    by: u32,
+    proof: &[Pubkey],
+) -> Instruction {
+    compressed_instruction(
+        program_id,
+        tree,
+        authority,
+        proof,
+        CounterInstructions::IncrementCompressed(IncrementCompressedArgs {
+            root,
+            index,
+            value,
+            by,
+        }),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn decrement_compressed(
+    program_id: &Pubkey,
+    tree: &Pubkey,
+    authority: &Pubkey,
+    root: [u8; 32],
+    index: u32,
+    value: u32,
+    by: u32,
+    proof: &[Pubkey],
+) -> Instruction {
+    compressed_instruction(
+        program_id,
+        tree,
+        authority,
+        proof,
+        CounterInstructions::DecrementCompressed(DecrementCompressedArgs {
+            root,
+            index,
+            value,
+            by,
+        }),
+    )
+}
+
+/// Brings `counter` up to the current layout; see [`crate::migration`].
+pub fn migrate_account(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstructions::MigrateAccount.pack(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::program_error::ProgramError;
+
+    use crate::state::{COOLDOWN_SECONDS, KIND_I64, ROUND_DOWN, ROUND_UP};
+
+    #[test]
+    fn test_increment_data() {
+        let program_id = Pubkey::new_unique();
+        let counter = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+
+        let instruction = increment(&program_id, &counter, &signer, 48);
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.data, vec![0, 48, 0, 0, 0, OVERFLOW_CHECKED]);
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                AccountMeta::new(counter, false),
+                AccountMeta::new_readonly(signer, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builders_round_trip() {
+        let program_id = Pubkey::new_unique();
+        let counter = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let instructions = [
+            increment(&program_id, &counter, &authority, 1),
+            decrement(&program_id, &counter, &authority, 1),
+            update(&program_id, &counter, &[&authority], 1),
+            reset(&program_id, &counter, &[&authority]),
+            initialize(&program_id, &counter, &authority, &authority),
+            pause(&program_id, &counter, &authority),
+            unpause(&program_id, &counter, &authority),
+            propose_authority(&program_id, &counter, &authority, &other),
+            accept_authority(&program_id, &counter, &other),
+            close(&program_id, &counter, &other, &[&authority]),
+            configure_multisig(&program_id, &counter, &authority, 1, &[other]),
+            approve(&program_id, &counter, &authority, &other, 3),
+            revoke(&program_id, &counter, &authority),
+            get(&program_id, &counter),
+            realloc(&program_id, &counter, &authority, &other, 1_000),
+            initialize_named(&program_id, &authority, &other, "page_views"),
+            set_epoch_scoped(&program_id, &counter, &authority, true),
+            set_decay(&program_id, &counter, &authority, 2),
+            set_rate_limit(&program_id, &counter, &authority, 4),
+            create_quota(&program_id, &counter, &authority, &other),
+            set_daily_cap(&program_id, &counter, &authority, 100),
+            set_fee(&program_id, &counter, &authority, &other, 5_000),
+            withdraw_treasury(&program_id, &counter, &authority, &other),
+            configure_payment(&program_id, &counter, &authority, &other, 10),
+            initialize_vault(&program_id, &counter, &other, &authority, &spl_token::id()),
+            withdraw_vault(
+                &program_id,
+                &counter,
+                &authority,
+                &other,
+                &other,
+                &spl_token::id(),
+                10,
+            ),
+            set_nft_gate(&program_id, &counter, &authority, &other),
+            configure_milestone_reward(&program_id, &counter, &authority, &other, 1_000, 1),
+            configure_milestone_nft(&program_id, &counter, &authority, 1_000),
+            claim_milestone_nft(
+                &program_id,
+                &counter,
+                &authority,
+                &other,
+                "Counter",
+                "CNT",
+                "",
+            ),
+            configure_oracle(&program_id, &counter, &authority, [1; 32], 60, 100, -2),
+            update_from_oracle(&program_id, &counter, &other),
+            request_random_increment(&program_id, &counter, &authority, &other, 6),
+            settle_random_increment(&program_id, &counter, &other),
+            set_allowlist_enabled(&program_id, &counter, &authority, true),
+            add_to_allowlist(&program_id, &counter, &authority, &other, &authority),
+            remove_from_allowlist(&program_id, &counter, &authority, &other, &authority),
+            set_blocklist_enabled(&program_id, &counter, &authority, true),
+            ban_caller(&program_id, &counter, &authority, &other, &authority),
+            unban_caller(&program_id, &counter, &authority, &other, &authority),
+            set_offchain_signer(&program_id, &counter, &authority, &other),
+            set_eth_authority(&program_id, &counter, &authority, &[1; 20]),
+            configure_wormhole(&program_id, &counter, &authority, 2, [1; 32]),
+            receive_wormhole_message(&program_id, &counter, &[1; 32], &authority),
+            set_governance(&program_id, &counter, &authority, &other, &other),
+            configure_crank(&program_id, &counter, &authority, 10, 1),
+            crank(&program_id, &counter, &other),
+            get_stats(&program_id, &counter),
+            initialize_history(&program_id, &counter, &authority, &other, 8),
+            checkpoint(&program_id, &counter, &other, 600),
+            merge(&program_id, &counter, &other, &authority, &other),
+            split(&program_id, &counter, &authority, &other, 1, "half"),
+            transfer(&program_id, &counter, &authority, &other, None, 1),
+            clone(&program_id, &counter, &authority, &other, "staging"),
+            set_authority(&program_id, &counter, &authority, Some(&other)),
+            freeze(&program_id, &counter, &authority),
+            thaw(&program_id, &counter, &authority),
+            set_expiry(&program_id, &counter, &authority, 1_000, &other),
+            reap(&program_id, &counter, &other, &authority),
+            set_kind(&program_id, &counter, &authority, KIND_I64),
+            increment_wide(&program_id, &counter, &authority, 1, OVERFLOW_CHECKED),
+            decrement_wide(&program_id, &counter, &authority, 1, OVERFLOW_SATURATING),
+            set_decimals(&program_id, &counter, &authority, 2),
+            increment_scaled(
+                &program_id,
+                &counter,
+                &authority,
+                1,
+                0,
+                ROUND_DOWN,
+                OVERFLOW_CHECKED,
+            ),
+            decrement_scaled(
+                &program_id,
+                &counter,
+                &authority,
+                1,
+                0,
+                ROUND_UP,
+                OVERFLOW_CHECKED,
+            ),
+            set_step(&program_id, &counter, &authority, 10),
+            initialize_config(&program_id, &authority, &other, 0),
+            update_config(&program_id, &other, &authority, 5_000, true),
+            initialize_array(&program_id, &counter, &authority, 8),
+            increment_at(&program_id, &counter, &authority, 3, 1),
+            decrement_at(&program_id, &counter, &authority, 3, 1),
+            update_at(&program_id, &counter, &authority, 3, 7),
+            initialize_keyed(&program_id, &counter, &authority, 4),
+            increment_keyed(&program_id, &counter, &authority, [1; 32], 1),
+            decrement_keyed(&program_id, &counter, &authority, [1; 32], 1),
+            remove_keyed(&program_id, &counter, &authority, [1; 32]),
+            resize_keyed(&program_id, &counter, &authority, &other, 8),
+            initialize_compressed_tree(&program_id, &counter, 14, 64),
+            create_compressed(&program_id, &counter, &authority),
+            increment_compressed(
+                &program_id,
+                &counter,
+                &authority,
+                [2; 32],
+                0,
+                0,
+                1,
+                &[other],
+            ),
+            decrement_compressed(
+                &program_id,
+                &counter,
+                &authority,
+                [2; 32],
+                0,
+                1,
+                1,
+                &[other],
+            ),
+            migrate_account(&program_id, &counter, &authority, &other),
+            set_memo_required(&program_id, &counter, &authority, true),
+            set_referrals(&program_id, &counter, &authority, true, 1_000),
+            create_referrer(&program_id, &counter, &other, &authority),
+            withdraw_referral_fees(&program_id, &counter, &other, &authority),
+            initialize_leaderboard(&program_id, &authority, &other, 10),
+            submit_score(&program_id, &counter, &authority),
+            set_stake_weighted(&program_id, &counter, &authority, true),
+            set_boost(&program_id, &counter, &authority, 2, 1),
+            lock_boost(&program_id, &counter, &other, 1_000_000_000, 86_400),
+            unlock_boost(&program_id, &counter, &other),
+            list_for_lease(&program_id, &counter, &authority, &other, 1_000_000, 3_600),
+            take_lease(&program_id, &counter, &other),
+            end_lease(&program_id, &counter, &authority, &other),
+            delist(&program_id, &counter, &authority, &other),
+            set_credit_price(&program_id, &counter, &authority, &other, 10_000),
+            buy_credits(&program_id, &counter, &other, 5),
+            set_cooldown(&program_id, &counter, &authority, 10, COOLDOWN_SECONDS),
+            create_cooldown(&program_id, &counter, &other, &authority),
+            log(&program_id, &counter),
+            increment_if_below(&program_id, &counter, &authority, 1, 10, true),
+            and(&program_id, &counter, &[&authority], 0b1010),
+            or(&program_id, &counter, &[&authority], 0b0101),
+            xor(&program_id, &counter, &[&authority], 0b1111),
+            shift_left(&program_id, &counter, &[&authority], 3),
+            shift_right(&program_id, &counter, &[&authority], 31),
+            set_modulus(&program_id, &counter, &authority, 60),
+            set_countdown(&program_id, &counter, &authority, 10),
+            increment_one(&program_id, &counter, &authority),
+            decrement_one(&program_id, &counter, &authority),
+        ];
+        for (variant, instruction) in instructions.iter().enumerate() {
+            assert_eq!(instruction.data[0] as usize, variant);
+            let unpacked = CounterInstructions::unpack(&instruction.data).unwrap();
+            assert_eq!(unpacked.pack(), instruction.data);
+
+            // A byte more or any fewer is malformed, but for Increment and
+            // Decrement without their mode.
+            let mut trailing = instruction.data.clone();
+            trailing.push(0);
+            assert_eq!(
+                CounterInstructions::unpack(&trailing).err(),
+                Some(ProgramError::InvalidInstructionData),
+                "{variant}"
+            );
+            for len in 0..instruction.data.len() {
+                if variant <= 1 && len == 5 {
+                    continue;
+                }
+                assert_eq!(
+                    CounterInstructions::unpack(&instruction.data[..len]).err(),
+                    Some(ProgramError::InvalidInstructionData),
+                    "{variant} truncated to {len}"
+                );
+            }
+        }
+    }
+}
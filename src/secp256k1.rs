@@ -0,0 +1,100 @@
+//! Just enough of the secp256k1 program's instruction format to build one and
+//! to read what it verified.
+//!
+//! Like the Ed25519 program, the secp256k1 program is a precompile checked
+//! before the transaction runs. Each signature is recovered to an Ethereum
+//! address, the last 20 bytes of the Keccak-256 hash of the public key, and
+//! compared with the address in the instruction. Unlike Ed25519, its offsets
+//! name instructions by their absolute index in the transaction.
+
+use solana_program::instruction::Instruction;
+
+pub use solana_program::secp256k1_program::ID;
+
+pub const ETH_ADDRESS_LEN: usize = 20;
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Signature count.
+const HEADER_LEN: usize = 1;
+/// Per signature: a `u16` offset and `u8` instruction index for each of the
+/// signature, address and message, plus the `u16` message length.
+const OFFSETS_LEN: usize = 11;
+
+/// A secp256k1 program instruction verifying `signature`, with
+/// `recovery_id`, by `eth_address` over `message`. `instruction_index` is
+/// where the instruction goes in the transaction; its offsets point to
+/// itself. Laid out as the Solana SDK does: offsets, address, signature,
+/// recovery id, message.
+pub fn new_instruction(
+    eth_address: &[u8; ETH_ADDRESS_LEN],
+    signature: &[u8; SIGNATURE_LEN],
+    recovery_id: u8,
+    message: &[u8],
+    instruction_index: u8,
+) -> Instruction {
+    let eth_address_offset = HEADER_LEN + OFFSETS_LEN;
+    let signature_offset = eth_address_offset + ETH_ADDRESS_LEN;
+    let message_offset = signature_offset + SIGNATURE_LEN + 1;
+
+    let mut data = Vec::with_capacity(message_offset + message.len());
+    data.push(1);
+    data.extend_from_slice(&(signature_offset as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(&(eth_address_offset as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(&(message_offset as u16).to_le_bytes());
+    data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(eth_address);
+    data.extend_from_slice(signature);
+    data.push(recovery_id);
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+/// The Ethereum address and message of the data of a secp256k1 instruction
+/// at `instruction_index` verifying a single signature, or `None` unless
+/// everything it verified is in `data`.
+pub fn signed_message(
+    data: &[u8],
+    instruction_index: u8,
+) -> Option<([u8; ETH_ADDRESS_LEN], &[u8])> {
+    if data.first() != Some(&1) {
+        return None;
+    }
+    let offsets = data.get(HEADER_LEN..HEADER_LEN + OFFSETS_LEN)?;
+    let offset = |at: usize| usize::from(u16::from_le_bytes([offsets[at], offsets[at + 1]]));
+    if [offsets[2], offsets[5], offsets[10]] != [instruction_index; 3] {
+        return None;
+    }
+    let eth_address = data.get(offset(3)..offset(3) + ETH_ADDRESS_LEN)?;
+    let message = data.get(offset(6)..offset(6) + offset(8))?;
+    Some((eth_address.try_into().ok()?, message))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signed_message() {
+        let eth_address = [9; ETH_ADDRESS_LEN];
+        let instruction = new_instruction(&eth_address, &[7; SIGNATURE_LEN], 1, b"counter", 2);
+        assert_eq!(instruction.program_id, ID);
+        assert_eq!(
+            signed_message(&instruction.data, 2),
+            Some((eth_address, &b"counter"[..]))
+        );
+        // Its offsets point at whatever instruction is at index 2.
+        assert_eq!(signed_message(&instruction.data, 1), None);
+
+        let mut data = instruction.data;
+        data[0] = 2;
+        assert_eq!(signed_message(&data, 2), None);
+        assert_eq!(signed_message(&[], 2), None);
+    }
+}
@@ -0,0 +1,2020 @@
+use std::fmt;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankAccount;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    native_token::LAMPORTS_PER_SOL,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::CounterError;
+
+/// Maximum number of signers a counter multisig can be configured with.
+pub const MAX_SIGNERS: usize = 11;
+
+/// Epochs without a write after which anyone may reap a counter as
+/// abandoned.
+pub const ABANDONED_AFTER_EPOCHS: u64 = 100;
+
+/// Share of a reaped counter's lamports, in basis points, that goes to
+/// whoever reaped it.
+pub const REAPER_SHARE_BPS: u64 = 500;
+
+/// Overflow policies an Increment or Decrement picks with its `mode`: fail,
+/// clamp to the `u32` range, or wrap around it.
+pub const OVERFLOW_CHECKED: u8 = 0;
+pub const OVERFLOW_SATURATING: u8 = 1;
+pub const OVERFLOW_WRAPPING: u8 = 2;
+
+/// Types of value a counter holds, chosen with `SetKind` before its first
+/// write. `u32` counters keep theirs in `counter`, signed ones in
+/// `signed_counter` and wide ones in `wide_counter`.
+pub const KIND_U32: u8 = 0;
+pub const KIND_I64: u8 = 1;
+pub const KIND_U128: u8 = 2;
+
+/// How [`rescale`] rounds an amount given with more decimals than the
+/// counter keeps: toward zero, away from zero, or to the nearest value with
+/// halves away from zero.
+pub const ROUND_DOWN: u8 = 0;
+pub const ROUND_UP: u8 = 1;
+pub const ROUND_NEAREST: u8 = 2;
+
+/// Units a counter's `cooldown` is measured in.
+pub const COOLDOWN_SLOTS: u8 = 0;
+pub const COOLDOWN_SECONDS: u8 = 1;
+
+/// States of a counter's `countdown`: off, counting down, or at zero and
+/// locked until re-armed.
+pub const COUNTDOWN_OFF: u8 = 0;
+pub const COUNTDOWN_RUNNING: u8 = 1;
+pub const COUNTDOWN_COMPLETED: u8 = 2;
+
+/// Converts `amount`, given with `from` decimals, to `to` decimals. Adding
+/// digits fails with `Overflow` past `u64::MAX`; dropping them rounds.
+pub fn rescale(amount: u64, from: u8, to: u8, rounding: u8) -> Result<u64, ProgramError> {
+    if !matches!(rounding, ROUND_DOWN | ROUND_UP | ROUND_NEAREST) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if from <= to {
+        if amount == 0 {
+            return Ok(0);
+        }
+        return 10u64
+            .checked_pow((to - from).into())
+            .and_then(|factor| amount.checked_mul(factor))
+            .ok_or_else(|| CounterError::Overflow.into());
+    }
+    // Past 10^38 the factor outgrows any `u64` amount, which rounds from zero.
+    let (quotient, rounds_up) = match 10u128.checked_pow((from - to).into()) {
+        Some(factor) => {
+            let remainder = u128::from(amount) % factor;
+            let rounds_up = match rounding {
+                ROUND_UP => remainder != 0,
+                ROUND_NEAREST => remainder * 2 >= factor,
+                _ => false,
+            };
+            (u128::from(amount) / factor, rounds_up)
+        }
+        None => (0, rounding == ROUND_UP && amount != 0),
+    };
+    // The quotient is at most a tenth of `amount`, so rounding up fits.
+    Ok(quotient as u64 + u64::from(rounds_up))
+}
+
+/// Marks an account as a counter at [`CounterAccount::DISCRIMINATOR_OFFSET`].
+/// Other accounts the program owns start with a pubkey or a length, so a
+/// match is a counter with high probability; unpack to be sure.
+pub const COUNTER_DISCRIMINATOR: u8 = 0xC7;
+
+/// Layout of the counters this build writes, recorded in
+/// [`CounterAccount::layout_version`]. Counters written in an older one are
+/// brought up to it by `MigrateAccount`; see [`crate::migration`].
+pub const COUNTER_LAYOUT_VERSION: u8 = 1;
+
+/// Counter state, read in place from the account data.
+///
+/// Fields are ordered so the `#[repr(C)]` layout has no implicit padding,
+/// which is what lets the struct be `Pod`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CounterAccount {
+    pub counter: u32,
+    /// Non-zero while the counter is paused; see [`CounterAccount::is_paused`].
+    pub paused: u8,
+    /// Number of multisig signatures required on privileged instructions;
+    /// zero when no multisig is configured.
+    pub multisig_threshold: u8,
+    pub multisig_signer_count: u8,
+    /// [`COUNTER_DISCRIMINATOR`] on counters initialized since it was
+    /// introduced, zero on older ones. Also keeps `authority` at an 8-byte
+    /// boundary.
+    pub discriminator: u8,
+    pub authority: Pubkey,
+    /// Authority proposed by `ProposeAuthority`, or the default pubkey when
+    /// no transfer is pending.
+    pub pending_authority: Pubkey,
+    /// Pubkey allowed to Increment/Decrement on the authority's behalf, or
+    /// the default pubkey when no delegate is approved.
+    pub delegate: Pubkey,
+    /// Remaining number of operations the delegate may perform.
+    pub delegated_allowance: u64,
+    // Spelled as a literal because shank cannot resolve `MAX_SIGNERS`.
+    pub multisig_signers: [Pubkey; 11],
+    /// Non-zero when the counter starts from zero again in each epoch; see
+    /// [`CounterAccount::is_epoch_scoped`].
+    pub epoch_scoped: u8,
+    /// Always zero; keeps `last_write_epoch` at an 8-byte boundary.
+    pub _reserved: [u8; 7],
+    /// Epoch of the last mutation of an epoch-scoped counter.
+    pub last_write_epoch: u64,
+    /// Units the counter loses per slot; zero disables decay.
+    pub decay_per_slot: u64,
+    /// Slot of the last write, which decay is also applied up to.
+    pub last_updated_slot: u64,
+    /// Mutations allowed per slot; zero disables rate limiting.
+    pub max_ops_per_slot: u32,
+    /// Mutations so far in `last_slot`.
+    pub ops_in_slot: u32,
+    /// Slot of the last rate-limited mutation.
+    pub last_slot: u64,
+    /// Total each caller may increment by per [`QUOTA_WINDOW_SECONDS`]; zero
+    /// disables the cap. Callers track their usage in a [`CallerQuota`].
+    pub daily_cap: u64,
+    /// Lamports charged into the counter's treasury on every
+    /// Increment/Decrement/Update/Reset; zero disables the fee.
+    pub fee_lamports: u64,
+    /// SPL mint Increment is paid in; see [`CounterAccount::payment_price`].
+    pub payment_mint: Pubkey,
+    /// Tokens of `payment_mint` each Increment moves into the counter's vault;
+    /// zero disables the payment.
+    pub payment_price: u64,
+    /// Collection whose NFT holders alone may Increment/Decrement; the default
+    /// pubkey leaves the counter ungated.
+    pub nft_gate_collection: Pubkey,
+    /// Mint of the milestone reward; see [`CounterAccount::milestone_every`].
+    pub reward_mint: Pubkey,
+    /// Tokens of `reward_mint` minted per milestone.
+    pub reward_amount: u64,
+    /// Milestones are the multiples of this; zero disables the reward.
+    pub milestone_every: u32,
+    /// Highest milestone, as `counter / milestone_every`, already rewarded.
+    pub rewarded_milestones: u32,
+    /// Value at which the milestone NFT can be claimed; zero disables it.
+    pub nft_target: u32,
+    /// Nonzero once the NFT for `nft_target` has been claimed.
+    pub nft_claimed: u8,
+    pub _padding2: [u8; 3],
+    /// Pyth feed `UpdateFromOracle` reads; all zeroes disables it.
+    pub oracle_feed_id: [u8; 32],
+    /// Oldest price, in seconds, `UpdateFromOracle` accepts.
+    pub oracle_max_age_seconds: u32,
+    /// The counter holds prices in units of `10^oracle_exponent`.
+    pub oracle_exponent: i32,
+    /// Widest confidence interval accepted, in basis points of the price.
+    pub oracle_max_confidence_bps: u16,
+    /// [`COOLDOWN_SLOTS`] or [`COOLDOWN_SECONDS`].
+    pub cooldown_unit: u8,
+    pub _padding3: [u8; 1],
+    /// Slots or seconds each caller must wait between mutations; zero
+    /// disables the cooldown. Callers track theirs in a [`CallerCooldown`].
+    pub cooldown: u32,
+    /// Randomness account of the pending random increment.
+    pub random_account: Pubkey,
+    /// `seed_slot` of `random_account` when the request was made; the
+    /// request is void if the account is committed again.
+    pub random_seed_slot: u64,
+    /// The pending random increment is between 1 and this.
+    pub random_max: u32,
+    /// Nonzero while a random increment is waiting to be settled.
+    pub random_pending: u8,
+    pub _padding4: [u8; 3],
+    /// Nonzero when only allow-listed signers may mutate the counter.
+    pub allowlist_enabled: u8,
+    /// Nonzero when banned signers are rejected.
+    pub blocklist_enabled: u8,
+    /// Nonzero when every mutation must come with an SPL Memo instruction in
+    /// its transaction.
+    pub memo_required: u8,
+    /// Nonzero when Increments are multiplied by the SOL their signer has
+    /// staked.
+    pub stake_weighted: u8,
+    /// Whole SOL a boost escrow must lock for its owner's Increments to be
+    /// multiplied by `boost_factor`.
+    pub boost_min_sol: u16,
+    /// Multiplier of boosted Increments; zero or one turns boosting off.
+    pub boost_factor: u8,
+    pub _padding5: [u8; 1],
+    /// Off-chain key whose Ed25519 signature authorizes each Increment, or
+    /// the default pubkey.
+    pub offchain_signer: Pubkey,
+    /// Nonce the off-chain signer must sign next; advances on every use.
+    pub offchain_nonce: u64,
+    /// Ethereum address whose secp256k1 signature authorizes each mutation,
+    /// or all zeroes.
+    pub eth_authority: [u8; 20],
+    /// Lamports `BuyCredits` charges per operation credit; nonzero makes
+    /// every mutation spend one of its signer's credits.
+    pub credit_price_lamports: u32,
+    /// Nonce the Ethereum authority must sign next; advances on every use.
+    pub eth_nonce: u64,
+    /// Wormhole emitter allowed to drive the counter, on chain
+    /// `wormhole_emitter_chain`.
+    pub wormhole_emitter_address: [u8; 32],
+    /// Wormhole chain ID of the emitter, or zero.
+    pub wormhole_emitter_chain: u16,
+    /// Basis points of the fee a referred Increment routes to its referrer.
+    pub referral_share_bps: u16,
+    /// Nonzero while Increment takes a referrer; see
+    /// [`CounterAccount::has_referrals`].
+    pub referrals_enabled: u8,
+    /// Nonzero while the counter is listed for lease; see
+    /// [`CounterAccount::is_listed_for_lease`].
+    pub lease_listed: u8,
+    /// One of the `COUNTDOWN_*` states.
+    pub countdown: u8,
+    pub _padding7: [u8; 1],
+    /// SPL Governance account that Update and Reset answer to, or the
+    /// default pubkey.
+    pub governance: Pubkey,
+    /// The governance's native treasury, which must sign Update and Reset.
+    pub governance_treasury: Pubkey,
+    /// Slots between scheduled resets, or zero.
+    pub crank_every_slots: u64,
+    /// Most a crank tips its caller.
+    pub crank_tip_lamports: u64,
+    /// Slot the schedule was configured or last cranked in.
+    pub last_crank_slot: u64,
+    pub total_increments: u64,
+    pub total_decrements: u64,
+    /// Resets, scheduled ones included.
+    pub total_resets: u64,
+    /// Highest value a write has left the counter at.
+    pub max_value_seen: u32,
+    /// Lowest value a write has left the counter at.
+    pub min_value_seen: u32,
+    /// Nonzero once a write set `max_value_seen` and `min_value_seen`.
+    pub values_seen: u8,
+    pub _padding8: [u8; 7],
+    /// Unix timestamp of the last write.
+    pub last_updated_ts: i64,
+    /// First signer of the last write, or the default pubkey if nobody
+    /// signed it.
+    pub last_writer: Pubkey,
+    /// Entries in the counter's history account, or zero without one.
+    pub history_capacity: u32,
+    /// Increments and decrements of a `u32` counter wrap within
+    /// `0..modulus`; zero leaves them to their overflow mode.
+    pub modulus: u32,
+    /// Nonzero once `SetAuthority` renounced the counter; see
+    /// [`CounterAccount::is_renounced`].
+    pub renounced: u8,
+    /// Nonzero while the value is frozen; see [`CounterAccount::is_frozen`].
+    pub frozen: u8,
+    pub _padding10: [u8; 6],
+    /// First slot in which the counter is expired, or zero if it never
+    /// expires.
+    pub expires_at_slot: u64,
+    /// Receives the counter's lamports when an expired counter is reaped.
+    pub expiry_recipient: Pubkey,
+    /// Epoch of the counter's creation or last write, whichever is later.
+    pub last_active_epoch: u64,
+    /// Paid to create the counter, or the default pubkey if the counter
+    /// predates payers being recorded.
+    pub rent_payer: Pubkey,
+    /// One of the `KIND_*` value types.
+    pub kind: u8,
+    /// Fractional digits of the value, which is otherwise stored as a plain
+    /// integer: 1234 with two decimals means 12.34.
+    pub decimals: u8,
+    pub _padding11: [u8; 6],
+    /// The value of a [`KIND_I64`] counter.
+    pub signed_counter: i64,
+    /// The little-endian value of a [`KIND_U128`] counter; see
+    /// [`CounterAccount::wide_value`]. Bytes, as `u128` alignment differs
+    /// between targets.
+    pub wide_counter: [u8; 16],
+    /// Increments and decrements must be multiples of this; zero or one
+    /// allows any amount.
+    pub step: u32,
+    /// Nonzero when the counter was created under the program config and
+    /// answers to its pause; see [`CounterAccount::is_governed`].
+    pub governed: u8,
+    /// Bump of the treasury PDA, stored by `SetFee`, or zero if the fee was
+    /// never set.
+    pub treasury_bump: u8,
+    /// Bump of the reward authority PDA, stored by
+    /// `ConfigureMilestoneReward`, or zero if rewards were never configured.
+    pub reward_authority_bump: u8,
+    /// The layout the counter was last written in, or zero on counters from
+    /// before layouts were versioned.
+    pub layout_version: u8,
+}
+
+/// A counter's value, typed by its kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CounterValue {
+    U32(u32),
+    I64(i64),
+    U128(u128),
+}
+
+impl CounterValue {
+    pub fn is_zero(self) -> bool {
+        matches!(self, Self::U32(0) | Self::I64(0) | Self::U128(0))
+    }
+
+    /// The little-endian bytes `Get` returns.
+    pub fn to_le_bytes(self) -> Vec<u8> {
+        match self {
+            Self::U32(value) => value.to_le_bytes().to_vec(),
+            Self::I64(value) => value.to_le_bytes().to_vec(),
+            Self::U128(value) => value.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Reads what `Get` returned, typed by its width.
+    pub fn from_le_bytes(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            4 => Some(Self::U32(u32::from_le_bytes(data.try_into().ok()?))),
+            8 => Some(Self::I64(i64::from_le_bytes(data.try_into().ok()?))),
+            16 => Some(Self::U128(u128::from_le_bytes(data.try_into().ok()?))),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CounterValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::U32(value) => value.fmt(f),
+            Self::I64(value) => value.fmt(f),
+            Self::U128(value) => value.fmt(f),
+        }
+    }
+}
+
+/// The statistics `GetStats` returns.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CounterStats {
+    pub total_increments: u64,
+    pub total_decrements: u64,
+    pub total_resets: u64,
+    pub max_value_seen: u32,
+    pub min_value_seen: u32,
+}
+
+impl CounterStats {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+}
+
+impl CounterAccount {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Offsets of the fields `getProgramAccounts` filters match on. New
+    /// fields only ever go at the end, so these never move; see
+    /// `client::filters`.
+    pub const DISCRIMINATOR_OFFSET: usize = 7;
+    pub const AUTHORITY_OFFSET: usize = 8;
+    pub const KIND_OFFSET: usize = 1136;
+    /// Readable on counters too short to unpack, which are all layout 0.
+    pub const LAYOUT_VERSION_OFFSET: usize = 1175;
+
+    /// The first layout, which held the value alone.
+    pub const MIN_LEN: usize = 4;
+
+    /// Copies a counter out of account data, which need not be aligned.
+    /// Bytes past [`CounterAccount::LEN`] are left for fields added by
+    /// `Realloc` and ignored here. Data from an older, shorter layout lacks
+    /// fields added since, which read as zero: their defaults.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < Self::MIN_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut account = Self::zeroed();
+        let len = data.len().min(Self::LEN);
+        bytemuck::bytes_of_mut(&mut account)[..len].copy_from_slice(&data[..len]);
+        Ok(account)
+    }
+
+    /// Writes the counter back over data it was [unpacked](Self::unpack)
+    /// from. Data from an older layout keeps its length, so writes to the
+    /// fields it lacks are dropped; `MigrateAccount` makes room for them.
+    pub(crate) fn store(&self, data: &mut [u8]) {
+        let len = data.len().min(Self::LEN);
+        data[..len].copy_from_slice(&bytemuck::bytes_of(self)[..len]);
+    }
+
+    /// Borrows a counter in place. The runtime hands programs 8-byte aligned
+    /// account data, so this only fails on short data.
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused != 0
+    }
+
+    /// Whether value writes pass the [`ProgramConfig`] and stop while it is
+    /// paused.
+    pub fn is_governed(&self) -> bool {
+        self.governed != 0
+    }
+
+    /// Renounced counters have no authority and never change again.
+    pub fn is_renounced(&self) -> bool {
+        self.renounced != 0
+    }
+
+    /// Frozen counters keep their value: writes fail, and neither decay nor
+    /// epoch rollover applies, until thawed.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen != 0
+    }
+
+    pub fn is_epoch_scoped(&self) -> bool {
+        self.epoch_scoped != 0
+    }
+
+    pub fn is_decaying(&self) -> bool {
+        self.decay_per_slot != 0
+    }
+
+    /// The counter's value at `slot` once decay since `last_updated_slot` is
+    /// taken off. Decay floors at zero.
+    pub fn decayed_value(&self, slot: u64) -> u32 {
+        let elapsed = slot.saturating_sub(self.last_updated_slot);
+        let decay = elapsed.saturating_mul(self.decay_per_slot);
+        // No larger than `counter`, so it fits back into a `u32`.
+        u64::from(self.counter).saturating_sub(decay) as u32
+    }
+
+    /// Applies decay up to `slot`. Returns the previous value if it changed.
+    pub(crate) fn apply_decay(&mut self, slot: u64) -> Option<u32> {
+        let old = self.counter;
+        self.counter = self.decayed_value(slot);
+        self.last_updated_slot = slot;
+        (self.counter != old).then_some(old)
+    }
+
+    pub(crate) fn record_write(&mut self, clock: &Clock, writer: &Pubkey) {
+        self.last_updated_slot = clock.slot;
+        self.last_updated_ts = clock.unix_timestamp;
+        self.last_writer = *writer;
+        self.last_active_epoch = clock.epoch;
+    }
+
+    pub(crate) fn record_creation(&mut self, clock: &Clock, rent_payer: &Pubkey) {
+        self.discriminator = COUNTER_DISCRIMINATOR;
+        self.layout_version = COUNTER_LAYOUT_VERSION;
+        self.last_active_epoch = clock.epoch;
+        self.rent_payer = *rent_payer;
+    }
+
+    pub fn requires_payment(&self) -> bool {
+        self.payment_price != 0
+    }
+
+    pub fn is_nft_gated(&self) -> bool {
+        self.nft_gate_collection != Pubkey::default()
+    }
+
+    pub fn has_milestone_reward(&self) -> bool {
+        self.milestone_every != 0
+    }
+
+    /// Marks every milestone up to the current value rewarded, returning how
+    /// many had not been yet.
+    pub fn take_milestones(&mut self) -> u32 {
+        if !self.has_milestone_reward() {
+            return 0;
+        }
+        let reached = self.counter / self.milestone_every;
+        let new = reached.saturating_sub(self.rewarded_milestones);
+        self.rewarded_milestones = self.rewarded_milestones.max(reached);
+        new
+    }
+
+    pub fn is_nft_claimed(&self) -> bool {
+        self.nft_claimed != 0
+    }
+
+    pub fn has_oracle(&self) -> bool {
+        self.oracle_feed_id != [0; 32]
+    }
+
+    pub fn has_random_request(&self) -> bool {
+        self.random_pending != 0
+    }
+
+    pub fn is_allowlisted(&self) -> bool {
+        self.allowlist_enabled != 0
+    }
+
+    pub fn has_blocklist(&self) -> bool {
+        self.blocklist_enabled != 0
+    }
+
+    pub fn requires_memo(&self) -> bool {
+        self.memo_required != 0
+    }
+
+    pub fn is_stake_weighted(&self) -> bool {
+        self.stake_weighted != 0
+    }
+
+    /// In credit mode Increment, Decrement, Update and Reset (wide and scaled
+    /// ones included) spend one of the signer's prepaid [`CreditBalance`]
+    /// credits.
+    pub fn has_credits(&self) -> bool {
+        self.credit_price_lamports != 0
+    }
+
+    pub fn has_boost(&self) -> bool {
+        self.boost_factor > 1
+    }
+
+    /// While listed, Increment, Decrement, Update and Reset (wide and scaled
+    /// ones included) pass the counter's [`Lease`], whose lessee holds the
+    /// authority over them while the lease runs.
+    pub fn is_listed_for_lease(&self) -> bool {
+        self.lease_listed != 0
+    }
+
+    pub fn has_referrals(&self) -> bool {
+        self.referrals_enabled != 0
+    }
+
+    /// The part of `fee_lamports` a referred Increment pays its referrer
+    /// rather than the treasury.
+    pub fn referral_fee(&self) -> u64 {
+        // At most `fee_lamports`, as the share is capped at 10,000 bps.
+        (u128::from(self.fee_lamports) * u128::from(self.referral_share_bps) / 10_000) as u64
+    }
+
+    pub fn has_offchain_signer(&self) -> bool {
+        self.offchain_signer != Pubkey::default()
+    }
+
+    pub fn has_eth_authority(&self) -> bool {
+        self.eth_authority != [0; 20]
+    }
+
+    pub fn has_wormhole_emitter(&self) -> bool {
+        self.wormhole_emitter_chain != 0
+    }
+
+    pub fn has_history(&self) -> bool {
+        self.history_capacity != 0
+    }
+
+    pub fn has_modulus(&self) -> bool {
+        self.modulus != 0
+    }
+
+    /// Countdown counters only decrement, and lock once they reach zero.
+    pub fn has_countdown(&self) -> bool {
+        self.countdown != COUNTDOWN_OFF
+    }
+
+    pub fn is_countdown_completed(&self) -> bool {
+        self.countdown == COUNTDOWN_COMPLETED
+    }
+
+    /// Only `u32` counters work with the features built on their value, such
+    /// as decay, milestones, history or moving value between counters.
+    pub fn is_u32(&self) -> bool {
+        self.kind == KIND_U32
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.kind == KIND_I64
+    }
+
+    pub fn is_wide(&self) -> bool {
+        self.kind == KIND_U128
+    }
+
+    pub fn wide_value(&self) -> u128 {
+        u128::from_le_bytes(self.wide_counter)
+    }
+
+    pub(crate) fn set_wide_value(&mut self, value: u128) {
+        self.wide_counter = value.to_le_bytes();
+    }
+
+    pub fn value(&self) -> CounterValue {
+        match self.kind {
+            KIND_I64 => CounterValue::I64(self.signed_counter),
+            KIND_U128 => CounterValue::U128(self.wide_value()),
+            _ => CounterValue::U32(self.counter),
+        }
+    }
+
+    /// Sets the value of a counter of any kind, as Update and Reset do.
+    pub(crate) fn set_value(&mut self, value: u32) {
+        match self.kind {
+            KIND_I64 => self.signed_counter = value.into(),
+            KIND_U128 => self.set_wide_value(value.into()),
+            _ => self.counter = value,
+        }
+    }
+
+    pub fn has_expiry(&self) -> bool {
+        self.expires_at_slot != 0
+    }
+
+    /// Expired counters reject writes and can be reaped by anyone.
+    pub fn is_expired(&self, slot: u64) -> bool {
+        self.has_expiry() && slot >= self.expires_at_slot
+    }
+
+    /// Counters nobody wrote to for [`ABANDONED_AFTER_EPOCHS`] can be reaped
+    /// by anyone, refunding their rent payer. Counters without one are only
+    /// reaped once expired.
+    pub fn is_abandoned(&self, epoch: u64) -> bool {
+        self.rent_payer != Pubkey::default()
+            && epoch
+                >= self
+                    .last_active_epoch
+                    .saturating_add(ABANDONED_AFTER_EPOCHS)
+    }
+
+    /// Who a reaped counter's lamports go to, less the reaper's share: the
+    /// expiry recipient once expired, else the rent payer once abandoned.
+    pub fn reap_recipient(&self, clock: &Clock) -> Option<Pubkey> {
+        if self.is_expired(clock.slot) {
+            Some(self.expiry_recipient)
+        } else if self.is_abandoned(clock.epoch) {
+            Some(self.rent_payer)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a scheduled reset is due in `slot`.
+    pub fn is_crank_due(&self, slot: u64) -> bool {
+        self.crank_every_slots != 0
+            && slot >= self.last_crank_slot.saturating_add(self.crank_every_slots)
+    }
+
+    pub fn has_daily_cap(&self) -> bool {
+        self.daily_cap != 0
+    }
+
+    /// With a cooldown Increment, Decrement, Update and Reset (wide and scaled
+    /// ones included) pass their signer's [`CallerCooldown`].
+    pub fn has_cooldown(&self) -> bool {
+        self.cooldown != 0
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        self.max_ops_per_slot != 0
+    }
+
+    /// Counts a mutation in `slot` against `max_ops_per_slot`.
+    pub(crate) fn record_op(&mut self, slot: u64) -> ProgramResult {
+        if slot != self.last_slot {
+            self.last_slot = slot;
+            self.ops_in_slot = 0;
+        }
+        if self.ops_in_slot >= self.max_ops_per_slot {
+            return Err(CounterError::RateLimited.into());
+        }
+        self.ops_in_slot += 1;
+        Ok(())
+    }
+
+    /// Records a write in `epoch`, first zeroing the counter if the last
+    /// write was in an earlier epoch. Returns the discarded value on a reset.
+    pub(crate) fn roll_epoch(&mut self, epoch: u64) -> Option<u32> {
+        let rolled = epoch > self.last_write_epoch;
+        self.last_write_epoch = epoch;
+        rolled.then(|| std::mem::take(&mut self.counter))
+    }
+
+    pub(crate) fn check_authority(&self, authority: &AccountInfo) -> ProgramResult {
+        if !authority.is_signer || *authority.key != self.authority {
+            return Err(CounterError::Unauthorized.into());
+        }
+        Ok(())
+    }
+
+    /// Counters without an authority accept mutations from anyone; owned
+    /// counters require the authority or the delegate, which spends one unit
+    /// of its allowance per operation.
+    ///
+    /// `signer` is the `(key, is_signer)` of the account after the counter.
+    /// It is passed unpacked so every entrypoint can share the check.
+    pub(crate) fn check_mutator(&mut self, signer: Option<(&Pubkey, bool)>) -> ProgramResult {
+        if self.authority == Pubkey::default() {
+            return Ok(());
+        }
+        let (key, is_signer) = signer.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if !is_signer {
+            return Err(CounterError::Unauthorized.into());
+        }
+        if *key == self.authority {
+            return Ok(());
+        }
+        if self.delegate != Pubkey::default() && *key == self.delegate {
+            self.delegated_allowance = self
+                .delegated_allowance
+                .checked_sub(1)
+                .ok_or(CounterError::AllowanceExceeded)?;
+            return Ok(());
+        }
+        Err(CounterError::Unauthorized.into())
+    }
+
+    pub(crate) fn increment(
+        &mut self,
+        signer: Option<(&Pubkey, bool)>,
+        value: u64,
+        mode: u8,
+    ) -> ProgramResult {
+        self.check_step(value)?;
+        // Writes go straight to the account, so check for overflow before
+        // the delegate's allowance is spent.
+        match self.kind {
+            KIND_I64 => {
+                let signed_counter = match mode {
+                    OVERFLOW_CHECKED => self
+                        .signed_counter
+                        .checked_add_unsigned(value)
+                        .ok_or(CounterError::Overflow)?,
+                    OVERFLOW_SATURATING => self.signed_counter.saturating_add_unsigned(value),
+                    OVERFLOW_WRAPPING => self.signed_counter.wrapping_add_unsigned(value),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                self.check_mutator(signer)?;
+                self.signed_counter = signed_counter;
+            }
+            KIND_U128 => {
+                let wide = self.wide_value();
+                let wide = match mode {
+                    OVERFLOW_CHECKED => wide
+                        .checked_add(value.into())
+                        .ok_or(CounterError::Overflow)?,
+                    OVERFLOW_SATURATING => wide.saturating_add(value.into()),
+                    OVERFLOW_WRAPPING => wide.wrapping_add(value.into()),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                self.check_mutator(signer)?;
+                self.set_wide_value(wide);
+            }
+            _ if self.has_modulus() => {
+                check_mode(mode)?;
+                let modulus = u64::from(self.modulus);
+                // Both terms are below 2^32, so the sum fits.
+                let counter = (u64::from(self.counter) + value % modulus) % modulus;
+                self.check_mutator(signer)?;
+                self.counter = counter as u32;
+            }
+            _ => {
+                let counter = match mode {
+                    OVERFLOW_CHECKED => u32::try_from(value)
+                        .ok()
+                        .and_then(|value| self.counter.checked_add(value))
+                        .ok_or(CounterError::Overflow)?,
+                    OVERFLOW_SATURATING => self
+                        .counter
+                        .saturating_add(u32::try_from(value).unwrap_or(u32::MAX)),
+                    // Only the low 32 bits matter modulo 2^32.
+                    OVERFLOW_WRAPPING => self.counter.wrapping_add(value as u32),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                self.check_mutator(signer)?;
+                self.counter = counter;
+            }
+        }
+        self.record_increment();
+        Ok(())
+    }
+
+    /// Signed counters go below zero; the mode only matters at `i64::MIN`.
+    pub(crate) fn decrement(
+        &mut self,
+        signer: Option<(&Pubkey, bool)>,
+        value: u64,
+        mode: u8,
+    ) -> ProgramResult {
+        self.check_step(value)?;
+        match self.kind {
+            KIND_I64 => {
+                let signed_counter = match mode {
+                    OVERFLOW_CHECKED => self
+                        .signed_counter
+                        .checked_sub_unsigned(value)
+                        .ok_or(CounterError::Underflow)?,
+                    OVERFLOW_SATURATING => self.signed_counter.saturating_sub_unsigned(value),
+                    OVERFLOW_WRAPPING => self.signed_counter.wrapping_sub_unsigned(value),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                self.check_mutator(signer)?;
+                self.signed_counter = signed_counter;
+            }
+            KIND_U128 => {
+                let wide = self.wide_value();
+                let wide = match mode {
+                    OVERFLOW_CHECKED => wide
+                        .checked_sub(value.into())
+                        .ok_or(CounterError::Underflow)?,
+                    OVERFLOW_SATURATING => wide.saturating_sub(value.into()),
+                    OVERFLOW_WRAPPING => wide.wrapping_sub(value.into()),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                self.check_mutator(signer)?;
+                self.set_wide_value(wide);
+            }
+            // A countdown stops at zero rather than fail or wrap past it.
+            _ if self.has_countdown() => {
+                check_mode(mode)?;
+                let counter = self
+                    .counter
+                    .saturating_sub(u32::try_from(value).unwrap_or(u32::MAX));
+                self.check_mutator(signer)?;
+                self.counter = counter;
+            }
+            _ if self.has_modulus() => {
+                check_mode(mode)?;
+                let modulus = u64::from(self.modulus);
+                // An Update may have left the value at or above the modulus.
+                let counter =
+                    (u64::from(self.counter) % modulus + modulus - value % modulus) % modulus;
+                self.check_mutator(signer)?;
+                self.counter = counter as u32;
+            }
+            _ => {
+                let counter = match mode {
+                    OVERFLOW_CHECKED => u32::try_from(value)
+                        .ok()
+                        .and_then(|value| self.counter.checked_sub(value))
+                        .ok_or(CounterError::Underflow)?,
+                    OVERFLOW_SATURATING => self
+                        .counter
+                        .saturating_sub(u32::try_from(value).unwrap_or(u32::MAX)),
+                    // Only the low 32 bits matter modulo 2^32.
+                    OVERFLOW_WRAPPING => self.counter.wrapping_sub(value as u32),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                self.check_mutator(signer)?;
+                self.counter = counter;
+            }
+        }
+        self.record_decrement();
+        Ok(())
+    }
+
+    fn check_step(&self, value: u64) -> ProgramResult {
+        // A step of zero has no remainder to check.
+        if value.checked_rem(self.step.into()).unwrap_or(0) != 0 {
+            return Err(CounterError::InvalidStep.into());
+        }
+        Ok(())
+    }
+
+    /// Folds the value a write left the counter at into `max_value_seen` and
+    /// `min_value_seen`. Writes that aren't increments, decrements or resets
+    /// only call this. Both are `u32`, so other kinds of counter skip it.
+    pub(crate) fn record_value(&mut self) {
+        if !self.is_u32() {
+            return;
+        }
+        if self.values_seen == 0 {
+            self.values_seen = 1;
+            self.max_value_seen = self.counter;
+            self.min_value_seen = self.counter;
+        }
+        self.max_value_seen = self.max_value_seen.max(self.counter);
+        self.min_value_seen = self.min_value_seen.min(self.counter);
+    }
+
+    pub(crate) fn record_increment(&mut self) {
+        self.total_increments = self.total_increments.saturating_add(1);
+        self.record_value();
+    }
+
+    pub(crate) fn record_decrement(&mut self) {
+        self.total_decrements = self.total_decrements.saturating_add(1);
+        self.record_value();
+    }
+
+    pub(crate) fn record_reset(&mut self) {
+        self.total_resets = self.total_resets.saturating_add(1);
+        self.record_value();
+    }
+
+    pub fn stats(&self) -> CounterStats {
+        CounterStats {
+            total_increments: self.total_increments,
+            total_decrements: self.total_decrements,
+            total_resets: self.total_resets,
+            max_value_seen: self.max_value_seen,
+            min_value_seen: self.min_value_seen,
+        }
+    }
+
+    pub(crate) fn check_multisig(&self, signers: &[AccountInfo]) -> ProgramResult {
+        let configured = self
+            .multisig_signers
+            .get(..self.multisig_signer_count as usize)
+            .unwrap_or_default();
+        let mut matched = [false; MAX_SIGNERS];
+        for signer in signers.iter().filter(|signer| signer.is_signer) {
+            if let Some(position) = configured.iter().position(|key| key == signer.key) {
+                matched[position] = true;
+            }
+        }
+        if matched.iter().filter(|&&m| m).count() < self.multisig_threshold as usize {
+            return Err(CounterError::NotEnoughSigners.into());
+        }
+        Ok(())
+    }
+
+    /// Update and Reset require the governance's native treasury when one is
+    /// configured.
+    pub(crate) fn check_governance(&self, signers: &[AccountInfo]) -> ProgramResult {
+        if self.governance == Pubkey::default() {
+            return Ok(());
+        }
+        if !signers
+            .iter()
+            .any(|signer| signer.is_signer && *signer.key == self.governance_treasury)
+        {
+            return Err(CounterError::GovernanceRequired.into());
+        }
+        Ok(())
+    }
+
+    /// Privileged instructions require the multisig when one is configured.
+    pub(crate) fn check_privileged(&self, signers: &[AccountInfo]) -> ProgramResult {
+        if self.multisig_threshold > 0 {
+            return self.check_multisig(signers);
+        }
+        Ok(())
+    }
+}
+
+/// A modular counter can't overflow, but its writes still name a valid
+/// overflow mode.
+fn check_mode(mode: u8) -> ProgramResult {
+    match mode {
+        OVERFLOW_CHECKED | OVERFLOW_SATURATING | OVERFLOW_WRAPPING => Ok(()),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// The message the off-chain signer signs to authorize an Increment of
+/// `counter`: its address followed by the little-endian `nonce`.
+pub fn offchain_message(counter: &Pubkey, nonce: u64) -> [u8; 40] {
+    let mut message = [0; 40];
+    message[..32].copy_from_slice(counter.as_ref());
+    message[32..].copy_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+/// The message the Ethereum authority signs to authorize a mutation of
+/// `counter`: [`offchain_message`] behind the prefix Ethereum wallets add
+/// when signing a message (EIP-191), so `personal_sign` produces it.
+pub fn eth_message(counter: &Pubkey, nonce: u64) -> Vec<u8> {
+    [
+        &b"\x19Ethereum Signed Message:\n40"[..],
+        &offchain_message(counter, nonce),
+    ]
+    .concat()
+}
+
+/// Prefix seed of named counter PDAs.
+pub const COUNTER_SEED: &[u8] = b"counter";
+
+/// Address of the counter `authority` created with `InitializeNamed { name }`.
+/// `name` must fit in a seed (32 bytes).
+pub fn find_counter_address(program_id: &Pubkey, authority: &Pubkey, name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[COUNTER_SEED, authority.as_ref(), name.as_bytes()],
+        program_id,
+    )
+}
+
+/// Seed of the program-wide registry PDA.
+pub const REGISTRY_SEED: &[u8] = b"registry";
+
+pub fn find_registry_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGISTRY_SEED], program_id)
+}
+
+/// Seed of the program-wide config PDA.
+pub const CONFIG_SEED: &[u8] = b"config";
+
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/// Program-wide settings, created once with `InitializeConfig` and changed
+/// by the super-admin with `UpdateConfig`. Counters created while it exists
+/// start with its fee and are governed by it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramConfig {
+    pub super_admin: Pubkey,
+    /// `fee_lamports` of counters created from now on.
+    pub default_fee_lamports: u64,
+    /// Nonzero while value writes to governed counters are refused.
+    pub paused: u8,
+    /// Bump of the config's own address.
+    pub bump: u8,
+    pub _padding: [u8; 6],
+}
+
+impl ProgramConfig {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused != 0
+    }
+}
+
+/// Start of the registry account: the number of entries that follow.
+///
+/// Entries are fixed-size and never removed, so page `n` of `k` entries sits
+/// at [`RegistryHeader::page_range`] and can be fetched on its own with an
+/// RPC `dataSlice`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryHeader {
+    pub count: u64,
+}
+
+/// One counter created through `Initialize`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryEntry {
+    pub counter: Pubkey,
+    pub created_slot: u64,
+}
+
+impl RegistryHeader {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Byte range of entries `[page * page_len, (page + 1) * page_len)` in
+    /// the registry account data.
+    pub fn page_range(page: usize, page_len: usize) -> std::ops::Range<usize> {
+        let start = Self::LEN + page * page_len * RegistryEntry::LEN;
+        start..start + page_len * RegistryEntry::LEN
+    }
+
+    /// Size of a registry holding `count` entries.
+    pub fn account_len(count: usize) -> usize {
+        Self::LEN + count * RegistryEntry::LEN
+    }
+}
+
+impl RegistryEntry {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Decodes a run of entries, such as one page fetched with `dataSlice`.
+    /// A trailing partial entry is ignored.
+    pub fn unpack_slice(data: &[u8]) -> Vec<Self> {
+        data.chunks_exact(Self::LEN)
+            .map(bytemuck::pod_read_unaligned)
+            .collect()
+    }
+}
+
+/// Prefix seed of treasury PDAs, `["treasury", counter]`.
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+/// Address collecting a counter's fees. It is a plain system account, so
+/// only its lamports matter.
+pub fn find_treasury_address(program_id: &Pubkey, counter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED, counter.as_ref()], program_id)
+}
+
+/// Prefix seed of vault authority PDAs, `["vault", counter]`.
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// PDA owning the counter's token vaults.
+pub fn find_vault_authority_address(program_id: &Pubkey, counter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, counter.as_ref()], program_id)
+}
+
+/// The counter's vault for `mint`: the vault authority's associated token
+/// account under `token_program`, spl-token or Token-2022.
+#[cfg(feature = "program")]
+pub fn find_vault_address(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    let (vault_authority, _) = find_vault_authority_address(program_id, counter);
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        &vault_authority,
+        mint,
+        token_program,
+    )
+}
+
+/// Prefix seed of the reward authority PDA, `["reward", counter]`.
+pub const REWARD_SEED: &[u8] = b"reward";
+
+/// PDA that must be the mint authority of the counter's reward mint.
+pub fn find_reward_authority_address(program_id: &Pubkey, counter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_SEED, counter.as_ref()], program_id)
+}
+
+/// Prefix seed of the NFT authority PDA, `["nft", counter]`.
+pub const NFT_SEED: &[u8] = b"nft";
+
+/// PDA that mints milestone NFTs and is their metadata's update authority.
+pub fn find_nft_authority_address(program_id: &Pubkey, counter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[NFT_SEED, counter.as_ref()], program_id)
+}
+
+/// Prefix seed of caller quota PDAs, `["quota", counter, caller]`.
+pub const QUOTA_SEED: &[u8] = b"quota";
+
+/// Length of the window a [`CallerQuota`] counts increments over.
+pub const QUOTA_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+pub fn find_quota_address(program_id: &Pubkey, counter: &Pubkey, caller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[QUOTA_SEED, counter.as_ref(), caller.as_ref()], program_id)
+}
+
+/// How much one caller has incremented a capped counter in the current
+/// window.
+///
+/// The account records which counter and caller it belongs to. Only the
+/// program can write accounts it owns and it only creates quotas at their
+/// PDA, so checking the owner and these fields is enough to trust it without
+/// re-deriving the address on every increment.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallerQuota {
+    pub counter: Pubkey,
+    pub caller: Pubkey,
+    /// Unix timestamp the current window started at.
+    pub window_start: i64,
+    /// Sum of increments in the current window.
+    pub total: u64,
+}
+
+impl CallerQuota {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Adds `amount` at unix time `now`, starting a new window once the
+    /// current one is [`QUOTA_WINDOW_SECONDS`] old.
+    pub(crate) fn record(&mut self, now: i64, amount: u64, cap: u64) -> ProgramResult {
+        if now.saturating_sub(self.window_start) >= QUOTA_WINDOW_SECONDS {
+            self.window_start = now;
+            self.total = 0;
+        }
+        self.total = self
+            .total
+            .checked_add(amount)
+            .filter(|total| *total <= cap)
+            .ok_or(CounterError::DailyCapExceeded)?;
+        Ok(())
+    }
+}
+
+/// Prefix seed of caller cooldown PDAs, `["cooldown", counter, caller]`.
+pub const COOLDOWN_SEED: &[u8] = b"cooldown";
+
+pub fn find_cooldown_address(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    caller: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[COOLDOWN_SEED, counter.as_ref(), caller.as_ref()],
+        program_id,
+    )
+}
+
+/// When one caller last mutated a counter with a cooldown. Trusted on its
+/// owner and fields, as a [`CallerQuota`] is.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallerCooldown {
+    pub counter: Pubkey,
+    pub caller: Pubkey,
+    pub last_slot: u64,
+    /// Unix timestamp of the last mutation.
+    pub last_timestamp: i64,
+    /// Mutations recorded; the first is never held back.
+    pub operations: u64,
+}
+
+impl CallerCooldown {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Records a mutation at `slot` and unix time `now`, unless it comes
+    /// within `cooldown` `unit`s of the last one.
+    pub(crate) fn record(&mut self, slot: u64, now: i64, cooldown: u32, unit: u8) -> ProgramResult {
+        let waited = match unit {
+            COOLDOWN_SECONDS => now.saturating_sub(self.last_timestamp) >= cooldown.into(),
+            _ => slot.saturating_sub(self.last_slot) >= cooldown.into(),
+        };
+        if self.operations != 0 && !waited {
+            return Err(CounterError::CooldownActive.into());
+        }
+        self.last_slot = slot;
+        self.last_timestamp = now;
+        self.operations = self.operations.saturating_add(1);
+        Ok(())
+    }
+}
+
+/// Prefix seed of allow-list entry PDAs, `["allowlist", counter, member]`.
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+
+pub fn find_allowlist_address(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    member: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ALLOWLIST_SEED, counter.as_ref(), member.as_ref()],
+        program_id,
+    )
+}
+
+/// Marks `member` as allowed to mutate `counter` while its allow-list is on.
+/// Trusted on owner and fields alone, like [`CallerQuota`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllowlistEntry {
+    pub counter: Pubkey,
+    pub member: Pubkey,
+}
+
+impl AllowlistEntry {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+}
+
+/// Prefix seed of ban entry PDAs, `["ban", counter, caller]`.
+pub const BAN_SEED: &[u8] = b"ban";
+
+pub fn find_ban_address(program_id: &Pubkey, counter: &Pubkey, caller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BAN_SEED, counter.as_ref(), caller.as_ref()], program_id)
+}
+
+/// Bars `caller` from mutating `counter` while its block-list is on. Unlike
+/// [`AllowlistEntry`], callers pass the address whether or not the entry
+/// exists, so it is re-derived rather than trusted.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BanEntry {
+    pub counter: Pubkey,
+    pub caller: Pubkey,
+}
+
+impl BanEntry {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+}
+
+/// Prefix seed of referrer entry PDAs, `["referrer", counter, referrer]`.
+pub const REFERRER_SEED: &[u8] = b"referrer";
+
+pub fn find_referrer_address(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    referrer: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REFERRER_SEED, counter.as_ref(), referrer.as_ref()],
+        program_id,
+    )
+}
+
+/// What `referrer` brought `counter`: the increments naming it and the fee
+/// share they paid it, held in the entry until withdrawn. Its address is
+/// re-derived from `bump` on every use, so other accounts of the same shape
+/// can't pass for it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReferrerEntry {
+    pub counter: Pubkey,
+    pub referrer: Pubkey,
+    /// Increments that named the referrer.
+    pub referrals: u64,
+    /// Sum of their values.
+    pub referred_value: u64,
+    /// Fee lamports routed to the referrer, withdrawn or not.
+    pub fees_earned: u64,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+impl ReferrerEntry {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+}
+
+/// Prefix seed of leaderboard PDAs, `["leaderboard", authority]`.
+pub const LEADERBOARD_SEED: &[u8] = b"leaderboard";
+
+pub fn find_leaderboard_address(program_id: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LEADERBOARD_SEED, authority.as_ref()], program_id)
+}
+
+/// Start of a leaderboard account: the `count` best-scoring counters of
+/// `authority`, highest first, as [`LeaderboardEntry`]s following the header
+/// in room for `capacity`. Of counters with equal scores, the one that
+/// reached it first ranks higher. The address is re-derived from `bump`, as
+/// a [`ReferrerEntry`]'s is.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LeaderboardHeader {
+    pub authority: Pubkey,
+    pub capacity: u32,
+    pub count: u32,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+/// A counter and the value it was last submitted at.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LeaderboardEntry {
+    pub counter: Pubkey,
+    pub score: u64,
+}
+
+impl LeaderboardHeader {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Largest capacity whose account the program can create, as for
+    /// [`HistoryHeader::MAX_CAPACITY`].
+    pub const MAX_CAPACITY: u32 =
+        ((MAX_PERMITTED_DATA_INCREASE - Self::LEN) / LeaderboardEntry::LEN) as u32;
+
+    /// Size of a leaderboard with room for `capacity` entries.
+    pub fn account_len(capacity: u32) -> usize {
+        Self::LEN + capacity as usize * LeaderboardEntry::LEN
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Decodes a leaderboard account's entries, highest score first.
+    pub fn unpack_entries(data: &[u8]) -> Result<Vec<LeaderboardEntry>, ProgramError> {
+        let header = Self::unpack(data)?;
+        let end = Self::account_len(header.count);
+        if header.count > header.capacity || data.len() < end {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(data[Self::LEN..end]
+            .chunks_exact(LeaderboardEntry::LEN)
+            .map(bytemuck::pod_read_unaligned)
+            .collect())
+    }
+
+    /// Moves `counter` to where `score` ranks it on the leaderboard `data`,
+    /// entering it if it is new and shifting the entries below down by one.
+    /// Returns its rank from zero, or `None` once it ranks below every entry
+    /// of a full board.
+    pub(crate) fn submit(
+        data: &mut [u8],
+        counter: &Pubkey,
+        score: u64,
+    ) -> Result<Option<u32>, ProgramError> {
+        const LEN: usize = LeaderboardEntry::LEN;
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (header, entries) = data.split_at_mut(Self::LEN);
+        let header: &mut Self =
+            bytemuck::try_from_bytes_mut(header).map_err(|_| ProgramError::InvalidAccountData)?;
+        let capacity = header.capacity as usize;
+        let mut count = header.count as usize;
+        let entries = entries
+            .get_mut(..capacity * LEN)
+            .filter(|_| count <= capacity)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let entry_at = |entries: &[u8], index: usize| -> LeaderboardEntry {
+            bytemuck::pod_read_unaligned(&entries[index * LEN..(index + 1) * LEN])
+        };
+
+        // Take the counter's old entry out, unless it keeps its place.
+        if let Some(index) = (0..count).find(|&index| entry_at(entries, index).counter == *counter)
+        {
+            if entry_at(entries, index).score == score {
+                return Ok(Some(index as u32));
+            }
+            entries.copy_within((index + 1) * LEN..count * LEN, index * LEN);
+            count -= 1;
+        }
+        let rank = (0..count)
+            .find(|&index| entry_at(entries, index).score < score)
+            .unwrap_or(count);
+        if rank == capacity {
+            header.count = count as u32;
+            return Ok(None);
+        }
+        // A full board drops its last entry to make room.
+        let kept = count.min(capacity - 1);
+        entries.copy_within(rank * LEN..kept * LEN, (rank + 1) * LEN);
+        entries[rank * LEN..(rank + 1) * LEN].copy_from_slice(bytemuck::bytes_of(
+            &LeaderboardEntry {
+                counter: *counter,
+                score,
+            },
+        ));
+        header.count = (kept + 1) as u32;
+        Ok(Some(rank as u32))
+    }
+}
+
+impl LeaderboardEntry {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+}
+
+/// Prefix seed of boost escrow PDAs, `["boost", counter, owner]`.
+pub const BOOST_SEED: &[u8] = b"boost";
+
+pub fn find_boost_address(program_id: &Pubkey, counter: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BOOST_SEED, counter.as_ref(), owner.as_ref()], program_id)
+}
+
+/// Lamports `owner` locked with `LockBoost` to boost its Increments of
+/// `counter`, held in the escrow on top of its rent until `unlocks_at`. The
+/// address is re-derived from `bump`, as a [`ReferrerEntry`]'s is.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoostEscrow {
+    pub counter: Pubkey,
+    pub owner: Pubkey,
+    pub locked_lamports: u64,
+    /// Unix timestamp from which `UnlockBoost` returns the lamports.
+    pub unlocks_at: i64,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+impl BoostEscrow {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Whether the escrow boosts Increments at `now` on a counter asking
+    /// for `min_sol`.
+    pub fn is_boosting(&self, now: i64, min_sol: u16) -> bool {
+        now < self.unlocks_at && self.locked_lamports >= u64::from(min_sol) * LAMPORTS_PER_SOL
+    }
+}
+
+/// Prefix seed of lease PDAs, `["lease", counter]`.
+pub const LEASE_SEED: &[u8] = b"lease";
+
+pub fn find_lease_address(program_id: &Pubkey, counter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LEASE_SEED, counter.as_ref()], program_id)
+}
+
+/// The terms a counter is listed for lease on, and its current lessee. The
+/// address is re-derived from `bump`, as a [`ReferrerEntry`]'s is.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lease {
+    pub counter: Pubkey,
+    /// Who last took the lease, or the default pubkey.
+    pub lessee: Pubkey,
+    /// Lamports a lessee pays the treasury for one lease.
+    pub price_lamports: u64,
+    /// Seconds a lease runs.
+    pub duration: u64,
+    /// Unix timestamp the current lease ends at.
+    pub expires_at: i64,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+impl Lease {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.lessee != Pubkey::default() && now < self.expires_at
+    }
+
+    /// The share of the price for the time left at `now`, which ending the
+    /// lease early refunds.
+    pub fn refund(&self, now: i64) -> u64 {
+        let left = self.expires_at.saturating_sub(now).max(0) as u64;
+        match self.duration {
+            0 => 0,
+            duration => {
+                (u128::from(self.price_lamports) * u128::from(left.min(duration))
+                    / u128::from(duration)) as u64
+            }
+        }
+    }
+}
+
+/// Prefix seed of credit balance PDAs, `["credits", counter, owner]`.
+pub const CREDITS_SEED: &[u8] = b"credits";
+
+pub fn find_credits_address(program_id: &Pubkey, counter: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CREDITS_SEED, counter.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// The operation credits `owner` bought for `counter` and has yet to spend.
+/// The address is re-derived from `bump`, as a [`ReferrerEntry`]'s is.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CreditBalance {
+    pub counter: Pubkey,
+    pub owner: Pubkey,
+    pub credits: u64,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+impl CreditBalance {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+}
+
+/// Prefix seed of consumed-VAA PDAs, `["received", counter, vaa_hash]`.
+pub const RECEIVED_SEED: &[u8] = b"received";
+
+/// Address of the PDA marking the VAA whose body hashes to `vaa_hash` as
+/// applied to `counter`.
+pub fn find_received_address(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    vaa_hash: &[u8; 32],
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECEIVED_SEED, counter.as_ref(), vaa_hash], program_id)
+}
+
+/// Records that the VAA with `sequence` from the counter's emitter was
+/// applied to `counter`; its existence is what prevents a replay.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReceivedVaa {
+    pub counter: Pubkey,
+    pub sequence: u64,
+}
+
+impl ReceivedVaa {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+}
+
+/// Prefix seed of history PDAs, `["history", counter]`.
+pub const HISTORY_SEED: &[u8] = b"history";
+
+pub fn find_history_address(program_id: &Pubkey, counter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[HISTORY_SEED, counter.as_ref()], program_id)
+}
+
+/// Start of a history account: a ring buffer of the last `capacity` values
+/// its counter was written to, as [`HistoryEntry`]s following the header.
+/// Trusted on owner and `counter`, like [`CallerQuota`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryHeader {
+    pub counter: Pubkey,
+    pub capacity: u64,
+    /// Entries ever recorded; the next one goes at `count % capacity`.
+    pub count: u64,
+}
+
+/// The value a write left the counter at, and its slot.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryEntry {
+    pub slot: u64,
+    pub value: u32,
+    pub _padding: [u8; 4],
+}
+
+impl HistoryHeader {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Largest capacity whose account the program can create, since the
+    /// system program allocates at most [`MAX_PERMITTED_DATA_INCREASE`]
+    /// bytes through a CPI.
+    pub const MAX_CAPACITY: u32 =
+        ((MAX_PERMITTED_DATA_INCREASE - Self::LEN) / HistoryEntry::LEN) as u32;
+
+    /// Size of a history holding `capacity` entries.
+    pub fn account_len(capacity: u32) -> usize {
+        Self::LEN + capacity as usize * HistoryEntry::LEN
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Decodes a history account's entries, oldest first.
+    pub fn unpack_entries(data: &[u8]) -> Result<Vec<HistoryEntry>, ProgramError> {
+        let header = Self::unpack(data)?;
+        let entries = data
+            .get(Self::LEN..)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .chunks_exact(HistoryEntry::LEN)
+            .map(bytemuck::pod_read_unaligned)
+            .collect::<Vec<HistoryEntry>>();
+        if entries.len() as u64 != header.capacity {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let filled = header.count.min(header.capacity) as usize;
+        let oldest = if header.count > header.capacity {
+            (header.count % header.capacity) as usize
+        } else {
+            0
+        };
+        Ok(entries
+            .iter()
+            .cycle()
+            .skip(oldest)
+            .take(filled)
+            .copied()
+            .collect())
+    }
+
+    /// Records `entry` in the history account `data`, overwriting the
+    /// oldest entry once the buffer is full.
+    pub(crate) fn append(data: &mut [u8], entry: HistoryEntry) -> ProgramResult {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (header, entries) = data.split_at_mut(Self::LEN);
+        let header: &mut Self =
+            bytemuck::try_from_bytes_mut(header).map_err(|_| ProgramError::InvalidAccountData)?;
+        if header.capacity == 0 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let start = (header.count % header.capacity) as usize * HistoryEntry::LEN;
+        entries
+            .get_mut(start..start + HistoryEntry::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .copy_from_slice(bytemuck::bytes_of(&entry));
+        header.count += 1;
+        Ok(())
+    }
+}
+
+impl HistoryEntry {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+}
+
+/// Start of a counter array account: `len` little-endian `u32` values
+/// follow, addressed by index, so related counters share one account's rent.
+/// The account is created by the caller at [`CounterArrayHeader::account_len`]
+/// and set up with `InitializeArray`; only `authority` writes to it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CounterArrayHeader {
+    pub authority: Pubkey,
+    pub len: u16,
+    pub _padding: [u8; 6],
+}
+
+impl CounterArrayHeader {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Size of an array holding `len` counters.
+    pub fn account_len(len: u16) -> usize {
+        Self::LEN + usize::from(len) * std::mem::size_of::<u32>()
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Decodes every value of a counter array account, by index.
+    pub fn unpack_values(data: &[u8]) -> Result<Vec<u32>, ProgramError> {
+        let header = Self::unpack(data)?;
+        (0..header.len)
+            .map(|index| Self::value(data, index))
+            .collect()
+    }
+
+    /// The counter at `index` of the array account `data`.
+    pub fn value(data: &[u8], index: u16) -> Result<u32, ProgramError> {
+        let range = Self::value_range(data, index)?;
+        Ok(u32::from_le_bytes(
+            data[range].try_into().expect("four bytes"),
+        ))
+    }
+
+    pub(crate) fn set_value(data: &mut [u8], index: u16, value: u32) -> ProgramResult {
+        let range = Self::value_range(data, index)?;
+        data[range].copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn value_range(data: &[u8], index: u16) -> Result<std::ops::Range<usize>, ProgramError> {
+        if index >= Self::unpack(data)?.len {
+            return Err(CounterError::IndexOutOfBounds.into());
+        }
+        let start = Self::account_len(index);
+        match data.len() >= start + std::mem::size_of::<u32>() {
+            true => Ok(start..start + std::mem::size_of::<u32>()),
+            false => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Start of a keyed counter account: up to `capacity` [`KeyedEntry`]s follow,
+/// the first `len` of them in use and sorted by key, so per-item tallies
+/// share one account instead of a PDA each. Created by the caller at
+/// [`KeyedCounterHeader::account_len`] like a counter array, and grown with
+/// `ResizeKeyed`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyedCounterHeader {
+    pub authority: Pubkey,
+    pub len: u32,
+    pub capacity: u32,
+}
+
+/// One keyed counter.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyedEntry {
+    pub key: [u8; 32],
+    pub value: u64,
+}
+
+impl KeyedCounterHeader {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Size of an account with room for `capacity` entries.
+    pub fn account_len(capacity: u32) -> usize {
+        Self::LEN + capacity as usize * KeyedEntry::LEN
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    pub(crate) fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        data.get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Decodes the entries in use, sorted by key.
+    pub fn unpack_entries(data: &[u8]) -> Result<Vec<KeyedEntry>, ProgramError> {
+        let header = Self::unpack(data)?;
+        Ok(data
+            .get(Self::LEN..Self::account_len(header.len))
+            .ok_or(ProgramError::InvalidAccountData)?
+            .chunks_exact(KeyedEntry::LEN)
+            .map(bytemuck::pod_read_unaligned)
+            .collect())
+    }
+
+    /// The value of `key`, or `None` if it has no entry.
+    pub fn get(data: &[u8], key: &[u8; 32]) -> Result<Option<u64>, ProgramError> {
+        let entries = Self::unpack_entries(data)?;
+        Ok(entries
+            .binary_search_by(|entry| entry.key.cmp(key))
+            .ok()
+            .map(|index| entries[index].value))
+    }
+
+    /// Replaces the value of `key` with what `write` makes of it, given
+    /// `None` for a key without an entry. Returning `None` removes the
+    /// entry. Returns the old and new values, zero standing for no entry.
+    pub(crate) fn write(
+        data: &mut [u8],
+        key: &[u8; 32],
+        write: impl FnOnce(Option<u64>) -> Result<Option<u64>, CounterError>,
+    ) -> Result<(u64, u64), ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (header, entries) = data.split_at_mut(Self::LEN);
+        let header: &mut Self =
+            bytemuck::try_from_bytes_mut(header).map_err(|_| ProgramError::InvalidAccountData)?;
+        let entries: &mut [KeyedEntry] = entries
+            .get_mut(..header.capacity as usize * KeyedEntry::LEN)
+            .and_then(|entries| bytemuck::try_cast_slice_mut(entries).ok())
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let len = header.len as usize;
+        let found = entries
+            .get(..len)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .binary_search_by(|entry| entry.key.cmp(key));
+        let old = found.ok().map(|index| entries[index].value);
+        let new = write(old)?;
+        match (found, new) {
+            (Ok(index), Some(value)) => entries[index].value = value,
+            (Ok(index), None) => {
+                entries.copy_within(index + 1..len, index);
+                entries[len - 1] = KeyedEntry::zeroed();
+                header.len -= 1;
+            }
+            (Err(index), Some(value)) => {
+                if len == entries.len() {
+                    return Err(CounterError::KeyedFull.into());
+                }
+                entries.copy_within(index..len, index + 1);
+                entries[index] = KeyedEntry { key: *key, value };
+                header.len += 1;
+            }
+            (Err(_), None) => {}
+        }
+        Ok((old.unwrap_or(0), new.unwrap_or(0)))
+    }
+}
+
+impl KeyedEntry {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+}
+
+/// Prefix seed of tree authority PDAs, `["tree_authority", tree]`, which
+/// sign for compressed counter trees.
+pub const TREE_AUTHORITY_SEED: &[u8] = b"tree_authority";
+
+pub fn find_tree_authority_address(program_id: &Pubkey, tree: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREE_AUTHORITY_SEED, tree.as_ref()], program_id)
+}
+
+/// Prefix seed of checkpoint PDAs, `["checkpoint", counter, period_index]`,
+/// where the period index is an epoch as a little-endian `u64`.
+pub const CHECKPOINT_SEED: &[u8] = b"checkpoint";
+
+pub fn find_checkpoint_address(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    period_index: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            CHECKPOINT_SEED,
+            counter.as_ref(),
+            &period_index.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// The counter's value when `Checkpoint` first ran in epoch `period_index`.
+/// Checkpoints are never written again, so they outlive any later change to
+/// the counter.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, ShankAccount)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    pub counter: Pubkey,
+    pub period_index: u64,
+    /// Slot the checkpoint was taken in.
+    pub slot: u64,
+    pub value: u32,
+    pub _padding: [u8; 4],
+}
+
+impl Checkpoint {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.get(..Self::LEN)
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+}
@@ -0,0 +1,92 @@
+//! Just enough of Switchboard On-Demand to read a randomness account, without
+//! depending on the Switchboard crates.
+//!
+//! On-Demand randomness replaced the retired Switchboard VRF. A client
+//! commits to a randomness account in one slot, and an oracle reveals its
+//! value in a later slot. That value is only readable in the slot it is
+//! revealed.
+
+use bytemuck::{Pod, Zeroable};
+use solana_program::{clock::Clock, program_error::ProgramError, pubkey, pubkey::Pubkey};
+
+/// The Switchboard On-Demand program, owner of randomness accounts.
+pub const ON_DEMAND_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+/// Anchor discriminator of `RandomnessAccountData`,
+/// `sha256("account:RandomnessAccountData")[..8]`.
+pub const RANDOMNESS_DISCRIMINATOR: [u8; 8] = [10, 66, 229, 135, 220, 239, 217, 114];
+
+/// A `RandomnessAccountData` account, after its discriminator.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct RandomnessAccount {
+    pub authority: Pubkey,
+    pub queue: Pubkey,
+    pub seed_slothash: [u8; 32],
+    /// Slot whose hash seeds the randomness; set when the client commits.
+    pub seed_slot: u64,
+    pub oracle: Pubkey,
+    /// Slot the oracle revealed `value` in.
+    pub reveal_slot: u64,
+    pub value: [u8; 32],
+    pub _ebuf2: [u8; 96],
+    pub _ebuf1: [u8; 128],
+}
+
+impl RandomnessAccount {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        data.strip_prefix(&RANDOMNESS_DISCRIMINATOR)
+            .and_then(|data| data.get(..Self::LEN))
+            .and_then(|data| bytemuck::try_pod_read_unaligned(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Packs the account the way Switchboard stores it, discriminator first.
+    pub fn pack(&self) -> Vec<u8> {
+        [&RANDOMNESS_DISCRIMINATOR[..], bytemuck::bytes_of(self)].concat()
+    }
+
+    /// Whether the client committed to this account in the previous slot,
+    /// so its value cannot have been revealed yet.
+    pub fn is_fresh_commit(&self, clock: &Clock) -> bool {
+        self.seed_slot == clock.slot.saturating_sub(1)
+    }
+
+    /// The revealed value, which Switchboard only vouches for in the slot it
+    /// was revealed.
+    pub fn value(&self, clock: &Clock) -> Option<[u8; 32]> {
+        (self.reveal_slot == clock.slot).then_some(self.value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_randomness_account() {
+        let mut account = RandomnessAccount::zeroed();
+        account.seed_slot = 99;
+        account.reveal_slot = 101;
+        account.value = [3; 32];
+        let data = account.pack();
+        assert_eq!(data.len(), 8 + 400);
+        assert_eq!(RandomnessAccount::unpack(&data).unwrap(), account);
+        assert_eq!(
+            RandomnessAccount::unpack(&data[1..]),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        let clock = |slot| Clock {
+            slot,
+            ..Clock::default()
+        };
+        assert!(account.is_fresh_commit(&clock(100)));
+        assert!(!account.is_fresh_commit(&clock(101)));
+        assert_eq!(account.value(&clock(100)), None);
+        assert_eq!(account.value(&clock(101)), Some([3; 32]));
+        assert_eq!(account.value(&clock(102)), None);
+    }
+}
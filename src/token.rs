@@ -0,0 +1,117 @@
+//! Token CPIs shared by the payment instructions.
+//!
+//! Counters accept both spl-token and Token-2022 mints. The mint's owner
+//! decides which program is called, and amounts account for Token-2022's
+//! transfer-fee extension.
+
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, program::invoke_signed,
+    program_error::ProgramError, sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::{Account, Mint},
+};
+
+/// Rejects anything but spl-token or Token-2022, and a token program that
+/// doesn't own `mint`.
+pub(crate) fn check_token_program(
+    mint: &AccountInfo,
+    token_program: &AccountInfo,
+) -> ProgramResult {
+    if *token_program.key != spl_token::id() && *token_program.key != spl_token_2022::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if mint.owner != token_program.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Amount to send so that `net_amount` arrives after the mint's transfer fee
+/// for the current epoch. Mints without the extension charge no fee.
+pub(crate) fn gross_amount(mint: &AccountInfo, net_amount: u64) -> Result<u64, ProgramError> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let Ok(transfer_fee) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(net_amount);
+    };
+    let fee = transfer_fee
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, net_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    net_amount
+        .checked_add(fee)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+/// `TransferChecked` through whichever token program owns `mint`.
+pub(crate) fn transfer_checked<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let decimals = StateWithExtensions::<Mint>::unpack(&mint.try_borrow_data()?)?
+        .base
+        .decimals;
+    invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            source.clone(),
+            mint.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Unpacks a token account owned by spl-token or Token-2022.
+pub(crate) fn unpack_account(token_account: &AccountInfo) -> Result<Account, ProgramError> {
+    if *token_account.owner != spl_token::id() && *token_account.owner != spl_token_2022::id() {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let data = token_account.try_borrow_data()?;
+    Ok(StateWithExtensions::<Account>::unpack(&data)?.base)
+}
+
+/// `MintTo` through whichever token program owns `mint`.
+pub(crate) fn mint_to<'a>(
+    token_program: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &spl_token_2022::instruction::mint_to(
+            token_program.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        &[
+            mint.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
@@ -0,0 +1,139 @@
+//! wasm-bindgen exports for web dapps: the common instruction builders,
+//! counter PDAs and account decoding.
+//!
+//! On wasm32, solana-program exports [`Pubkey`] and [`Instruction`] to
+//! JavaScript itself, so builders take and return them as they are. Build
+//! with `--no-default-features --features wasm` so no entrypoint is
+//! compiled in. Builders take a single authority; multisig counters need the
+//! Rust [`sdk`].
+
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use wasm_bindgen::prelude::*;
+
+use crate::sdk;
+use crate::state;
+use crate::CounterAccount;
+
+#[wasm_bindgen]
+pub fn initialize(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    sdk::initialize(program_id, counter, authority, payer)
+}
+
+#[wasm_bindgen(js_name = initializeNamed)]
+pub fn initialize_named(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    name: &str,
+) -> Instruction {
+    sdk::initialize_named(program_id, authority, payer, name)
+}
+
+#[wasm_bindgen]
+pub fn increment(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    value: u32,
+) -> Instruction {
+    sdk::increment(program_id, counter, signer, value)
+}
+
+#[wasm_bindgen]
+pub fn decrement(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    signer: &Pubkey,
+    value: u32,
+) -> Instruction {
+    sdk::decrement(program_id, counter, signer, value)
+}
+
+#[wasm_bindgen]
+pub fn update(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    value: u32,
+) -> Instruction {
+    sdk::update(program_id, counter, &[authority], value)
+}
+
+#[wasm_bindgen]
+pub fn reset(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    sdk::reset(program_id, counter, &[authority])
+}
+
+/// The address of the counter `authority` names `name`.
+#[wasm_bindgen(js_name = findCounterAddress)]
+pub fn find_counter_address(program_id: &Pubkey, authority: &Pubkey, name: &str) -> Pubkey {
+    state::find_counter_address(program_id, authority, name).0
+}
+
+/// A decoded counter account.
+#[wasm_bindgen]
+pub struct Counter(CounterAccount);
+
+#[wasm_bindgen]
+impl Counter {
+    /// The value in decimal, whatever the counter's kind, since a `u128`
+    /// does not fit a JavaScript number.
+    #[wasm_bindgen(getter)]
+    pub fn value(&self) -> String {
+        self.0.value().to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn authority(&self) -> Pubkey {
+        self.0.authority
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> u8 {
+        self.0.kind
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn decimals(&self) -> u8 {
+        self.0.decimals
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn paused(&self) -> bool {
+        self.0.is_paused()
+    }
+}
+
+/// Decodes counter account data, or returns `undefined` if it is too
+/// short to be a counter.
+#[wasm_bindgen(js_name = decodeCounter)]
+pub fn decode_counter(data: &[u8]) -> Option<Counter> {
+    CounterAccount::unpack(data).ok().map(Counter)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_decode_counter() {
+        let mut account = CounterAccount::zeroed();
+        account.counter = 7;
+        account.authority = Pubkey::new_unique();
+        let counter = decode_counter(bytemuck::bytes_of(&account)).unwrap();
+        assert_eq!(counter.value(), "7");
+        assert_eq!(counter.authority(), account.authority);
+        assert!(!counter.paused());
+        assert!(decode_counter(&[0; 2]).is_none());
+
+        let program_id = Pubkey::new_unique();
+        let instruction = increment(&program_id, &Pubkey::new_unique(), &account.authority, 2);
+        assert_eq!(instruction.program_id, program_id);
+    }
+}
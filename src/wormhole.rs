@@ -0,0 +1,153 @@
+//! Just enough of the Wormhole Core Bridge to read a posted VAA, without
+//! depending on the Wormhole crates, plus the payload this program accepts
+//! in one.
+//!
+//! The bridge checks a VAA's guardian signatures before posting its message
+//! to a `PostedVAA` account, whose address is seeded with the hash of the
+//! VAA body. An account the bridge owns at the address matching its own
+//! contents was therefore signed by the guardians.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{keccak, program_error::ProgramError, pubkey, pubkey::Pubkey};
+
+/// The Wormhole Core Bridge, owner of posted VAAs.
+pub const CORE_BRIDGE_ID: Pubkey = pubkey!("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+
+/// Prefix seed of `PostedVAA` accounts, followed by the VAA hash.
+pub const POSTED_VAA_SEED: &[u8] = b"PostedVAA";
+
+/// Leading bytes of a `PostedVAA` account.
+pub const POSTED_VAA_MAGIC: [u8; 4] = *b"vaa\x01";
+
+/// A `PostedVAA` account, after its magic.
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct PostedVaa {
+    pub vaa_version: u8,
+    pub consistency_level: u8,
+    /// The VAA's timestamp.
+    pub vaa_time: u32,
+    pub vaa_signature_account: Pubkey,
+    pub submission_time: u32,
+    pub nonce: u32,
+    pub sequence: u64,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub payload: Vec<u8>,
+}
+
+impl PostedVaa {
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut rest = data
+            .strip_prefix(&POSTED_VAA_MAGIC)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Self::deserialize(&mut rest).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Packs the account the way the bridge stores it, magic first.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = POSTED_VAA_MAGIC.to_vec();
+        self.serialize(&mut data).expect("serializing into a Vec");
+        data
+    }
+
+    /// Keccak-256 of the VAA body, its fields big-endian, as the bridge
+    /// seeds the account with.
+    pub fn hash(&self) -> [u8; 32] {
+        keccak::hashv(&[
+            &self.vaa_time.to_be_bytes(),
+            &self.nonce.to_be_bytes(),
+            &self.emitter_chain.to_be_bytes(),
+            &self.emitter_address,
+            &self.sequence.to_be_bytes(),
+            &[self.consistency_level],
+            &self.payload,
+        ])
+        .to_bytes()
+    }
+}
+
+/// Address of the `PostedVAA` account for the VAA whose body hashes to
+/// `hash`.
+pub fn find_posted_vaa_address(hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POSTED_VAA_SEED, hash], &CORE_BRIDGE_ID)
+}
+
+/// Operation codes of a [`CounterMessage`].
+pub const OP_INCREMENT: u8 = 0;
+pub const OP_DECREMENT: u8 = 1;
+pub const OP_UPDATE: u8 = 2;
+pub const OP_RESET: u8 = 3;
+
+/// The payload `ReceiveWormholeMessage` applies: the counter's address, an
+/// operation code and its value. Packed without padding and big-endian, as
+/// `abi.encodePacked(bytes32, uint8, uint32)` produces on an EVM chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterMessage {
+    pub counter: Pubkey,
+    pub op: u8,
+    /// Ignored by [`OP_RESET`].
+    pub value: u32,
+}
+
+impl CounterMessage {
+    pub const LEN: usize = 32 + 1 + 4;
+
+    pub fn unpack(payload: &[u8]) -> Option<Self> {
+        let payload: &[u8; Self::LEN] = payload.try_into().ok()?;
+        let (counter, rest) = payload.split_at(32);
+        Some(Self {
+            counter: Pubkey::try_from(counter).ok()?,
+            op: rest[0],
+            value: u32::from_be_bytes(rest[1..].try_into().ok()?),
+        })
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        [self.counter.as_ref(), &[self.op], &self.value.to_be_bytes()].concat()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_posted_vaa() {
+        let message = CounterMessage {
+            counter: Pubkey::new_unique(),
+            op: OP_UPDATE,
+            value: 0x0102_0304,
+        };
+        let payload = message.pack();
+        assert_eq!(payload.len(), CounterMessage::LEN);
+        assert_eq!(payload[33..], [1, 2, 3, 4]);
+        assert_eq!(CounterMessage::unpack(&payload), Some(message));
+        assert_eq!(CounterMessage::unpack(&payload[1..]), None);
+
+        let vaa = PostedVaa {
+            vaa_version: 1,
+            consistency_level: 1,
+            vaa_time: 1_700_000_000,
+            vaa_signature_account: Pubkey::new_unique(),
+            submission_time: 1_700_000_010,
+            nonce: 7,
+            sequence: 42,
+            emitter_chain: 2,
+            emitter_address: [5; 32],
+            payload,
+        };
+        let data = vaa.pack();
+        assert_eq!(data[..4], *b"vaa\x01");
+        assert_eq!(PostedVaa::unpack(&data).unwrap(), vaa);
+        assert_eq!(
+            PostedVaa::unpack(&data[1..]),
+            Err(ProgramError::InvalidAccountData)
+        );
+        // The hash covers the body, not where or when it was posted.
+        let mut reposted = vaa.clone();
+        reposted.submission_time += 1;
+        assert_eq!(reposted.hash(), vaa.hash());
+        reposted.sequence += 1;
+        assert_ne!(reposted.hash(), vaa.hash());
+    }
+}
@@ -0,0 +1,178 @@
+//! Tests of the Anchor build, run in the program-test bank.
+
+#![cfg(feature = "anchor")]
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use counter::{
+    anchor::{self, Counter},
+    error::CounterError,
+    state::OVERFLOW_CHECKED,
+    CounterAccount,
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction, pubkey::Pubkey,
+    system_program,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+/// Anchor's entrypoint ties the accounts slice to the accounts' lifetime,
+/// which the program-test processor signature doesn't.
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+    anchor::entry(program_id, accounts, data)
+}
+
+fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(anchor::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn load(context: &mut ProgramTestContext, counter: &Pubkey) -> CounterAccount {
+    let account = context
+        .banks_client
+        .get_account(*counter)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, anchor::ID);
+    assert_eq!(account.data.len(), Counter::LEN);
+    *bytemuck::from_bytes(&account.data[8..])
+}
+
+#[tokio::test]
+async fn test_anchor_counter() {
+    let program_test = ProgramTest::new("counter", anchor::ID, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let (counter, authority) = (Keypair::new(), Keypair::new());
+    let payer = context.payer.pubkey();
+
+    let accounts = anchor::accounts::Initialize {
+        counter: counter.pubkey(),
+        authority: authority.pubkey(),
+        payer,
+        system_program: system_program::id(),
+    };
+    let initialize = instruction(accounts, anchor::instruction::Initialize {});
+    process(&mut context, initialize, &[&counter, &authority])
+        .await
+        .unwrap();
+    let state = load(&mut context, &counter.pubkey()).await;
+    assert_eq!(state.authority, authority.pubkey());
+
+    let mutate = || anchor::accounts::Mutate {
+        counter: counter.pubkey(),
+        signer: authority.pubkey(),
+    };
+    let increment = anchor::instruction::Increment {
+        value: 5,
+        mode: OVERFLOW_CHECKED,
+    };
+    process(
+        &mut context,
+        instruction(mutate(), increment),
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let decrement = anchor::instruction::Decrement {
+        value: 2,
+        mode: OVERFLOW_CHECKED,
+    };
+    process(
+        &mut context,
+        instruction(mutate(), decrement),
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(load(&mut context, &counter.pubkey()).await.counter, 3);
+
+    // A stranger can't pause the counter, and nobody can write it paused.
+    let stranger = Keypair::new();
+    let pause = |authority| {
+        let accounts = anchor::accounts::Authorized {
+            counter: counter.pubkey(),
+            authority,
+        };
+        instruction(accounts, anchor::instruction::Pause {})
+    };
+    assert_eq!(
+        process(&mut context, pause(stranger.pubkey()), &[&stranger])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::Unauthorized as u32)
+        )
+    );
+    process(&mut context, pause(authority.pubkey()), &[&authority])
+        .await
+        .unwrap();
+    let increment = anchor::instruction::Increment {
+        value: 1,
+        mode: OVERFLOW_CHECKED,
+    };
+    assert_eq!(
+        process(
+            &mut context,
+            instruction(mutate(), increment),
+            &[&authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::Paused as u32)
+        )
+    );
+
+    let destination = Pubkey::new_unique();
+    let accounts = anchor::accounts::Close {
+        counter: counter.pubkey(),
+        destination,
+        authority: authority.pubkey(),
+    };
+    process(
+        &mut context,
+        instruction(accounts, anchor::instruction::Close {}),
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(counter.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+    assert!(context.banks_client.get_balance(destination).await.unwrap() > 0);
+}
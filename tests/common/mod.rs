@@ -0,0 +1,135 @@
+//! LiteSVM harness shared by the suites that run the compiled program.
+//!
+//! Build the program first with `cargo build-sbf`; the harness looks for
+//! `counter.so` in `$SBF_OUT_DIR` (default `target/deploy`) and panics when
+//! the artifact is missing. Tests using it are `#[ignore]`d so that plain
+//! `cargo test` doesn't need the artifact.
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use base64::Engine;
+use borsh::BorshDeserialize;
+use counter::{events::CounterEvent, sdk, CounterAccount};
+use litesvm::{types::TransactionMetadata, LiteSVM};
+use solana_program::{instruction::Instruction, pubkey::Pubkey, system_instruction};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+fn program_path() -> PathBuf {
+    let out_dir = std::env::var("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/deploy"));
+    let path = out_dir.join("counter.so");
+    assert!(
+        path.exists(),
+        "{} not found, run `cargo build-sbf` first",
+        path.display()
+    );
+    path
+}
+
+pub struct TestContext {
+    pub svm: LiteSVM,
+    pub payer: Keypair,
+    pub program_id: Pubkey,
+}
+
+impl TestContext {
+    pub fn new() -> Self {
+        let path = program_path();
+        let program_id = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(program_id, path).unwrap();
+        svm.airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+        Self {
+            svm,
+            payer,
+            program_id,
+        }
+    }
+
+    /// Returns the compute units consumed by the transaction.
+    pub fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<u64, TransactionError> {
+        self.send(instructions, signers)
+            .map(|meta| meta.compute_units_consumed)
+    }
+
+    /// Returns the events the transaction emitted, in order.
+    pub fn process_events(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Vec<CounterEvent>, TransactionError> {
+        let meta = self.send(instructions, signers)?;
+        Ok(meta
+            .logs
+            .iter()
+            .filter_map(|log| log.strip_prefix("Program data: "))
+            .map(|data| {
+                let data = base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .unwrap();
+                CounterEvent::try_from_slice(&data).unwrap()
+            })
+            .collect())
+    }
+
+    fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<TransactionMetadata, TransactionError> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self
+            .svm
+            .send_transaction(transaction)
+            .map_err(|meta| meta.err);
+        self.svm.expire_blockhash();
+        result
+    }
+
+    pub fn create_counter(&mut self, authority: &Keypair) -> Pubkey {
+        let counter = Keypair::new();
+        let lamports = self
+            .svm
+            .minimum_balance_for_rent_exemption(CounterAccount::LEN);
+        let instructions = [
+            system_instruction::create_account(
+                &self.payer.pubkey(),
+                &counter.pubkey(),
+                lamports,
+                CounterAccount::LEN as u64,
+                &self.program_id,
+            ),
+            sdk::initialize(
+                &self.program_id,
+                &counter.pubkey(),
+                &authority.pubkey(),
+                &self.payer.pubkey(),
+            ),
+        ];
+        self.process(&instructions, &[&counter, authority]).unwrap();
+        counter.pubkey()
+    }
+
+    pub fn counter(&self, counter: &Pubkey) -> CounterAccount {
+        let account = self.svm.get_account(counter).unwrap();
+        CounterAccount::unpack(&account.data).unwrap()
+    }
+}
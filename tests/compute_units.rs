@@ -0,0 +1,317 @@
+//! Compute-unit budgets for each instruction, measured against the compiled
+//! program in LiteSVM.
+//!
+//! Every instruction has a ceiling in [`BUDGETS`]; the suite fails if one goes
+//! over so regressions show up in review rather than on mainnet, naming each
+//! instruction over budget with the units it consumed. The budgets are
+//! ceilings with some headroom, not exact costs; when a change legitimately
+//! costs more, raise the budget in the same commit.
+//!
+//! Like the LiteSVM suite it needs `counter.so`, so it is ignored by default:
+//! run `cargo build-sbf && cargo test --test compute_units -- --ignored`.
+
+mod common;
+
+use common::TestContext;
+use counter::{sdk, CounterAccount};
+use solana_program::{instruction::Instruction, pubkey::Pubkey, system_instruction};
+use solana_sdk::signature::{Keypair, Signer};
+
+const BUDGETS: &[(&str, u64)] = &[
+    ("initialize", 20_000),
+    ("initialize_named", 30_000),
+    ("increment", 6_000),
+    ("decrement", 6_000),
+    ("increment_one", 6_000),
+    ("decrement_one", 6_000),
+    ("update", 6_000),
+    ("reset", 6_000),
+    ("pause", 6_000),
+    ("unpause", 6_000),
+    ("set_epoch_scoped", 6_000),
+    ("set_decay", 6_000),
+    ("set_rate_limit", 6_000),
+    ("propose_authority", 6_000),
+    ("accept_authority", 6_000),
+    ("configure_multisig", 8_000),
+    ("update_multisig", 8_000),
+    ("approve", 6_000),
+    ("delegated_increment", 6_000),
+    ("revoke", 6_000),
+    ("create_quota", 15_000),
+    ("set_daily_cap", 6_000),
+    ("capped_increment", 6_000),
+    ("set_fee", 15_000),
+    ("fee_increment", 15_000),
+    ("withdraw_treasury", 15_000),
+    ("get", 6_000),
+    ("close", 6_000),
+];
+
+fn budget(name: &str) -> u64 {
+    BUDGETS
+        .iter()
+        .find(|(budget_name, _)| *budget_name == name)
+        .map(|(_, units)| *units)
+        .unwrap_or_else(|| panic!("no compute budget for {name}"))
+}
+
+struct Bench {
+    context: TestContext,
+    over_budget: Vec<String>,
+}
+
+impl Bench {
+    fn measure(&mut self, name: &str, instruction: Instruction, signers: &[&Keypair]) {
+        let units = self.context.process(&[instruction], signers).unwrap();
+        let budget = budget(name);
+        if units > budget {
+            self.over_budget
+                .push(format!("{name} consumed {units} CUs, budget is {budget}"));
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs the compiled program: cargo build-sbf, then pass --ignored"]
+fn test_compute_unit_budgets() {
+    let mut bench = Bench {
+        context: TestContext::new(),
+        over_budget: Vec::new(),
+    };
+    let program_id = bench.context.program_id;
+    let authority = Keypair::new();
+    let new_authority = Keypair::new();
+    let delegate = Keypair::new();
+    let members = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let member_keys: Vec<Pubkey> = members.iter().map(|member| member.pubkey()).collect();
+
+    // Create the account separately so the system program's share of
+    // `create_counter` doesn't count against Initialize.
+    let counter = Keypair::new();
+    let lamports = bench
+        .context
+        .svm
+        .minimum_balance_for_rent_exemption(CounterAccount::LEN);
+    bench
+        .context
+        .process(
+            &[system_instruction::create_account(
+                &bench.context.payer.pubkey(),
+                &counter.pubkey(),
+                lamports,
+                CounterAccount::LEN as u64,
+                &program_id,
+            )],
+            &[&counter],
+        )
+        .unwrap();
+    let counter = counter.pubkey();
+    bench.measure(
+        "initialize",
+        sdk::initialize(
+            &program_id,
+            &counter,
+            &authority.pubkey(),
+            &bench.context.payer.pubkey(),
+        ),
+        &[&authority],
+    );
+
+    bench.measure(
+        "initialize_named",
+        sdk::initialize_named(
+            &program_id,
+            &authority.pubkey(),
+            &bench.context.payer.pubkey(),
+            "page_views",
+        ),
+        &[&authority],
+    );
+
+    bench.measure(
+        "increment",
+        sdk::increment(&program_id, &counter, &authority.pubkey(), 10),
+        &[&authority],
+    );
+    bench.measure(
+        "decrement",
+        sdk::decrement(&program_id, &counter, &authority.pubkey(), 3),
+        &[&authority],
+    );
+    bench.measure(
+        "increment_one",
+        sdk::increment_one(&program_id, &counter, &authority.pubkey()),
+        &[&authority],
+    );
+    bench.measure(
+        "decrement_one",
+        sdk::decrement_one(&program_id, &counter, &authority.pubkey()),
+        &[&authority],
+    );
+    bench.measure(
+        "update",
+        sdk::update(&program_id, &counter, &[&authority.pubkey()], 42),
+        &[&authority],
+    );
+    bench.measure(
+        "reset",
+        sdk::reset(&program_id, &counter, &[&authority.pubkey()]),
+        &[&authority],
+    );
+    bench.measure(
+        "pause",
+        sdk::pause(&program_id, &counter, &authority.pubkey()),
+        &[&authority],
+    );
+    bench.measure(
+        "unpause",
+        sdk::unpause(&program_id, &counter, &authority.pubkey()),
+        &[&authority],
+    );
+    bench.measure(
+        "set_epoch_scoped",
+        sdk::set_epoch_scoped(&program_id, &counter, &authority.pubkey(), true),
+        &[&authority],
+    );
+    bench.measure(
+        "set_decay",
+        sdk::set_decay(&program_id, &counter, &authority.pubkey(), 1),
+        &[&authority],
+    );
+    bench.measure(
+        "set_rate_limit",
+        sdk::set_rate_limit(&program_id, &counter, &authority.pubkey(), 1_000),
+        &[&authority],
+    );
+    bench.measure(
+        "approve",
+        sdk::approve(
+            &program_id,
+            &counter,
+            &authority.pubkey(),
+            &delegate.pubkey(),
+            5,
+        ),
+        &[&authority],
+    );
+    bench.measure(
+        "delegated_increment",
+        sdk::increment(&program_id, &counter, &delegate.pubkey(), 1),
+        &[&delegate],
+    );
+    bench.measure(
+        "revoke",
+        sdk::revoke(&program_id, &counter, &authority.pubkey()),
+        &[&authority],
+    );
+    bench.measure(
+        "propose_authority",
+        sdk::propose_authority(
+            &program_id,
+            &counter,
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+        ),
+        &[&authority],
+    );
+    bench.measure(
+        "accept_authority",
+        sdk::accept_authority(&program_id, &counter, &new_authority.pubkey()),
+        &[&new_authority],
+    );
+    bench.measure(
+        "configure_multisig",
+        sdk::configure_multisig(
+            &program_id,
+            &counter,
+            &new_authority.pubkey(),
+            2,
+            &member_keys,
+        ),
+        &[&new_authority],
+    );
+    bench.measure(
+        "update_multisig",
+        sdk::update(
+            &program_id,
+            &counter,
+            &[&member_keys[0], &member_keys[1]],
+            7,
+        ),
+        &[&members[0], &members[1]],
+    );
+    bench.measure(
+        "create_quota",
+        sdk::create_quota(
+            &program_id,
+            &counter,
+            &new_authority.pubkey(),
+            &bench.context.payer.pubkey(),
+        ),
+        &[&new_authority],
+    );
+    bench.measure(
+        "set_daily_cap",
+        sdk::set_daily_cap(&program_id, &counter, &new_authority.pubkey(), 1_000),
+        &[&new_authority],
+    );
+    bench.measure(
+        "capped_increment",
+        sdk::increment_capped(&program_id, &counter, &new_authority.pubkey(), 1),
+        &[&new_authority],
+    );
+    let payer = bench.context.payer.pubkey();
+    bench.measure(
+        "set_fee",
+        sdk::set_fee(
+            &program_id,
+            &counter,
+            &new_authority.pubkey(),
+            &payer,
+            1_000_000,
+        ),
+        &[&new_authority],
+    );
+    bench.measure(
+        "fee_increment",
+        sdk::with_fee(
+            sdk::increment_capped(&program_id, &counter, &new_authority.pubkey(), 1),
+            &counter,
+            &payer,
+        ),
+        &[&new_authority],
+    );
+    bench.measure(
+        "withdraw_treasury",
+        sdk::withdraw_treasury(&program_id, &counter, &new_authority.pubkey(), &payer),
+        &[&new_authority],
+    );
+    bench.measure("get", sdk::get(&program_id, &counter), &[]);
+    bench.measure(
+        "close",
+        sdk::close(
+            &program_id,
+            &counter,
+            &Pubkey::new_unique(),
+            &[&member_keys[0], &member_keys[1]],
+        ),
+        &[&members[0], &members[1]],
+    );
+
+    assert!(
+        bench.over_budget.is_empty(),
+        "compute budget regressions:\n{}",
+        bench.over_budget.join("\n")
+    );
+}
+
+#[test]
+fn test_budget_names_are_unique() {
+    for (i, (name, _)) in BUDGETS.iter().enumerate() {
+        assert!(
+            BUDGETS[i + 1..].iter().all(|(other, _)| other != name),
+            "duplicate budget for {name}"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Fast tests that run the compiled program in LiteSVM.
+//!
+//! They are ignored by default, as they need `counter.so` from
+//! `cargo build-sbf`; run them with `cargo test --test litesvm -- --ignored`.
+
+mod common;
+
+use common::TestContext;
+use counter::{error::CounterError, events::CounterEvent, sdk, CounterAccount};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+fn custom_error(error: CounterError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+}
+
+#[test]
+#[ignore = "needs the compiled program: cargo build-sbf, then pass --ignored"]
+fn test_increment_decrement() {
+    let mut context = TestContext::new();
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority);
+    let program_id = context.program_id;
+
+    let compute_units = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                48,
+            )],
+            &[&authority],
+        )
+        .unwrap();
+    assert!(compute_units > 0);
+    assert_eq!(context.counter(&counter).counter, 48);
+
+    let compute_units = context
+        .process(
+            &[sdk::decrement(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                100,
+            )],
+            &[&authority],
+        )
+        .unwrap();
+    assert!(compute_units > 0);
+    assert_eq!(context.counter(&counter).counter, 0);
+}
+
+#[test]
+#[ignore = "needs the compiled program: cargo build-sbf, then pass --ignored"]
+fn test_update_reset() {
+    let mut context = TestContext::new();
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority);
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::update(
+                &program_id,
+                &counter,
+                &[&authority.pubkey()],
+                33,
+            )],
+            &[&authority],
+        )
+        .unwrap();
+    assert_eq!(context.counter(&counter).counter, 33);
+
+    context
+        .process(
+            &[sdk::reset(&program_id, &counter, &[&authority.pubkey()])],
+            &[&authority],
+        )
+        .unwrap();
+    assert_eq!(context.counter(&counter).counter, 0);
+}
+
+#[test]
+#[ignore = "needs the compiled program: cargo build-sbf, then pass --ignored"]
+fn test_paused_counter_rejects_increment() {
+    let mut context = TestContext::new();
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority);
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::pause(&program_id, &counter, &authority.pubkey())],
+            &[&authority],
+        )
+        .unwrap();
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Paused));
+}
+
+#[test]
+#[ignore = "needs the compiled program: cargo build-sbf, then pass --ignored"]
+fn test_close() {
+    let mut context = TestContext::new();
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let counter = context.create_counter(&authority);
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::close(
+                &program_id,
+                &counter,
+                &destination,
+                &[&authority.pubkey()],
+            )],
+            &[&authority],
+        )
+        .unwrap();
+    assert_eq!(
+        context.svm.get_balance(&destination),
+        Some(
+            context
+                .svm
+                .minimum_balance_for_rent_exemption(CounterAccount::LEN)
+        )
+    );
+}
+
+#[test]
+#[ignore = "needs the compiled program: cargo build-sbf, then pass --ignored"]
+fn test_events() {
+    let mut context = TestContext::new();
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority);
+    let program_id = context.program_id;
+
+    let events = context
+        .process_events(
+            &[
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 5),
+                sdk::decrement(&program_id, &counter, &authority.pubkey(), 7),
+                sdk::update(&program_id, &counter, &[&authority.pubkey()], 9),
+            ],
+            &[&authority],
+        )
+        .unwrap();
+    assert_eq!(
+        events,
+        [
+            CounterEvent::Incremented {
+                old: 0,
+                new: 5,
+                by: 5
+            },
+            CounterEvent::Decremented {
+                old: 5,
+                new: 0,
+                by: 7
+            },
+            CounterEvent::Updated { old: 0, new: 9 },
+        ]
+    );
+}
+
+#[test]
+#[ignore = "needs the compiled program: cargo build-sbf, then pass --ignored"]
+fn test_countdown_events() {
+    let mut context = TestContext::new();
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority);
+    let program_id = context.program_id;
+
+    let events = context
+        .process_events(
+            &[
+                sdk::set_countdown(&program_id, &counter, &authority.pubkey(), 2),
+                sdk::decrement(&program_id, &counter, &authority.pubkey(), 1),
+                sdk::decrement(&program_id, &counter, &authority.pubkey(), 5),
+            ],
+            &[&authority],
+        )
+        .unwrap();
+    assert_eq!(
+        events,
+        [
+            CounterEvent::CountdownSet { old: 0, target: 2 },
+            CounterEvent::Decremented {
+                old: 2,
+                new: 1,
+                by: 1
+            },
+            CounterEvent::Decremented {
+                old: 1,
+                new: 0,
+                by: 5
+            },
+            CounterEvent::CountdownCompleted,
+        ]
+    );
+}
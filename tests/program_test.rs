@@ -0,0 +1,5791 @@
+use bytemuck::Zeroable;
+use counter::{
+    compression,
+    cpi::{self, CpiContext},
+    error::CounterError,
+    governance,
+    instructions::{CounterInstructions, InitializeNamedArgs},
+    memo,
+    metadata::{self, find_metadata_address, Collection, Metadata, METADATA_V1_KEY},
+    process_instruction,
+    pyth::{self, PriceFeedMessage, PriceUpdateV2, VerificationLevel},
+    sdk,
+    state::{
+        eth_message, find_boost_address, find_checkpoint_address, find_cooldown_address,
+        find_counter_address, find_credits_address, find_history_address, find_leaderboard_address,
+        find_lease_address, find_quota_address, find_referrer_address, find_registry_address,
+        find_reward_authority_address, find_treasury_address, find_vault_address, offchain_message,
+        BoostEscrow, CallerCooldown, CallerQuota, Checkpoint, CounterArrayHeader, CounterStats,
+        CreditBalance, HistoryHeader, KeyedCounterHeader, KeyedEntry, LeaderboardHeader, Lease,
+        ReferrerEntry, RegistryEntry, RegistryHeader, ABANDONED_AFTER_EPOCHS, COOLDOWN_SECONDS,
+        COUNTDOWN_COMPLETED, COUNTDOWN_OFF, COUNTDOWN_RUNNING, COUNTER_DISCRIMINATOR,
+        COUNTER_LAYOUT_VERSION, KIND_I64, KIND_U128, OVERFLOW_CHECKED, OVERFLOW_SATURATING,
+        OVERFLOW_WRAPPING, QUOTA_WINDOW_SECONDS, REAPER_SHARE_BPS, ROUND_DOWN, ROUND_NEAREST,
+    },
+    switchboard::{self, RandomnessAccount},
+    wormhole::{self, CounterMessage, PostedVaa},
+    CounterAccount,
+};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    native_token::LAMPORTS_PER_SOL,
+    program::{invoke_signed, set_return_data},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    stake::{
+        self,
+        stake_flags::StakeFlags,
+        state::{Authorized, Delegation, Meta, Stake, StakeStateV2},
+    },
+    system_instruction, system_program,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+};
+use spl_token_2022::{
+    extension::{
+        transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType,
+        StateWithExtensions,
+    },
+    instruction as token_instruction,
+    state::{Account as TokenAccount, Mint},
+};
+
+/// Stands in for Token Metadata, which program tests don't ship: accepts the
+/// two instructions `ClaimMilestoneNft` sends once the authorities signed.
+fn token_metadata_stub(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Both instructions put the mint authority, update authority and payer
+    // at indices 2 to 4, in some order.
+    match data.first() {
+        Some(&metadata::CREATE_METADATA_ACCOUNT_V3 | &metadata::CREATE_MASTER_EDITION_V3) => {}
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+    if !accounts[2..5].iter().all(|account| account.is_signer) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Stands in for SPL Governance executing a passed proposal: invokes the
+/// counter program at index 2 on the counter at index 3, signed by the
+/// native treasury at index 1 of the governance at index 0.
+fn governance_stub(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let [governance, native_treasury, counter_program, counter] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let (_, bump) = governance::find_native_treasury_address(program_id, governance.key);
+    invoke_signed(
+        &Instruction {
+            program_id: *counter_program.key,
+            accounts: vec![
+                AccountMeta::new(*counter.key, false),
+                AccountMeta::new_readonly(*native_treasury.key, true),
+            ],
+            data: data.to_vec(),
+        },
+        &[counter.clone(), native_treasury.clone()],
+        &[&[
+            governance::NATIVE_TREASURY_SEED,
+            governance.key.as_ref(),
+            &[bump],
+        ]],
+    )
+}
+
+/// Stands in for a program driving the counter through `counter::cpi`:
+/// increments the counter at index 1 by `data[0]` as its delegate, the PDA
+/// at index 2, then returns the value it reads back.
+fn cpi_caller_stub(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let [counter_program, counter, delegate] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let (_, bump) = Pubkey::find_program_address(&[CPI_DELEGATE_SEED], program_id);
+    let accounts = cpi::Mutate {
+        counter: counter.clone(),
+        signer: delegate.clone(),
+    };
+    cpi::increment(
+        CpiContext::new_with_signer(
+            counter_program.clone(),
+            accounts,
+            &[&[CPI_DELEGATE_SEED, &[bump]]],
+        ),
+        data[0].into(),
+    )?;
+    let accounts = cpi::Read {
+        counter: counter.clone(),
+    };
+    let value = cpi::get(CpiContext::new(counter_program.clone(), accounts))?;
+    set_return_data(&value.to_le_bytes());
+    Ok(())
+}
+
+const CPI_CALLER_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+const CPI_DELEGATE_SEED: &[u8] = b"delegate";
+
+/// Stands in for SPL Account Compression, which program tests don't ship,
+/// with a flat list of leaves instead of a Merkle tree: a `u32` count, then
+/// the leaves from byte 8. A replacement must name the leaf it replaces, but
+/// its root and proof are taken on trust.
+fn compression_stub(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let [tree, authority, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut tree = tree.try_borrow_mut_data()?;
+    let (discriminator, data) = data.split_at(8);
+    let count = u32::from_le_bytes(tree[..4].try_into().unwrap()) as usize;
+    let leaf = |index: usize| 8 + 32 * index..8 + 32 * (index + 1);
+    match discriminator.try_into().unwrap() {
+        compression::INIT_EMPTY_MERKLE_TREE => tree.fill(0),
+        compression::APPEND => {
+            tree[leaf(count)].copy_from_slice(data);
+            tree[..4].copy_from_slice(&(count as u32 + 1).to_le_bytes());
+        }
+        compression::REPLACE_LEAF => {
+            let index = u32::from_le_bytes(data[96..].try_into().unwrap()) as usize;
+            if index >= count || tree[leaf(index)] != data[32..64] {
+                return Err(ProgramError::InvalidArgument);
+            }
+            tree[leaf(index)].copy_from_slice(&data[64..96]);
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+    Ok(())
+}
+
+struct TestContext {
+    program_context: ProgramTestContext,
+    program_id: Pubkey,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let mut program_test =
+            ProgramTest::new("counter", program_id, processor!(process_instruction));
+        program_test.add_program(
+            "token_metadata_stub",
+            metadata::ID,
+            processor!(token_metadata_stub),
+        );
+        program_test.add_program(
+            "governance_stub",
+            governance::PROGRAM_ID,
+            processor!(governance_stub),
+        );
+        program_test.add_program(
+            "compression_stub",
+            compression::PROGRAM_ID,
+            processor!(compression_stub),
+        );
+        program_test.add_program(
+            "cpi_caller_stub",
+            CPI_CALLER_ID,
+            processor!(cpi_caller_stub),
+        );
+        Self {
+            program_context: program_test.start_with_context().await,
+            program_id,
+        }
+    }
+
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        // A fresh blockhash keeps repeated identical instructions from being
+        // rejected as already processed.
+        let blockhash = self
+            .program_context
+            .get_new_latest_blockhash()
+            .await
+            .unwrap();
+        let payer = &self.program_context.payer;
+        let mut all_signers = vec![payer];
+        all_signers.extend(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.program_context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|err| err.unwrap())
+    }
+
+    async fn create_counter(&mut self, authority: &Keypair) -> Pubkey {
+        let counter = Keypair::new();
+        let lamports = Rent::default().minimum_balance(CounterAccount::LEN);
+        let instructions = [
+            system_instruction::create_account(
+                &self.program_context.payer.pubkey(),
+                &counter.pubkey(),
+                lamports,
+                CounterAccount::LEN as u64,
+                &self.program_id,
+            ),
+            sdk::initialize(
+                &self.program_id,
+                &counter.pubkey(),
+                &authority.pubkey(),
+                &self.program_context.payer.pubkey(),
+            ),
+        ];
+        self.process(&instructions, &[&counter, authority])
+            .await
+            .unwrap();
+        counter.pubkey()
+    }
+
+    /// An account holding `counter_account`, but owned by some other program.
+    fn forge_counter(&mut self, counter_account: CounterAccount) -> Pubkey {
+        let counter = Pubkey::new_unique();
+        let data = bytemuck::bytes_of(&counter_account).to_vec();
+        self.program_context.set_account(
+            &counter,
+            &Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 0,
+            }
+            .into(),
+        );
+        counter
+    }
+
+    async fn data(&mut self, address: &Pubkey) -> Vec<u8> {
+        self.program_context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap()
+            .data
+    }
+
+    async fn counter(&mut self, counter: &Pubkey) -> CounterAccount {
+        let account = self
+            .program_context
+            .banks_client
+            .get_account(*counter)
+            .await
+            .unwrap()
+            .unwrap();
+        CounterAccount::unpack(&account.data).unwrap()
+    }
+
+    async fn token_balance(&mut self, token_account: &Pubkey) -> u64 {
+        let account = self
+            .program_context
+            .banks_client
+            .get_account(*token_account)
+            .await
+            .unwrap()
+            .unwrap();
+        StateWithExtensions::<TokenAccount>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+}
+
+fn custom_error(error: CounterError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+}
+
+#[tokio::test]
+async fn test_initialize() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+
+    let counter = context.create_counter(&authority).await;
+
+    let counter_account = context.counter(&counter).await;
+    assert_eq!(counter_account.counter, 0);
+    assert_eq!(counter_account.authority, authority.pubkey());
+    assert_eq!(counter_account.discriminator, COUNTER_DISCRIMINATOR);
+
+    let err = context
+        .process(
+            &[sdk::initialize(
+                &context.program_id,
+                &counter,
+                &authority.pubkey(),
+                &context.program_context.payer.pubkey(),
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn test_increment_decrement() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                48,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 48);
+
+    context
+        .process(
+            &[sdk::decrement(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                16,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 32);
+
+    context
+        .process(
+            &[sdk::decrement(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                100,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 0);
+
+    let stranger = Keypair::new();
+    let err = context
+        .process(
+            &[sdk::increment(&program_id, &counter, &stranger.pubkey(), 1)],
+            &[&stranger],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+}
+
+#[tokio::test]
+async fn test_increment_one_decrement_one() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+
+    let increment_one = sdk::increment_one(&program_id, &counter, &signer);
+    assert_eq!(increment_one.data.len(), 1);
+    // Floors at zero like `sdk::decrement`, then steps up and back down.
+    for (instruction, expected) in [
+        (sdk::decrement_one(&program_id, &counter, &signer), 0),
+        (increment_one.clone(), 1),
+        (increment_one, 2),
+        (sdk::decrement_one(&program_id, &counter, &signer), 1),
+    ] {
+        context
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap();
+        assert_eq!(context.counter(&counter).await.counter, expected);
+    }
+
+    let stranger = Keypair::new();
+    let err = context
+        .process(
+            &[sdk::increment_one(
+                &program_id,
+                &counter,
+                &stranger.pubkey(),
+            )],
+            &[&stranger],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+}
+
+#[tokio::test]
+async fn test_unknown_instruction() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &[0x7f],
+        vec![AccountMeta::new(counter, false)],
+    );
+    let err = context.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(err, custom_error(CounterError::UnknownInstruction));
+}
+
+#[tokio::test]
+async fn test_read_only_counter() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let mut increment = sdk::increment(&context.program_id, &counter, &authority.pubkey(), 1);
+    increment.accounts[0].is_writable = false;
+    let err = context
+        .process(&[increment], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::CounterNotWritable));
+    // Reads take it read-only.
+    context
+        .process(&[sdk::get(&context.program_id, &counter)], &[])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_overflow_modes() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+
+    let err = context
+        .process(
+            &[sdk::decrement_with_mode(
+                &program_id,
+                &counter,
+                &signer,
+                1,
+                OVERFLOW_CHECKED,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Underflow));
+
+    context
+        .process(
+            &[sdk::decrement_with_mode(
+                &program_id,
+                &counter,
+                &signer,
+                1,
+                OVERFLOW_WRAPPING,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, u32::MAX);
+
+    context
+        .process(
+            &[sdk::increment_with_mode(
+                &program_id,
+                &counter,
+                &signer,
+                5,
+                OVERFLOW_SATURATING,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, u32::MAX);
+
+    context
+        .process(
+            &[sdk::increment_with_mode(
+                &program_id,
+                &counter,
+                &signer,
+                5,
+                OVERFLOW_WRAPPING,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 4);
+
+    let err = context
+        .process(
+            &[sdk::increment_with_mode(
+                &program_id,
+                &counter,
+                &signer,
+                1,
+                3,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn test_increment_if_below() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+
+    // 0 and 3 are below the threshold of 5; 6 isn't.
+    for _ in 0..2 {
+        context
+            .process(
+                &[sdk::increment_if_below(
+                    &program_id,
+                    &counter,
+                    &signer,
+                    3,
+                    5,
+                    false,
+                )],
+                &[&authority],
+            )
+            .await
+            .unwrap();
+    }
+    assert_eq!(context.counter(&counter).await.counter, 6);
+    let err = context
+        .process(
+            &[sdk::increment_if_below(
+                &program_id,
+                &counter,
+                &signer,
+                3,
+                5,
+                false,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::ThresholdReached));
+
+    // With `skip` it succeeds and leaves the counter alone, but only for a
+    // signer who could have incremented it.
+    context
+        .process(
+            &[sdk::increment_if_below(
+                &program_id,
+                &counter,
+                &signer,
+                3,
+                5,
+                true,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 6);
+    let stranger = Keypair::new();
+    let err = context
+        .process(
+            &[sdk::increment_if_below(
+                &program_id,
+                &counter,
+                &stranger.pubkey(),
+                3,
+                5,
+                true,
+            )],
+            &[&stranger],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+}
+
+#[tokio::test]
+async fn test_update_reset() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    // Nothing else restricts this counter, but somebody still has to sign.
+    for instruction in [
+        sdk::update(&program_id, &counter, &[], 33),
+        sdk::reset(&program_id, &counter, &[]),
+    ] {
+        let err = context.process(&[instruction], &[]).await.unwrap_err();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        );
+    }
+
+    let signer = Keypair::new();
+    context
+        .process(
+            &[sdk::update(&program_id, &counter, &[&signer.pubkey()], 33)],
+            &[&signer],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 33);
+
+    context
+        .process(
+            &[sdk::reset(&program_id, &counter, &[&signer.pubkey()])],
+            &[&signer],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 0);
+}
+
+#[tokio::test]
+async fn test_bitwise() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signers = [&authority.pubkey()];
+
+    context
+        .process(
+            &[sdk::update(&program_id, &counter, &signers, 0b1100)],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    for (instruction, expected) in [
+        (sdk::and(&program_id, &counter, &signers, 0b1010), 0b1000),
+        (sdk::or(&program_id, &counter, &signers, 0b0011), 0b1011),
+        (sdk::xor(&program_id, &counter, &signers, 0b1111), 0b0100),
+        (
+            sdk::shift_left(&program_id, &counter, &signers, 4),
+            0b0100_0000,
+        ),
+        (
+            sdk::shift_right(&program_id, &counter, &signers, 2),
+            0b0001_0000,
+        ),
+        (sdk::shift_left(&program_id, &counter, &signers, 31), 0),
+    ] {
+        context
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap();
+        assert_eq!(context.counter(&counter).await.counter, expected);
+    }
+
+    for instruction in [
+        sdk::shift_left(&program_id, &counter, &signers, 32),
+        sdk::shift_right(&program_id, &counter, &signers, u8::MAX),
+    ] {
+        let err = context
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap_err();
+        assert_eq!(err, custom_error(CounterError::InvalidShift));
+    }
+    let err = context
+        .process(&[sdk::or(&program_id, &counter, &[], 1)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn test_pause_unpause() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::pause(&program_id, &counter, &authority.pubkey())],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Paused));
+
+    context
+        .process(
+            &[sdk::unpause(&program_id, &counter, &authority.pubkey())],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 1);
+}
+
+#[tokio::test]
+async fn test_authority_transfer() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let new_authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::propose_authority(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &new_authority.pubkey(),
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        context.counter(&counter).await.pending_authority,
+        new_authority.pubkey()
+    );
+
+    context
+        .process(
+            &[sdk::accept_authority(
+                &program_id,
+                &counter,
+                &new_authority.pubkey(),
+            )],
+            &[&new_authority],
+        )
+        .await
+        .unwrap();
+    let counter_account = context.counter(&counter).await;
+    assert_eq!(counter_account.authority, new_authority.pubkey());
+    assert_eq!(counter_account.pending_authority, Pubkey::default());
+}
+
+#[tokio::test]
+async fn test_multisig() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let members = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let member_keys: Vec<Pubkey> = members.iter().map(|member| member.pubkey()).collect();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::configure_multisig(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                2,
+                &member_keys,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let err = context
+        .process(
+            &[sdk::update(&program_id, &counter, &[&member_keys[0]], 7)],
+            &[&members[0]],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::NotEnoughSigners));
+
+    context
+        .process(
+            &[sdk::update(
+                &program_id,
+                &counter,
+                &[&member_keys[0], &member_keys[1]],
+                7,
+            )],
+            &[&members[0], &members[1]],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 7);
+
+    // The authority alone can't lower the threshold it is bound by.
+    let err = context
+        .process(
+            &[sdk::configure_multisig(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                0,
+                &[],
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::NotEnoughSigners));
+
+    // A key listed twice would only count once.
+    let err = context
+        .process(
+            &[sdk::reconfigure_multisig(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &[&member_keys[0], &member_keys[1]],
+                2,
+                &[member_keys[0], member_keys[0]],
+            )],
+            &[&authority, &members[0], &members[1]],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::InvalidMultisigConfig));
+
+    context
+        .process(
+            &[sdk::reconfigure_multisig(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &[&member_keys[0], &member_keys[2]],
+                0,
+                &[],
+            )],
+            &[&authority, &members[0], &members[2]],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.multisig_threshold, 0);
+}
+
+#[tokio::test]
+async fn test_approve_revoke() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let delegate = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::approve(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &delegate.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    context
+        .process(
+            &[sdk::increment(&program_id, &counter, &delegate.pubkey(), 5)],
+            &[&delegate],
+        )
+        .await
+        .unwrap();
+    let err = context
+        .process(
+            &[sdk::increment(&program_id, &counter, &delegate.pubkey(), 6)],
+            &[&delegate],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::AllowanceExceeded));
+
+    context
+        .process(
+            &[sdk::revoke(&program_id, &counter, &authority.pubkey())],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.delegate, Pubkey::default());
+}
+
+#[tokio::test]
+async fn test_close() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[sdk::close(
+                &program_id,
+                &counter,
+                &destination,
+                &[&authority.pubkey()],
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    assert!(context
+        .program_context
+        .banks_client
+        .get_account(counter)
+        .await
+        .unwrap()
+        .is_none());
+    let destination_account = context
+        .program_context
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        destination_account.lamports,
+        Rent::default().minimum_balance(CounterAccount::LEN)
+    );
+}
+
+#[tokio::test]
+async fn test_get() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                42,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let payer = &context.program_context.payer;
+    let transaction = Transaction::new_signed_with_payer(
+        &[sdk::get(&program_id, &counter)],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.program_context.last_blockhash,
+    );
+    let simulation = context
+        .program_context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    assert_eq!(return_data.program_id, program_id);
+    assert_eq!(return_data.data, 42u32.to_le_bytes());
+}
+
+#[tokio::test]
+async fn test_log() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                42,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let payer = &context.program_context.payer;
+    let transaction = Transaction::new_signed_with_payer(
+        &[sdk::log(&program_id, &counter)],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.program_context.last_blockhash,
+    );
+    let simulation = context
+        .program_context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(simulation.result, Some(Ok(())));
+    let logs = simulation.simulation_details.unwrap().logs;
+    assert!(logs.contains(&"Program log: value: 42".to_string()));
+    assert!(logs.contains(&format!("Program log: authority: {}", authority.pubkey())));
+    assert!(logs
+        .iter()
+        .any(|log| log.starts_with("Program log: stats: ")));
+    assert!(logs
+        .iter()
+        .any(|log| log.starts_with("Program log: state: ")));
+}
+
+#[tokio::test]
+async fn test_cpi_helpers() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let (delegate, _) = Pubkey::find_program_address(&[CPI_DELEGATE_SEED], &CPI_CALLER_ID);
+    context
+        .process(
+            &[
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 2),
+                sdk::approve(&program_id, &counter, &authority.pubkey(), &delegate, 10),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let payer = &context.program_context.payer;
+    let instruction = Instruction {
+        program_id: CPI_CALLER_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(program_id, false),
+            AccountMeta::new(counter, false),
+            AccountMeta::new_readonly(delegate, false),
+        ],
+        data: vec![3],
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.program_context.last_blockhash,
+    );
+    let simulation = context
+        .program_context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(simulation.result.unwrap(), Ok(()));
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    assert_eq!(return_data.program_id, CPI_CALLER_ID);
+    assert_eq!(return_data.data, 5u32.to_le_bytes());
+}
+
+#[tokio::test]
+async fn test_initialize_requires_rent_exemption() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = Keypair::new();
+    let lamports = Rent::default().minimum_balance(CounterAccount::LEN) - 1;
+    let instructions = [
+        system_instruction::create_account(
+            &context.program_context.payer.pubkey(),
+            &counter.pubkey(),
+            lamports,
+            CounterAccount::LEN as u64,
+            &context.program_id,
+        ),
+        sdk::initialize(
+            &context.program_id,
+            &counter.pubkey(),
+            &authority.pubkey(),
+            &context.program_context.payer.pubkey(),
+        ),
+    ];
+
+    let err = context
+        .process(&instructions, &[&counter, &authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(CounterError::NotRentExempt as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_realloc() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let new_size = CounterAccount::LEN as u64 + 128;
+
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                3,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    context
+        .process(
+            &[sdk::realloc(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &payer,
+                new_size,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let account = context
+        .program_context
+        .banks_client
+        .get_account(counter)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len() as u64, new_size);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(new_size as usize)
+    );
+    assert_eq!(context.counter(&counter).await.counter, 3);
+
+    // The grown counter keeps working.
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 4);
+
+    let err = context
+        .process(
+            &[sdk::realloc(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &payer,
+                CounterAccount::LEN as u64 - 1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidRealloc)
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_account() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+
+    // A counter holding 5 in the original layout: the value alone, and no
+    // authority.
+    let counter = Pubkey::new_unique();
+    let data = 5u32.to_le_bytes().to_vec();
+    context.program_context.set_account(
+        &counter,
+        &Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into(),
+    );
+    // It keeps working before it is migrated, with the fields it lacks at
+    // their defaults.
+    let increment = sdk::increment(&program_id, &counter, &authority.pubkey(), 1);
+    context
+        .process(std::slice::from_ref(&increment), &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.data(&counter).await, 6u32.to_le_bytes());
+
+    // Initialize would have nowhere to store the authority.
+    let initialize = sdk::initialize(&program_id, &counter, &authority.pubkey(), &payer);
+    let err = context
+        .process(&[initialize], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::MigrationRequired));
+
+    // Having no authority, it can be migrated by anyone who signs.
+    let migrate = sdk::migrate_account(&program_id, &counter, &authority.pubkey(), &payer);
+    let mut unsigned = migrate.clone();
+    unsigned.accounts[1].is_signer = false;
+    let err = context.process(&[unsigned], &[]).await.unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    context
+        .process(std::slice::from_ref(&migrate), &[&authority])
+        .await
+        .unwrap();
+    let account = context
+        .program_context
+        .banks_client
+        .get_account(counter)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), CounterAccount::LEN);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(CounterAccount::LEN)
+    );
+    let migrated = context.counter(&counter).await;
+    assert_eq!(migrated.counter, 6);
+    assert_eq!(migrated.authority, Pubkey::default());
+    assert_eq!(migrated.discriminator, COUNTER_DISCRIMINATOR);
+    assert_eq!(migrated.layout_version, COUNTER_LAYOUT_VERSION);
+
+    context.process(&[increment], &[&authority]).await.unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 7);
+
+    // Counters in the current layout have nothing to migrate, and those
+    // with an authority answer to it.
+    let current = context.create_counter(&authority).await;
+    assert_eq!(
+        context.counter(&current).await.layout_version,
+        COUNTER_LAYOUT_VERSION
+    );
+    let mut wrong_authority = sdk::migrate_account(&program_id, &current, &payer, &payer);
+    wrong_authority.accounts[1].is_signer = true;
+    let err = context.process(&[wrong_authority], &[]).await.unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::Unauthorized as u32)
+        )
+    );
+    context
+        .process(
+            &[sdk::migrate_account(
+                &program_id,
+                &current,
+                &authority.pubkey(),
+                &payer,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_registry() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let first = context.create_counter(&authority).await;
+    let second = context.create_counter(&authority).await;
+
+    let (registry, _) = find_registry_address(&context.program_id);
+    let account = context
+        .program_context
+        .banks_client
+        .get_account(registry)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, context.program_id);
+    assert_eq!(account.data.len(), RegistryHeader::account_len(2));
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(RegistryHeader::account_len(2))
+    );
+
+    let header: RegistryHeader = bytemuck::pod_read_unaligned(&account.data[..RegistryHeader::LEN]);
+    assert_eq!(header.count, 2);
+    let entries = RegistryEntry::unpack_slice(&account.data[RegistryHeader::LEN..]);
+    let counters: Vec<Pubkey> = entries.iter().map(|entry| entry.counter).collect();
+    assert_eq!(counters, [first, second]);
+    assert_eq!(
+        RegistryEntry::unpack_slice(&account.data[RegistryHeader::page_range(1, 1)]),
+        entries[1..]
+    );
+}
+
+#[tokio::test]
+async fn test_leaderboard() {
+    let mut context = TestContext::new().await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let authority = Keypair::new();
+    let counters = [
+        context.create_counter(&authority).await,
+        context.create_counter(&authority).await,
+        context.create_counter(&authority).await,
+    ];
+    let (leaderboard, bump) = find_leaderboard_address(&program_id, &authority.pubkey());
+
+    let mut instructions = vec![sdk::initialize_leaderboard(
+        &program_id,
+        &authority.pubkey(),
+        &payer,
+        2,
+    )];
+    for (counter, value) in counters.iter().zip([5, 9, 7]) {
+        instructions.push(sdk::update(
+            &program_id,
+            counter,
+            &[&authority.pubkey()],
+            value,
+        ));
+        instructions.push(sdk::submit_score(&program_id, counter, &authority.pubkey()));
+    }
+    context.process(&instructions, &[&authority]).await.unwrap();
+    let data = context.data(&leaderboard).await;
+    assert_eq!(data.len(), LeaderboardHeader::account_len(2));
+    let header = LeaderboardHeader::unpack(&data).unwrap();
+    assert_eq!(header.authority, authority.pubkey());
+    assert_eq!(header.bump, bump);
+    let standings = |data: &[u8]| -> Vec<(Pubkey, u64)> {
+        LeaderboardHeader::unpack_entries(data)
+            .unwrap()
+            .iter()
+            .map(|entry| (entry.counter, entry.score))
+            .collect()
+    };
+    // The first counter fell off once the third beat it.
+    assert_eq!(standings(&data), [(counters[1], 9), (counters[2], 7)]);
+
+    // Submissions move counters already on the board up and down.
+    context
+        .process(
+            &[
+                sdk::increment(&program_id, &counters[0], &authority.pubkey(), 5),
+                sdk::submit_score(&program_id, &counters[0], &authority.pubkey()),
+                sdk::reset(&program_id, &counters[1], &[&authority.pubkey()]),
+                sdk::submit_score(&program_id, &counters[1], &authority.pubkey()),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let data = context.data(&leaderboard).await;
+    assert_eq!(standings(&data), [(counters[0], 10), (counters[1], 0)]);
+
+    // Counters of other authorities aren't ranked.
+    let stranger = Keypair::new();
+    let other = context.create_counter(&stranger).await;
+    let err = context
+        .process(
+            &[sdk::submit_score(&program_id, &other, &authority.pubkey())],
+            &[],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::Unauthorized as u32)
+        )
+    );
+
+    // Nor are lookalikes another program keeps for the authority.
+    let forged = context.forge_counter(CounterAccount {
+        authority: authority.pubkey(),
+        counter: u32::MAX,
+        ..Zeroable::zeroed()
+    });
+    let err = context
+        .process(
+            &[sdk::submit_score(&program_id, &forged, &authority.pubkey())],
+            &[],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+    let data = context.data(&leaderboard).await;
+    assert_eq!(standings(&data), [(counters[0], 10), (counters[1], 0)]);
+}
+
+#[tokio::test]
+async fn test_initialize_named() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+
+    for name in ["page_views", "signups"] {
+        context
+            .process(
+                &[sdk::initialize_named(
+                    &program_id,
+                    &authority.pubkey(),
+                    &payer,
+                    name,
+                )],
+                &[&authority],
+            )
+            .await
+            .unwrap();
+    }
+    let (page_views, _) = find_counter_address(&program_id, &authority.pubkey(), "page_views");
+    let (signups, _) = find_counter_address(&program_id, &authority.pubkey(), "signups");
+    assert_ne!(page_views, signups);
+    assert_eq!(
+        context.counter(&page_views).await.authority,
+        authority.pubkey()
+    );
+
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &page_views,
+                &authority.pubkey(),
+                5,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&page_views).await.counter, 5);
+    assert_eq!(context.counter(&signups).await.counter, 0);
+
+    let err = context
+        .process(
+            &[sdk::initialize_named(
+                &program_id,
+                &authority.pubkey(),
+                &payer,
+                "signups",
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    // The builder can't derive an address for an oversized name, so send one
+    // by hand.
+    let mut instruction = sdk::initialize_named(&program_id, &authority.pubkey(), &payer, "x");
+    instruction.data = CounterInstructions::InitializeNamed(InitializeNamedArgs {
+        name: "x".repeat(33),
+    })
+    .pack();
+    let err = context
+        .process(&[instruction], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MaxSeedLengthExceeded)
+    );
+}
+
+#[tokio::test]
+async fn test_epoch_scoped() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[
+                sdk::set_epoch_scoped(&program_id, &counter, &authority.pubkey(), true),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 5),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 5);
+
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    let next_epoch = context
+        .program_context
+        .genesis_config()
+        .epoch_schedule
+        .get_first_slot_in_epoch(clock.epoch + 1);
+    context.program_context.warp_to_slot(next_epoch).unwrap();
+
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                2,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let counter_account = context.counter(&counter).await;
+    assert_eq!(counter_account.counter, 2);
+    assert_eq!(counter_account.last_write_epoch, clock.epoch + 1);
+
+    let stranger = Keypair::new();
+    let err = context
+        .process(
+            &[sdk::set_epoch_scoped(
+                &program_id,
+                &counter,
+                &stranger.pubkey(),
+                false,
+            )],
+            &[&stranger],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+}
+
+#[tokio::test]
+async fn test_decay() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+
+    context
+        .process(
+            &[
+                sdk::set_decay(&program_id, &counter, &authority.pubkey(), 2),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 100),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let start = context.counter(&counter).await;
+    assert_eq!(start.counter, 100);
+
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    context
+        .program_context
+        .warp_to_slot(clock.slot + 10)
+        .unwrap();
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let decayed = context.counter(&counter).await;
+    let elapsed = decayed.last_updated_slot - start.last_updated_slot;
+    assert!(elapsed >= 10);
+    assert_eq!(decayed.counter, 100 - 2 * elapsed as u32 + 1);
+
+    // Decay floors at zero.
+    context
+        .program_context
+        .warp_to_slot(decayed.last_updated_slot + 1_000)
+        .unwrap();
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 1);
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let increment = sdk::increment(&program_id, &counter, &authority.pubkey(), 1);
+
+    context
+        .process(
+            &[sdk::set_rate_limit(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                2,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    // A transaction executes within a single slot.
+    let err = context
+        .process(
+            &[increment.clone(), increment.clone(), increment.clone()],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(CounterError::RateLimited as u32)
+        )
+    );
+
+    context
+        .process(&[increment.clone(), increment.clone()], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 2);
+}
+
+#[tokio::test]
+async fn test_daily_cap() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+
+    context
+        .process(
+            &[
+                sdk::set_daily_cap(&program_id, &counter, &authority.pubkey(), 10),
+                sdk::create_quota(&program_id, &counter, &authority.pubkey(), &payer),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    // Capped counters need the quota account.
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    context
+        .process(
+            &[sdk::increment_capped(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                7,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let err = context
+        .process(
+            &[sdk::increment_capped(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                4,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::DailyCapExceeded));
+
+    let (quota, _) = find_quota_address(&program_id, &counter, &authority.pubkey());
+    let account = context
+        .program_context
+        .banks_client
+        .get_account(quota)
+        .await
+        .unwrap()
+        .unwrap();
+    let quota = CallerQuota::unpack(&account.data).unwrap();
+    assert_eq!(quota.caller, authority.pubkey());
+    assert_eq!(quota.total, 7);
+
+    // A day later the window starts over.
+    let mut clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    clock.unix_timestamp += QUOTA_WINDOW_SECONDS;
+    context.program_context.set_sysvar(&clock);
+    context
+        .process(
+            &[sdk::increment_capped(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                10,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 17);
+}
+
+#[tokio::test]
+async fn test_cooldown() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    context
+        .process(
+            &[
+                sdk::set_cooldown(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    60,
+                    COOLDOWN_SECONDS,
+                ),
+                sdk::create_cooldown(&program_id, &counter, &authority.pubkey(), &payer),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let increment = |value| {
+        sdk::with_cooldown(
+            sdk::increment(&program_id, &counter, &authority.pubkey(), value),
+            &counter,
+            &authority.pubkey(),
+        )
+    };
+    context
+        .process(&[increment(1)], &[&authority])
+        .await
+        .unwrap();
+    let err = context
+        .process(&[increment(2)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::CooldownActive));
+
+    // A minute later the caller may write again.
+    let (cooldown, _) = find_cooldown_address(&program_id, &counter, &authority.pubkey());
+    let last = CallerCooldown::unpack(&context.data(&cooldown).await).unwrap();
+    assert_eq!(last.operations, 1);
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    context.program_context.set_sysvar(&Clock {
+        unix_timestamp: last.last_timestamp + 60,
+        ..clock
+    });
+    context
+        .process(&[increment(2)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 3);
+}
+
+#[tokio::test]
+async fn test_fee_and_withdraw_treasury() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let (treasury, treasury_bump) = find_treasury_address(&program_id, &counter);
+    let treasury_minimum = Rent::default().minimum_balance(0);
+
+    context
+        .process(
+            &[sdk::set_fee(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &payer,
+                1_000_000,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    // Later fee payments check the treasury against the stored bump.
+    assert_eq!(context.counter(&counter).await.treasury_bump, treasury_bump);
+
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    context
+        .process(
+            &[
+                sdk::with_fee(
+                    sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+                    &counter,
+                    &payer,
+                ),
+                sdk::with_fee(
+                    sdk::reset(&program_id, &counter, &[&authority.pubkey()]),
+                    &counter,
+                    &payer,
+                ),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let banks_client = &mut context.program_context.banks_client;
+    assert_eq!(
+        banks_client.get_balance(treasury).await.unwrap(),
+        treasury_minimum + 2_000_000
+    );
+
+    let destination = Pubkey::new_unique();
+    context
+        .process(
+            &[sdk::withdraw_treasury(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &destination,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let banks_client = &mut context.program_context.banks_client;
+    assert_eq!(
+        banks_client.get_balance(treasury).await.unwrap(),
+        treasury_minimum
+    );
+    assert_eq!(
+        banks_client.get_balance(destination).await.unwrap(),
+        2_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_referrals() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let (treasury, _) = find_treasury_address(&program_id, &counter);
+    let treasury_minimum = Rent::default().minimum_balance(0);
+    let referrer = Keypair::new();
+    let (entry, entry_bump) = find_referrer_address(&program_id, &counter, &referrer.pubkey());
+
+    context
+        .process(
+            &[
+                sdk::set_fee(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &payer,
+                    5_000_000,
+                ),
+                sdk::set_referrals(&program_id, &counter, &authority.pubkey(), true, 2_000),
+                sdk::create_referrer(&program_id, &counter, &referrer.pubkey(), &payer),
+                sdk::create_referrer(&program_id, &counter, &authority.pubkey(), &payer),
+            ],
+            &[&authority, &referrer],
+        )
+        .await
+        .unwrap();
+    let counter_state = context.counter(&counter).await;
+    assert!(counter_state.has_referrals());
+    assert_eq!(counter_state.referral_share_bps, 2_000);
+
+    // A share above 100% is refused.
+    let err = context
+        .process(
+            &[sdk::set_referrals(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                true,
+                10_001,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    let increment = |value, referrer: Option<&Pubkey>| {
+        sdk::with_referrer(
+            sdk::with_fee(
+                sdk::increment(&program_id, &counter, &authority.pubkey(), value),
+                &counter,
+                &payer,
+            ),
+            &counter,
+            referrer,
+        )
+    };
+    context
+        .process(
+            &[
+                increment(3, Some(&referrer.pubkey())),
+                increment(4, None),
+                increment(5, Some(&referrer.pubkey())),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 12);
+
+    let banks_client = &mut context.program_context.banks_client;
+    let entry_account = banks_client.get_account(entry).await.unwrap().unwrap();
+    let entry_minimum = Rent::default().minimum_balance(ReferrerEntry::LEN);
+    assert_eq!(entry_account.lamports, entry_minimum + 2_000_000);
+    let entry_state = ReferrerEntry::unpack(&entry_account.data).unwrap();
+    assert_eq!(entry_state.counter, counter);
+    assert_eq!(entry_state.referrer, referrer.pubkey());
+    assert_eq!(entry_state.bump, entry_bump);
+    assert_eq!(entry_state.referrals, 2);
+    assert_eq!(entry_state.referred_value, 8);
+    assert_eq!(entry_state.fees_earned, 2_000_000);
+    assert_eq!(
+        banks_client.get_balance(treasury).await.unwrap(),
+        treasury_minimum + 13_000_000
+    );
+
+    // The signer can't refer itself.
+    let err = context
+        .process(&[increment(1, Some(&authority.pubkey()))], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::SelfReferral as u32)
+        )
+    );
+
+    // Only the referrer withdraws its fees, down to the entry's rent.
+    let destination = Pubkey::new_unique();
+    let mut unsigned =
+        sdk::withdraw_referral_fees(&program_id, &counter, &referrer.pubkey(), &destination);
+    unsigned.accounts[1].is_signer = false;
+    let err = context.process(&[unsigned], &[]).await.unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    context
+        .process(
+            &[sdk::withdraw_referral_fees(
+                &program_id,
+                &counter,
+                &referrer.pubkey(),
+                &destination,
+            )],
+            &[&referrer],
+        )
+        .await
+        .unwrap();
+    let banks_client = &mut context.program_context.banks_client;
+    assert_eq!(
+        banks_client.get_balance(entry).await.unwrap(),
+        entry_minimum
+    );
+    assert_eq!(
+        banks_client.get_balance(destination).await.unwrap(),
+        2_000_000
+    );
+}
+
+/// Pays a price of 300 tokens for one increment and withdraws it again.
+/// `transfer_fee_basis_points` makes a Token-2022 mint with a transfer fee.
+async fn check_token_payment(token_program: Pubkey, transfer_fee_basis_points: Option<u16>) {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+
+    let mint = Keypair::new();
+    let source = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint.pubkey(),
+        &token_program,
+    );
+    let destination =
+        get_associated_token_address_with_program_id(&payer, &mint.pubkey(), &token_program);
+    let extensions: &[ExtensionType] = match transfer_fee_basis_points {
+        Some(_) => &[ExtensionType::TransferFeeConfig],
+        None => &[],
+    };
+    let mint_len = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+    let mut setup = vec![system_instruction::create_account(
+        &payer,
+        &mint.pubkey(),
+        Rent::default().minimum_balance(mint_len),
+        mint_len as u64,
+        &token_program,
+    )];
+    if let Some(basis_points) = transfer_fee_basis_points {
+        setup.push(
+            initialize_transfer_fee_config(
+                &token_program,
+                &mint.pubkey(),
+                None,
+                None,
+                basis_points,
+                u64::MAX,
+            )
+            .unwrap(),
+        );
+    }
+    setup.extend([
+        token_instruction::initialize_mint2(&token_program, &mint.pubkey(), &payer, None, 0)
+            .unwrap(),
+        create_associated_token_account(
+            &payer,
+            &authority.pubkey(),
+            &mint.pubkey(),
+            &token_program,
+        ),
+        create_associated_token_account(&payer, &payer, &mint.pubkey(), &token_program),
+        token_instruction::mint_to(&token_program, &mint.pubkey(), &source, &payer, &[], 1_000)
+            .unwrap(),
+    ]);
+    context.process(&setup, &[&mint]).await.unwrap();
+
+    context
+        .process(
+            &[
+                sdk::configure_payment(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &mint.pubkey(),
+                    300,
+                ),
+                sdk::initialize_vault(
+                    &program_id,
+                    &counter,
+                    &mint.pubkey(),
+                    &payer,
+                    &token_program,
+                ),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    let wrong_program = if token_program == spl_token::id() {
+        spl_token_2022::id()
+    } else {
+        spl_token::id()
+    };
+    let err = context
+        .process(
+            &[sdk::with_payment(
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+                &counter,
+                &mint.pubkey(),
+                &source,
+                &wrong_program,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+
+    context
+        .process(
+            &[sdk::with_payment(
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+                &counter,
+                &mint.pubkey(),
+                &source,
+                &token_program,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 1);
+    let vault = find_vault_address(&program_id, &counter, &mint.pubkey(), &token_program);
+    assert_eq!(context.token_balance(&vault).await, 300);
+    // At 1%, paying costs ceil(304 * 1%) = 4 on top of the price and
+    // withdrawing the 300 loses ceil(300 * 1%) = 3.
+    let (payment_fee, withdraw_fee) = match transfer_fee_basis_points {
+        Some(100) => (4, 3),
+        Some(_) => unreachable!(),
+        None => (0, 0),
+    };
+    assert_eq!(context.token_balance(&source).await, 700 - payment_fee);
+
+    context
+        .process(
+            &[sdk::withdraw_vault(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &mint.pubkey(),
+                &destination,
+                &token_program,
+                300,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.token_balance(&vault).await, 0);
+    assert_eq!(
+        context.token_balance(&destination).await,
+        300 - withdraw_fee
+    );
+}
+
+#[tokio::test]
+async fn test_token_payment() {
+    check_token_payment(spl_token::id(), None).await;
+}
+
+#[tokio::test]
+async fn test_token_payment_token_2022() {
+    check_token_payment(spl_token_2022::id(), None).await;
+}
+
+#[tokio::test]
+async fn test_token_payment_transfer_fee() {
+    check_token_payment(spl_token_2022::id(), Some(100)).await;
+}
+
+#[tokio::test]
+async fn test_nft_gate() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let collection = Pubkey::new_unique();
+
+    let mint = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint.pubkey(),
+        &spl_token::id(),
+    );
+    context
+        .process(
+            &[
+                system_instruction::create_account(
+                    &payer,
+                    &mint.pubkey(),
+                    Rent::default().minimum_balance(Mint::LEN),
+                    Mint::LEN as u64,
+                    &spl_token::id(),
+                ),
+                token_instruction::initialize_mint2(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    &payer,
+                    None,
+                    0,
+                )
+                .unwrap(),
+                create_associated_token_account(
+                    &payer,
+                    &authority.pubkey(),
+                    &mint.pubkey(),
+                    &spl_token::id(),
+                ),
+                token_instruction::mint_to(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    &token_account,
+                    &payer,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                sdk::set_nft_gate(&program_id, &counter, &authority.pubkey(), &collection),
+            ],
+            &[&mint, &authority],
+        )
+        .await
+        .unwrap();
+
+    let set_metadata = |context: &mut TestContext, verified: bool| {
+        let metadata = Metadata {
+            key: METADATA_V1_KEY,
+            update_authority: payer,
+            mint: mint.pubkey(),
+            name: "Counter Pass".to_string(),
+            symbol: "PASS".to_string(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: Some(Collection {
+                verified,
+                key: collection,
+            }),
+        };
+        let data = borsh::to_vec(&metadata).unwrap();
+        context.program_context.set_account(
+            &find_metadata_address(&mint.pubkey()).0,
+            &Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: metadata::ID,
+                executable: false,
+                rent_epoch: 0,
+            }
+            .into(),
+        );
+    };
+    let increment = sdk::increment(&program_id, &counter, &authority.pubkey(), 2);
+    let gated_increment = sdk::with_nft(increment.clone(), &token_account, &mint.pubkey());
+
+    let err = context
+        .process(std::slice::from_ref(&increment), &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    set_metadata(&mut context, false);
+    let err = context
+        .process(std::slice::from_ref(&gated_increment), &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::NftRequired as u32)
+        )
+    );
+
+    set_metadata(&mut context, true);
+    context
+        .process(
+            &[
+                gated_increment,
+                sdk::with_nft(
+                    sdk::decrement(&program_id, &counter, &authority.pubkey(), 1),
+                    &token_account,
+                    &mint.pubkey(),
+                ),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 1);
+
+    context
+        .process(
+            &[
+                sdk::set_nft_gate(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &Pubkey::default(),
+                ),
+                increment,
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 3);
+}
+
+#[tokio::test]
+async fn test_milestone_reward() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let (reward_authority, _) = find_reward_authority_address(&program_id, &counter);
+
+    let mint = Keypair::new();
+    let destination = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint.pubkey(),
+        &spl_token_2022::id(),
+    );
+    context
+        .process(
+            &[
+                system_instruction::create_account(
+                    &payer,
+                    &mint.pubkey(),
+                    Rent::default().minimum_balance(Mint::LEN),
+                    Mint::LEN as u64,
+                    &spl_token_2022::id(),
+                ),
+                token_instruction::initialize_mint2(
+                    &spl_token_2022::id(),
+                    &mint.pubkey(),
+                    &reward_authority,
+                    None,
+                    0,
+                )
+                .unwrap(),
+                create_associated_token_account(
+                    &payer,
+                    &authority.pubkey(),
+                    &mint.pubkey(),
+                    &spl_token_2022::id(),
+                ),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 5),
+                sdk::configure_milestone_reward(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &mint.pubkey(),
+                    10,
+                    7,
+                ),
+            ],
+            &[&mint, &authority],
+        )
+        .await
+        .unwrap();
+
+    let increment = |value| {
+        sdk::with_milestone_reward(
+            sdk::increment(&program_id, &counter, &authority.pubkey(), value),
+            &counter,
+            &mint.pubkey(),
+            &destination,
+            &spl_token_2022::id(),
+        )
+    };
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    // 5 -> 9 reaches no milestone; 9 -> 31 reaches 10, 20 and 30.
+    context
+        .process(&[increment(4)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.token_balance(&destination).await, 0);
+    context
+        .process(&[increment(22)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.token_balance(&destination).await, 21);
+
+    // Passing 30 again after going back below it mints nothing.
+    context
+        .process(
+            &[
+                sdk::decrement(&program_id, &counter, &authority.pubkey(), 5),
+                increment(5),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 31);
+    assert_eq!(context.token_balance(&destination).await, 21);
+
+    let wrong_mint = Pubkey::new_unique();
+    let err = context
+        .process(
+            &[sdk::with_milestone_reward(
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 9),
+                &counter,
+                &wrong_mint,
+                &destination,
+                &spl_token_2022::id(),
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::InvalidRewardMint as u32)
+        )
+    );
+
+    context
+        .process(&[increment(9)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.token_balance(&destination).await, 28);
+}
+
+#[tokio::test]
+async fn test_claim_milestone_nft() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let claimer = context.program_context.payer.pubkey();
+    let claim = |mint: &Keypair| {
+        sdk::claim_milestone_nft(
+            &program_id,
+            &counter,
+            &claimer,
+            &mint.pubkey(),
+            "Counter #10",
+            "CNT",
+            "https://example.com/10.json",
+        )
+    };
+
+    let mint = Keypair::new();
+    context
+        .process(
+            &[sdk::configure_milestone_nft(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                10,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let err = context
+        .process(&[claim(&mint)], &[&mint])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::MilestoneNotReached as u32)
+        )
+    );
+
+    context
+        .process(
+            &[
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 10),
+                claim(&mint),
+            ],
+            &[&authority, &mint],
+        )
+        .await
+        .unwrap();
+    let token_account =
+        get_associated_token_address_with_program_id(&claimer, &mint.pubkey(), &spl_token::id());
+    assert_eq!(context.token_balance(&token_account).await, 1);
+    assert!(context.counter(&counter).await.is_nft_claimed());
+
+    let second_mint = Keypair::new();
+    let err = context
+        .process(&[claim(&second_mint)], &[&second_mint])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::MilestoneNftClaimed as u32)
+        )
+    );
+
+    // A new target allows another claim.
+    context
+        .process(
+            &[
+                sdk::configure_milestone_nft(&program_id, &counter, &authority.pubkey(), 10),
+                claim(&second_mint),
+            ],
+            &[&authority, &second_mint],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_update_from_oracle() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let feed_id = [9; 32];
+    let now = context
+        .program_context
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+    context
+        .process(
+            &[sdk::configure_oracle(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                feed_id,
+                60,
+                50,
+                -2,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    // Stores `update` as the price account and returns the instruction
+    // reading it. `fresh` has a confidence of 0.1%, under the 0.5% allowed.
+    let price_update = Pubkey::new_unique();
+    let update_from = |context: &mut TestContext, update: PriceUpdateV2| {
+        let data = update.pack();
+        context.program_context.set_account(
+            &price_update,
+            &Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: pyth::RECEIVER_ID,
+                executable: false,
+                rent_epoch: 0,
+            }
+            .into(),
+        );
+        sdk::update_from_oracle(&program_id, &counter, &price_update)
+    };
+    let fresh = PriceUpdateV2 {
+        write_authority: Pubkey::new_unique(),
+        verification_level: VerificationLevel::Full,
+        price_message: PriceFeedMessage {
+            feed_id,
+            price: 6_512_345_678_000,
+            conf: 6_512_345_678,
+            exponent: -8,
+            publish_time: now - 10,
+            prev_publish_time: now - 11,
+            ema_price: 6_500_000_000_000,
+            ema_conf: 6_500_000_000,
+        },
+        posted_slot: 1,
+    };
+
+    let instruction = update_from(&mut context, fresh);
+    context.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 6_512_345);
+
+    let mut rejected = Vec::new();
+    let mut stale = fresh;
+    stale.price_message.publish_time = now - 61;
+    rejected.push((stale, CounterError::StalePrice));
+    let mut uncertain = fresh;
+    uncertain.price_message.conf = fresh.price_message.conf * 10;
+    rejected.push((uncertain, CounterError::PriceTooUncertain));
+    let mut partial = fresh;
+    partial.verification_level = VerificationLevel::Partial { num_signatures: 3 };
+    rejected.push((partial, CounterError::InvalidPriceFeed));
+    let mut other_feed = fresh;
+    other_feed.price_message.feed_id = [8; 32];
+    rejected.push((other_feed, CounterError::InvalidPriceFeed));
+    let mut negative = fresh;
+    negative.price_message.price = -1;
+    negative.price_message.conf = 0;
+    rejected.push((negative, CounterError::Overflow));
+    for (update, expected) in rejected {
+        let instruction = update_from(&mut context, update);
+        let err = context.process(&[instruction], &[]).await.unwrap_err();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(expected as u32))
+        );
+    }
+    assert_eq!(context.counter(&counter).await.counter, 6_512_345);
+}
+
+#[tokio::test]
+async fn test_random_increment() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let randomness = Pubkey::new_unique();
+    let set_randomness = |context: &mut TestContext, account: &RandomnessAccount| {
+        let data = account.pack();
+        context.program_context.set_account(
+            &randomness,
+            &Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: switchboard::ON_DEMAND_ID,
+                executable: false,
+                rent_epoch: 0,
+            }
+            .into(),
+        );
+    };
+    let request =
+        sdk::request_random_increment(&program_id, &counter, &authority.pubkey(), &randomness, 6);
+    let settle = sdk::settle_random_increment(&program_id, &counter, &randomness);
+    let custom = |error: CounterError| {
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+    };
+
+    // Warping the bank after `set_account` trips its capitalization check,
+    // so the slots are moved through the clock alone.
+    let set_slot = |context: &mut TestContext, slot| {
+        context.program_context.set_sysvar(&Clock {
+            slot,
+            ..Clock::default()
+        })
+    };
+
+    // The client commits in slot 99 and requests in slot 100.
+    set_slot(&mut context, 100);
+    let mut account = RandomnessAccount::zeroed();
+    account.seed_slot = 98;
+    set_randomness(&mut context, &account);
+    let err = context
+        .process(std::slice::from_ref(&request), &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom(CounterError::InvalidRandomness));
+    account.seed_slot = 99;
+    set_randomness(&mut context, &account);
+    let err = context
+        .process(std::slice::from_ref(&settle), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom(CounterError::NoRandomRequest));
+    context
+        .process(std::slice::from_ref(&request), &[&authority])
+        .await
+        .unwrap();
+    assert!(context.counter(&counter).await.has_random_request());
+
+    // Nothing settles before the oracle reveals the value.
+    set_slot(&mut context, 102);
+    let err = context
+        .process(std::slice::from_ref(&settle), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom(CounterError::RandomnessNotRevealed));
+
+    // 17 maps onto 17 % 6 + 1 = 6.
+    account.reveal_slot = 102;
+    account.value[0] = 17;
+    set_randomness(&mut context, &account);
+    context.process(&[settle], &[]).await.unwrap();
+    let counter_account = context.counter(&counter).await;
+    assert_eq!(counter_account.counter, 6);
+    assert!(!counter_account.has_random_request());
+}
+
+#[tokio::test]
+async fn test_allowlist() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let delegate = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let not_allowlisted = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(CounterError::NotAllowlisted as u32),
+    );
+    let increment = sdk::with_allowlist(
+        sdk::increment(&program_id, &counter, &delegate.pubkey(), 1),
+        &counter,
+        &delegate.pubkey(),
+    );
+    context
+        .process(
+            &[
+                sdk::approve(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &delegate.pubkey(),
+                    10,
+                ),
+                sdk::set_allowlist_enabled(&program_id, &counter, &authority.pubkey(), true),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let err = context
+        .process(std::slice::from_ref(&increment), &[&delegate])
+        .await
+        .unwrap_err();
+    assert_eq!(err, not_allowlisted);
+
+    context
+        .process(
+            &[
+                sdk::add_to_allowlist(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &delegate.pubkey(),
+                    &payer,
+                ),
+                increment.clone(),
+            ],
+            &[&authority, &delegate],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 1);
+
+    // The authority needs an entry of its own.
+    let err = context
+        .process(
+            &[sdk::with_allowlist(
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+                &counter,
+                &delegate.pubkey(),
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, not_allowlisted);
+
+    context
+        .process(
+            &[sdk::remove_from_allowlist(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &delegate.pubkey(),
+                &payer,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let err = context
+        .process(std::slice::from_ref(&increment), &[&delegate])
+        .await
+        .unwrap_err();
+    assert_eq!(err, not_allowlisted);
+
+    context
+        .process(
+            &[
+                sdk::set_allowlist_enabled(&program_id, &counter, &authority.pubkey(), false),
+                sdk::increment(&program_id, &counter, &delegate.pubkey(), 1),
+            ],
+            &[&authority, &delegate],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 2);
+}
+
+#[tokio::test]
+async fn test_blocklist() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let delegate = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let increment = sdk::with_blocklist(
+        sdk::increment(&program_id, &counter, &delegate.pubkey(), 1),
+        &counter,
+        &delegate.pubkey(),
+    );
+    context
+        .process(
+            &[
+                sdk::approve(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &delegate.pubkey(),
+                    10,
+                ),
+                sdk::set_blocklist_enabled(&program_id, &counter, &authority.pubkey(), true),
+                increment.clone(),
+            ],
+            &[&authority, &delegate],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 1);
+
+    // Someone else's ban entry address doesn't count.
+    let err = context
+        .process(
+            &[sdk::with_blocklist(
+                sdk::increment(&program_id, &counter, &delegate.pubkey(), 1),
+                &counter,
+                &authority.pubkey(),
+            )],
+            &[&delegate],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    context
+        .process(
+            &[sdk::ban_caller(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &delegate.pubkey(),
+                &payer,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let err = context
+        .process(std::slice::from_ref(&increment), &[&delegate])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::CallerBanned as u32)
+        )
+    );
+
+    context
+        .process(
+            &[
+                sdk::unban_caller(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &delegate.pubkey(),
+                    &payer,
+                ),
+                increment,
+            ],
+            &[&authority, &delegate],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 2);
+}
+
+#[tokio::test]
+async fn test_offchain_authorization() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let service = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let increment = |value| {
+        sdk::with_offchain_authorization(sdk::increment(
+            &program_id,
+            &counter,
+            &authority.pubkey(),
+            value,
+        ))
+    };
+    let authorization = |signer: &Keypair, nonce| {
+        let signature = signer.sign_message(&offchain_message(&counter, nonce));
+        sdk::offchain_authorization(
+            &signer.pubkey(),
+            signature.as_ref().try_into().unwrap(),
+            &counter,
+            nonce,
+        )
+    };
+    context
+        .process(
+            &[sdk::set_offchain_signer(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &service.pubkey(),
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let err = context
+        .process(&[increment(1)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::InvalidOffchainSignature as u32)
+        )
+    );
+
+    context
+        .process(&[authorization(&service, 0), increment(1)], &[&authority])
+        .await
+        .unwrap();
+    let counter_account = context.counter(&counter).await;
+    assert_eq!(counter_account.counter, 1);
+    assert_eq!(counter_account.offchain_nonce, 1);
+
+    // Neither a replayed nonce nor another key's signature will do.
+    for authorization in [authorization(&service, 0), authorization(&authority, 1)] {
+        let err = context
+            .process(&[authorization, increment(2)], &[&authority])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(CounterError::InvalidOffchainSignature as u32)
+            )
+        );
+    }
+
+    let err = context
+        .process(
+            &[
+                authorization(&service, 1),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 2),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::UnsupportedSysvar)
+    );
+
+    context
+        .process(&[authorization(&service, 1), increment(2)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 3);
+}
+
+#[tokio::test]
+async fn test_eth_authority() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    // The fee payer signs in place of the authority.
+    let payer = context.program_context.payer.pubkey();
+    let eth_key = |seed| {
+        let secret_key = libsecp256k1::SecretKey::parse(&[seed; 32]).unwrap();
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        let hash = keccak::hash(&public_key.serialize()[1..]).to_bytes();
+        (secret_key, <[u8; 20]>::try_from(&hash[12..]).unwrap())
+    };
+    let (secret_key, eth_address) = eth_key(1);
+    let authorization = |(secret_key, eth_address): (libsecp256k1::SecretKey, [u8; 20]), nonce| {
+        let hash = keccak::hash(&eth_message(&counter, nonce)).to_bytes();
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), &secret_key);
+        sdk::eth_authorization(
+            &eth_address,
+            &signature.serialize(),
+            recovery_id.serialize(),
+            &counter,
+            nonce,
+            0,
+        )
+    };
+    context
+        .process(
+            &[sdk::set_eth_authority(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &eth_address,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let increment =
+        |value| sdk::with_eth_authorization(sdk::increment(&program_id, &counter, &payer, value));
+    let err = context.process(&[increment(1)], &[]).await.unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::InvalidEthSignature as u32)
+        )
+    );
+
+    context
+        .process(
+            &[authorization((secret_key, eth_address), 0), increment(5)],
+            &[],
+        )
+        .await
+        .unwrap();
+    let counter_account = context.counter(&counter).await;
+    assert_eq!(counter_account.counter, 5);
+    assert_eq!(counter_account.eth_nonce, 1);
+
+    // Neither a replayed nonce nor another key's signature will do.
+    for authorization in [
+        authorization((secret_key, eth_address), 0),
+        authorization(eth_key(2), 1),
+    ] {
+        let err = context
+            .process(&[authorization, increment(1)], &[])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(CounterError::InvalidEthSignature as u32)
+            )
+        );
+    }
+
+    context
+        .process(
+            &[
+                authorization((secret_key, eth_address), 1),
+                sdk::with_eth_authorization(sdk::decrement(&program_id, &counter, &payer, 2)),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 3);
+}
+
+#[tokio::test]
+async fn test_memo_required() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let increment = sdk::with_memo_required(sdk::increment(
+        &program_id,
+        &counter,
+        &authority.pubkey(),
+        1,
+    ));
+    let err = context
+        .process(
+            &[
+                sdk::set_memo_required(&program_id, &counter, &authority.pubkey(), true),
+                increment.clone(),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(CounterError::MemoRequired as u32)
+        )
+    );
+
+    // The memo may go before or after the mutation.
+    context
+        .process(
+            &[
+                sdk::set_memo_required(&program_id, &counter, &authority.pubkey(), true),
+                memo::new_instruction("first", &[&authority.pubkey()]),
+                increment.clone(),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let update = sdk::with_memo_required(sdk::update(
+        &program_id,
+        &counter,
+        &[&authority.pubkey()],
+        5,
+    ));
+    context
+        .process(
+            &[update, memo::new_instruction("second", &[])],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 5);
+
+    let err = context
+        .process(std::slice::from_ref(&increment), &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::MemoRequired as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_stake_weighted() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    context
+        .process(
+            &[sdk::set_stake_weighted(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                true,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert!(context.counter(&counter).await.is_stake_weighted());
+
+    // Stores a stake account delegating 3.5 SOL since `activation_epoch`.
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    let store_stake = |context: &mut TestContext, staker: Pubkey, owner: Pubkey| {
+        let stake = Pubkey::new_unique();
+        let state = StakeStateV2::Stake(
+            Meta {
+                authorized: Authorized {
+                    staker,
+                    withdrawer: staker,
+                },
+                ..Meta::default()
+            },
+            Stake {
+                delegation: Delegation {
+                    voter_pubkey: Pubkey::new_unique(),
+                    stake: 3 * LAMPORTS_PER_SOL + LAMPORTS_PER_SOL / 2,
+                    activation_epoch: clock.epoch,
+                    ..Delegation::default()
+                },
+                credits_observed: 0,
+            },
+            StakeFlags::empty(),
+        );
+        let mut data = borsh::to_vec(&state).unwrap();
+        data.resize(StakeStateV2::size_of(), 0);
+        context.program_context.set_account(
+            &stake,
+            &Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner,
+                executable: false,
+                rent_epoch: 0,
+            }
+            .into(),
+        );
+        stake
+    };
+    let stake = store_stake(&mut context, authority.pubkey(), stake::program::ID);
+    let increment = |stake: &Pubkey| {
+        sdk::with_stake(
+            sdk::increment(&program_id, &counter, &authority.pubkey(), 2),
+            stake,
+        )
+    };
+    let custom = |error: CounterError| {
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+    };
+
+    // The stake counts from the epoch after its delegation.
+    let err = context
+        .process(&[increment(&stake)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom(CounterError::StakeNotActive));
+    context.program_context.set_sysvar(&Clock {
+        epoch: clock.epoch + 1,
+        ..clock
+    });
+    context
+        .process(&[increment(&stake)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 6);
+
+    // Only the stake's own authorities may lend it, and only real stake.
+    let others = store_stake(&mut context, Pubkey::new_unique(), stake::program::ID);
+    let err = context
+        .process(&[increment(&others)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom(CounterError::Unauthorized));
+    let fake = store_stake(&mut context, authority.pubkey(), system_program::ID);
+    let err = context
+        .process(&[increment(&fake)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+}
+
+#[tokio::test]
+async fn test_boost() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let (escrow, escrow_bump) = find_boost_address(&program_id, &counter, &authority.pubkey());
+    context
+        .process(
+            &[
+                system_instruction::transfer(&payer, &authority.pubkey(), 3 * LAMPORTS_PER_SOL),
+                sdk::set_boost(&program_id, &counter, &authority.pubkey(), 3, 1),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert!(context.counter(&counter).await.has_boost());
+
+    let increment = |owner: Option<&Pubkey>| {
+        sdk::with_boost(
+            sdk::increment(&program_id, &counter, &authority.pubkey(), 2),
+            &counter,
+            owner,
+        )
+    };
+    let lock =
+        |lamports| sdk::lock_boost(&program_id, &counter, &authority.pubkey(), lamports, 100);
+    // Without an escrow, and with one locking less than the minimum, the
+    // increment counts once; then three times.
+    let authority_key = authority.pubkey();
+    context
+        .process(
+            &[
+                increment(None),
+                lock(LAMPORTS_PER_SOL / 2),
+                increment(Some(&authority_key)),
+                lock(LAMPORTS_PER_SOL),
+                increment(Some(&authority_key)),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 10);
+    let data = context.data(&escrow).await;
+    let boost = BoostEscrow::unpack(&data).unwrap();
+    assert_eq!(boost.owner, authority.pubkey());
+    assert_eq!(boost.locked_lamports, 3 * LAMPORTS_PER_SOL / 2);
+    assert_eq!(boost.bump, escrow_bump);
+
+    let unlock = sdk::unlock_boost(&program_id, &counter, &authority.pubkey());
+    let err = context
+        .process(std::slice::from_ref(&unlock), &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::BoostStillLocked as u32)
+        )
+    );
+
+    // Once unlocked, the escrow stops boosting and can be closed.
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    context.program_context.set_sysvar(&Clock {
+        unix_timestamp: boost.unlocks_at,
+        ..clock
+    });
+    context
+        .process(&[increment(Some(&authority_key))], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 12);
+    let banks_client = &mut context.program_context.banks_client;
+    let escrow_lamports = banks_client.get_balance(escrow).await.unwrap();
+    let before = banks_client.get_balance(authority.pubkey()).await.unwrap();
+    context.process(&[unlock], &[&authority]).await.unwrap();
+    let banks_client = &mut context.program_context.banks_client;
+    assert!(banks_client.get_account(escrow).await.unwrap().is_none());
+    assert_eq!(
+        banks_client.get_balance(authority.pubkey()).await.unwrap(),
+        before + escrow_lamports
+    );
+}
+
+#[tokio::test]
+async fn test_lease() {
+    let mut context = TestContext::new().await;
+    let (authority, lessee) = (Keypair::new(), Keypair::new());
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let (lease, _) = find_lease_address(&program_id, &counter);
+    let (treasury, _) = find_treasury_address(&program_id, &counter);
+    context
+        .process(
+            &[
+                system_instruction::transfer(&payer, &lessee.pubkey(), 3 * LAMPORTS_PER_SOL),
+                sdk::list_for_lease(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &payer,
+                    LAMPORTS_PER_SOL,
+                    1_000,
+                ),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert!(context.counter(&counter).await.is_listed_for_lease());
+
+    let increment = |signer: &Keypair| {
+        sdk::with_lease(
+            sdk::increment(&program_id, &counter, &signer.pubkey(), 1),
+            &counter,
+        )
+    };
+    // Between leases the authority mutates; during one only the lessee does.
+    context
+        .process(&[increment(&authority)], &[&authority])
+        .await
+        .unwrap();
+    let err = context
+        .process(&[increment(&lessee)], &[&lessee])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+    let take = sdk::take_lease(&program_id, &counter, &lessee.pubkey());
+    context
+        .process(&[take.clone(), increment(&lessee)], &[&lessee])
+        .await
+        .unwrap();
+    let err = context
+        .process(&[increment(&authority)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+    assert_eq!(context.counter(&counter).await.counter, 2);
+    // The lessee only stands in for the authority; the stored one stays.
+    assert_eq!(
+        context.counter(&counter).await.authority,
+        authority.pubkey()
+    );
+    let terms = Lease::unpack(&context.data(&lease).await).unwrap();
+    assert_eq!(terms.lessee, lessee.pubkey());
+
+    let err = context
+        .process(std::slice::from_ref(&take), &[&lessee])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::LeaseActive));
+    let delist = sdk::delist(&program_id, &counter, &authority.pubkey(), &payer);
+    let err = context
+        .process(std::slice::from_ref(&delist), &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::LeaseActive));
+    // Nor can the owner close, stop or hand over the counter under the lessee.
+    let stranger = Pubkey::new_unique();
+    for instruction in [
+        sdk::close(&program_id, &counter, &payer, &[&authority.pubkey()]),
+        sdk::pause(&program_id, &counter, &authority.pubkey()),
+        sdk::freeze(&program_id, &counter, &authority.pubkey()),
+        sdk::set_authority(&program_id, &counter, &authority.pubkey(), Some(&stranger)),
+        sdk::set_authority(&program_id, &counter, &authority.pubkey(), None),
+    ] {
+        let err = context
+            .process(&[sdk::with_lease(instruction, &counter)], &[&authority])
+            .await
+            .unwrap_err();
+        assert_eq!(err, custom_error(CounterError::LeaseActive));
+    }
+
+    // Ending it with a quarter left refunds a quarter of the price.
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    context.program_context.set_sysvar(&Clock {
+        unix_timestamp: terms.expires_at - 250,
+        ..clock
+    });
+    let banks_client = &mut context.program_context.banks_client;
+    let before = banks_client.get_balance(lessee.pubkey()).await.unwrap();
+    let end = |signer: &Keypair| {
+        sdk::end_lease(&program_id, &counter, &signer.pubkey(), &lessee.pubkey())
+    };
+    context
+        .process(&[end(&authority), increment(&authority)], &[&authority])
+        .await
+        .unwrap();
+    let banks_client = &mut context.program_context.banks_client;
+    assert_eq!(
+        banks_client.get_balance(lessee.pubkey()).await.unwrap(),
+        before + LAMPORTS_PER_SOL / 4
+    );
+
+    // A lessee ending its own lease gets nothing back.
+    let before_treasury = banks_client.get_balance(treasury).await.unwrap();
+    context
+        .process(&[take, end(&lessee)], &[&lessee])
+        .await
+        .unwrap();
+    let banks_client = &mut context.program_context.banks_client;
+    assert_eq!(
+        banks_client.get_balance(treasury).await.unwrap(),
+        before_treasury + LAMPORTS_PER_SOL
+    );
+
+    context.process(&[delist], &[&authority]).await.unwrap();
+    assert!(context
+        .program_context
+        .banks_client
+        .get_account(lease)
+        .await
+        .unwrap()
+        .is_none());
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 4);
+}
+
+#[tokio::test]
+async fn test_credits() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let (treasury, _) = find_treasury_address(&program_id, &counter);
+    let (credits, credits_bump) = find_credits_address(&program_id, &counter, &authority.pubkey());
+    let buy = sdk::buy_credits(&program_id, &counter, &authority.pubkey(), 2);
+    let err = context
+        .process(
+            &[
+                system_instruction::transfer(&payer, &authority.pubkey(), LAMPORTS_PER_SOL),
+                buy.clone(),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(CounterError::CreditsDisabled as u32)
+        )
+    );
+
+    let set_price = |lamports| {
+        sdk::set_credit_price(&program_id, &counter, &authority.pubkey(), &payer, lamports)
+    };
+    context
+        .process(
+            &[
+                system_instruction::transfer(&payer, &authority.pubkey(), LAMPORTS_PER_SOL),
+                set_price(100_000),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert!(context.counter(&counter).await.has_credits());
+    let banks_client = &mut context.program_context.banks_client;
+    let before = banks_client.get_balance(treasury).await.unwrap();
+    context.process(&[buy], &[&authority]).await.unwrap();
+    let banks_client = &mut context.program_context.banks_client;
+    assert_eq!(
+        banks_client.get_balance(treasury).await.unwrap(),
+        before + 200_000
+    );
+    let balance = CreditBalance::unpack(&context.data(&credits).await).unwrap();
+    assert_eq!(balance.owner, authority.pubkey());
+    assert_eq!(balance.credits, 2);
+    assert_eq!(balance.bump, credits_bump);
+
+    // Each mutation spends a credit, and none can go through without one.
+    let increment = |value| {
+        sdk::with_credits(
+            sdk::increment(&program_id, &counter, &authority.pubkey(), value),
+            &counter,
+            &authority.pubkey(),
+        )
+    };
+    context
+        .process(&[increment(1), increment(2)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 3);
+    let err = context
+        .process(&[increment(3)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::NoCredits));
+
+    // Leaving credit mode frees mutations again.
+    context
+        .process(
+            &[
+                set_price(0),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 4),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 7);
+}
+
+#[tokio::test]
+async fn test_receive_wormhole_message() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let emitter_address = [7; 32];
+    context
+        .process(
+            &[sdk::configure_wormhole(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                2,
+                emitter_address,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    // Posts a VAA from `emitter_address` on `emitter_chain` the way the Core
+    // Bridge would and returns the instruction applying it.
+    let mut sequence = 0;
+    let mut post = |context: &mut TestContext, emitter_chain, op, value| {
+        sequence += 1;
+        let vaa = PostedVaa {
+            vaa_version: 1,
+            consistency_level: 1,
+            vaa_time: 1_700_000_000,
+            vaa_signature_account: Pubkey::new_unique(),
+            submission_time: 1_700_000_010,
+            nonce: 0,
+            sequence,
+            emitter_chain,
+            emitter_address,
+            payload: CounterMessage { counter, op, value }.pack(),
+        };
+        let data = vaa.pack();
+        context.program_context.set_account(
+            &wormhole::find_posted_vaa_address(&vaa.hash()).0,
+            &Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: wormhole::CORE_BRIDGE_ID,
+                executable: false,
+                rent_epoch: 0,
+            }
+            .into(),
+        );
+        sdk::receive_wormhole_message(&program_id, &counter, &vaa.hash(), &payer)
+    };
+
+    let increment = post(&mut context, 2, wormhole::OP_INCREMENT, 5);
+    context
+        .process(std::slice::from_ref(&increment), &[])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 5);
+    let err = context.process(&[increment], &[]).await.unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::VaaAlreadyConsumed as u32)
+        )
+    );
+
+    for instruction in [
+        post(&mut context, 3, wormhole::OP_INCREMENT, 1),
+        post(&mut context, 2, 9, 1),
+    ] {
+        let err = context.process(&[instruction], &[]).await.unwrap_err();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CounterError::InvalidVaa as u32)
+            )
+        );
+    }
+
+    let decrement = post(&mut context, 2, wormhole::OP_DECREMENT, 2);
+    let update = post(&mut context, 2, wormhole::OP_UPDATE, 40);
+    context.process(&[decrement], &[]).await.unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 3);
+    context.process(&[update], &[]).await.unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 40);
+}
+
+#[tokio::test]
+async fn test_governance() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let governance = Pubkey::new_unique();
+    let (native_treasury, _) =
+        governance::find_native_treasury_address(&governance::PROGRAM_ID, &governance);
+    // What executing a passed proposal holding `instruction` looks like.
+    let execute = |instruction: Instruction| Instruction {
+        program_id: governance::PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(governance, false),
+            AccountMeta::new_readonly(native_treasury, false),
+            AccountMeta::new_readonly(program_id, false),
+            AccountMeta::new(counter, false),
+        ],
+        data: instruction.data,
+    };
+    context
+        .process(
+            &[
+                sdk::update(&program_id, &counter, &[&authority.pubkey()], 7),
+                sdk::set_governance(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &governance::PROGRAM_ID,
+                    &governance,
+                ),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    for instruction in [
+        sdk::update(&program_id, &counter, &[&authority.pubkey()], 1),
+        sdk::reset(&program_id, &counter, &[&authority.pubkey()]),
+    ] {
+        let err = context
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CounterError::GovernanceRequired as u32)
+            )
+        );
+    }
+    // Increments don't answer to the governance.
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 8);
+
+    context
+        .process(
+            &[execute(sdk::update(
+                &program_id,
+                &counter,
+                &[&native_treasury],
+                40,
+            ))],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 40);
+    context
+        .process(
+            &[execute(sdk::reset(
+                &program_id,
+                &counter,
+                &[&native_treasury],
+            ))],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 0);
+}
+
+#[tokio::test]
+async fn test_crank() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let cranker = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let (treasury, _) = find_treasury_address(&program_id, &counter);
+    let rent = Rent::default().minimum_balance(0);
+    context
+        .process(
+            &[
+                // Enough for one tip and a bit of another.
+                system_instruction::transfer(&payer, &treasury, rent + 1_500),
+                system_instruction::transfer(&payer, &cranker.pubkey(), rent),
+                sdk::configure_crank(&program_id, &counter, &authority.pubkey(), 100, 1_000),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 5),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let crank = sdk::crank(&program_id, &counter, &cranker.pubkey());
+    let err = context
+        .process(std::slice::from_ref(&crank), &[&cranker])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::CrankNotDue as u32)
+        )
+    );
+
+    // The treasury runs dry on the second crank; the third still resets.
+    let mut slot = context.counter(&counter).await.last_crank_slot;
+    for tipped in [1_000, 1_500, 1_500] {
+        slot += 100;
+        context.program_context.warp_to_slot(slot).unwrap();
+        context
+            .process(
+                &[
+                    sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+                    crank.clone(),
+                ],
+                &[&authority, &cranker],
+            )
+            .await
+            .unwrap();
+        let counter_account = context.counter(&counter).await;
+        assert_eq!(counter_account.counter, 0);
+        assert_eq!(counter_account.last_crank_slot, slot);
+        let balance = context
+            .program_context
+            .banks_client
+            .get_balance(cranker.pubkey())
+            .await
+            .unwrap();
+        assert_eq!(balance, rent + tipped);
+    }
+}
+
+#[tokio::test]
+async fn test_get_stats() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    context
+        .process(
+            &[
+                sdk::update(&program_id, &counter, &[&authority.pubkey()], 10),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 5),
+                sdk::decrement(&program_id, &counter, &authority.pubkey(), 12),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+                sdk::reset(&program_id, &counter, &[&authority.pubkey()]),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let payer = &context.program_context.payer;
+    let transaction = Transaction::new_signed_with_payer(
+        &[sdk::get_stats(&program_id, &counter)],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.program_context.last_blockhash,
+    );
+    let simulation = context
+        .program_context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    assert_eq!(
+        CounterStats::unpack(&return_data.data).unwrap(),
+        CounterStats {
+            total_increments: 2,
+            total_decrements: 1,
+            total_resets: 1,
+            max_value_seen: 15,
+            min_value_seen: 0,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_last_write() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    context.program_context.warp_to_slot(100).unwrap();
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    let counter_account = context.counter(&counter).await;
+    assert_eq!(counter_account.last_updated_slot, clock.slot);
+    assert_eq!(counter_account.last_updated_ts, clock.unix_timestamp);
+    assert_eq!(counter_account.last_writer, authority.pubkey());
+}
+
+#[tokio::test]
+async fn test_history() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let increment = |value| {
+        sdk::with_history(
+            sdk::increment(&program_id, &counter, &authority.pubkey(), value),
+            &counter,
+        )
+    };
+    context
+        .process(
+            &[
+                sdk::initialize_history(&program_id, &counter, &authority.pubkey(), &payer, 3),
+                increment(1),
+                increment(2),
+                increment(3),
+                increment(4),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.history_capacity, 3);
+
+    let (history, _) = find_history_address(&program_id, &counter);
+    let account = context
+        .program_context
+        .banks_client
+        .get_account(history)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), HistoryHeader::account_len(3));
+    let header = HistoryHeader::unpack(&account.data).unwrap();
+    assert_eq!(header.counter, counter);
+    assert_eq!(header.count, 4);
+    // The first value was overwritten by the fourth.
+    let entries = HistoryHeader::unpack_entries(&account.data).unwrap();
+    assert_eq!(
+        entries.iter().map(|entry| entry.value).collect::<Vec<_>>(),
+        [3, 6, 10]
+    );
+    let slot = context.counter(&counter).await.last_updated_slot;
+    assert!(entries.iter().all(|entry| entry.slot == slot));
+
+    // Writes can't skip the history once it exists.
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    let err = context
+        .process(
+            &[sdk::initialize_history(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &payer,
+                5,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn test_checkpoint() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    let epoch = clock.epoch;
+    context
+        .process(
+            &[
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 5),
+                sdk::checkpoint(&program_id, &counter, &payer, epoch),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    // One checkpoint per epoch.
+    let err = context
+        .process(
+            &[
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+                sdk::checkpoint(&program_id, &counter, &payer, epoch),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::AccountAlreadyInitialized)
+    );
+    // Only the current epoch can be checkpointed.
+    let err = context
+        .process(
+            &[sdk::checkpoint(&program_id, &counter, &payer, epoch + 1)],
+            &[],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    let next_epoch = context
+        .program_context
+        .genesis_config()
+        .epoch_schedule
+        .get_first_slot_in_epoch(epoch + 1);
+    context.program_context.warp_to_slot(next_epoch).unwrap();
+    context
+        .process(
+            &[
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 2),
+                sdk::checkpoint(&program_id, &counter, &payer, epoch + 1),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    for (period_index, value) in [(epoch, 5), (epoch + 1, 7)] {
+        let (address, _) = find_checkpoint_address(&program_id, &counter, period_index);
+        let account = context
+            .program_context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap();
+        let checkpoint = Checkpoint::unpack(&account.data).unwrap();
+        assert_eq!(checkpoint.counter, counter);
+        assert_eq!(checkpoint.period_index, period_index);
+        assert_eq!(checkpoint.value, value);
+    }
+}
+
+#[tokio::test]
+async fn test_merge() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let source = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let destination = Pubkey::new_unique();
+    context
+        .process(
+            &[
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 3),
+                sdk::increment(&program_id, &source, &authority.pubkey(), 4),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    // Both counters must answer to the signer.
+    let other_authority = Keypair::new();
+    let other = context.create_counter(&other_authority).await;
+    let err = context
+        .process(
+            &[sdk::merge(
+                &program_id,
+                &counter,
+                &other,
+                &authority.pubkey(),
+                &destination,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+
+    context
+        .process(
+            &[sdk::merge(
+                &program_id,
+                &counter,
+                &source,
+                &authority.pubkey(),
+                &destination,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 7);
+    assert!(context
+        .program_context
+        .banks_client
+        .get_account(source)
+        .await
+        .unwrap()
+        .is_none());
+    let refund = context
+        .program_context
+        .banks_client
+        .get_balance(destination)
+        .await
+        .unwrap();
+    assert_eq!(refund, Rent::default().minimum_balance(CounterAccount::LEN));
+}
+
+#[tokio::test]
+async fn test_split() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                10,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let err = context
+        .process(
+            &[sdk::split(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &payer,
+                11,
+                "half",
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::InsufficientValue));
+
+    context
+        .process(
+            &[sdk::split(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                &payer,
+                4,
+                "half",
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 6);
+    let (new_counter, _) = find_counter_address(&program_id, &authority.pubkey(), "half");
+    let new_counter_account = context.counter(&new_counter).await;
+    assert_eq!(new_counter_account.counter, 4);
+    assert_eq!(new_counter_account.authority, authority.pubkey());
+}
+
+#[tokio::test]
+async fn test_transfer() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let other_authority = Keypair::new();
+    let destination = context.create_counter(&other_authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let (history, _) = find_history_address(&program_id, &destination);
+    context
+        .process(
+            &[
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 5),
+                sdk::initialize_history(
+                    &program_id,
+                    &destination,
+                    &other_authority.pubkey(),
+                    &payer,
+                    4,
+                ),
+            ],
+            &[&authority, &other_authority],
+        )
+        .await
+        .unwrap();
+
+    let transfer = |amount| {
+        sdk::transfer(
+            &program_id,
+            &counter,
+            &authority.pubkey(),
+            &destination,
+            Some(&history),
+            amount,
+        )
+    };
+    let err = context
+        .process(&[transfer(6)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::InsufficientValue));
+    // Only the source's authority signs.
+    context
+        .process(&[transfer(2)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 3);
+    assert_eq!(context.counter(&destination).await.counter, 2);
+
+    let account = context
+        .program_context
+        .banks_client
+        .get_account(history)
+        .await
+        .unwrap()
+        .unwrap();
+    let entries = HistoryHeader::unpack_entries(&account.data).unwrap();
+    assert_eq!(
+        entries.iter().map(|entry| entry.value).collect::<Vec<_>>(),
+        [2]
+    );
+
+    // Destinations pass the gates any write of theirs would.
+    let expired = context.create_counter(&other_authority).await;
+    let wrapping = context.create_counter(&other_authority).await;
+    context
+        .process(
+            &[
+                sdk::set_expiry(&program_id, &expired, &other_authority.pubkey(), 1, &payer),
+                sdk::set_modulus(&program_id, &wrapping, &other_authority.pubkey(), 3),
+            ],
+            &[&other_authority],
+        )
+        .await
+        .unwrap();
+    let transfer = |destination, amount| {
+        sdk::transfer(
+            &program_id,
+            &counter,
+            &authority.pubkey(),
+            destination,
+            None,
+            amount,
+        )
+    };
+    let err = context
+        .process(&[transfer(&expired, 1)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Expired));
+    // A modulus counter can't take more than fits below its modulus.
+    let err = context
+        .process(&[transfer(&wrapping, 3)], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Overflow));
+    context
+        .process(&[transfer(&wrapping, 2)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&wrapping).await.counter, 2);
+    assert_eq!(context.counter(&counter).await.counter, 1);
+}
+
+#[tokio::test]
+async fn test_clone() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    context
+        .process(
+            &[
+                sdk::set_rate_limit(&program_id, &counter, &authority.pubkey(), 3),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 8),
+                sdk::initialize_history(&program_id, &counter, &authority.pubkey(), &payer, 2),
+                sdk::clone(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &payer,
+                    "staging",
+                ),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let original = context.counter(&counter).await;
+    let (copy, _) = find_counter_address(&program_id, &authority.pubkey(), "staging");
+    assert_eq!(
+        context.counter(&copy).await,
+        CounterAccount {
+            history_capacity: 0,
+            ..original
+        }
+    );
+    // The copy is written without the original's history.
+    context
+        .process(
+            &[sdk::increment(&program_id, &copy, &authority.pubkey(), 1)],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&copy).await.counter, 9);
+    assert_eq!(context.counter(&counter).await.counter, 8);
+
+    // A source another program made up can't be passed off as a counter.
+    let forged = context.forge_counter(CounterAccount {
+        authority: authority.pubkey(),
+        counter: u32::MAX,
+        ..Zeroable::zeroed()
+    });
+    let err = context
+        .process(
+            &[sdk::clone(
+                &program_id,
+                &forged,
+                &authority.pubkey(),
+                &payer,
+                "forged",
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+}
+
+#[tokio::test]
+async fn test_clone_with_fee_and_lease() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    context
+        .process(
+            &[
+                sdk::set_fee(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &payer,
+                    1_000_000,
+                ),
+                sdk::list_for_lease(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &payer,
+                    LAMPORTS_PER_SOL,
+                    1_000,
+                ),
+                sdk::clone(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    &payer,
+                    "staging",
+                ),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    // The copy keeps the fee but pays it into its own treasury, and has no
+    // lease of its own to pass.
+    let (copy, _) = find_counter_address(&program_id, &authority.pubkey(), "staging");
+    let copy_account = context.counter(&copy).await;
+    assert_eq!(copy_account.fee_lamports, 1_000_000);
+    assert!(!copy_account.is_listed_for_lease());
+    assert_eq!(copy_account.discriminator, COUNTER_DISCRIMINATOR);
+    assert_eq!(copy_account.layout_version, COUNTER_LAYOUT_VERSION);
+    context
+        .process(
+            &[sdk::with_fee(
+                sdk::increment(&program_id, &copy, &authority.pubkey(), 1),
+                &copy,
+                &payer,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let (treasury, _) = find_treasury_address(&program_id, &copy);
+    assert_eq!(context.counter(&copy).await.counter, 1);
+    assert!(
+        context
+            .program_context
+            .banks_client
+            .get_balance(treasury)
+            .await
+            .unwrap()
+            >= 5_000
+    );
+}
+
+#[tokio::test]
+async fn test_set_authority() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let new_authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    context
+        .process(
+            &[
+                sdk::set_authority(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    Some(&new_authority.pubkey()),
+                ),
+                sdk::increment(&program_id, &counter, &new_authority.pubkey(), 2),
+            ],
+            &[&authority, &new_authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        context.counter(&counter).await.authority,
+        new_authority.pubkey()
+    );
+
+    context
+        .process(
+            &[sdk::set_authority(
+                &program_id,
+                &counter,
+                &new_authority.pubkey(),
+                None,
+            )],
+            &[&new_authority],
+        )
+        .await
+        .unwrap();
+    let counter_account = context.counter(&counter).await;
+    assert!(counter_account.is_renounced());
+    assert_eq!(counter_account.authority, Pubkey::default());
+
+    // Without an authority the counter is frozen, not open to anyone.
+    let anyone = Keypair::new();
+    for instruction in [
+        sdk::increment(&program_id, &counter, &anyone.pubkey(), 1),
+        sdk::initialize(
+            &program_id,
+            &counter,
+            &anyone.pubkey(),
+            &context.program_context.payer.pubkey(),
+        ),
+    ] {
+        let err = context
+            .process(&[instruction], &[&anyone])
+            .await
+            .unwrap_err();
+        assert_eq!(err, custom_error(CounterError::Renounced));
+    }
+    assert_eq!(context.counter(&counter).await.counter, 2);
+}
+
+#[tokio::test]
+async fn test_freeze() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    context
+        .process(
+            &[
+                sdk::set_decay(&program_id, &counter, &authority.pubkey(), 2),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 100),
+                sdk::freeze(&program_id, &counter, &authority.pubkey()),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+    let frozen = context.counter(&counter).await;
+    context
+        .program_context
+        .warp_to_slot(frozen.last_updated_slot + 10)
+        .unwrap();
+    // Not even the authority can write, and the value doesn't decay.
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Frozen));
+
+    // Decay resumes from the thaw, not from before the freeze.
+    context
+        .process(
+            &[
+                sdk::thaw(&program_id, &counter, &authority.pubkey()),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let thawed = context.counter(&counter).await;
+    assert!(!thawed.is_frozen());
+    assert_eq!(thawed.counter, 101);
+}
+
+#[tokio::test]
+async fn test_expiry() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let reaper = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let recipient = Pubkey::new_unique();
+    let clock: Clock = context
+        .program_context
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    let expires_at_slot = clock.slot + 10;
+    let rent = Rent::default().minimum_balance(0);
+    context
+        .process(
+            &[
+                system_instruction::transfer(&payer, &reaper.pubkey(), rent),
+                sdk::set_expiry(
+                    &program_id,
+                    &counter,
+                    &authority.pubkey(),
+                    expires_at_slot,
+                    &recipient,
+                ),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let reap = sdk::reap(&program_id, &counter, &recipient, &reaper.pubkey());
+    let err = context
+        .process(std::slice::from_ref(&reap), &[&reaper])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::NotExpired));
+
+    context
+        .program_context
+        .warp_to_slot(expires_at_slot)
+        .unwrap();
+    let err = context
+        .process(
+            &[sdk::increment(
+                &program_id,
+                &counter,
+                &authority.pubkey(),
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Expired));
+    let err = context
+        .process(
+            &[sdk::reap(
+                &program_id,
+                &counter,
+                &Pubkey::new_unique(),
+                &reaper.pubkey(),
+            )],
+            &[&reaper],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    context.process(&[reap], &[&reaper]).await.unwrap();
+    let lamports = Rent::default().minimum_balance(CounterAccount::LEN);
+    let reaper_share = lamports * REAPER_SHARE_BPS / 10_000;
+    let banks_client = &mut context.program_context.banks_client;
+    assert!(banks_client.get_account(counter).await.unwrap().is_none());
+    assert_eq!(
+        banks_client.get_balance(recipient).await.unwrap(),
+        lamports - reaper_share
+    );
+    assert_eq!(
+        banks_client.get_balance(reaper.pubkey()).await.unwrap(),
+        rent + reaper_share
+    );
+}
+
+#[tokio::test]
+async fn test_reap_abandoned() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let reaper = Keypair::new();
+    context.program_context.warp_to_epoch(1).unwrap();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    // Enough to pay the fee for reaping, too.
+    let rent = 2 * Rent::default().minimum_balance(0);
+    context
+        .process(
+            &[
+                system_instruction::transfer(&payer, &reaper.pubkey(), rent),
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.rent_payer, payer);
+
+    // Idle, but not for long enough.
+    context
+        .program_context
+        .warp_to_epoch(ABANDONED_AFTER_EPOCHS)
+        .unwrap();
+    let reap = sdk::reap(&program_id, &counter, &payer, &reaper.pubkey());
+    let err = context
+        .process(std::slice::from_ref(&reap), &[&reaper])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::NotExpired));
+
+    context
+        .program_context
+        .warp_to_epoch(ABANDONED_AFTER_EPOCHS + 1)
+        .unwrap();
+    let payer_lamports = context
+        .program_context
+        .banks_client
+        .get_balance(payer)
+        .await
+        .unwrap();
+    // The reaper pays the fee, so the payer's balance only grows by its share.
+    let blockhash = context
+        .program_context
+        .get_new_latest_blockhash()
+        .await
+        .unwrap();
+    let transaction =
+        Transaction::new_signed_with_payer(&[reap], Some(&reaper.pubkey()), &[&reaper], blockhash);
+    context
+        .program_context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let lamports = Rent::default().minimum_balance(CounterAccount::LEN);
+    let reaper_share = lamports * REAPER_SHARE_BPS / 10_000;
+    let banks_client = &mut context.program_context.banks_client;
+    assert!(banks_client.get_account(counter).await.unwrap().is_none());
+    assert_eq!(
+        banks_client.get_balance(payer).await.unwrap(),
+        payer_lamports + lamports - reaper_share
+    );
+}
+
+#[tokio::test]
+async fn test_signed_counter() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+    context
+        .process(
+            &[
+                sdk::set_kind(&program_id, &counter, &signer, KIND_I64),
+                sdk::decrement(&program_id, &counter, &signer, 5),
+                sdk::increment(&program_id, &counter, &signer, 2),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.counter(&counter).await;
+    assert!(account.is_signed());
+    assert_eq!(account.signed_counter, -3);
+    assert_eq!(account.counter, 0);
+
+    let payer = &context.program_context.payer;
+    let transaction = Transaction::new_signed_with_payer(
+        &[sdk::get(&program_id, &counter)],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.program_context.last_blockhash,
+    );
+    let simulation = context
+        .program_context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    assert_eq!(return_data.data, (-3i64).to_le_bytes());
+
+    // The kind is fixed after the first write, and `u32`-only features stay off.
+    let err = context
+        .process(
+            &[sdk::set_kind(&program_id, &counter, &signer, KIND_I64)],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::KindLocked));
+    let err = context
+        .process(
+            &[sdk::set_decay(&program_id, &counter, &signer, 1)],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::UnsupportedKind));
+
+    context
+        .process(
+            &[sdk::reset(&program_id, &counter, &[&signer])],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.signed_counter, 0);
+}
+
+#[tokio::test]
+async fn test_wide_counter() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let narrow = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+    let increment = sdk::increment_wide(&program_id, &counter, &signer, u64::MAX, OVERFLOW_CHECKED);
+    context
+        .process(
+            &[
+                sdk::set_kind(&program_id, &counter, &signer, KIND_U128),
+                increment.clone(),
+                increment,
+                sdk::increment(&program_id, &counter, &signer, 2),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.counter(&counter).await;
+    assert!(account.is_wide());
+    assert_eq!(account.wide_value(), 2 * u128::from(u64::MAX) + 2);
+
+    let payer = &context.program_context.payer;
+    let transaction = Transaction::new_signed_with_payer(
+        &[sdk::get(&program_id, &counter)],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.program_context.last_blockhash,
+    );
+    let simulation = context
+        .program_context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    assert_eq!(
+        return_data.data,
+        (2 * u128::from(u64::MAX) + 2).to_le_bytes()
+    );
+
+    context
+        .process(
+            &[sdk::decrement_wide(
+                &program_id,
+                &counter,
+                &signer,
+                u64::MAX,
+                OVERFLOW_CHECKED,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        context.counter(&counter).await.wide_value(),
+        u128::from(u64::MAX) + 2
+    );
+
+    // A `u32` counter takes wide amounts only while they fit.
+    let err = context
+        .process(
+            &[sdk::increment_wide(
+                &program_id,
+                &narrow,
+                &signer,
+                u64::from(u32::MAX) + 1,
+                OVERFLOW_CHECKED,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Overflow));
+    context
+        .process(
+            &[sdk::increment_wide(
+                &program_id,
+                &narrow,
+                &signer,
+                u64::from(u32::MAX) + 1,
+                OVERFLOW_SATURATING,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&narrow).await.counter, u32::MAX);
+}
+
+#[tokio::test]
+async fn test_decimals() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+    // 1.235 rounds to 1.24 or down to 1.23, and 5 is 5.00.
+    context
+        .process(
+            &[
+                sdk::set_decimals(&program_id, &counter, &signer, 2),
+                sdk::increment_scaled(
+                    &program_id,
+                    &counter,
+                    &signer,
+                    1_235,
+                    3,
+                    ROUND_NEAREST,
+                    OVERFLOW_CHECKED,
+                ),
+                sdk::increment_scaled(
+                    &program_id,
+                    &counter,
+                    &signer,
+                    1_235,
+                    3,
+                    ROUND_DOWN,
+                    OVERFLOW_CHECKED,
+                ),
+                sdk::increment_scaled(
+                    &program_id,
+                    &counter,
+                    &signer,
+                    5,
+                    0,
+                    ROUND_DOWN,
+                    OVERFLOW_CHECKED,
+                ),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.counter(&counter).await;
+    assert_eq!(account.decimals, 2);
+    assert_eq!(account.counter, 747);
+
+    let err = context
+        .process(
+            &[sdk::decrement_scaled(
+                &program_id,
+                &counter,
+                &signer,
+                1,
+                0,
+                3,
+                OVERFLOW_CHECKED,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+    let err = context
+        .process(
+            &[sdk::set_decimals(&program_id, &counter, &signer, 3)],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::NonZeroValue));
+}
+
+#[tokio::test]
+async fn test_step() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+    context
+        .process(
+            &[
+                sdk::set_step(&program_id, &counter, &signer, 10),
+                sdk::increment(&program_id, &counter, &signer, 30),
+                sdk::decrement(&program_id, &counter, &signer, 10),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.counter(&counter).await;
+    assert_eq!(account.step, 10);
+    assert_eq!(account.counter, 20);
+
+    for instruction in [
+        sdk::increment(&program_id, &counter, &signer, 15),
+        sdk::decrement(&program_id, &counter, &signer, 5),
+        sdk::increment_wide(&program_id, &counter, &signer, 1, OVERFLOW_CHECKED),
+    ] {
+        let err = context
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap_err();
+        assert_eq!(err, custom_error(CounterError::InvalidStep));
+    }
+
+    // Zero lifts the step again.
+    context
+        .process(
+            &[
+                sdk::set_step(&program_id, &counter, &signer, 0),
+                sdk::increment(&program_id, &counter, &signer, 3),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 23);
+}
+
+#[tokio::test]
+async fn test_modulus() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+    context
+        .process(
+            &[sdk::set_modulus(&program_id, &counter, &signer, 60)],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.modulus, 60);
+
+    // A clock's minutes: 50, then past the hour to 5, back before it to 55,
+    // and u64::MAX, which is 15 modulo 60, onwards to 10.
+    for (instruction, expected) in [
+        (sdk::increment(&program_id, &counter, &signer, 50), 50),
+        (sdk::increment(&program_id, &counter, &signer, 15), 5),
+        (sdk::decrement(&program_id, &counter, &signer, 10), 55),
+        (
+            sdk::increment_wide(&program_id, &counter, &signer, u64::MAX, OVERFLOW_CHECKED),
+            10,
+        ),
+        // An Update isn't bound by the modulus, but the next decrement is.
+        (sdk::update(&program_id, &counter, &[&signer], 100), 100),
+        (sdk::decrement(&program_id, &counter, &signer, 1), 39),
+    ] {
+        context
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap();
+        assert_eq!(context.counter(&counter).await.counter, expected);
+    }
+
+    let err = context
+        .process(
+            &[sdk::increment_with_mode(
+                &program_id,
+                &counter,
+                &signer,
+                1,
+                3,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+
+    // Zero lifts the modulus again.
+    context
+        .process(
+            &[
+                sdk::set_modulus(&program_id, &counter, &signer, 0),
+                sdk::increment(&program_id, &counter, &signer, 30),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 69);
+}
+
+#[tokio::test]
+async fn test_countdown() {
+    let mut context = TestContext::new().await;
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let program_id = context.program_id;
+    let signer = authority.pubkey();
+    context
+        .process(
+            &[
+                sdk::increment(&program_id, &counter, &signer, 7),
+                sdk::set_countdown(&program_id, &counter, &signer, 3),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.counter(&counter).await;
+    assert_eq!(account.countdown, COUNTDOWN_RUNNING);
+    assert_eq!(account.counter, 3);
+
+    // Nothing but a decrement moves a countdown.
+    for instruction in [
+        sdk::increment(&program_id, &counter, &signer, 1),
+        sdk::update(&program_id, &counter, &[&signer], 9),
+        sdk::reset(&program_id, &counter, &[&signer]),
+    ] {
+        let err = context
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap_err();
+        assert_eq!(err, custom_error(CounterError::CountdownOnly));
+    }
+
+    // Decrements stop at zero, even checked ones, and the last one locks it.
+    context
+        .process(
+            &[
+                sdk::decrement(&program_id, &counter, &signer, 1),
+                sdk::decrement_with_mode(&program_id, &counter, &signer, 5, OVERFLOW_CHECKED),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.counter(&counter).await;
+    assert_eq!(account.countdown, COUNTDOWN_COMPLETED);
+    assert_eq!(account.counter, 0);
+    let err = context
+        .process(
+            &[sdk::decrement(&program_id, &counter, &signer, 1)],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::CountdownCompleted));
+
+    // Only the authority re-arms it.
+    let stranger = Keypair::new();
+    let err = context
+        .process(
+            &[sdk::set_countdown(
+                &program_id,
+                &counter,
+                &stranger.pubkey(),
+                5,
+            )],
+            &[&stranger],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+    context
+        .process(
+            &[
+                sdk::set_countdown(&program_id, &counter, &signer, 5),
+                sdk::decrement(&program_id, &counter, &signer, 2),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.counter(&counter).await;
+    assert_eq!(account.countdown, COUNTDOWN_RUNNING);
+    assert_eq!(account.counter, 3);
+
+    // A target of zero leaves countdown mode where it stands.
+    context
+        .process(
+            &[
+                sdk::set_countdown(&program_id, &counter, &signer, 0),
+                sdk::increment(&program_id, &counter, &signer, 1),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.counter(&counter).await;
+    assert_eq!(account.countdown, COUNTDOWN_OFF);
+    assert_eq!(account.counter, 4);
+}
+
+#[tokio::test]
+async fn test_program_config() {
+    let mut context = TestContext::new().await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let super_admin = Keypair::new();
+    let fee = Rent::default().minimum_balance(0);
+    context
+        .process(
+            &[sdk::initialize_config(
+                &program_id,
+                &payer,
+                &super_admin.pubkey(),
+                fee,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    let err = context
+        .process(
+            &[sdk::initialize_config(&program_id, &payer, &payer, 0)],
+            &[],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    // Counters created from now on are governed and charge the default fee.
+    let authority = Keypair::new();
+    let counter = context.create_counter(&authority).await;
+    let account = context.counter(&counter).await;
+    assert!(account.is_governed());
+    assert_eq!(account.fee_lamports, fee);
+    let increment = || {
+        sdk::with_config(sdk::with_fee(
+            sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+            &counter,
+            &payer,
+        ))
+    };
+    context
+        .process(&[increment()], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(context.counter(&counter).await.counter, 1);
+
+    let err = context
+        .process(
+            &[sdk::update_config(&program_id, &payer, &payer, 0, true)],
+            &[],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+    context
+        .process(
+            &[sdk::update_config(
+                &program_id,
+                &super_admin.pubkey(),
+                &super_admin.pubkey(),
+                fee,
+                true,
+            )],
+            &[&super_admin],
+        )
+        .await
+        .unwrap();
+    let err = context
+        .process(&[increment()], &[&authority])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::ProgramPaused));
+
+    // Leaving the config out doesn't get around the pause.
+    let err = context
+        .process(
+            &[sdk::with_fee(
+                sdk::increment(&program_id, &counter, &authority.pubkey(), 1),
+                &counter,
+                &payer,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+}
+
+#[tokio::test]
+async fn test_counter_array() {
+    let mut context = TestContext::new().await;
+    let program_id = context.program_id;
+    let authority = Keypair::new();
+    let signer = authority.pubkey();
+    let array = Keypair::new();
+    let len = CounterArrayHeader::account_len(4);
+    context
+        .process(
+            &[
+                system_instruction::create_account(
+                    &context.program_context.payer.pubkey(),
+                    &array.pubkey(),
+                    Rent::default().minimum_balance(len),
+                    len as u64,
+                    &program_id,
+                ),
+                sdk::initialize_array(&program_id, &array.pubkey(), &signer, 4),
+                sdk::increment_at(&program_id, &array.pubkey(), &signer, 1, 5),
+                sdk::increment_at(&program_id, &array.pubkey(), &signer, 3, 9),
+                sdk::decrement_at(&program_id, &array.pubkey(), &signer, 3, 2),
+                sdk::update_at(&program_id, &array.pubkey(), &signer, 0, 42),
+            ],
+            &[&array, &authority],
+        )
+        .await
+        .unwrap();
+    let data = context.data(&array.pubkey()).await;
+    assert_eq!(CounterArrayHeader::unpack(&data).unwrap().authority, signer);
+    assert_eq!(
+        CounterArrayHeader::unpack_values(&data).unwrap(),
+        [42, 5, 0, 7]
+    );
+
+    let err = context
+        .process(
+            &[sdk::increment_at(
+                &program_id,
+                &array.pubkey(),
+                &signer,
+                4,
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::IndexOutOfBounds));
+    let err = context
+        .process(
+            &[sdk::decrement_at(
+                &program_id,
+                &array.pubkey(),
+                &signer,
+                2,
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Underflow));
+    let other = Keypair::new();
+    let err = context
+        .process(
+            &[sdk::update_at(
+                &program_id,
+                &array.pubkey(),
+                &other.pubkey(),
+                0,
+                0,
+            )],
+            &[&other],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Unauthorized));
+    let err = context
+        .process(
+            &[sdk::initialize_array(
+                &program_id,
+                &array.pubkey(),
+                &other.pubkey(),
+                4,
+            )],
+            &[&other],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn test_keyed_counters() {
+    let mut context = TestContext::new().await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let authority = Keypair::new();
+    let signer = authority.pubkey();
+    let keyed = Keypair::new();
+    let len = KeyedCounterHeader::account_len(2);
+    let (apples, pears, plums) = ([3; 32], [1; 32], [2; 32]);
+    context
+        .process(
+            &[
+                system_instruction::create_account(
+                    &payer,
+                    &keyed.pubkey(),
+                    Rent::default().minimum_balance(len),
+                    len as u64,
+                    &program_id,
+                ),
+                sdk::initialize_keyed(&program_id, &keyed.pubkey(), &signer, 2),
+                sdk::increment_keyed(&program_id, &keyed.pubkey(), &signer, apples, 5),
+                sdk::increment_keyed(&program_id, &keyed.pubkey(), &signer, pears, 1),
+                sdk::increment_keyed(&program_id, &keyed.pubkey(), &signer, apples, 2),
+                sdk::decrement_keyed(&program_id, &keyed.pubkey(), &signer, pears, 1),
+            ],
+            &[&keyed, &authority],
+        )
+        .await
+        .unwrap();
+    let account = context.data(&keyed.pubkey()).await;
+    assert_eq!(KeyedCounterHeader::get(&account, &apples), Ok(Some(7)));
+    assert_eq!(KeyedCounterHeader::get(&account, &plums), Ok(None));
+    // Entries are kept sorted by key.
+    assert_eq!(
+        KeyedCounterHeader::unpack_entries(&account).unwrap(),
+        [
+            KeyedEntry {
+                key: pears,
+                value: 0
+            },
+            KeyedEntry {
+                key: apples,
+                value: 7
+            },
+        ]
+    );
+
+    let err = context
+        .process(
+            &[sdk::increment_keyed(
+                &program_id,
+                &keyed.pubkey(),
+                &signer,
+                plums,
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::KeyedFull));
+    let err = context
+        .process(
+            &[sdk::decrement_keyed(
+                &program_id,
+                &keyed.pubkey(),
+                &signer,
+                plums,
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::KeyNotFound));
+
+    // Growing the account, or removing an entry, makes room.
+    context
+        .process(
+            &[
+                sdk::resize_keyed(&program_id, &keyed.pubkey(), &signer, &payer, 3),
+                sdk::increment_keyed(&program_id, &keyed.pubkey(), &signer, plums, 4),
+                sdk::remove_keyed(&program_id, &keyed.pubkey(), &signer, pears),
+            ],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+    let account = context.data(&keyed.pubkey()).await;
+    assert_eq!(account.len(), KeyedCounterHeader::account_len(3));
+    let header = KeyedCounterHeader::unpack(&account).unwrap();
+    assert_eq!((header.len, header.capacity), (2, 3));
+    assert_eq!(
+        KeyedCounterHeader::unpack_entries(&account).unwrap(),
+        [
+            KeyedEntry {
+                key: plums,
+                value: 4
+            },
+            KeyedEntry {
+                key: apples,
+                value: 7
+            },
+        ]
+    );
+
+    let err = context
+        .process(
+            &[sdk::resize_keyed(
+                &program_id,
+                &keyed.pubkey(),
+                &signer,
+                &payer,
+                1,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_compressed_counters() {
+    let mut context = TestContext::new().await;
+    let program_id = context.program_id;
+    let payer = context.program_context.payer.pubkey();
+    let authority = Keypair::new();
+    let signer = authority.pubkey();
+    let tree = Keypair::new();
+    let len = 8 + 32 * 4;
+    context
+        .process(
+            &[
+                system_instruction::create_account(
+                    &payer,
+                    &tree.pubkey(),
+                    Rent::default().minimum_balance(len),
+                    len as u64,
+                    &compression::PROGRAM_ID,
+                ),
+                sdk::initialize_compressed_tree(&program_id, &tree.pubkey(), 14, 64),
+                sdk::create_compressed(&program_id, &tree.pubkey(), &payer),
+                sdk::create_compressed(&program_id, &tree.pubkey(), &signer),
+                sdk::increment_compressed(
+                    &program_id,
+                    &tree.pubkey(),
+                    &signer,
+                    [0; 32],
+                    1,
+                    0,
+                    5,
+                    &[],
+                ),
+                sdk::decrement_compressed(
+                    &program_id,
+                    &tree.pubkey(),
+                    &signer,
+                    [0; 32],
+                    1,
+                    5,
+                    2,
+                    &[],
+                ),
+            ],
+            &[&tree, &authority],
+        )
+        .await
+        .unwrap();
+    let account = context.data(&tree.pubkey()).await;
+    assert_eq!(account[..4], 2u32.to_le_bytes());
+    assert_eq!(account[8..40], compression::leaf(&payer, 0));
+    assert_eq!(account[40..72], compression::leaf(&signer, 3));
+
+    // The leaf commits to the value and authority, so neither can be forged.
+    let err = context
+        .process(
+            &[sdk::increment_compressed(
+                &program_id,
+                &tree.pubkey(),
+                &signer,
+                [0; 32],
+                1,
+                4,
+                1,
+                &[],
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    let err = context
+        .process(
+            &[sdk::increment_compressed(
+                &program_id,
+                &tree.pubkey(),
+                &payer,
+                [0; 32],
+                1,
+                3,
+                1,
+                &[],
+            )],
+            &[],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    let err = context
+        .process(
+            &[sdk::decrement_compressed(
+                &program_id,
+                &tree.pubkey(),
+                &signer,
+                [0; 32],
+                1,
+                3,
+                4,
+                &[],
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_error(CounterError::Underflow));
+}
@@ -0,0 +1,468 @@
+//! Property tests comparing the processor against a reference model.
+
+// `counter_account()` nests a strategy per field of `CounterAccount`.
+#![recursion_limit = "256"]
+
+use counter::{
+    error::CounterError,
+    instructions::{
+        AndArgs, CounterInstructions, DecrementArgs, IncrementArgs, OrArgs, ShiftLeftArgs,
+        ShiftRightArgs, UpdateArgs, XorArgs,
+    },
+    process_instruction,
+    state::{
+        rescale, CounterValue, OVERFLOW_CHECKED, OVERFLOW_SATURATING, OVERFLOW_WRAPPING,
+        ROUND_DOWN, ROUND_NEAREST, ROUND_UP,
+    },
+    CounterAccount, MAX_SIGNERS,
+};
+use proptest::prelude::*;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::{Clock, Epoch},
+    entrypoint::SUCCESS,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+};
+
+/// Writes read the clock, which only the runtime provides; this one stays at
+/// slot zero.
+struct ClockStub;
+
+impl SyscallStubs for ClockStub {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: `Clock::get` passes a pointer to a `Clock`.
+        unsafe { var_addr.cast::<Clock>().write(Clock::default()) };
+        SUCCESS
+    }
+}
+
+fn stub_clock() {
+    static STUB: std::sync::Once = std::sync::Once::new();
+    STUB.call_once(|| {
+        set_syscall_stubs(Box::new(ClockStub));
+    });
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Increment(u32, u8),
+    Decrement(u32, u8),
+    Update(u32),
+    Reset,
+    And(u32),
+    Or(u32),
+    Xor(u32),
+    ShiftLeft(u8),
+    ShiftRight(u8),
+}
+
+impl Op {
+    fn instruction_data(&self) -> Vec<u8> {
+        match *self {
+            Op::Increment(value, mode) => {
+                CounterInstructions::Increment(IncrementArgs { value, mode })
+            }
+            Op::Decrement(value, mode) => {
+                CounterInstructions::Decrement(DecrementArgs { value, mode })
+            }
+            Op::Update(value) => CounterInstructions::Update(UpdateArgs { value }),
+            Op::Reset => CounterInstructions::Reset,
+            Op::And(value) => CounterInstructions::And(AndArgs { value }),
+            Op::Or(value) => CounterInstructions::Or(OrArgs { value }),
+            Op::Xor(value) => CounterInstructions::Xor(XorArgs { value }),
+            Op::ShiftLeft(bits) => CounterInstructions::ShiftLeft(ShiftLeftArgs { bits }),
+            Op::ShiftRight(bits) => CounterInstructions::ShiftRight(ShiftRightArgs { bits }),
+        }
+        .pack()
+    }
+
+    /// Reference semantics of each overflow mode.
+    fn apply(&self, model: u32) -> Result<u32, ProgramError> {
+        match *self {
+            Op::Increment(value, OVERFLOW_CHECKED) => model
+                .checked_add(value)
+                .ok_or_else(|| CounterError::Overflow.into()),
+            Op::Increment(value, OVERFLOW_SATURATING) => Ok(model.saturating_add(value)),
+            Op::Increment(value, OVERFLOW_WRAPPING) => Ok(model.wrapping_add(value)),
+            Op::Decrement(value, OVERFLOW_CHECKED) => model
+                .checked_sub(value)
+                .ok_or_else(|| CounterError::Underflow.into()),
+            Op::Decrement(value, OVERFLOW_SATURATING) => Ok(model.saturating_sub(value)),
+            Op::Decrement(value, OVERFLOW_WRAPPING) => Ok(model.wrapping_sub(value)),
+            Op::Increment(..) | Op::Decrement(..) => Err(ProgramError::InvalidInstructionData),
+            Op::Update(value) => Ok(value),
+            Op::Reset => Ok(0),
+            Op::And(value) => Ok(model & value),
+            Op::Or(value) => Ok(model | value),
+            Op::Xor(value) => Ok(model ^ value),
+            Op::ShiftLeft(bits) => model
+                .checked_shl(bits.into())
+                .ok_or_else(|| CounterError::InvalidShift.into()),
+            Op::ShiftRight(bits) => model
+                .checked_shr(bits.into())
+                .ok_or_else(|| CounterError::InvalidShift.into()),
+        }
+    }
+}
+
+fn value() -> impl Strategy<Value = u32> {
+    prop_oneof![0..1_000u32, any::<u32>(), Just(u32::MAX)]
+}
+
+fn mode() -> impl Strategy<Value = u8> {
+    prop_oneof![
+        Just(OVERFLOW_CHECKED),
+        Just(OVERFLOW_SATURATING),
+        Just(OVERFLOW_WRAPPING),
+        any::<u8>(),
+    ]
+}
+
+fn bits() -> impl Strategy<Value = u8> {
+    prop_oneof![0..32u8, any::<u8>()]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (value(), mode()).prop_map(|(value, mode)| Op::Increment(value, mode)),
+        (value(), mode()).prop_map(|(value, mode)| Op::Decrement(value, mode)),
+        value().prop_map(Op::Update),
+        Just(Op::Reset),
+        value().prop_map(Op::And),
+        value().prop_map(Op::Or),
+        value().prop_map(Op::Xor),
+        bits().prop_map(Op::ShiftLeft),
+        bits().prop_map(Op::ShiftRight),
+    ]
+}
+
+// `counter_account` builds the signer array with `uniform11`.
+const _: () = assert!(MAX_SIGNERS == 11);
+
+fn pubkey() -> impl Strategy<Value = Pubkey> {
+    any::<[u8; 32]>().prop_map(Pubkey::new_from_array)
+}
+
+prop_compose! {
+    fn counter_account()(
+        counter in any::<u32>(),
+        authority in pubkey(),
+        paused in any::<u8>(),
+        pending_authority in pubkey(),
+        multisig_threshold in any::<u8>(),
+        multisig_signer_count in any::<u8>(),
+        multisig_signers in prop::array::uniform11(pubkey()),
+        delegate in pubkey(),
+        delegated_allowance in any::<u64>(),
+        epoch_scoped in any::<u8>(),
+        last_write_epoch in any::<u64>(),
+        decay_per_slot in any::<u64>(),
+        last_updated_slot in any::<u64>(),
+        max_ops_per_slot in any::<u32>(),
+        ops_in_slot in any::<u32>(),
+        last_slot in any::<u64>(),
+        daily_cap in any::<u64>(),
+        fee_lamports in any::<u64>(),
+        payment_mint in pubkey(),
+        payment_price in any::<u64>(),
+        nft_gate_collection in pubkey(),
+        reward_mint in pubkey(),
+        reward_amount in any::<u64>(),
+        milestone_every in any::<u32>(),
+        rewarded_milestones in any::<u32>(),
+        nft_target in any::<u32>(),
+        nft_claimed in any::<u8>(),
+        oracle_feed_id in any::<[u8; 32]>(),
+        oracle_max_age_seconds in any::<u32>(),
+        oracle_exponent in any::<i32>(),
+        oracle_max_confidence_bps in any::<u16>(),
+        cooldown_unit in any::<u8>(),
+        cooldown in any::<u32>(),
+        random_account in pubkey(),
+        random_seed_slot in any::<u64>(),
+        random_max in any::<u32>(),
+        random_pending in any::<u8>(),
+        allowlist_enabled in any::<u8>(),
+        blocklist_enabled in any::<u8>(),
+        memo_required in any::<u8>(),
+        stake_weighted in any::<u8>(),
+        boost_min_sol in any::<u16>(),
+        boost_factor in any::<u8>(),
+        offchain_signer in pubkey(),
+        offchain_nonce in any::<u64>(),
+        eth_authority in any::<[u8; 20]>(),
+        credit_price_lamports in any::<u32>(),
+        eth_nonce in any::<u64>(),
+        wormhole_emitter_address in any::<[u8; 32]>(),
+        wormhole_emitter_chain in any::<u16>(),
+        referral_share_bps in any::<u16>(),
+        referrals_enabled in any::<u8>(),
+        lease_listed in any::<u8>(),
+        countdown in any::<u8>(),
+        governance in pubkey(),
+        governance_treasury in pubkey(),
+        crank_every_slots in any::<u64>(),
+        crank_tip_lamports in any::<u64>(),
+        last_crank_slot in any::<u64>(),
+        total_increments in any::<u64>(),
+        total_decrements in any::<u64>(),
+        total_resets in any::<u64>(),
+        max_value_seen in any::<u32>(),
+        min_value_seen in any::<u32>(),
+        values_seen in any::<u8>(),
+        last_updated_ts in any::<i64>(),
+        last_writer in pubkey(),
+        history_capacity in any::<u32>(),
+        modulus in any::<u32>(),
+        renounced in any::<u8>(),
+        frozen in any::<u8>(),
+        expires_at_slot in any::<u64>(),
+        expiry_recipient in pubkey(),
+        last_active_epoch in any::<u64>(),
+        rent_payer in pubkey(),
+        kind in any::<u8>(),
+        decimals in any::<u8>(),
+        signed_counter in any::<i64>(),
+        wide_counter in any::<[u8; 16]>(),
+        step in any::<u32>(),
+        governed in any::<u8>(),
+        treasury_bump in any::<u8>(),
+        reward_authority_bump in any::<u8>(),
+        discriminator in any::<u8>(),
+        layout_version in any::<u8>(),
+    ) -> CounterAccount {
+        CounterAccount {
+            counter,
+            paused,
+            multisig_threshold,
+            multisig_signer_count,
+            discriminator,
+            authority,
+            pending_authority,
+            delegate,
+            delegated_allowance,
+            multisig_signers,
+            epoch_scoped,
+            _reserved: [0; 7],
+            last_write_epoch,
+            decay_per_slot,
+            last_updated_slot,
+            max_ops_per_slot,
+            ops_in_slot,
+            last_slot,
+            daily_cap,
+            fee_lamports,
+            payment_mint,
+            payment_price,
+            nft_gate_collection,
+            reward_mint,
+            reward_amount,
+            milestone_every,
+            rewarded_milestones,
+            nft_target,
+            nft_claimed,
+            _padding2: [0; 3],
+            oracle_feed_id,
+            oracle_max_age_seconds,
+            oracle_exponent,
+            oracle_max_confidence_bps,
+            cooldown_unit,
+            _padding3: [0; 1],
+            cooldown,
+            random_account,
+            random_seed_slot,
+            random_max,
+            random_pending,
+            _padding4: [0; 3],
+            allowlist_enabled,
+            blocklist_enabled,
+            memo_required,
+            stake_weighted,
+            boost_min_sol,
+            boost_factor,
+            _padding5: [0; 1],
+            offchain_signer,
+            offchain_nonce,
+            eth_authority,
+            credit_price_lamports,
+            eth_nonce,
+            wormhole_emitter_address,
+            wormhole_emitter_chain,
+            referral_share_bps,
+            referrals_enabled,
+            lease_listed,
+            countdown,
+            _padding7: [0; 1],
+            governance,
+            governance_treasury,
+            crank_every_slots,
+            crank_tip_lamports,
+            last_crank_slot,
+            total_increments,
+            total_decrements,
+            total_resets,
+            max_value_seen,
+            min_value_seen,
+            values_seen,
+            _padding8: [0; 7],
+            last_updated_ts,
+            last_writer,
+            history_capacity,
+            modulus,
+            renounced,
+            frozen,
+            _padding10: [0; 6],
+            expires_at_slot,
+            expiry_recipient,
+            last_active_epoch,
+            rent_payer,
+            kind,
+            decimals,
+            _padding11: [0; 6],
+            signed_counter,
+            wide_counter,
+            step,
+            governed,
+            treasury_bump,
+            reward_authority_bump,
+            layout_version,
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn processor_matches_model(ops in prop::collection::vec(op(), 1..64)) {
+        stub_clock();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0; CounterAccount::LEN];
+        let owner = Pubkey::default();
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        // Update and Reset need somebody signing.
+        let signer_key = Pubkey::new_unique();
+        let mut signer_lamports = 0;
+        let mut signer_data = vec![];
+        let signer = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut signer_lamports,
+            &mut signer_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let accounts = vec![account, signer];
+
+        let mut model = 0u32;
+        for op in &ops {
+            let expected = op.apply(model);
+            let result = process_instruction(&program_id, &accounts, &op.instruction_data());
+            let counter = CounterAccount::unpack(&accounts[0].data.borrow())
+                .unwrap()
+                .counter;
+            match expected {
+                Ok(next) => {
+                    prop_assert_eq!(result, Ok(()));
+                    model = next;
+                }
+                Err(err) => prop_assert_eq!(result, Err(err)),
+            }
+            // Failed operations must leave the counter untouched.
+            prop_assert_eq!(counter, model);
+        }
+    }
+
+    #[test]
+    fn decrement_floors_at_zero(start in any::<u32>(), value in any::<u32>()) {
+        let ops = [Op::Update(start), Op::Decrement(value, OVERFLOW_SATURATING)];
+        let mut model = 0;
+        for op in &ops {
+            model = op.apply(model).unwrap();
+        }
+        prop_assert_eq!(model, start.saturating_sub(value));
+    }
+
+    #[test]
+    fn counter_account_round_trips(account in counter_account()) {
+        let data = bytemuck::bytes_of(&account);
+        prop_assert_eq!(data.len(), CounterAccount::LEN);
+        prop_assert_eq!(CounterAccount::unpack(data).unwrap(), account);
+        // Unaligned account data must still decode.
+        let mut shifted = vec![0u8; CounterAccount::LEN + 1];
+        shifted[1..].copy_from_slice(data);
+        prop_assert_eq!(CounterAccount::unpack(&shifted[1..]).unwrap(), account);
+        let value = account.value();
+        prop_assert_eq!(CounterValue::from_le_bytes(&value.to_le_bytes()), Some(value));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn counter_account_json_round_trips(account in counter_account()) {
+        let json = serde_json::to_string(&account).unwrap();
+        prop_assert_eq!(serde_json::from_str::<CounterAccount>(&json).unwrap(), account);
+    }
+
+    #[test]
+    fn filter_offsets_match_layout(account in counter_account()) {
+        let data = bytemuck::bytes_of(&account);
+        prop_assert_eq!(data[CounterAccount::DISCRIMINATOR_OFFSET], account.discriminator);
+        prop_assert_eq!(
+            &data[CounterAccount::AUTHORITY_OFFSET..][..32],
+            account.authority.as_ref()
+        );
+        prop_assert_eq!(data[CounterAccount::KIND_OFFSET], account.kind);
+        prop_assert_eq!(
+            data[CounterAccount::LAYOUT_VERSION_OFFSET],
+            account.layout_version
+        );
+    }
+
+    #[test]
+    fn unpack_fills_legacy_fields_with_defaults(
+        account in counter_account(),
+        len in CounterAccount::MIN_LEN..CounterAccount::LEN,
+    ) {
+        let data = &bytemuck::bytes_of(&account)[..len];
+        let unpacked = CounterAccount::unpack(data).unwrap();
+        let unpacked = bytemuck::bytes_of(&unpacked);
+        prop_assert_eq!(&unpacked[..len], data);
+        prop_assert!(unpacked[len..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn rescale_rounds_within_one(amount in any::<u64>(), from in any::<u8>(), to in any::<u8>()) {
+        let down = rescale(amount, from, to, ROUND_DOWN);
+        let nearest = rescale(amount, from, to, ROUND_NEAREST);
+        let up = rescale(amount, from, to, ROUND_UP);
+        if from <= to {
+            // Nothing to round.
+            prop_assert_eq!(&down, &nearest);
+            prop_assert_eq!(&down, &up);
+        } else {
+            let (down, nearest, up) = (down.unwrap(), nearest.unwrap(), up.unwrap());
+            prop_assert!(down <= nearest && nearest <= up && up - down <= 1);
+        }
+    }
+
+    #[test]
+    fn rescale_round_trips(amount in any::<u64>(), decimals in 0..20u8, extra in 0..20u8) {
+        if let Ok(scaled) = rescale(amount, decimals, decimals + extra, ROUND_DOWN) {
+            prop_assert_eq!(rescale(scaled, decimals + extra, decimals, ROUND_UP), Ok(amount));
+        }
+    }
+
+}